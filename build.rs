@@ -1,7 +1,66 @@
+use std::path::Path;
+use std::process::Command;
+
 fn main() {
     #[cfg(feature = "grpc")]
     {
         tonic_build::compile_protos("proto/kindling.proto")
             .expect("Failed to compile protobuf");
     }
+
+    embed_build_info();
+}
+
+/// Build details served by `GET /api/v1/version`. Each is optional: a
+/// source tarball has no git metadata, and Nix builds set
+/// `SOURCE_DATE_EPOCH` so the timestamp stays reproducible.
+fn embed_build_info() {
+    println!("cargo:rerun-if-env-changed=KINDLING_GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    for path in [".git/HEAD", ".git/packed-refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+    if let Some(head_ref) = std::fs::read_to_string(".git/HEAD")
+        .ok()
+        .and_then(|head| head.strip_prefix("ref: ").map(|r| r.trim().to_string()))
+    {
+        let path = format!(".git/{}", head_ref);
+        if Path::new(&path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+
+    let commit = std::env::var("KINDLING_GIT_COMMIT")
+        .ok()
+        .or_else(|| command_output("git", &["rev-parse", "HEAD"]));
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=KINDLING_GIT_COMMIT={}", commit);
+    }
+
+    let timestamp = std::env::var("SOURCE_DATE_EPOCH").ok().or_else(|| {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|d| d.as_secs().to_string())
+    });
+    if let Some(timestamp) = timestamp {
+        println!("cargo:rustc-env=KINDLING_BUILD_TIMESTAMP={}", timestamp);
+    }
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    if let Some(version) = command_output(&rustc, &["--version"]) {
+        println!("cargo:rustc-env=KINDLING_RUSTC_VERSION={}", version);
+    }
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
 }
@@ -29,6 +29,10 @@ with lib; let
       backend = "upstream";
       daemon = {
         http_addr = daemonCfg.httpAddr;
+        unix_socket = daemonCfg.unixSocket;
+        pid_file = daemonCfg.pidFile;
+        cors_allowed_origins = daemonCfg.corsAllowedOrigins;
+        advertise = daemonCfg.advertise;
         grpc_addr = daemonCfg.grpcAddr;
         log_level = daemonCfg.logLevel;
         telemetry = {
@@ -62,6 +66,30 @@ in {
       description = "HTTP listen address for REST and GraphQL APIs";
     };
 
+    unixSocket = mkOption {
+      type = types.nullOr types.str;
+      default = null;
+      description = "Also serve the API on this Unix socket (mode 0660)";
+    };
+
+    pidFile = mkOption {
+      type = types.nullOr types.str;
+      default = null;
+      description = "Write the daemon PID to this file while it runs";
+    };
+
+    corsAllowedOrigins = mkOption {
+      type = types.listOf types.str;
+      default = [];
+      description = "Origins allowed to call the HTTP API from a browser (\"*\" allows any)";
+    };
+
+    advertise = mkOption {
+      type = types.bool;
+      default = false;
+      description = "Announce the daemon on the LAN over mDNS (_kindling._tcp) for `kindling discover`";
+    };
+
     grpcAddr = mkOption {
       type = types.str;
       default = "127.0.0.1:9101";
@@ -0,0 +1,273 @@
+//! Bearer-token authorization for the HTTP API.
+//!
+//! With `daemon.auth.tokens` set, every request except the `/health` and
+//! `/ready` probes needs `Authorization: Bearer <token>` for a token whose
+//! scope covers the route: GET and HEAD need `read` (except the costly
+//! optimise estimate), any other method mutates state and needs `write`.
+//! `/graphql` needs `read` whatever the method; its mutations check for
+//! `write` themselves. A missing or unknown token gets 401, a token with
+//! too narrow a scope 403. The matched token's scope rides along as a
+//! request extension so handlers can ask for more than the route needs.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::{Request, State};
+use axum::http::{header, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use tracing::warn;
+
+use crate::config::{ApiToken, AuthConfig, TokenScope};
+use crate::secret::Secret;
+
+/// Routes reachable without a token, so probes keep working.
+const OPEN_PATHS: &[&str] = &["/health", "/ready"];
+
+/// GET routes expensive enough to need `write`: the optimise estimate reads
+/// every file in the store.
+const WRITE_GET_PATHS: &[&str] = &["/api/v1/store/optimise/estimate"];
+
+/// Queries and mutations share one endpoint, so the schema enforces scope.
+const GRAPHQL_PATH: &str = "/graphql";
+
+/// Scope a request needs; `None` for open routes.
+pub fn required_scope(method: &Method, path: &str) -> Option<TokenScope> {
+    if OPEN_PATHS.contains(&path) {
+        None
+    } else if WRITE_GET_PATHS.contains(&path) {
+        Some(TokenScope::Write)
+    } else if path == GRAPHQL_PATH || method == Method::GET || method == Method::HEAD {
+        Some(TokenScope::Read)
+    } else {
+        Some(TokenScope::Write)
+    }
+}
+
+/// Whether a caller may rewrite node.yaml. Unlike other mutations this is
+/// refused outright when auth is off: `scope` is `None` then, since no
+/// token was checked.
+pub fn can_edit_identity(scope: Option<TokenScope>) -> bool {
+    scope.is_some_and(|s| s >= TokenScope::Write)
+}
+
+/// `config` with each `secret:<name>` token replaced by what `resolve`
+/// returns for `<name>`. Literal tokens are kept as they are.
+pub fn resolve_tokens(
+    config: &AuthConfig,
+    resolve: impl Fn(&str) -> Result<Secret>,
+) -> Result<AuthConfig> {
+    let mut resolved = config.clone();
+    for token in &mut resolved.tokens {
+        if let Some(name) = token.secret_ref() {
+            token.token = resolve(name).with_context(|| {
+                format!(
+                    "resolving API token {}",
+                    token.name.as_deref().unwrap_or("unnamed")
+                )
+            })?;
+        }
+    }
+    Ok(resolved)
+}
+
+/// Enforce `config`'s tokens on `router`. With no tokens configured the
+/// router is returned as-is.
+pub fn with_auth(router: Router, config: &AuthConfig) -> Router {
+    if config.tokens.is_empty() {
+        return router;
+    }
+    let tokens = Arc::new(config.tokens.clone());
+    router.layer(middleware::from_fn_with_state(tokens, authorize))
+}
+
+async fn authorize(
+    State(tokens): State<Arc<Vec<ApiToken>>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(required) = required_scope(request.method(), request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim);
+    let Some(token) = presented.and_then(|p| find_token(&tokens, p)) else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            "missing or unknown bearer token",
+        )
+            .into_response();
+    };
+    if token.scope < required {
+        warn!(
+            token = token.name.as_deref().unwrap_or("unnamed"),
+            path = request.uri().path(),
+            "token scope too narrow for request"
+        );
+        return (
+            StatusCode::FORBIDDEN,
+            format!("this endpoint needs a {:?} token", required).to_lowercase(),
+        )
+            .into_response();
+    }
+    request.extensions_mut().insert(token.scope);
+    next.run(request).await
+}
+
+fn find_token<'a>(tokens: &'a [ApiToken], presented: &str) -> Option<&'a ApiToken> {
+    // Check every token so the time taken doesn't hint at which matched.
+    tokens.iter().fold(None, |found, t| {
+        if constant_time_eq(t.token.expose(), presented) {
+            Some(t)
+        } else {
+            found
+        }
+    })
+}
+
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secret::Secret;
+    use axum::routing::{get, post};
+
+    async fn serve() -> String {
+        let config = AuthConfig {
+            tokens: vec![
+                ApiToken {
+                    name: Some("dashboard".to_string()),
+                    token: Secret::new("read-token".to_string()),
+                    scope: TokenScope::Read,
+                },
+                ApiToken {
+                    name: Some("ops".to_string()),
+                    token: Secret::new("write-token".to_string()),
+                    scope: TokenScope::Write,
+                },
+            ],
+        };
+        let app = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .route("/api/v1/report", get(|| async { "report" }))
+            .route("/api/v1/gc/run", post(|| async { "gc" }));
+        let app = with_auth(app, &config);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    async fn status(method: Method, url: &str, token: Option<&str>) -> StatusCode {
+        let mut request = reqwest::Client::new().request(method, url);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        request.send().await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn read_token_is_limited_to_read_routes() {
+        let base = serve().await;
+        let report = format!("{}/api/v1/report", base);
+        let gc = format!("{}/api/v1/gc/run", base);
+
+        assert_eq!(
+            status(Method::GET, &report, Some("read-token")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(Method::POST, &gc, Some("read-token")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            status(Method::POST, &gc, Some("write-token")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(Method::GET, &report, Some("write-token")).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            required_scope(&Method::PUT, "/api/v1/fleet/nodes/edge-1/annotations"),
+            Some(TokenScope::Write)
+        );
+        assert_eq!(
+            required_scope(&Method::DELETE, "/api/v1/anything"),
+            Some(TokenScope::Write)
+        );
+        assert_eq!(
+            required_scope(&Method::POST, "/graphql"),
+            Some(TokenScope::Read)
+        );
+        assert_eq!(
+            required_scope(&Method::GET, "/api/v1/store/optimise/estimate"),
+            Some(TokenScope::Write)
+        );
+        let identity = format!("{}/api/v1/identity", base);
+        assert_eq!(
+            status(Method::PATCH, &identity, Some("read-token")).await,
+            StatusCode::FORBIDDEN
+        );
+        assert!(can_edit_identity(Some(TokenScope::Write)));
+        assert!(!can_edit_identity(Some(TokenScope::Read)));
+        assert!(!can_edit_identity(None));
+    }
+
+    #[tokio::test]
+    async fn health_is_open_and_other_routes_need_a_known_token() {
+        let base = serve().await;
+        let report = format!("{}/api/v1/report", base);
+
+        assert_eq!(
+            status(Method::GET, &format!("{}/health", base), None).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(Method::GET, &report, None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(Method::GET, &report, Some("guess")).await,
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[test]
+    fn secret_ref_tokens_resolve_through_the_provider() {
+        let token = |token: &str| ApiToken {
+            name: Some("ops".to_string()),
+            token: Secret::new(token.to_string()),
+            scope: TokenScope::Write,
+        };
+        let config = AuthConfig {
+            tokens: vec![token("literal-token"), token("secret:api_token")],
+        };
+        let resolved = resolve_tokens(&config, |name| match name {
+            "api_token" => Ok(Secret::new("from-provider".to_string())),
+            _ => anyhow::bail!("no such secret"),
+        })
+        .unwrap();
+        assert_eq!(resolved.tokens[0].token.expose(), "literal-token");
+        assert_eq!(resolved.tokens[1].token.expose(), "from-provider");
+
+        let missing = AuthConfig {
+            tokens: vec![token("secret:other")],
+        };
+        let err = resolve_tokens(&missing, |_| anyhow::bail!("no such secret")).unwrap_err();
+        assert!(format!("{:#}", err).contains("resolving API token ops"));
+    }
+}
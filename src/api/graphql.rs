@@ -1,9 +1,14 @@
-use async_graphql::{Context, EmptySubscription, Object, Schema};
+use async_graphql::{Context, EmptySubscription, Guard, Object, Schema, SimpleObject};
+use chrono::{DateTime, Utc};
 use std::sync::Arc;
 
+use crate::api::{auth, rest};
+use crate::config::TokenScope;
+use crate::domain::fleet_store::{FleetNode, FleetStore};
 use crate::domain::nix_service::NixService;
 use crate::domain::node_report::{NodeReport, StoredReport};
 use crate::domain::node_service::NodeService;
+use crate::domain::report_health::{self, OverallStatus};
 use crate::domain::types::*;
 use crate::node_identity::NodeIdentity;
 
@@ -30,6 +35,36 @@ impl QueryRoot {
             .map_err(|e| async_graphql::Error::new(e.to_string()))
     }
 
+    async fn gc_roots(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] include_transient: bool,
+    ) -> async_graphql::Result<Vec<GcRoot>> {
+        let svc = ctx.data::<Arc<NixService>>()?;
+        svc.gc_roots(include_transient)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))
+    }
+
+    async fn why_depends(
+        &self,
+        ctx: &Context<'_>,
+        from: String,
+        to: String,
+    ) -> async_graphql::Result<Vec<String>> {
+        let svc = ctx.data::<Arc<NixService>>()?;
+        svc.why_depends(&from, &to)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))
+    }
+
+    async fn system_closure(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<String>> {
+        let svc = ctx.data::<Arc<NixService>>()?;
+        svc.system_closure()
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))
+    }
+
     async fn nix_config(&self, ctx: &Context<'_>) -> async_graphql::Result<NixConfig> {
         let svc = ctx.data::<Arc<NixService>>()?;
         svc.nix_config()
@@ -49,6 +84,11 @@ impl QueryRoot {
             .map_err(|e| async_graphql::Error::new(e.to_string()))
     }
 
+    /// Version, commit and compiled-in features of the daemon.
+    async fn version(&self) -> BuildInfo {
+        BuildInfo::current()
+    }
+
     async fn health(&self, ctx: &Context<'_>) -> async_graphql::Result<DaemonHealth> {
         let svc = ctx.data::<Arc<NixService>>()?;
         Ok(svc.health().await)
@@ -74,12 +114,80 @@ impl QueryRoot {
         let node = ctx.data::<Arc<NodeService>>()?;
         Ok(node.cached_report().await)
     }
+
+    /// Reports pushed to this node's fleet controller. Errors unless
+    /// `fleet_controller.enabled` is set.
+    async fn fleet(&self, ctx: &Context<'_>) -> async_graphql::Result<Fleet> {
+        match ctx.data::<Option<Arc<FleetStore>>>()? {
+            Some(store) => Ok(Fleet(store.clone())),
+            None => Err(async_graphql::Error::new("fleet controller not enabled")),
+        }
+    }
+}
+
+pub struct Fleet(Arc<FleetStore>);
+
+#[Object]
+impl Fleet {
+    /// Every node that has pushed a report, by hostname.
+    async fn nodes(&self) -> Vec<FleetNodeView> {
+        self.0
+            .nodes()
+            .await
+            .into_iter()
+            .map(|(hostname, node)| FleetNodeView::new(hostname, node))
+            .collect()
+    }
+
+    async fn node(&self, hostname: String) -> Option<FleetNodeView> {
+        let node = self.0.node(&hostname).await?;
+        Some(FleetNodeView::new(hostname, node))
+    }
+}
+
+/// A fleet node's latest report as the controller received it.
+#[derive(SimpleObject)]
+pub struct FleetNodeView {
+    pub hostname: String,
+    pub status: OverallStatus,
+    /// When the controller received the report.
+    pub last_seen: DateTime<Utc>,
+    pub report: NodeReport,
+}
+
+impl FleetNodeView {
+    fn new(hostname: String, node: FleetNode) -> Self {
+        let report = node.report.report;
+        Self {
+            hostname,
+            status: report_health::classify(&report).0,
+            last_seen: node.received_at,
+            report,
+        }
+    }
+}
+
+/// Guard for every mutation: with auth on, the request's token must have
+/// write scope. POST /graphql itself only needs read, so queries work with
+/// a read token. With auth off no scope is attached and mutations run.
+struct WriteScope;
+
+impl Guard for WriteScope {
+    async fn check(&self, ctx: &Context<'_>) -> async_graphql::Result<()> {
+        match ctx.data_opt::<TokenScope>() {
+            Some(scope) if *scope < TokenScope::Write => Err(async_graphql::Error::new(
+                "this mutation needs a write token",
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 pub struct MutationRoot;
 
 #[Object]
 impl MutationRoot {
+    #[graphql(guard = "WriteScope")]
     async fn run_gc(&self, ctx: &Context<'_>) -> async_graphql::Result<GcResult> {
         let svc = ctx.data::<Arc<NixService>>()?;
         svc.trigger_gc()
@@ -87,6 +195,7 @@ impl MutationRoot {
             .map_err(|e| async_graphql::Error::new(e.to_string()))
     }
 
+    #[graphql(guard = "WriteScope")]
     async fn optimise_store(&self, ctx: &Context<'_>) -> async_graphql::Result<OptimiseResult> {
         let svc = ctx.data::<Arc<NixService>>()?;
         svc.optimise_store()
@@ -95,17 +204,128 @@ impl MutationRoot {
     }
 
     /// Trigger a fresh discovery → store → cache cycle and return the result.
+    #[graphql(guard = "WriteScope")]
     async fn refresh_report(&self, ctx: &Context<'_>) -> async_graphql::Result<StoredReport> {
         let node = ctx.data::<Arc<NodeService>>()?;
         node.refresh()
             .await
             .map_err(|e| async_graphql::Error::new(e.to_string()))
     }
+
+    /// Set a node.yaml field by dot path (value parsed as YAML), save the
+    /// file and return the reloaded identity.
+    #[graphql(guard = "WriteScope")]
+    async fn set_identity_field(
+        &self,
+        ctx: &Context<'_>,
+        path: String,
+        value: String,
+    ) -> async_graphql::Result<NodeIdentity> {
+        if !auth::can_edit_identity(ctx.data_opt::<TokenScope>().copied()) {
+            return Err(async_graphql::Error::new(rest::IDENTITY_EDIT_FORBIDDEN));
+        }
+        let node = ctx.data::<Arc<NodeService>>()?;
+        node.set_identity_field(&path, &value)
+            .await
+            .map_err(|e| async_graphql::Error::new(format!("{:#}", e)))
+    }
 }
 
-pub fn build_schema(nix_service: Arc<NixService>, node_service: Arc<NodeService>) -> KindlingSchema {
+pub fn build_schema(
+    nix_service: Arc<NixService>,
+    node_service: Arc<NodeService>,
+    fleet: Option<Arc<FleetStore>>,
+) -> KindlingSchema {
     Schema::build(QueryRoot, MutationRoot, EmptySubscription)
         .data(nix_service)
         .data(node_service)
+        .data(fleet)
         .finish()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DaemonConfig;
+    use crate::domain::node_report::tests::make_test_report;
+
+    fn schema(fleet: Option<Arc<FleetStore>>) -> KindlingSchema {
+        let config = DaemonConfig::default();
+        build_schema(
+            NixService::new(config.clone()),
+            Arc::new(NodeService::new(config.identity, config.report)),
+            fleet,
+        )
+    }
+
+    #[tokio::test]
+    async fn fleet_query_returns_ingested_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(
+            FleetStore::open(dir.path().join("fleet.json"))
+                .await
+                .unwrap(),
+        );
+        let mut report = make_test_report();
+        report.hostname = "edge-1".to_string();
+        store
+            .ingest("edge-1", StoredReport::new(report))
+            .await
+            .unwrap();
+        let schema = schema(Some(store));
+
+        let resp = schema
+            .execute(
+                r#"{ fleet {
+                    nodes { hostname status lastSeen report { hostname daemonVersion } }
+                    node(hostname: "edge-1") { hostname }
+                    missing: node(hostname: "edge-2") { hostname }
+                } }"#,
+            )
+            .await;
+        assert!(resp.errors.is_empty(), "{:?}", resp.errors);
+        let data = resp.data.into_json().unwrap();
+        let nodes = data["fleet"]["nodes"].as_array().unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0]["hostname"], "edge-1");
+        assert_eq!(nodes[0]["report"]["hostname"], "edge-1");
+        assert!(nodes[0]["status"].is_string());
+        assert!(nodes[0]["lastSeen"].is_string());
+        assert_eq!(data["fleet"]["node"]["hostname"], "edge-1");
+        assert!(data["fleet"]["missing"].is_null());
+    }
+
+    #[tokio::test]
+    async fn fleet_query_errors_without_controller() {
+        let resp = schema(None)
+            .execute("{ fleet { nodes { hostname } } }")
+            .await;
+        assert_eq!(resp.errors.len(), 1);
+        assert_eq!(resp.errors[0].message, "fleet controller not enabled");
+    }
+
+    #[tokio::test]
+    async fn mutations_need_write_scope_and_identity_edits_need_auth() {
+        let schema = schema(None);
+        let mutation =
+            r#"mutation { setIdentityField(path: "hostname", value: "x") { hostname } }"#;
+        let error = |scope: Option<TokenScope>| {
+            let mut request = async_graphql::Request::new(mutation);
+            if let Some(scope) = scope {
+                request = request.data(scope);
+            }
+            let schema = schema.clone();
+            async move { schema.execute(request).await.errors[0].message.clone() }
+        };
+
+        assert_eq!(
+            error(Some(TokenScope::Read)).await,
+            "this mutation needs a write token"
+        );
+        assert_eq!(error(None).await, rest::IDENTITY_EDIT_FORBIDDEN);
+        // Past both checks; fails only because there is no node.yaml.
+        assert!(error(Some(TokenScope::Write))
+            .await
+            .contains("no node identity"));
+    }
+}
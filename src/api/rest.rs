@@ -1,12 +1,25 @@
-use axum::extract::State;
-use axum::http::StatusCode;
-use axum::routing::{get, post};
-use axum::{Json, Router};
+use axum::body::Body;
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
+use axum::http::{header, HeaderValue, Method, Request, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post, put};
+use axum::{Extension, Json, Router};
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::decompression::RequestDecompressionLayer;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
 
-use crate::domain::nix_service::NixService;
+use crate::api::auth;
+use crate::config::TokenScope;
+use crate::domain::fleet_store::{self, AppliedConfig, FleetNodeSummary, FleetStore, NodeFilter};
+use crate::domain::nix_service::{validate_gc_age, validate_store_path, NixService};
 use crate::domain::node_report::StoredReport;
 use crate::domain::node_service::NodeService;
+use crate::domain::report_collector::{self, CollectorRegistry};
+use crate::domain::report_delta::ReportDelta;
+use crate::domain::report_metrics;
 use crate::domain::types::*;
 use crate::node_identity::NodeIdentity;
 use crate::server::bootstrap::{BootstrapPhase, BootstrapState};
@@ -17,34 +30,153 @@ use crate::server::health;
 pub struct AppState {
     pub nix: Arc<NixService>,
     pub node: Arc<NodeService>,
+    /// Present when `fleet_controller.enabled` is set.
+    pub fleet: Option<Arc<FleetStore>>,
+    /// `fleet_controller.max_report_bytes`; 0 keeps axum's default limit.
+    pub fleet_max_report_bytes: usize,
 }
 
+/// Why an identity edit was refused; shared with the GraphQL mutation.
+pub const IDENTITY_EDIT_FORBIDDEN: &str =
+    "editing node.yaml needs daemon.auth.tokens set and a write-scope token";
+
 pub fn router(state: AppState) -> Router {
+    // Reports may arrive gzip-encoded; the body limit applies to the
+    // decoded stream, so compressed uploads can't smuggle past it.
+    let ingest = post(fleet_ingest).layer(RequestDecompressionLayer::new());
+    let ingest = match state.fleet_max_report_bytes {
+        0 => ingest,
+        n => ingest.layer(DefaultBodyLimit::max(n)),
+    };
+
     Router::new()
         .route("/health", get(health))
         .route("/ready", get(ready))
+        .route("/metrics", get(metrics))
+        .route("/api/v1/version", get(version))
         .route("/api/v1/status", get(status))
         .route("/api/v1/platform", get(platform))
         .route("/api/v1/store", get(store))
+        .route("/api/v1/store/roots", get(gc_roots))
+        .route("/api/v1/store/why-depends", get(why_depends))
+        .route("/api/v1/store/system-closure", get(system_closure))
         .route("/api/v1/config", get(nix_config))
         .route("/api/v1/gc", get(gc_status))
         .route("/api/v1/gc/run", post(gc_run))
         .route("/api/v1/store/optimise", post(optimise_store))
+        .route("/api/v1/store/optimise/estimate", get(optimise_estimate))
+        .route("/api/v1/store/verify", post(verify_store))
         .route("/api/v1/caches", get(caches))
         // Node identity + report endpoints
-        .route("/api/v1/identity", get(identity))
+        .route("/api/v1/identity", get(identity).patch(set_identity_field))
         .route("/api/v1/report", get(report))
         .route("/api/v1/report/refresh", post(refresh_report))
+        .route("/api/v1/processes/stream", get(process_stream))
+        // Fleet controller endpoints
+        .route("/api/v1/fleet/nodes", get(fleet_nodes))
+        .route("/api/v1/fleet/nodes/{hostname}/report", ingest)
+        .route(
+            "/api/v1/fleet/nodes/{hostname}/last-change",
+            get(fleet_last_change),
+        )
+        .route(
+            "/api/v1/fleet/nodes/{hostname}/annotations",
+            get(fleet_annotations).put(fleet_annotate),
+        )
+        .route(
+            "/api/v1/fleet/nodes/{hostname}/applied",
+            put(fleet_record_applied),
+        )
         // Server mode endpoints
         .route("/api/v1/server/status", get(server_status))
         .route("/api/v1/server/health", get(server_health))
         .with_state(state)
 }
 
+/// Allow browser calls from `origins` (`daemon.cors_allowed_origins`).
+/// With none configured the router is returned as-is, so cross-origin
+/// requests stay blocked; `*` allows any origin and is meant for local
+/// development only.
+pub fn with_cors(router: Router, origins: &[String]) -> anyhow::Result<Router> {
+    if origins.is_empty() {
+        return Ok(router);
+    }
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        tracing::warn!(
+            "cors_allowed_origins contains '*': any website can call the daemon API from a browser"
+        );
+        AllowOrigin::any()
+    } else {
+        let origins = origins
+            .iter()
+            .map(|o| {
+                HeaderValue::from_str(o.trim_end_matches('/'))
+                    .map_err(|_| anyhow::anyhow!("invalid CORS origin '{}'", o))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+    let request_id = header::HeaderName::from_static("x-request-id");
+    Ok(router.layer(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH])
+            .allow_headers([
+                header::AUTHORIZATION,
+                header::CONTENT_TYPE,
+                request_id.clone(),
+            ])
+            .expose_headers([request_id]),
+    ))
+}
+
+/// Per-request tracing with a correlation id: an incoming `X-Request-Id` is
+/// kept (so one id can follow a fleet operation across nodes), otherwise a
+/// UUID is generated. The id is recorded on the request span and echoed
+/// in the response.
+pub fn with_request_tracing(router: Router) -> Router {
+    let request_id = header::HeaderName::from_static("x-request-id");
+    router
+        .layer(PropagateRequestIdLayer::new(request_id.clone()))
+        .layer(
+            TraceLayer::new_for_http().make_span_with(|request: &Request<_>| {
+                let request_id = request
+                    .headers()
+                    .get("x-request-id")
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default();
+                tracing::info_span!(
+                    "request",
+                    method = %request.method(),
+                    uri = %request.uri(),
+                    request_id,
+                )
+            }),
+        )
+        .layer(SetRequestIdLayer::new(request_id, MakeRequestUuid))
+}
+
 async fn health(State(state): State<AppState>) -> Json<DaemonHealth> {
     Json(state.nix.health().await)
 }
 
+async fn version() -> Json<BuildInfo> {
+    Json(BuildInfo::current())
+}
+
+/// Prometheus text exposition of report collection timings.
+async fn metrics(
+    State(state): State<AppState>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    let body = state
+        .node
+        .cached_report()
+        .await
+        .map(|stored| report_metrics::render(&stored.report))
+        .unwrap_or_default();
+    ([(header::CONTENT_TYPE, report_metrics::CONTENT_TYPE)], body)
+}
+
 async fn ready(State(state): State<AppState>) -> Result<Json<NixStatus>, StatusCode> {
     let s = state.nix.status().await;
     if s.installed {
@@ -73,6 +205,57 @@ async fn store(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
 }
 
+#[derive(serde::Deserialize)]
+struct GcRootsQuery {
+    /// Include runtime roots held by running processes.
+    #[serde(default)]
+    include_transient: bool,
+}
+
+async fn gc_roots(
+    State(state): State<AppState>,
+    Query(query): Query<GcRootsQuery>,
+) -> Result<Json<Vec<GcRoot>>, (StatusCode, String)> {
+    state
+        .nix
+        .gc_roots(query.include_transient)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+#[derive(serde::Deserialize)]
+struct WhyDependsQuery {
+    from: String,
+    to: String,
+}
+
+async fn why_depends(
+    State(state): State<AppState>,
+    Query(query): Query<WhyDependsQuery>,
+) -> Result<Json<Vec<String>>, (StatusCode, String)> {
+    for path in [&query.from, &query.to] {
+        validate_store_path(path).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    }
+    state
+        .nix
+        .why_depends(&query.from, &query.to)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+async fn system_closure(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<String>>, (StatusCode, String)> {
+    state
+        .nix
+        .system_closure()
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
 async fn nix_config(
     State(state): State<AppState>,
 ) -> Result<Json<NixConfig>, (StatusCode, String)> {
@@ -88,12 +271,53 @@ async fn gc_status(State(state): State<AppState>) -> Json<GcStatus> {
     Json(state.nix.gc_status().await)
 }
 
+/// `?older_than=30d` deletes older generations first (via
+/// nix-collect-garbage); `?max_freed=<bytes>` caps what is freed.
 async fn gc_run(
     State(state): State<AppState>,
+    Query(options): Query<GcOptions>,
 ) -> Result<Json<GcResult>, (StatusCode, String)> {
+    if let Some(ref age) = options.older_than {
+        validate_gc_age(age).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    }
+    state
+        .nix
+        .trigger_gc_with(&options)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Repeatable `path=/nix/store/...` limits the check (default: whole
+/// store); `repair=true` asks nix to re-fetch or rebuild bad paths.
+async fn verify_store(
+    State(state): State<AppState>,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Json<StoreVerifyResult>, (StatusCode, String)> {
+    let mut paths = Vec::new();
+    let mut repair = false;
+    for (key, value) in params {
+        match key.as_str() {
+            "path" => {
+                validate_store_path(&value)
+                    .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+                paths.push(value);
+            }
+            "repair" => {
+                repair = value.parse().map_err(|_| {
+                    (
+                        StatusCode::BAD_REQUEST,
+                        format!("invalid repair '{}'", value),
+                    )
+                })?;
+            }
+            _ => {}
+        }
+    }
+    let paths = (!paths.is_empty()).then_some(paths);
     state
         .nix
-        .trigger_gc()
+        .verify_store(paths, repair)
         .await
         .map(Json)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
@@ -110,6 +334,21 @@ async fn optimise_store(
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
 }
 
+/// Dry run of `optimise`: scans the store and reports what hard-linking
+/// duplicates would free. 409 while another scan is running.
+async fn optimise_estimate(
+    State(state): State<AppState>,
+) -> Result<Json<OptimiseEstimate>, (StatusCode, String)> {
+    match state.nix.estimate_optimise().await {
+        Ok(Some(estimate)) => Ok(Json(estimate)),
+        Ok(None) => Err((
+            StatusCode::CONFLICT,
+            "a store scan is already running".to_string(),
+        )),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
 async fn caches(
     State(state): State<AppState>,
 ) -> Result<Json<Vec<CacheInfo>>, (StatusCode, String)> {
@@ -137,6 +376,33 @@ async fn identity(
         })
 }
 
+#[derive(serde::Deserialize)]
+struct IdentityFieldUpdate {
+    /// Dot path into node.yaml, e.g. `hardware.cpu.cores`.
+    path: String,
+    /// New value, parsed as YAML.
+    value: String,
+}
+
+/// Set one node.yaml field, save it and return the reloaded identity.
+/// 403 unless the caller holds a write token (so never with auth off),
+/// 400 if the change would leave node.yaml invalid.
+async fn set_identity_field(
+    State(state): State<AppState>,
+    scope: Option<Extension<TokenScope>>,
+    Json(update): Json<IdentityFieldUpdate>,
+) -> Result<Json<NodeIdentity>, (StatusCode, String)> {
+    if !auth::can_edit_identity(scope.map(|Extension(s)| s)) {
+        return Err((StatusCode::FORBIDDEN, IDENTITY_EDIT_FORBIDDEN.to_string()));
+    }
+    state
+        .node
+        .set_identity_field(&update.path, &update.value)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("{:#}", e)))
+}
+
 /// Serve the cached report from memory. Never triggers collection.
 /// Returns 503 if the cache is empty (initial collection hasn't completed yet).
 async fn report(
@@ -155,18 +421,188 @@ async fn report(
         })
 }
 
+#[derive(serde::Deserialize)]
+struct RefreshQuery {
+    /// Comma-separated section names; absent means a full refresh.
+    #[serde(default)]
+    sections: Option<String>,
+}
+
 /// Trigger a fresh discovery → store → cache cycle and return the result.
+/// With `?sections=health,network` only those sections are re-collected.
 async fn refresh_report(
     State(state): State<AppState>,
+    Query(query): Query<RefreshQuery>,
 ) -> Result<Json<StoredReport>, (StatusCode, String)> {
-    state
-        .node
-        .refresh()
+    let sections: Vec<String> = query
+        .sections
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
+    let result = if sections.is_empty() {
+        state.node.refresh().await
+    } else {
+        CollectorRegistry::platform(state.node.report_config())
+            .only(&sections)
+            .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+        state.node.refresh_sections(&sections).await
+    };
+    result
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Every process from a fresh `ps` as JSON Lines, streamed as it is read.
+async fn process_stream() -> Result<Response, (StatusCode, String)> {
+    let lines = report_collector::process_ndjson()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)))?;
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+fn fleet_store(state: &AppState) -> Result<&Arc<FleetStore>, (StatusCode, String)> {
+    state.fleet.as_ref().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            "fleet controller not enabled".to_string(),
+        )
+    })
+}
+
+/// Accept a report pushed by a node (`kindling report --push`).
+/// Returns the delta against the node's previous report.
+async fn fleet_ingest(
+    State(state): State<AppState>,
+    Path(hostname): Path<String>,
+    Json(stored): Json<StoredReport>,
+) -> Result<Json<ReportDelta>, (StatusCode, String)> {
+    let fleet = fleet_store(&state)?;
+    fleet_store::validate_hostname(&hostname)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    if stored.report.hostname != hostname {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "report hostname '{}' does not match '{}'",
+                stored.report.hostname, hostname
+            ),
+        ));
+    }
+    fleet
+        .ingest(&hostname, stored)
         .await
         .map(Json)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
 }
 
+/// Nodes known to the controller, filtered by repeatable `tag=<tag>` and
+/// `label=<key>=<value>` parameters (all must match).
+async fn fleet_nodes(
+    State(state): State<AppState>,
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Json<Vec<FleetNodeSummary>>, (StatusCode, String)> {
+    let fleet = fleet_store(&state)?;
+    let values = |key: &str| -> Vec<String> {
+        params
+            .iter()
+            .filter(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+            .collect()
+    };
+    let filter = NodeFilter::parse(&values("tag"), &values("label"))
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    Ok(Json(fleet.list(&filter).await))
+}
+
+/// What changed between a node's last two reports.
+async fn fleet_last_change(
+    State(state): State<AppState>,
+    Path(hostname): Path<String>,
+) -> Result<Json<ReportDelta>, (StatusCode, String)> {
+    fleet_store(&state)?
+        .last_change(&hostname)
+        .await
+        .map(Json)
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("no reports received from '{}'", hostname),
+            )
+        })
+}
+
+/// Operator annotations on a node.
+async fn fleet_annotations(
+    State(state): State<AppState>,
+    Path(hostname): Path<String>,
+) -> Result<Json<BTreeMap<String, String>>, (StatusCode, String)> {
+    fleet_store(&state)?
+        .node(&hostname)
+        .await
+        .map(|node| Json(node.annotations))
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                format!("no reports received from '{}'", hostname),
+            )
+        })
+}
+
+/// Merge a `key: value` map into a node's annotations; an empty value
+/// removes the key. Returns the annotations after the change.
+async fn fleet_annotate(
+    State(state): State<AppState>,
+    Path(hostname): Path<String>,
+    Json(changes): Json<BTreeMap<String, String>>,
+) -> Result<Json<BTreeMap<String, String>>, (StatusCode, String)> {
+    if changes.keys().any(|k| k.is_empty()) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "annotation keys must not be empty".to_string(),
+        ));
+    }
+    match fleet_store(&state)?.annotate(&hostname, changes).await {
+        Ok(Some(annotations)) => Ok(Json(annotations)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            format!("no reports received from '{}'", hostname),
+        )),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct AppliedUpdate {
+    /// Deploy checksum of the config just applied.
+    checksum: String,
+}
+
+/// Record the config `kindling fleet apply` just deployed to a node.
+async fn fleet_record_applied(
+    State(state): State<AppState>,
+    Path(hostname): Path<String>,
+    Json(update): Json<AppliedUpdate>,
+) -> Result<Json<AppliedConfig>, (StatusCode, String)> {
+    match fleet_store(&state)?
+        .record_applied(&hostname, &update.checksum)
+        .await
+    {
+        Ok(Some(applied)) => Ok(Json(applied)),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            format!("no reports received from '{}'", hostname),
+        )),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
 /// Server bootstrap status (phase, cluster name, errors).
 async fn server_status() -> Json<BootstrapState> {
     Json(BootstrapState::load_or_default(""))
@@ -196,3 +632,386 @@ struct ServerHealthResponse {
     k3s: health::K3sHealthStatus,
     fluxcd: health::FluxcdHealthStatus,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DaemonConfig;
+    use crate::domain::node_report::tests::make_test_report;
+
+    async fn serve_fleet(max_report_bytes: usize, dir: &std::path::Path) -> String {
+        let config = DaemonConfig::default();
+        let state = AppState {
+            nix: NixService::new(config.clone()),
+            node: Arc::new(NodeService::new(
+                config.identity.clone(),
+                config.report.clone(),
+            )),
+            fleet: Some(Arc::new(
+                FleetStore::open(dir.join("fleet.json")).await.unwrap(),
+            )),
+            fleet_max_report_bytes: max_report_bytes,
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state)).await.unwrap() });
+        format!("http://{}/api/v1/fleet/nodes/test-node/report", addr)
+    }
+
+    #[tokio::test]
+    async fn fleet_ingest_rejects_oversized_body() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = serve_fleet(64 * 1024, dir.path()).await;
+        let http = reqwest::Client::new();
+
+        let body = serde_json::to_vec(&StoredReport::new(make_test_report())).unwrap();
+        assert!(body.len() < 64 * 1024);
+        let resp = http
+            .post(&url)
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let oversized = vec![b' '; 64 * 1024 + 1];
+        let resp = http
+            .post(&url)
+            .header("content-type", "application/json")
+            .body(oversized)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn metrics_endpoint_matches_cli_rendering() {
+        use crate::domain::report_store::ReportStore;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache_file = dir.path().join("report.json");
+        let mut report = make_test_report();
+        report
+            .collection_durations_ms
+            .insert("hardware".to_string(), 42);
+        report
+            .collection_durations_ms
+            .insert("nix".to_string(), 310);
+        ReportStore::new(cache_file.clone())
+            .write(&StoredReport::new(report.clone()))
+            .await
+            .unwrap();
+
+        let mut config = DaemonConfig::default();
+        config.report.cache_file = cache_file.display().to_string();
+        let node = Arc::new(NodeService::new(
+            config.identity.clone(),
+            config.report.clone(),
+        ));
+        node.load_from_disk().await;
+        let state = AppState {
+            nix: NixService::new(config),
+            node,
+            fleet: None,
+            fleet_max_report_bytes: 0,
+        };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/metrics", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router(state)).await.unwrap() });
+
+        let body = reqwest::get(&url).await.unwrap().text().await.unwrap();
+        assert_eq!(body, report_metrics::render(&report));
+        assert!(body.contains("kindling_report_section_duration_ms{section=\"nix\"} 310\n"));
+    }
+
+    #[tokio::test]
+    async fn process_stream_yields_json_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = serve_fleet(0, dir.path()).await;
+        let base = url.trim_end_matches("/api/v1/fleet/nodes/test-node/report");
+
+        let resp = reqwest::get(format!("{}/api/v1/processes/stream", base))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/x-ndjson");
+        let body = resp.text().await.unwrap();
+        let pids: Vec<u64> = body
+            .lines()
+            .map(|line| {
+                let v: serde_json::Value = serde_json::from_str(line).unwrap();
+                v["pid"].as_u64().unwrap()
+            })
+            .collect();
+        assert!(pids.contains(&u64::from(std::process::id())));
+    }
+
+    #[tokio::test]
+    async fn identity_patch_saves_and_reloads() {
+        let dir = tempfile::tempdir().unwrap();
+        let node_yaml = dir.path().join("node.yaml");
+        NodeIdentity::from_bootstrap("cloud-server", "test-node", "ops", None)
+            .save(&node_yaml)
+            .unwrap();
+        let config = DaemonConfig::default();
+        let state = AppState {
+            nix: NixService::new(config.clone()),
+            node: Arc::new(NodeService::with_identity_path(
+                config.identity.clone(),
+                config.report.clone(),
+                node_yaml.clone(),
+            )),
+            fleet: None,
+            fleet_max_report_bytes: 0,
+        };
+        let serve = |app: Router| async move {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/api/v1/identity", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+            url
+        };
+        let http = reqwest::Client::new();
+        let set_cores = serde_json::json!({"path": "hardware.cpu.cores", "value": "8"});
+
+        // With auth off nobody may rewrite node.yaml.
+        let open_url = serve(router(state.clone())).await;
+        let resp = http.patch(&open_url).json(&set_cores).send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            NodeIdentity::load(&node_yaml).unwrap().hardware.cpu.cores,
+            None
+        );
+
+        let auth = crate::config::AuthConfig {
+            tokens: vec![crate::config::ApiToken {
+                name: None,
+                token: crate::secret::Secret::new("write-token".to_string()),
+                scope: TokenScope::Write,
+            }],
+        };
+        let url = serve(auth::with_auth(router(state), &auth)).await;
+        let http = reqwest::Client::builder()
+            .default_headers(
+                [(
+                    header::AUTHORIZATION,
+                    HeaderValue::from_static("Bearer write-token"),
+                )]
+                .into_iter()
+                .collect(),
+            )
+            .build()
+            .unwrap();
+        let resp = http.patch(&url).json(&set_cores).send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let identity: NodeIdentity = http.get(&url).send().await.unwrap().json().await.unwrap();
+        assert_eq!(identity.hardware.cpu.cores, Some(8));
+        assert_eq!(
+            NodeIdentity::load(&node_yaml).unwrap().hardware.cpu.cores,
+            Some(8)
+        );
+
+        // A value that doesn't fit the schema is rejected and not saved.
+        let resp = http
+            .patch(&url)
+            .json(&serde_json::json!({"path": "hardware.cpu.cores", "value": "lots"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert!(resp.text().await.unwrap().contains("hardware.cpu.cores"));
+        assert_eq!(
+            NodeIdentity::load(&node_yaml).unwrap().hardware.cpu.cores,
+            Some(8)
+        );
+    }
+
+    #[tokio::test]
+    async fn version_reports_crate_version_and_features() {
+        use crate::client::{ClientTls, KindlingClient, DEFAULT_TIMEOUT};
+
+        let dir = tempfile::tempdir().unwrap();
+        let url = serve_fleet(0, dir.path()).await;
+        let base = url.trim_end_matches("/api/v1/fleet/nodes/test-node/report");
+
+        let client = KindlingClient::new(base, DEFAULT_TIMEOUT, &ClientTls::default()).unwrap();
+        let info = client.version().await.unwrap();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.grpc, cfg!(feature = "grpc"));
+        assert_eq!(info.features.contains(&"grpc".to_string()), info.grpc);
+    }
+
+    #[tokio::test]
+    async fn refresh_rejects_unknown_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = serve_fleet(0, dir.path()).await;
+        let base = url.trim_end_matches("/api/v1/fleet/nodes/test-node/report");
+
+        let resp = reqwest::Client::new()
+            .post(format!("{}/api/v1/report/refresh?sections=health,bogus", base))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        assert!(resp.text().await.unwrap().contains("'bogus'"));
+    }
+
+    #[tokio::test]
+    async fn fleet_nodes_filters_by_tag_and_label() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = serve_fleet(0, dir.path()).await;
+        let base = url.trim_end_matches("/test-node/report");
+        let http = reqwest::Client::new();
+        for (host, tag, role) in [("web-1", "prod", "server"), ("web-2", "staging", "server")] {
+            let mut report = make_test_report();
+            report.hostname = host.to_string();
+            report.declared.tags = vec![tag.to_string()];
+            report.declared.labels.insert("role".to_string(), role.to_string());
+            let resp = http
+                .post(format!("{}/{}/report", base, host))
+                .json(&StoredReport::new(report))
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+
+        let nodes: Vec<serde_json::Value> = http
+            .get(format!("{}?tag=prod&label=role%3Dserver", base))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0]["hostname"], "web-1");
+
+        let resp = http.get(format!("{}?label=role", base)).send().await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn responses_carry_request_id() {
+        let app = with_request_tracing(Router::new().route("/ping", get(|| async { "pong" })));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ping", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let http = reqwest::Client::new();
+
+        let resp = http.get(&url).send().await.unwrap();
+        let generated = resp.headers()["x-request-id"].to_str().unwrap().to_string();
+        assert_eq!(generated.len(), 36, "expected a UUID, got {}", generated);
+
+        let resp = http
+            .get(&url)
+            .header("x-request-id", "fleet-apply-42")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.headers()["x-request-id"], "fleet-apply-42");
+    }
+
+    #[tokio::test]
+    async fn cors_allows_configured_origins_only() {
+        let ping = || Router::new().route("/ping", get(|| async { "pong" }));
+        let origins = vec!["https://dash.example.com/".to_string()];
+        let app = with_cors(ping(), &origins).unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/ping", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let http = reqwest::Client::new();
+
+        let resp = http
+            .get(&url)
+            .header("origin", "https://dash.example.com")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            resp.headers()["access-control-allow-origin"],
+            "https://dash.example.com"
+        );
+
+        let resp = http
+            .get(&url)
+            .header("origin", "https://evil.example.com")
+            .send()
+            .await
+            .unwrap();
+        assert!(!resp.headers().contains_key("access-control-allow-origin"));
+
+        // Annotating a node from a dashboard is a PUT.
+        let resp = http
+            .request(reqwest::Method::OPTIONS, &url)
+            .header("origin", "https://dash.example.com")
+            .header("access-control-request-method", "PUT")
+            .send()
+            .await
+            .unwrap();
+        let allowed = resp.headers()["access-control-allow-methods"]
+            .to_str()
+            .unwrap();
+        assert!(allowed.contains("PUT"), "{}", allowed);
+
+        assert!(with_cors(ping(), &["bad\norigin".to_string()]).is_err());
+    }
+
+    /// Gzip `data` using stored (uncompressed) deflate blocks.
+    fn gzip_stored(data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+        let mut chunks = data.chunks(u16::MAX as usize).peekable();
+        if chunks.peek().is_none() {
+            out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+        }
+        while let Some(chunk) = chunks.next() {
+            let len = chunk.len() as u16;
+            out.push(chunks.peek().is_none() as u8);
+            out.extend_from_slice(&len.to_le_bytes());
+            out.extend_from_slice(&(!len).to_le_bytes());
+            out.extend_from_slice(chunk);
+        }
+        let crc = data.iter().fold(!0u32, |mut crc, &byte| {
+            crc ^= byte as u32;
+            for _ in 0..8 {
+                crc = (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg());
+            }
+            crc
+        });
+        out.extend_from_slice(&(!crc).to_le_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        out
+    }
+
+    #[tokio::test]
+    async fn fleet_ingest_accepts_gzip_and_limits_decoded_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = serve_fleet(64 * 1024, dir.path()).await;
+        let http = reqwest::Client::new();
+
+        let body = serde_json::to_vec(&StoredReport::new(make_test_report())).unwrap();
+        let resp = http
+            .post(&url)
+            .header("content-type", "application/json")
+            .header("content-encoding", "gzip")
+            .body(gzip_stored(&body))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        // Padding the JSON pushes the decoded size over the limit.
+        let mut padded = body.clone();
+        padded.extend(std::iter::repeat_n(b' ', 64 * 1024));
+        let resp = http
+            .post(&url)
+            .header("content-type", "application/json")
+            .header("content-encoding", "gzip")
+            .body(gzip_stored(&padded))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+}
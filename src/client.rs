@@ -1,19 +1,85 @@
 //! Typed HTTP client for the kindling daemon REST API.
 
 use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::{bail, Context, Result};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
 use reqwest::Client;
 
-use crate::config::NodeTarget;
+use crate::config::{Config, NodeTarget};
 use crate::domain::node_report::StoredReport;
 use crate::domain::types::{
-    CacheInfo, DaemonHealth, GcResult, GcStatus, NixConfig, NixStatus, OptimiseResult, PlatformInfo,
-    StoreInfo,
+    BuildInfo, CacheInfo, DaemonHealth, GcOptions, GcResult, GcRoot, GcStatus, NixConfig,
+    NixStatus, OptimiseEstimate, OptimiseResult, PlatformInfo, StoreInfo, StoreVerifyResult,
 };
-use crate::node_identity::NodeIdentity;
+use crate::node_identity::{FleetPeer, NodeIdentity};
+use crate::paths::expand_path;
 
 const DEFAULT_BASE_URL: &str = "http://127.0.0.1:9100";
+/// Request timeout for calls without a more specific one.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Requests over a Unix socket still need an HTTP URL; the host is ignored.
+const UNIX_BASE_URL: &str = "http://localhost";
+
+/// Bearer token sent with every API request when set, for daemons with
+/// `daemon.auth.tokens` configured.
+pub const TOKEN_ENV: &str = "KINDLING_API_TOKEN";
+
+/// Client builder that sends the `KINDLING_API_TOKEN` bearer token, if any,
+/// and verifies `https://` servers as `tls` says.
+pub fn api_client_builder(tls: &ClientTls) -> Result<reqwest::ClientBuilder> {
+    let builder = tls.apply(Client::builder())?;
+    let token = match std::env::var(TOKEN_ENV) {
+        Ok(token) if !token.is_empty() => token,
+        _ => return Ok(builder),
+    };
+    let mut value = HeaderValue::from_str(&format!("Bearer {}", token))
+        .with_context(|| format!("{} is not a valid header value", TOKEN_ENV))?;
+    value.set_sensitive(true);
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, value);
+    Ok(builder.default_headers(headers))
+}
+
+/// How to verify `https://` daemons beyond the system trust roots.
+#[derive(Debug, Clone, Default)]
+pub struct ClientTls {
+    /// Extra PEM CA certificate to trust, e.g. a self-signed daemon cert.
+    pub ca_cert: Option<PathBuf>,
+    /// Accept any server certificate. Only for testing.
+    pub insecure: bool,
+}
+
+impl ClientTls {
+    /// `tls.ca_cert` from the config file.
+    pub fn from_config(cfg: &Config) -> Self {
+        Self {
+            ca_cert: cfg.tls.ca_cert.as_deref().map(expand_path),
+            insecure: false,
+        }
+    }
+
+    /// Fill a CA certificate not given on the command line from the config.
+    pub fn or_config(mut self, cfg: &Config) -> Self {
+        if self.ca_cert.is_none() {
+            self.ca_cert = Self::from_config(cfg).ca_cert;
+        }
+        self
+    }
+
+    fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        if let Some(ref path) = self.ca_cert {
+            let pem = std::fs::read(path)
+                .with_context(|| format!("reading CA certificate {}", path.display()))?;
+            let cert = reqwest::Certificate::from_pem(&pem)
+                .with_context(|| format!("parsing CA certificate {}", path.display()))?;
+            builder = builder.add_root_certificate(cert);
+        }
+        Ok(builder.danger_accept_invalid_certs(self.insecure))
+    }
+}
 
 pub struct KindlingClient {
     base_url: String,
@@ -21,11 +87,16 @@ pub struct KindlingClient {
 }
 
 impl KindlingClient {
-    pub fn new(base_url: &str) -> Result<Self> {
-        let http = Client::builder()
-            .timeout(std::time::Duration::from_secs(10))
-            .build()
-            .context("building HTTP client")?;
+    /// `base_url` is `http(s)://host:port` or `unix:///path/to/socket`.
+    /// `timeout` bounds each request from connect to the end of the body;
+    /// `tls` says how to verify an `https://` daemon.
+    pub fn new(base_url: &str, timeout: Duration, tls: &ClientTls) -> Result<Self> {
+        let builder = api_client_builder(tls)?.timeout(timeout);
+        let (builder, base_url) = match base_url.strip_prefix("unix://") {
+            Some(socket) => (with_unix_socket(builder, socket)?, UNIX_BASE_URL),
+            None => (builder, base_url),
+        };
+        let http = builder.build().context("building HTTP client")?;
         Ok(Self {
             base_url: base_url.trim_end_matches('/').to_string(),
             http,
@@ -33,17 +104,25 @@ impl KindlingClient {
     }
 
     /// Resolve a client from the nodes map.
-    /// `None` name → localhost default. `Some(name)` → look up in nodes map.
+    /// `None` name → localhost default. `Some(name)` → look up in nodes map,
+    /// falling back to `url_template` when the name isn't listed there.
     pub fn from_node(
         name: Option<&str>,
         nodes: &BTreeMap<String, NodeTarget>,
+        url_template: Option<&str>,
+        peers: &[FleetPeer],
+        timeout: Duration,
+        tls: &ClientTls,
     ) -> Result<Self> {
         match name {
-            None => Self::new(DEFAULT_BASE_URL),
-            Some(n) => match nodes.get(n) {
-                Some(target) => Self::new(&target.url),
-                None => bail!(
-                    "node '{}' not found in config. Available nodes: {}",
+            None => Self::new(DEFAULT_BASE_URL, timeout, tls),
+            Some(n) => match (nodes.get(n), url_template) {
+                (Some(target), _) => Self::new(&target.url, timeout, tls),
+                (None, Some(template)) => {
+                    Self::new(&expand_url_template(template, n, peers), timeout, tls)
+                }
+                (None, None) => bail!(
+                    "node '{}' not found in config and no node_url_template set. Available nodes: {}",
                     n,
                     if nodes.is_empty() {
                         "(none configured)".to_string()
@@ -55,10 +134,34 @@ impl KindlingClient {
         }
     }
 
+    /// Resolve a client using the loaded config. Fleet peers are read from
+    /// node.yaml (best-effort) only when the template needs `{hostname}`.
+    pub fn from_config(
+        name: Option<&str>,
+        cfg: &Config,
+        timeout: Duration,
+        tls: &ClientTls,
+    ) -> Result<Self> {
+        let template = cfg.node_url_template.as_deref();
+        let peers = match template {
+            Some(t) if name.is_some() && t.contains("{hostname}") => {
+                NodeIdentity::load(&NodeIdentity::default_path())
+                    .map(|identity| identity.fleet.peers)
+                    .unwrap_or_default()
+            }
+            _ => Vec::new(),
+        };
+        Self::from_node(name, &cfg.nodes, template, &peers, timeout, tls)
+    }
+
     pub async fn health(&self) -> Result<DaemonHealth> {
         self.get("/health").await
     }
 
+    pub async fn version(&self) -> Result<BuildInfo> {
+        self.get("/api/v1/version").await
+    }
+
     pub async fn status(&self) -> Result<NixStatus> {
         self.get("/api/v1/status").await
     }
@@ -71,6 +174,43 @@ impl KindlingClient {
         self.get("/api/v1/store").await
     }
 
+    pub async fn gc_roots(&self, include_transient: bool) -> Result<Vec<GcRoot>> {
+        if include_transient {
+            self.get("/api/v1/store/roots?include_transient=true").await
+        } else {
+            self.get("/api/v1/store/roots").await
+        }
+    }
+
+    pub async fn why_depends(&self, from: &str, to: &str) -> Result<Vec<String>> {
+        let url = format!("{}/api/v1/store/why-depends", self.base_url);
+        let resp = self
+            .http
+            .get(&url)
+            .query(&[("from", from), ("to", to)])
+            .send()
+            .await
+            .with_context(|| format!("GET {}", url))?;
+
+        if !resp.status().is_success() {
+            bail!(
+                "{} returned {}: {}",
+                url,
+                resp.status(),
+                resp.text().await.unwrap_or_default()
+            );
+        }
+
+        resp.json()
+            .await
+            .with_context(|| format!("parsing response from {}", url))
+    }
+
+    /// Store paths in the closure of the daemon host's current system.
+    pub async fn system_closure(&self) -> Result<Vec<String>> {
+        self.get("/api/v1/store/system-closure").await
+    }
+
     pub async fn nix_config(&self) -> Result<NixConfig> {
         self.get("/api/v1/config").await
     }
@@ -79,14 +219,59 @@ impl KindlingClient {
         self.get("/api/v1/gc").await
     }
 
-    pub async fn gc_run(&self) -> Result<GcResult> {
-        self.post("/api/v1/gc/run").await
+    pub async fn gc_run(&self, options: &GcOptions) -> Result<GcResult> {
+        let mut params = Vec::new();
+        if let Some(ref age) = options.older_than {
+            params.push(format!("older_than={}", age));
+        }
+        if let Some(max) = options.max_freed {
+            params.push(format!("max_freed={}", max));
+        }
+        if params.is_empty() {
+            self.post("/api/v1/gc/run").await
+        } else {
+            self.post(&format!("/api/v1/gc/run?{}", params.join("&")))
+                .await
+        }
+    }
+
+    /// Verify `paths`, or the whole store when empty.
+    pub async fn store_verify(&self, paths: &[String], repair: bool) -> Result<StoreVerifyResult> {
+        let mut params: Vec<(&str, &str)> = paths.iter().map(|p| ("path", p.as_str())).collect();
+        if repair {
+            params.push(("repair", "true"));
+        }
+        let url = format!("{}/api/v1/store/verify", self.base_url);
+        let resp = self
+            .http
+            .post(&url)
+            .query(&params)
+            .send()
+            .await
+            .with_context(|| format!("POST {}", url))?;
+
+        if !resp.status().is_success() {
+            bail!(
+                "{} returned {}: {}",
+                url,
+                resp.status(),
+                resp.text().await.unwrap_or_default()
+            );
+        }
+
+        resp.json()
+            .await
+            .with_context(|| format!("parsing response from {}", url))
     }
 
     pub async fn optimise(&self) -> Result<OptimiseResult> {
         self.post("/api/v1/store/optimise").await
     }
 
+    pub async fn optimise_estimate(&self) -> Result<OptimiseEstimate> {
+        self.get("/api/v1/store/optimise/estimate").await
+    }
+
     pub async fn caches(&self) -> Result<Vec<CacheInfo>> {
         self.get("/api/v1/caches").await
     }
@@ -142,27 +327,124 @@ impl KindlingClient {
     }
 }
 
+#[cfg(unix)]
+fn with_unix_socket(
+    builder: reqwest::ClientBuilder,
+    socket: &str,
+) -> Result<reqwest::ClientBuilder> {
+    if socket.is_empty() {
+        bail!("unix:// URL is missing a socket path");
+    }
+    Ok(builder.unix_socket(socket))
+}
+
+#[cfg(not(unix))]
+fn with_unix_socket(
+    _builder: reqwest::ClientBuilder,
+    _socket: &str,
+) -> Result<reqwest::ClientBuilder> {
+    bail!("unix:// URLs are only supported on Unix platforms")
+}
+
+/// Substitute `{name}` and `{hostname}` in a node URL template.
+/// `{hostname}` comes from the matching fleet peer, or the name itself.
+fn expand_url_template(template: &str, name: &str, peers: &[FleetPeer]) -> String {
+    let hostname = peers
+        .iter()
+        .find(|p| p.name == name)
+        .map(|p| p.hostname.as_str())
+        .unwrap_or(name);
+    template
+        .replace("{name}", name)
+        .replace("{hostname}", hostname)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::config::NodeTarget;
 
+    const TLS: ClientTls = ClientTls {
+        ca_cert: None,
+        insecure: false,
+    };
+
+    #[test]
+    fn ca_cert_flag_overrides_config() {
+        let mut cfg = Config::default();
+        cfg.tls.ca_cert = Some("/etc/kindling/ca.pem".to_string());
+        assert_eq!(
+            ClientTls::from_config(&cfg).ca_cert,
+            Some(PathBuf::from("/etc/kindling/ca.pem"))
+        );
+        assert_eq!(
+            TLS.clone().or_config(&cfg).ca_cert,
+            Some(PathBuf::from("/etc/kindling/ca.pem"))
+        );
+        let flag = ClientTls {
+            ca_cert: Some(PathBuf::from("/tmp/other.pem")),
+            insecure: false,
+        };
+        assert_eq!(
+            flag.or_config(&cfg).ca_cert,
+            Some(PathBuf::from("/tmp/other.pem"))
+        );
+        // A missing CA file fails when the client is built, not silently.
+        assert!(api_client_builder(&ClientTls::from_config(&cfg)).is_err());
+    }
+
     #[test]
     fn new_strips_trailing_slash() {
-        let client = KindlingClient::new("http://example.com:9100/").unwrap();
+        let client =
+            KindlingClient::new("http://example.com:9100/", DEFAULT_TIMEOUT, &TLS).unwrap();
         assert_eq!(client.base_url, "http://example.com:9100");
     }
 
     #[test]
     fn new_preserves_url_without_trailing_slash() {
-        let client = KindlingClient::new("http://example.com:9100").unwrap();
+        let client = KindlingClient::new("http://example.com:9100", DEFAULT_TIMEOUT, &TLS).unwrap();
         assert_eq!(client.base_url, "http://example.com:9100");
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn new_unix_socket_url() {
+        let client =
+            KindlingClient::new("unix:///run/kindling/api.sock", DEFAULT_TIMEOUT, &TLS).unwrap();
+        assert_eq!(client.base_url, UNIX_BASE_URL);
+        assert!(KindlingClient::new("unix://", DEFAULT_TIMEOUT, &TLS).is_err());
+    }
+
+    #[tokio::test]
+    async fn new_applies_configured_timeout() {
+        // Accepts connections but never answers.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                held.push(stream);
+            }
+        });
+
+        let client = KindlingClient::new(
+            &format!("http://{}", addr),
+            Duration::from_millis(200),
+            &TLS,
+        )
+        .unwrap();
+        let started = std::time::Instant::now();
+        let err = client.health().await.unwrap_err();
+        assert!(started.elapsed() < DEFAULT_TIMEOUT);
+        let source = err.downcast_ref::<reqwest::Error>().unwrap();
+        assert!(source.is_timeout(), "{:#}", err);
+    }
+
     #[test]
     fn from_node_none_uses_default() {
         let nodes = BTreeMap::new();
-        let client = KindlingClient::from_node(None, &nodes).unwrap();
+        let client =
+            KindlingClient::from_node(None, &nodes, None, &[], DEFAULT_TIMEOUT, &TLS).unwrap();
         assert_eq!(client.base_url, DEFAULT_BASE_URL);
     }
 
@@ -176,7 +458,9 @@ mod tests {
                 description: Some("Production".to_string()),
             },
         );
-        let client = KindlingClient::from_node(Some("prod"), &nodes).unwrap();
+        let client =
+            KindlingClient::from_node(Some("prod"), &nodes, None, &[], DEFAULT_TIMEOUT, &TLS)
+                .unwrap();
         assert_eq!(client.base_url, "https://prod.example.com:9100");
     }
 
@@ -197,7 +481,8 @@ mod tests {
                 description: None,
             },
         );
-        let result = KindlingClient::from_node(Some("dev"), &nodes);
+        let result =
+            KindlingClient::from_node(Some("dev"), &nodes, None, &[], DEFAULT_TIMEOUT, &TLS);
         assert!(result.is_err());
         let msg = result.err().unwrap().to_string();
         assert!(msg.contains("dev"));
@@ -209,9 +494,87 @@ mod tests {
     #[test]
     fn from_node_not_found_empty_map() {
         let nodes = BTreeMap::new();
-        let result = KindlingClient::from_node(Some("ghost"), &nodes);
+        let result =
+            KindlingClient::from_node(Some("ghost"), &nodes, None, &[], DEFAULT_TIMEOUT, &TLS);
         assert!(result.is_err());
         let msg = result.err().unwrap().to_string();
         assert!(msg.contains("none configured"));
     }
+
+    fn peer(name: &str, hostname: &str) -> FleetPeer {
+        FleetPeer {
+            name: name.to_string(),
+            hostname: hostname.to_string(),
+            ssh_user: "root".to_string(),
+        }
+    }
+
+    #[test]
+    fn from_node_uses_template_for_unlisted_name() {
+        let nodes = BTreeMap::new();
+        let client = KindlingClient::from_node(
+            Some("web-1"),
+            &nodes,
+            Some("http://{name}.internal:9100"),
+            &[],
+            DEFAULT_TIMEOUT,
+            &TLS,
+        )
+        .unwrap();
+        assert_eq!(client.base_url, "http://web-1.internal:9100");
+    }
+
+    #[test]
+    fn from_node_explicit_entry_beats_template() {
+        let mut nodes = BTreeMap::new();
+        nodes.insert(
+            "prod".to_string(),
+            NodeTarget {
+                url: "https://prod.example.com:9100".to_string(),
+                description: None,
+            },
+        );
+        let client = KindlingClient::from_node(
+            Some("prod"),
+            &nodes,
+            Some("http://{name}.internal:9100"),
+            &[],
+            DEFAULT_TIMEOUT,
+            &TLS,
+        )
+        .unwrap();
+        assert_eq!(client.base_url, "https://prod.example.com:9100");
+    }
+
+    #[test]
+    fn from_node_none_ignores_template() {
+        let nodes = BTreeMap::new();
+        let client = KindlingClient::from_node(
+            None,
+            &nodes,
+            Some("http://{name}.internal:9100"),
+            &[],
+            DEFAULT_TIMEOUT,
+            &TLS,
+        )
+        .unwrap();
+        assert_eq!(client.base_url, DEFAULT_BASE_URL);
+    }
+
+    #[test]
+    fn expand_url_template_hostname_from_peers() {
+        let peers = vec![peer("db", "10.0.0.5")];
+        assert_eq!(
+            expand_url_template("http://{hostname}:9100/{name}", "db", &peers),
+            "http://10.0.0.5:9100/db"
+        );
+    }
+
+    #[test]
+    fn expand_url_template_hostname_falls_back_to_name() {
+        assert_eq!(
+            expand_url_template("http://{hostname}:9100", "cache", &[peer("db", "10.0.0.5")]),
+            "http://cache:9100"
+        );
+    }
 }
@@ -7,9 +7,134 @@ use anyhow::{bail, Context, Result};
 use colored::Colorize;
 use std::process::Command;
 
-use crate::node_identity::{self, nix_gen};
+use crate::commands::{history, profile};
+use crate::node_identity::{self, nix_gen, SshBuilderConfig};
+use crate::output::{self, progress};
+use crate::paths::expand_path;
+use crate::secret::Secret;
 
-pub fn run(diff_only: bool) -> Result<()> {
+/// `--build-host` value that builds locally even when node.yaml names a
+/// builder.
+const LOCAL_BUILD_HOST: &str = "local";
+
+/// Resolved system rebuild invocation for a profile.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RebuildPlan {
+    /// Rebuild binary (`darwin-rebuild`, `nixos-rebuild`, or an override).
+    pub cmd: String,
+    /// Whether the target is nix-darwin (controls SIGTERM masking).
+    pub is_darwin: bool,
+}
+
+/// Map a profile to its rebuild command via the profile registry.
+///
+/// Known profiles must target the running OS (`host_platform`). Profiles
+/// not in the registry (custom kindling-profiles entries) are assumed to
+/// target the host. `rebuild_cmd` replaces the derived command but not the
+/// platform check.
+pub fn resolve_rebuild(
+    profile_name: &str,
+    rebuild_cmd: Option<&str>,
+    host_platform: &str,
+) -> Result<RebuildPlan> {
+    let platform = match profile::find_profile(profile_name) {
+        Some(p) => {
+            if p.platform != host_platform {
+                bail!(
+                    "profile '{}' targets {} but this host is {}\n   \
+                     Pass --profile to select a {} profile.",
+                    p.name,
+                    p.platform,
+                    host_platform,
+                    host_platform
+                );
+            }
+            p.platform
+        }
+        None => host_platform,
+    };
+
+    let cmd = match rebuild_cmd {
+        Some(cmd) => cmd.to_string(),
+        None => profile::rebuild_command(platform)
+            .with_context(|| format!("no rebuild command known for platform '{platform}'"))?
+            .to_string(),
+    };
+
+    Ok(RebuildPlan {
+        cmd,
+        is_darwin: platform == "darwin",
+    })
+}
+
+/// Remote machine the rebuild offloads its builds to.
+#[derive(Debug, PartialEq, Eq)]
+pub struct BuildHost {
+    /// SSH destination, e.g. `builder.example.com` or `nix@builder`.
+    pub host: String,
+    pub identity_file: Option<String>,
+}
+
+/// `--build-host` if given (`local` disables offloading), else the
+/// identity's `network.ssh.builder`. The builder's identity file is used
+/// whenever the host names that builder.
+pub fn resolve_build_host(
+    flag: Option<&str>,
+    builder: Option<&SshBuilderConfig>,
+) -> Option<BuildHost> {
+    let host = match flag {
+        Some(LOCAL_BUILD_HOST) => return None,
+        Some(host) => host.to_string(),
+        None => builder?.fqdn.clone(),
+    };
+    let destination = host.rsplit('@').next().unwrap_or(&host);
+    let identity_file = builder
+        .filter(|b| destination == b.fqdn || destination == b.hostname)
+        .and_then(|b| b.identity_file.clone());
+    Some(BuildHost {
+        host,
+        identity_file,
+    })
+}
+
+/// Rebuild arguments that send every build to `build_host`: a single
+/// `--builders` machine spec for `system` and `--max-jobs 0` so nothing
+/// builds locally. The builder fetches from substituters itself.
+fn builder_args(build_host: &BuildHost, system: &str) -> Vec<String> {
+    let key = build_host
+        .identity_file
+        .as_deref()
+        .map(|f| expand_path(f).display().to_string())
+        .unwrap_or_else(|| "-".to_string());
+    vec![
+        "--builders".to_string(),
+        format!("ssh://{} {} {}", build_host.host, system, key),
+        "--max-jobs".to_string(),
+        "0".to_string(),
+        "--option".to_string(),
+        "builders-use-substitutes".to_string(),
+        "true".to_string(),
+    ]
+}
+
+/// Nix system double for this machine, e.g. `aarch64-darwin`.
+fn local_system() -> String {
+    let os = match std::env::consts::OS {
+        "macos" => "darwin",
+        other => other,
+    };
+    format!("{}-{}", std::env::consts::ARCH, os)
+}
+
+pub fn run(
+    diff_only: bool,
+    no_activate: bool,
+    switch_on_success_only: bool,
+    out_link: Option<&std::path::Path>,
+    profile_override: Option<&str>,
+    rebuild_cmd: Option<&str>,
+    build_host: Option<&str>,
+) -> Result<()> {
     let node_path = node_identity::NodeIdentity::default_path();
 
     if !node_path.exists() {
@@ -21,39 +146,92 @@ pub fn run(diff_only: bool) -> Result<()> {
         );
     }
 
-    println!("{} Reading {}", ">>".blue().bold(), node_path.display());
-    let identity = node_identity::NodeIdentity::load(&node_path)?;
+    progress!("{} Reading {}", ">>".blue().bold(), node_path.display());
+    let mut identity = node_identity::NodeIdentity::load(&node_path)?;
+    if let Some(name) = profile_override {
+        identity.profile = name.to_string();
+    }
+    let plan = resolve_rebuild(&identity.profile, rebuild_cmd, profile::host_platform())?;
+    let build_host = resolve_build_host(build_host, identity.network.ssh.builder.as_ref());
 
-    println!(
+    progress!(
         "{} Profile: {}, Hostname: {}, User: {}",
         "ok".green().bold(),
         identity.profile,
         identity.hostname,
         identity.user.name
     );
-    println!();
+    if let Some(ref bh) = build_host {
+        progress!("{} Building on {}", "::".blue().bold(), bh.host);
+    }
+    progress!();
 
     // Generate Nix files
-    println!("{} Generating Nix configuration", ">>".blue().bold());
+    progress!("{} Generating Nix configuration", ">>".blue().bold());
     let gen_dir = nix_gen::generate(&identity)?;
-    println!(
+    progress!(
         "{} Generated files in {}",
         "ok".green().bold(),
         gen_dir.display()
     );
-    println!();
+    progress!();
 
     if diff_only {
-        println!("{} Diff mode — showing what would change", ">>".blue().bold());
-        run_rebuild_diff(&identity, &gen_dir)?;
+        progress!("{} Diff mode — showing what would change", ">>".blue().bold());
+        run_rebuild_diff(&identity, &gen_dir, &plan, build_host.as_ref())?;
+    } else if no_activate {
+        let out_link = out_link
+            .map(std::path::Path::to_path_buf)
+            .unwrap_or_else(|| gen_dir.join("result"));
+        progress!("{} Building system configuration", ">>".blue().bold());
+        let store_path = run_build(&identity, &gen_dir, &plan, build_host.as_ref(), &out_link)?;
+        progress!();
+        // The build result stays on stdout under --quiet.
+        println!(
+            "{} Built {} -> {}",
+            "ok".green().bold(),
+            out_link.display(),
+            store_path.display()
+        );
+    } else if switch_on_success_only {
+        let result = run_build_then_switch(&identity, &gen_dir, &plan, build_host.as_ref());
+        record_history(&node_path, &identity, &result);
+        result?;
     } else {
-        println!("{} Applying system configuration", ">>".blue().bold());
-        run_rebuild(&identity, &gen_dir)?;
+        progress!("{} Applying system configuration", ">>".blue().bold());
+        let result = run_rebuild(&identity, &gen_dir, &plan, build_host.as_ref());
+        record_history(&node_path, &identity, &result);
+        result?;
     }
 
     Ok(())
 }
 
+/// Append the outcome of an apply to the history log. Failing to record
+/// history never fails the apply itself.
+fn record_history(
+    node_path: &std::path::Path,
+    identity: &node_identity::NodeIdentity,
+    result: &Result<()>,
+) {
+    let entry = history::ApplyHistoryEntry {
+        timestamp: chrono::Utc::now(),
+        profile: identity.profile.clone(),
+        hostname: identity.hostname.clone(),
+        node_checksum: history::file_checksum(node_path).unwrap_or_default(),
+        system_path: history::current_system_path(),
+        success: result.is_ok(),
+        error: result.as_ref().err().map(|e| e.to_string()),
+    };
+    if let Err(e) = history::append(&history::history_path(), &entry) {
+        eprintln!(
+            "{} Could not record apply history: {}",
+            "!!".yellow().bold(),
+            e
+        );
+    }
+}
+
 /// Run a full rebuild from a node.yaml path, with an optional context label
 /// printed before the rebuild command (e.g. `"[bootstrap: nix_rebuild_running]"`).
 pub fn run_rebuild_from_path_with_context(
@@ -61,41 +239,60 @@ pub fn run_rebuild_from_path_with_context(
     context: Option<&str>,
 ) -> Result<()> {
     if let Some(ctx) = context {
-        println!(
+        progress!(
             "{} Bootstrap phase: {}",
             "::".blue().bold(),
             ctx,
         );
     }
     let identity = node_identity::NodeIdentity::load(node_path)?;
+    let plan = resolve_rebuild(&identity.profile, None, profile::host_platform())?;
     let gen_dir = nix_gen::generate(&identity)?;
-    run_rebuild(&identity, &gen_dir)
+    run_rebuild(&identity, &gen_dir, &plan, None)
+}
+
+/// GitHub token for private flake inputs: `/etc/nix/github-access-token`,
+/// else the `github_token` secret from the node's secrets provider.
+fn github_access_token(identity: &node_identity::NodeIdentity) -> Option<Secret> {
+    let from_file = std::fs::read_to_string("/etc/nix/github-access-token")
+        .ok()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+        .map(Secret::new);
+    from_file.or_else(|| {
+        node_identity::secrets::resolve(&identity.secrets, "github_token").ok()
+    })
 }
 
-fn run_rebuild(identity: &node_identity::NodeIdentity, gen_dir: &std::path::Path) -> Result<()> {
-    let is_darwin = matches!(identity.profile.as_str(), "macos-developer");
+fn run_rebuild(
+    identity: &node_identity::NodeIdentity,
+    gen_dir: &std::path::Path,
+    plan: &RebuildPlan,
+    build_host: Option<&BuildHost>,
+) -> Result<()> {
+    let is_darwin = plan.is_darwin;
     let flake_ref = format!("{}#{}", gen_dir.display(), identity.hostname);
 
-    let cmd = if is_darwin { "darwin-rebuild" } else { "nixos-rebuild" };
-    let mut args = vec!["switch".to_string(), "--flake".to_string(), flake_ref.clone()];
+    let cmd = plan.cmd.as_str();
+    let mut args = switch_args(&flake_ref);
+    if let Some(bh) = build_host {
+        args.extend(builder_args(bh, &local_system()));
+    }
 
     // Inject GitHub access token for private flake inputs if available.
     // Uses --option to pass directly to nix — NIX_CONFIG env var is NOT
     // inherited by the nix daemon, so env-based injection doesn't work.
-    let token_path = std::path::Path::new("/etc/nix/github-access-token");
-    if token_path.exists() {
-        if let Ok(token) = std::fs::read_to_string(token_path) {
-            let token = token.trim();
-            if !token.is_empty() {
-                args.push("--option".to_string());
-                args.push("access-tokens".to_string());
-                args.push(format!("github.com={token}"));
-                println!(
-                    "{} Injecting GitHub access-tokens via --option for private flake inputs",
-                    "::".blue().bold()
-                );
-            }
-        }
+    // The logged command line shows the token as `[redacted]`.
+    let mut shown = args.join(" ");
+    if let Some(token) = github_access_token(identity) {
+        args.push("--option".to_string());
+        args.push("access-tokens".to_string());
+        args.push(format!("github.com={}", token.expose()));
+        shown.push_str(&format!(" --option access-tokens github.com={}", token));
+        progress!(
+            "{} Injecting GitHub access-tokens via --option for private flake inputs",
+            "::".blue().bold()
+        );
     }
 
     let arg_refs: Vec<&str> = args.iter().map(String::as_str).collect();
@@ -105,35 +302,33 @@ fn run_rebuild(identity: &node_identity::NodeIdentity, gen_dir: &std::path::Path
     let status = if !is_darwin {
         // Mask SIGTERM so switch-to-configuration can't kill us
         unsafe { libc::signal(libc::SIGTERM, libc::SIG_IGN); }
-        println!(
+        progress!(
             "{} Running: {} {} (SIGTERM masked)",
             ">>".blue().bold(),
             cmd,
-            args.join(" ")
+            shown
         );
         let result = Command::new(cmd)
             .args(&arg_refs)
+            .stdout(output::child_stdout())
             .status()
             .with_context(|| format!("failed to run {cmd}"));
         // Restore default SIGTERM handling after rebuild
         unsafe { libc::signal(libc::SIGTERM, libc::SIG_DFL); }
         result?
     } else {
-        println!(
-            "{} Running: {} {}",
-            ">>".blue().bold(),
-            cmd,
-            args.join(" ")
-        );
+        progress!("{} Running: {} {}", ">>".blue().bold(), cmd, shown);
         Command::new(cmd)
             .args(&arg_refs)
+            .stdout(output::child_stdout())
             .status()
             .with_context(|| format!("failed to run {cmd}"))?
     };
 
     if status.success() {
-        println!();
-        println!(
+        record_activation(gen_dir);
+        progress!();
+        progress!(
             "{} System configuration applied successfully",
             "ok".green().bold()
         );
@@ -144,23 +339,246 @@ fn run_rebuild(identity: &node_identity::NodeIdentity, gen_dir: &std::path::Path
     Ok(())
 }
 
+/// Mark the generated flake as what `/run/current-system` now runs, so the
+/// report only pairs its lock with the system built from it. Best-effort.
+fn record_activation(gen_dir: &std::path::Path) {
+    let Ok(system) = std::fs::canonicalize("/run/current-system") else {
+        return;
+    };
+    if let Err(e) = nix_gen::record_activation(gen_dir, &system) {
+        eprintln!(
+            "{} Could not record activation: {:#}",
+            "!!".yellow().bold(),
+            e
+        );
+    }
+}
+
+/// Arguments for a rebuild that builds and activates in one command.
+fn switch_args(flake_ref: &str) -> Vec<String> {
+    vec![
+        "switch".to_string(),
+        "--flake".to_string(),
+        flake_ref.to_string(),
+    ]
+}
+
+/// System profile that a switch points at the new generation.
+const SYSTEM_PROFILE: &str = "/nix/var/nix/profiles/system";
+
+/// Commands that make the built `system` the running configuration, as
+/// `nixos-rebuild switch` / `darwin-rebuild switch` do once they have
+/// built: set the system profile to it, then run its activation.
+fn activation_commands(plan: &RebuildPlan, system: &std::path::Path) -> [Vec<String>; 2] {
+    let set_profile = vec![
+        "nix-env".to_string(),
+        "-p".to_string(),
+        SYSTEM_PROFILE.to_string(),
+        "--set".to_string(),
+        system.display().to_string(),
+    ];
+    let activate = if plan.is_darwin {
+        vec![system.join("activate").display().to_string()]
+    } else {
+        vec![
+            system
+                .join("bin/switch-to-configuration")
+                .display()
+                .to_string(),
+            "switch".to_string(),
+        ]
+    };
+    [set_profile, activate]
+}
+
+/// Activate the already-built `system` without re-evaluating the flake.
+fn activate_built_system(plan: &RebuildPlan, system: &std::path::Path) -> Result<()> {
+    for argv in activation_commands(plan, system) {
+        progress!("{} Running: {}", ">>".blue().bold(), argv.join(" "));
+        // As in run_rebuild: switch-to-configuration may SIGTERM us.
+        if !plan.is_darwin {
+            unsafe { libc::signal(libc::SIGTERM, libc::SIG_IGN); }
+        }
+        let result = Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdout(output::child_stdout())
+            .status()
+            .with_context(|| format!("failed to run {}", argv[0]));
+        if !plan.is_darwin {
+            unsafe { libc::signal(libc::SIGTERM, libc::SIG_DFL); }
+        }
+        let status = result?;
+        if !status.success() {
+            bail!("{} exited with status {}", argv[0], status);
+        }
+    }
+    progress!();
+    progress!(
+        "{} System configuration applied successfully",
+        "ok".green().bold()
+    );
+    Ok(())
+}
+
+/// `--switch-on-success-only`: build without touching the running system,
+/// then, only once the build has succeeded, activate exactly the store path
+/// that was built.
+fn run_build_then_switch(
+    identity: &node_identity::NodeIdentity,
+    gen_dir: &std::path::Path,
+    plan: &RebuildPlan,
+    build_host: Option<&BuildHost>,
+) -> Result<()> {
+    progress!(
+        "{} Phase 1/2: building system configuration",
+        ">>".blue().bold()
+    );
+    let out_link = gen_dir.join("result");
+    let store_path = run_build(identity, gen_dir, plan, build_host, &out_link)
+        .context("build failed; the running system was not changed")?;
+    progress!();
+    progress!("{} Built {}", "ok".green().bold(), store_path.display());
+    progress!();
+
+    progress!(
+        "{} Phase 2/2: switching to the built system",
+        ">>".blue().bold()
+    );
+    activate_built_system(plan, &store_path)?;
+    record_activation(gen_dir);
+
+    if let Ok(current) = std::fs::canonicalize("/run/current-system") {
+        if current != store_path {
+            eprintln!(
+                "{} /run/current-system is {}, not the {} built in phase 1",
+                "!!".yellow().bold(),
+                current.display(),
+                store_path.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Arguments for a build-only rebuild. `nixos-rebuild build` links the
+/// result wherever `--out-link` says; `darwin-rebuild build` has no such
+/// flag and always writes `./result`.
+fn build_args(
+    plan: &RebuildPlan,
+    flake_ref: &str,
+    out_link: Option<&std::path::Path>,
+) -> Vec<String> {
+    let mut args = vec![
+        "build".to_string(),
+        "--flake".to_string(),
+        flake_ref.to_string(),
+    ];
+    if let (Some(link), false) = (out_link, plan.is_darwin) {
+        args.push("--out-link".to_string());
+        args.push(link.display().to_string());
+    }
+    args
+}
+
+/// Build the system without activating it and keep the result at
+/// `out_link` as a GC root. Returns the built store path.
+fn run_build(
+    identity: &node_identity::NodeIdentity,
+    gen_dir: &std::path::Path,
+    plan: &RebuildPlan,
+    build_host: Option<&BuildHost>,
+    out_link: &std::path::Path,
+) -> Result<std::path::PathBuf> {
+    let flake_ref = format!("{}#{}", gen_dir.display(), identity.hostname);
+    // The build runs from `gen_dir`, so resolve a relative `--out-link`
+    // against our own working directory first.
+    let out_link = std::path::absolute(out_link)
+        .with_context(|| format!("resolving {}", out_link.display()))?;
+
+    let cmd = plan.cmd.as_str();
+    let mut args = build_args(plan, &flake_ref, Some(&out_link));
+    if let Some(bh) = build_host {
+        args.extend(builder_args(bh, &local_system()));
+    }
+
+    progress!(
+        "{} Running: {} {}",
+        ">>".blue().bold(),
+        cmd,
+        args.join(" ")
+    );
+    progress!(
+        "{} (build only — will not activate)",
+        "::".blue().bold()
+    );
+    if let Some(token) = github_access_token(identity) {
+        args.push("--option".to_string());
+        args.push("access-tokens".to_string());
+        args.push(format!("github.com={}", token.expose()));
+    }
+
+    // darwin-rebuild links `./result`, so run it from the generated flake
+    // directory and re-root the result at `out_link` afterwards.
+    let status = Command::new(cmd)
+        .args(&args)
+        .current_dir(gen_dir)
+        .stdout(output::child_stdout())
+        .status()
+        .with_context(|| format!("failed to run {cmd}"))?;
+    if !status.success() {
+        bail!("{} exited with status {}", cmd, status);
+    }
+
+    let built_link = if plan.is_darwin {
+        gen_dir.join("result")
+    } else {
+        out_link.clone()
+    };
+    let store_path = std::fs::canonicalize(&built_link)
+        .with_context(|| format!("build produced no result at {}", built_link.display()))?;
+
+    if built_link != out_link {
+        let status = Command::new("nix-store")
+            .arg("--add-root")
+            .arg(&out_link)
+            .arg("--realise")
+            .arg(&store_path)
+            .stdout(std::process::Stdio::null())
+            .status()
+            .context("failed to run nix-store --add-root")?;
+        if !status.success() {
+            bail!(
+                "nix-store --add-root {} exited with status {}",
+                out_link.display(),
+                status
+            );
+        }
+    }
+
+    Ok(store_path)
+}
+
 fn run_rebuild_diff(
     identity: &node_identity::NodeIdentity,
     gen_dir: &std::path::Path,
+    plan: &RebuildPlan,
+    build_host: Option<&BuildHost>,
 ) -> Result<()> {
-    let is_darwin = matches!(identity.profile.as_str(), "macos-developer");
     let flake_ref = format!("{}#{}", gen_dir.display(), identity.hostname);
 
-    let cmd = if is_darwin { "darwin-rebuild" } else { "nixos-rebuild" };
-    let args = vec!["build", "--flake", &flake_ref];
+    let cmd = plan.cmd.as_str();
+    let mut args = build_args(plan, &flake_ref, None);
+    if let Some(bh) = build_host {
+        args.extend(builder_args(bh, &local_system()));
+    }
 
-    println!(
+    progress!(
         "{} Running: {} {}",
         ">>".blue().bold(),
         cmd,
         args.join(" ")
     );
-    println!(
+    progress!(
         "{} (build only — will not activate)",
         "::".blue().bold()
     );
@@ -174,7 +592,7 @@ fn run_rebuild_diff(
         // Show diff between current system and built result
         let result_path = gen_dir.join("result");
         if result_path.exists() {
-            println!();
+            progress!();
             println!("{} Diff against current system:", ">>".blue().bold());
             let result_str = result_path.display().to_string();
             let _ = Command::new("nix")
@@ -182,8 +600,8 @@ fn run_rebuild_diff(
                 .status();
         }
 
-        println!();
-        println!(
+        progress!();
+        progress!(
             "{} Build succeeded. Run `kindling apply` to activate.",
             "ok".green().bold()
         );
@@ -193,3 +611,155 @@ fn run_rebuild_diff(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_rebuild_darwin_profile() {
+        let plan = resolve_rebuild("macos-developer", None, "darwin").unwrap();
+        assert_eq!(plan.cmd, "darwin-rebuild");
+        assert!(plan.is_darwin);
+    }
+
+    #[test]
+    fn resolve_rebuild_linux_profile() {
+        let plan = resolve_rebuild("k3s-server", None, "linux").unwrap();
+        assert_eq!(plan.cmd, "nixos-rebuild");
+        assert!(!plan.is_darwin);
+    }
+
+    #[test]
+    fn resolve_rebuild_platform_mismatch_errors() {
+        let err = resolve_rebuild("macos-developer", None, "linux").unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("targets darwin"));
+        assert!(msg.contains("host is linux"));
+    }
+
+    #[test]
+    fn resolve_rebuild_unknown_profile_uses_host() {
+        let plan = resolve_rebuild("cloud-server", None, "linux").unwrap();
+        assert_eq!(plan.cmd, "nixos-rebuild");
+        let plan = resolve_rebuild("custom-mac", None, "darwin").unwrap();
+        assert_eq!(plan.cmd, "darwin-rebuild");
+    }
+
+    #[test]
+    fn build_args_with_out_link() {
+        let link = std::path::Path::new("/var/lib/kindling/next-system");
+        let nixos = resolve_rebuild("k3s-server", None, "linux").unwrap();
+        assert_eq!(
+            build_args(&nixos, "/tmp/gen#edge-1", Some(link)),
+            vec![
+                "build",
+                "--flake",
+                "/tmp/gen#edge-1",
+                "--out-link",
+                "/var/lib/kindling/next-system",
+            ]
+        );
+        assert_eq!(build_args(&nixos, "/tmp/gen#edge-1", None).len(), 3);
+
+        let darwin = resolve_rebuild("macos-developer", None, "darwin").unwrap();
+        assert_eq!(
+            build_args(&darwin, "/tmp/gen#mac", Some(link)),
+            vec!["build", "--flake", "/tmp/gen#mac"]
+        );
+    }
+
+    #[test]
+    fn two_phase_activates_the_built_path() {
+        let system = std::path::Path::new("/nix/store/abc-nixos-system-edge-1");
+        let nixos = resolve_rebuild("k3s-server", None, "linux").unwrap();
+        assert_eq!(
+            activation_commands(&nixos, system),
+            [
+                vec![
+                    "nix-env",
+                    "-p",
+                    "/nix/var/nix/profiles/system",
+                    "--set",
+                    "/nix/store/abc-nixos-system-edge-1",
+                ],
+                vec![
+                    "/nix/store/abc-nixos-system-edge-1/bin/switch-to-configuration",
+                    "switch",
+                ],
+            ]
+        );
+
+        let system = std::path::Path::new("/nix/store/def-darwin-system-mac");
+        let darwin = resolve_rebuild("macos-developer", None, "darwin").unwrap();
+        let [set_profile, activate] = activation_commands(&darwin, system);
+        assert_eq!(set_profile[4], "/nix/store/def-darwin-system-mac");
+        assert_eq!(activate, vec!["/nix/store/def-darwin-system-mac/activate"]);
+    }
+
+    #[test]
+    fn resolve_rebuild_cmd_override_wins() {
+        let plan = resolve_rebuild("k3s-agent", Some("/opt/bin/nixos-rebuild-ng"), "linux").unwrap();
+        assert_eq!(plan.cmd, "/opt/bin/nixos-rebuild-ng");
+        assert!(!plan.is_darwin);
+    }
+
+    #[test]
+    fn resolve_rebuild_cmd_override_keeps_platform_check() {
+        assert!(resolve_rebuild("macos-developer", Some("nixos-rebuild"), "linux").is_err());
+    }
+
+    fn sample_builder() -> SshBuilderConfig {
+        SshBuilderConfig {
+            hostname: "forge".to_string(),
+            fqdn: "forge.lab.example.com".to_string(),
+            identity_file: Some("/etc/nix/builder_ed25519".to_string()),
+        }
+    }
+
+    #[test]
+    fn build_host_defaults_to_identity_builder() {
+        let builder = sample_builder();
+        let bh = resolve_build_host(None, Some(&builder)).unwrap();
+        assert_eq!(bh.host, "forge.lab.example.com");
+        assert_eq!(bh.identity_file.as_deref(), Some("/etc/nix/builder_ed25519"));
+
+        assert_eq!(resolve_build_host(None, None), None);
+        assert_eq!(resolve_build_host(Some("local"), Some(&builder)), None);
+    }
+
+    #[test]
+    fn build_host_flag_overrides_builder() {
+        let builder = sample_builder();
+        let bh = resolve_build_host(Some("nix@forge"), Some(&builder)).unwrap();
+        assert_eq!(bh.host, "nix@forge");
+        assert_eq!(bh.identity_file.as_deref(), Some("/etc/nix/builder_ed25519"));
+
+        let other = resolve_build_host(Some("big-box"), Some(&builder)).unwrap();
+        assert_eq!(other.identity_file, None);
+    }
+
+    #[test]
+    fn builder_args_from_sample_config() {
+        let builder = sample_builder();
+        let bh = resolve_build_host(None, Some(&builder)).unwrap();
+        assert_eq!(
+            builder_args(&bh, "x86_64-linux"),
+            vec![
+                "--builders",
+                "ssh://forge.lab.example.com x86_64-linux /etc/nix/builder_ed25519",
+                "--max-jobs",
+                "0",
+                "--option",
+                "builders-use-substitutes",
+                "true",
+            ]
+        );
+
+        let keyless = BuildHost {
+            host: "big-box".to_string(),
+            identity_file: None,
+        };
+        assert_eq!(builder_args(&keyless, "aarch64-darwin")[1], "ssh://big-box aarch64-darwin -");
+    }
+}
@@ -1,9 +1,13 @@
-use anyhow::Result;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
 
 use crate::commands::install;
 use crate::nix;
 use crate::node_identity::{nix_gen, NodeIdentity};
+use crate::output::progress;
+use crate::paths::expand_path;
 use crate::tools;
 use crate::{direnv_setup, tend_setup};
 
@@ -12,33 +16,36 @@ pub fn run(
     skip_direnv: bool,
     skip_tend: bool,
     org: Option<String>,
+    tend_timeout: u64,
     no_confirm: bool,
     profile: Option<String>,
     hostname: Option<String>,
     user: Option<String>,
     age_key_file: Option<String>,
     node_config: Option<String>,
+    from_url: Option<String>,
+    token_file: Option<String>,
 ) -> Result<()> {
-    println!("{}", "kindling bootstrap".bold());
-    println!();
+    progress!("{}", "kindling bootstrap".bold());
+    progress!();
 
     let mut actions: Vec<&str> = Vec::new();
 
     // ── Step 1: Nix ──────────────────────────────────────────────
-    println!("{} Step 1: Nix", ">>".blue().bold());
+    progress!("{} Step 1: Nix", ">>".blue().bold());
 
     let nix_status = nix::detect();
     if nix_status.installed {
         if let Some(ver) = &nix_status.version {
-            println!("{} Nix {} already installed", "ok".green().bold(), ver);
+            progress!("{} Nix {} already installed", "ok".green().bold(), ver);
         } else {
-            println!("{} Nix already installed", "ok".green().bold());
+            progress!("{} Nix already installed", "ok".green().bold());
         }
     } else {
         if !no_confirm
             && !confirm("Nix is not installed. Install it now?")? {
-                println!("{} Skipping nix install", "::".blue().bold());
-                println!("   Run `kindling install` when you're ready.");
+                progress!("{} Skipping nix install", "::".blue().bold());
+                progress!("   Run `kindling install` when you're ready.");
                 return Ok(());
             }
         install::install_now()?;
@@ -46,15 +53,15 @@ pub fn run(
         tools::prepend_nix_profile_to_path();
         actions.push("Installed Nix");
     }
-    println!();
+    progress!();
 
     // ── Step 2: direnv ───────────────────────────────────────────
     if !skip_direnv {
-        println!("{} Step 2: direnv", ">>".blue().bold());
+        progress!("{} Step 2: direnv", ">>".blue().bold());
 
         if direnv_setup::ensure_installed().is_ok() {
             if let Err(e) = direnv_setup::ensure_shell_hook() {
-                println!(
+                eprintln!(
                     "{} Could not inject direnv hook: {}",
                     "!!".yellow().bold(),
                     e
@@ -64,7 +71,7 @@ pub fn run(
             }
 
             if let Err(e) = direnv_setup::install_direnv_lib() {
-                println!(
+                eprintln!(
                     "{} Could not install direnv lib: {}",
                     "!!".yellow().bold(),
                     e
@@ -73,17 +80,17 @@ pub fn run(
                 actions.push("Installed use_kindling direnv lib");
             }
         }
-        println!();
+        progress!();
     }
 
     // ── Step 3: tend ─────────────────────────────────────────────
     if !skip_tend {
-        println!("{} Step 3: tend", ">>".blue().bold());
+        progress!("{} Step 3: tend", ">>".blue().bold());
 
         if tend_setup::ensure_installed().is_ok() {
             if let Some(ref org_name) = org {
                 if let Err(e) = tend_setup::ensure_config(org_name) {
-                    println!(
+                    eprintln!(
                         "{} Could not create tend config: {}",
                         "!!".yellow().bold(),
                         e
@@ -93,29 +100,53 @@ pub fn run(
                 }
             }
 
-            if let Err(e) = tend_setup::sync() {
-                println!(
-                    "{} tend sync failed: {}",
-                    "!!".yellow().bold(),
-                    e
-                );
-            } else {
-                actions.push("Synced workspace repos");
+            match tend_setup::sync(Duration::from_secs(tend_timeout)) {
+                Err(e) => {
+                    eprintln!(
+                        "{} tend sync failed: {}",
+                        "!!".yellow().bold(),
+                        e
+                    );
+                }
+                Ok(Some(summary)) if !summary.failed.is_empty() || !summary.success => {
+                    eprintln!(
+                        "{} tend sync incomplete (non-fatal): {} synced, {} failed{}",
+                        "!!".yellow().bold(),
+                        summary.synced.len(),
+                        summary.failed.len(),
+                        if summary.failed.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" ({})", summary.failed.join(", "))
+                        }
+                    );
+                }
+                Ok(Some(_)) => actions.push("Synced workspace repos"),
+                Ok(None) => {}
             }
         }
-        println!();
+        progress!();
     }
 
     // ── Step 4: Node Identity ────────────────────────────────────
-    let has_profile_args = profile.is_some() || node_config.is_some();
+    let has_profile_args = profile.is_some() || node_config.is_some() || from_url.is_some();
 
     if has_profile_args {
-        println!("{} Step 4: Node Identity", ">>".blue().bold());
+        progress!("{} Step 4: Node Identity", ">>".blue().bold());
 
-        let identity = if let Some(config_path) = node_config {
+        let identity = if let Some(url) = from_url {
+            progress!("  Fetching node config from {}", url);
+            let token = token_file
+                .map(|path| {
+                    std::fs::read_to_string(expand_path(&path))
+                        .with_context(|| format!("reading token file {}", path))
+                })
+                .transpose()?;
+            fetch_node_identity(&url, token.as_deref().map(str::trim))?
+        } else if let Some(config_path) = node_config {
             // Load from existing node.yaml
             let path = std::path::PathBuf::from(&config_path);
-            println!("  Loading node config from {}", config_path);
+            progress!("  Loading node config from {}", config_path);
             NodeIdentity::load(&path)?
         } else {
             // Build from CLI flags
@@ -158,38 +189,38 @@ pub fn run(
         // Save node.yaml
         let node_path = NodeIdentity::default_path();
         identity.save(&node_path)?;
-        println!(
+        progress!(
             "{} Node identity saved to {}",
             "ok".green().bold(),
             node_path.display()
         );
         actions.push("Created node identity");
-        println!();
+        progress!();
 
         // ── Step 5: Nix Generation ───────────────────────────────
-        println!("{} Step 5: Nix Generation", ">>".blue().bold());
+        progress!("{} Step 5: Nix Generation", ">>".blue().bold());
 
         let gen_dir = nix_gen::generate(&identity)?;
-        println!(
+        progress!(
             "{} Generated Nix config in {}",
             "ok".green().bold(),
             gen_dir.display()
         );
         actions.push("Generated Nix configuration");
-        println!();
+        progress!();
 
         // ── Step 6: System Activate ──────────────────────────────
         if !no_confirm {
-            println!(
+            progress!(
                 "{} Generated config is ready at {}",
                 "::".blue().bold(),
                 gen_dir.display()
             );
-            println!(
+            progress!(
                 "{} Run `kindling apply` to activate the system configuration.",
                 "::".blue().bold()
             );
-            println!(
+            progress!(
                 "{} Or run `kindling apply --diff` to preview changes first.",
                 "::".blue().bold()
             );
@@ -197,21 +228,21 @@ pub fn run(
     }
 
     // ── Summary ──────────────────────────────────────────────────
-    println!("{}", "── Summary ──".bold());
+    progress!("{}", "── Summary ──".bold());
     if actions.is_empty() {
-        println!("  Everything was already set up.");
+        progress!("  Everything was already set up.");
     } else {
         for action in &actions {
-            println!("  {} {}", "+".green().bold(), action);
+            progress!("  {} {}", "+".green().bold(), action);
         }
     }
-    println!();
-    println!(
+    progress!();
+    progress!(
         "{} Restart your shell to pick up any PATH changes.",
         "::".blue().bold()
     );
     if !has_profile_args {
-        println!(
+        progress!(
             "{} In project directories, run `direnv allow` to activate.",
             "::".blue().bold()
         );
@@ -220,9 +251,93 @@ pub fn run(
     Ok(())
 }
 
+/// How long to wait for the node.yaml download as a whole.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Download node.yaml from `url` and check it parses as a NodeIdentity.
+fn fetch_node_identity(url: &str, bearer_token: Option<&str>) -> Result<NodeIdentity> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()?;
+    let mut request = client.get(url);
+    if let Some(token) = bearer_token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .with_context(|| format!("fetching node config from {}", url))?;
+    let status = response.status();
+    if !status.is_success() {
+        bail!("fetching node config from {} failed: HTTP {}", url, status);
+    }
+    let body = response
+        .text()
+        .with_context(|| format!("reading node config from {}", url))?;
+    serde_yaml::from_str(&body)
+        .with_context(|| format!("{} did not return a valid node.yaml", url))
+}
+
 fn confirm(prompt: &str) -> Result<bool> {
     eprint!("{} {} [y/N] ", "??".blue().bold(), prompt);
     let mut input = String::new();
     std::io::stdin().read_line(&mut input)?;
     Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Serve one canned HTTP response; returns the URL and the raw request.
+    fn serve_once(status: &str, body: &str) -> (String, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/nodes/edge-1.yaml", listener.local_addr().unwrap());
+        let response = format!(
+            "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let n = stream.read(&mut buf).unwrap();
+            stream.write_all(response.as_bytes()).unwrap();
+            String::from_utf8_lossy(&buf[..n]).into_owned()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn fetch_node_identity_parses_yaml_and_sends_token() {
+        let (url, server) = serve_once(
+            "200 OK",
+            "version: \"1\"\nprofile: k3s-server\nhostname: edge-1\n",
+        );
+        let identity = fetch_node_identity(&url, Some("s3cret")).unwrap();
+        assert_eq!(identity.hostname, "edge-1");
+        assert_eq!(identity.profile, "k3s-server");
+
+        let request = server.join().unwrap().to_lowercase();
+        assert!(request.starts_with("get /nodes/edge-1.yaml"));
+        assert!(request.contains("authorization: bearer s3cret"));
+    }
+
+    #[test]
+    fn fetch_node_identity_rejects_http_errors() {
+        let (url, server) = serve_once("404 Not Found", "no such node");
+        let err = fetch_node_identity(&url, None).unwrap_err();
+        server.join().unwrap();
+        assert!(err.to_string().contains("HTTP 404"), "{}", err);
+    }
+
+    #[test]
+    fn fetch_node_identity_rejects_invalid_yaml() {
+        let (url, server) = serve_once("200 OK", "<html>login required</html>");
+        let err = fetch_node_identity(&url, None).unwrap_err();
+        server.join().unwrap();
+        assert!(err.to_string().contains("did not return a valid node.yaml"));
+    }
+}
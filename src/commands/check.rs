@@ -1,5 +1,7 @@
 use colored::Colorize;
 
+use crate::config;
+use crate::domain::nix_service::parse_nix_config;
 use crate::nix;
 use crate::platform;
 
@@ -23,6 +25,33 @@ pub fn run() -> anyhow::Result<()> {
         if let Some(path) = &status.nix_path {
             println!("  path:     {}", path.display());
         }
+        if !status.conflicting_installs.is_empty() {
+            println!(
+                "  {} multiple nix installations found:",
+                "!!".yellow().bold()
+            );
+            for install in &status.conflicting_installs {
+                println!("            {}", install);
+            }
+        }
+        let required = required_features();
+        if let Some(missing) = status
+            .nix_path
+            .as_deref()
+            .and_then(|nix| missing_features(nix, &required))
+        {
+            if !missing.is_empty() {
+                println!(
+                    "  {} experimental-features is missing: {}",
+                    "!!".yellow().bold(),
+                    missing.join(" ")
+                );
+                println!(
+                    "            add `experimental-features = {}` to nix.conf",
+                    required.join(" ")
+                );
+            }
+        }
         std::process::exit(0);
     } else {
         println!("  nix:      {}", "not installed".red());
@@ -30,3 +59,23 @@ pub fn run() -> anyhow::Result<()> {
         std::process::exit(1);
     }
 }
+
+fn required_features() -> Vec<String> {
+    config::load()
+        .ok()
+        .and_then(|c| c.daemon)
+        .map(|d| d.required_features)
+        .unwrap_or_else(config::default_required_features)
+}
+
+/// Required experimental features not enabled in the active nix.conf.
+/// `None` when the nix config can't be read.
+fn missing_features(nix: &std::path::Path, required: &[String]) -> Option<Vec<String>> {
+    let output = std::process::Command::new(nix)
+        .args(["show-config", "--json"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).ok()?;
+    Some(parse_nix_config(&json).features_missing(required))
+}
@@ -0,0 +1,32 @@
+//! `kindling config show` — the effective config and where each value came
+//! from.
+//!
+//! The config is merged from defaults, /etc/kindling/config.yaml,
+//! `KINDLING_*` environment variables, ~/.config/kindling/config.yaml and
+//! ./.kindling.yaml, later layers winning. Each value is printed with the
+//! layer that set it.
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::config;
+
+pub fn show(format: &str) -> Result<()> {
+    let values = config::provenance()?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&values)?);
+        return Ok(());
+    }
+
+    let width = values.keys().map(String::len).max().unwrap_or(0);
+    for (key, entry) in &values {
+        println!(
+            "  {}  {}  {}",
+            format!("{:width$}", key).bold(),
+            entry.value,
+            format!("({})", entry.source).dimmed()
+        );
+    }
+    Ok(())
+}
@@ -1,12 +1,21 @@
-use anyhow::Result;
+use std::path::Path;
 
-use crate::config;
+use anyhow::{bail, Result};
+use colored::Colorize;
 
+use crate::config::{self, DaemonConfig};
+use crate::paths::{expand_path, probe_writable};
+
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     http_addr: Option<String>,
+    unix_socket: Option<String>,
+    allow_insecure: bool,
+    pid_file: Option<String>,
     grpc_addr: Option<String>,
     log_level: Option<String>,
     config_path: Option<String>,
+    config_check: bool,
 ) -> Result<()> {
     // Load config from figment chain (optionally with an extra file on top)
     let mut daemon_config = if let Some(path) = config_path {
@@ -21,6 +30,15 @@ pub fn run(
     if let Some(addr) = http_addr {
         daemon_config.http_addr = addr;
     }
+    if let Some(path) = unix_socket {
+        daemon_config.unix_socket = Some(path);
+    }
+    if allow_insecure {
+        daemon_config.insecure_bind = config::InsecureBindPolicy::Allow;
+    }
+    if let Some(path) = pid_file {
+        daemon_config.pid_file = Some(path);
+    }
     if let Some(addr) = grpc_addr {
         daemon_config.grpc_addr = addr;
     }
@@ -28,7 +46,165 @@ pub fn run(
         daemon_config.log_level = level;
     }
 
+    if config_check {
+        return report_config_check(&daemon_config);
+    }
+
     // Build tokio runtime explicitly (no #[tokio::main] on fn main)
     let runtime = tokio::runtime::Runtime::new()?;
     runtime.block_on(crate::server::daemon::run(daemon_config))
 }
+
+/// `--config-check`: print the verdict, failing when there are problems.
+fn report_config_check(config: &DaemonConfig) -> Result<()> {
+    let problems = check_config(config);
+    if problems.is_empty() {
+        println!("{} daemon config OK", "ok".green().bold());
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("{} {}", "!!".red().bold(), problem);
+    }
+    bail!("daemon config has {} problem(s)", problems.len())
+}
+
+/// Everything that would stop the daemon from starting (or make part of it
+/// fail) once the config itself has parsed.
+fn check_config(config: &DaemonConfig) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if config.http_addr.is_empty() {
+        if config.unix_socket.is_none() {
+            problems.push(
+                "no listener configured: set daemon.http_addr or daemon.unix_socket".to_string(),
+            );
+        }
+    } else if !is_listen_addr(&config.http_addr) {
+        problems.push(format!(
+            "http_addr '{}' is not a valid host:port address",
+            config.http_addr
+        ));
+    }
+    if cfg!(feature = "grpc") && config.grpc_addr.parse::<std::net::SocketAddr>().is_err() {
+        problems.push(format!(
+            "grpc_addr '{}' is not a valid ip:port address",
+            config.grpc_addr
+        ));
+    }
+    if let Err(e) = reqwest::Url::parse(&config.telemetry.vector_url) {
+        problems.push(format!(
+            "telemetry.vector_url '{}' does not parse: {}",
+            config.telemetry.vector_url, e
+        ));
+    }
+    if !config.report.cache_file.is_empty() {
+        let cache_file = expand_path(&config.report.cache_file);
+        if let Some(dir) = cache_file.parent() {
+            if let Err(e) = check_writable(dir) {
+                problems.push(format!(
+                    "report cache dir {} is not writable: {}",
+                    dir.display(),
+                    e
+                ));
+            }
+        }
+    }
+
+    problems
+}
+
+/// An `ip:port` or `hostname:port` TCP listen address.
+fn is_listen_addr(addr: &str) -> bool {
+    if addr.parse::<std::net::SocketAddr>().is_ok() {
+        return true;
+    }
+    match addr.rsplit_once(':') {
+        Some((host, port)) => {
+            !host.is_empty()
+                && !host.contains(|c: char| c.is_whitespace() || c == '/')
+                && port.parse::<u16>().is_ok()
+        }
+        None => false,
+    }
+}
+
+/// Probe that files can be created in `dir`, or in its nearest existing
+/// ancestor when the daemon would have to create it.
+fn check_writable(dir: &Path) -> std::io::Result<()> {
+    let existing = dir
+        .ancestors()
+        .find(|d| d.exists())
+        .unwrap_or_else(|| Path::new("."));
+    probe_writable(existing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_config(dir: &Path) -> DaemonConfig {
+        let mut config = DaemonConfig::default();
+        config.report.cache_file = dir.join("kindling/report.json").display().to_string();
+        config
+    }
+
+    #[test]
+    fn valid_config_has_no_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = valid_config(dir.path());
+        assert!(check_config(&config).is_empty());
+
+        config.http_addr = "localhost:9100".to_string();
+        assert!(check_config(&config).is_empty());
+
+        // UDS-only is fine too.
+        config.http_addr = String::new();
+        config.unix_socket = Some("/run/kindling/api.sock".to_string());
+        assert!(check_config(&config).is_empty());
+    }
+
+    #[test]
+    fn unparseable_http_addr_is_a_problem() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = valid_config(dir.path());
+        config.http_addr = "not an address".to_string();
+        let problems = check_config(&config);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("http_addr 'not an address'"));
+
+        config.http_addr = "127.0.0.1:99999".to_string();
+        assert_eq!(check_config(&config).len(), 1);
+        assert!(report_config_check(&config).is_err());
+    }
+
+    #[test]
+    fn missing_listener_and_bad_telemetry_url_are_problems() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = valid_config(dir.path());
+        config.http_addr = String::new();
+        config.telemetry.vector_url = "not a url".to_string();
+        let problems = check_config(&config);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].contains("no listener configured"));
+        assert!(problems[1].contains("telemetry.vector_url"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unwritable_cache_dir_is_a_problem() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let locked = dir.path().join("locked");
+        std::fs::create_dir(&locked).unwrap();
+        std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o555)).unwrap();
+        // Root ignores directory permissions; nothing to test there.
+        if check_writable(&locked).is_ok() {
+            return;
+        }
+        let config = valid_config(&locked);
+        let problems = check_config(&config);
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("not writable"));
+    }
+}
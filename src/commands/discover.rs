@@ -0,0 +1,70 @@
+//! `kindling discover` — find kindling daemons on the LAN over mDNS.
+//!
+//! Daemons with `daemon.advertise` set answer `_kindling._tcp` queries.
+//! `--save` adds each one to `nodes` in ~/.config/kindling/config.yaml so
+//! `kindling query --node <hostname>` can reach it.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::config::{self, NodeTarget};
+use crate::server::mdns;
+
+pub fn run(timeout_secs: u64, save: bool, format: &str) -> Result<()> {
+    let rt = tokio::runtime::Runtime::new()?;
+    let daemons = rt.block_on(mdns::browse(Duration::from_secs(timeout_secs)))?;
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&daemons)?);
+    } else if daemons.is_empty() {
+        println!(
+            "{} No kindling daemons answered within {}s (is daemon.advertise set?)",
+            "::".blue().bold(),
+            timeout_secs
+        );
+    } else {
+        for daemon in &daemons {
+            let addresses: Vec<String> = daemon.addresses.iter().map(|a| a.to_string()).collect();
+            println!(
+                "  {} {} {}:{} {}",
+                "ok".green().bold(),
+                daemon.instance.bold(),
+                daemon.host,
+                daemon.port,
+                format!(
+                    "[{}] {}",
+                    addresses.join(", "),
+                    daemon.version.as_deref().unwrap_or("")
+                )
+                .dimmed()
+            );
+        }
+    }
+
+    if save && !daemons.is_empty() {
+        let nodes: BTreeMap<String, NodeTarget> = daemons
+            .iter()
+            .map(|d| {
+                (
+                    d.instance.clone(),
+                    NodeTarget {
+                        url: d.url(),
+                        description: Some(format!("discovered via mDNS as {}", d.host)),
+                    },
+                )
+            })
+            .collect();
+        let count = nodes.len();
+        config::save_nodes(nodes)?;
+        // Keep stdout parseable in json mode.
+        eprintln!(
+            "{} Saved {} node(s) to the user config",
+            "ok".green().bold(),
+            count
+        );
+    }
+    Ok(())
+}
@@ -1,14 +1,26 @@
-//! `kindling fleet status` / `kindling fleet apply <node>`
+//! `kindling fleet status` / `kindling fleet apply <node>|--all` /
+//! `kindling fleet drift` / `kindling fleet list` / `kindling fleet export` /
+//! `kindling fleet annotate <node> key=value`
 //!
 //! Fleet management commands for multi-node deployments.
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
 use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use crate::node_identity;
+use crate::client::{api_client_builder, ClientTls};
+use crate::config;
+use crate::domain::fleet_drift::{self, DriftSeverity};
+use crate::domain::fleet_store::{self, AppliedConfig, FleetNode, FleetNodeSummary, NodeFilter};
+use crate::domain::node_report::StoredReport;
+use crate::node_identity::{self, nix_gen, FleetPeer, NodeIdentity};
+use crate::paths::expand_path;
 
-pub fn status() -> Result<()> {
+pub fn status(controller_url: &str) -> Result<()> {
     let node_path = node_identity::NodeIdentity::default_path();
 
     if !node_path.exists() {
@@ -30,6 +42,16 @@ pub fn status() -> Result<()> {
     println!("{}", "Fleet Status".bold());
     println!();
 
+    let applied = Controller::connect(controller_url)?
+        .applied()
+        .unwrap_or_else(|e| {
+            println!(
+                "{} Applied configs unavailable: {:#}",
+                "!!".yellow().bold(),
+                e
+            );
+            BTreeMap::new()
+        });
     for peer in &identity.fleet.peers {
         let reachable = check_ssh_connectivity(&peer.hostname, &peer.ssh_user);
         let status_icon = if reachable {
@@ -46,13 +68,36 @@ pub fn status() -> Result<()> {
             peer.hostname.dimmed(),
             status_text
         );
+        if let Some(config) = applied.get(&peer.name) {
+            println!(
+                "      applied {} at {}",
+                config.checksum.dimmed(),
+                config.applied_at.format("%Y-%m-%d %H:%M:%S UTC")
+            );
+        }
     }
 
     println!();
     Ok(())
 }
 
-pub fn apply(node: &str) -> Result<()> {
+/// Deploy to one peer, or every peer with `all`. Each successful deploy is
+/// recorded on the fleet controller at `controller_url` with the
+/// [`deploy_checksum`](nix_gen::deploy_checksum) of the peer's resolved
+/// identity and flake lock; with `only_changed`, a peer whose checksum
+/// matches its recorded one is skipped.
+///
+/// An `all` run records each peer's progress in a run file as it goes;
+/// `resume` continues the most recent run, skipping peers it already
+/// finished and retrying the rest.
+pub fn apply(
+    node: Option<&str>,
+    all: bool,
+    only_changed: bool,
+    build_locally: bool,
+    resume: bool,
+    controller_url: &str,
+) -> Result<()> {
     let node_path = node_identity::NodeIdentity::default_path();
 
     if !node_path.exists() {
@@ -64,88 +109,785 @@ pub fn apply(node: &str) -> Result<()> {
     }
 
     let identity = node_identity::NodeIdentity::load(&node_path)?;
+    let controller = Controller::connect(controller_url)?;
+    // Without the controller nothing is known to be applied, so nothing
+    // is skipped.
+    let applied = if only_changed {
+        controller.applied().unwrap_or_else(|e| {
+            println!(
+                "{} Applied configs unavailable, deploying every peer: {:#}",
+                "!!".yellow().bold(),
+                e
+            );
+            BTreeMap::new()
+        })
+    } else {
+        BTreeMap::new()
+    };
 
-    let peer = identity
-        .fleet
-        .peers
-        .iter()
-        .find(|p| p.name == node);
+    let peers: Vec<&FleetPeer> = if all {
+        identity.fleet.peers.iter().collect()
+    } else {
+        let node = node.context("no fleet node given")?;
+        match identity.fleet.peers.iter().find(|p| p.name == node) {
+            Some(peer) => vec![peer],
+            None => {
+                eprintln!(
+                    "{} Unknown fleet node: {}",
+                    "!!".red().bold(),
+                    node
+                );
+                eprintln!("   Known peers:");
+                for p in &identity.fleet.peers {
+                    eprintln!("     - {}", p.name);
+                }
+                std::process::exit(1);
+            }
+        }
+    };
 
-    match peer {
-        Some(peer) => {
+    let runs_dir = apply_runs_dir();
+    let mut run = match (all, resume) {
+        (true, true) => {
+            let run = ApplyRun::latest(&runs_dir)?.context("no fleet apply run to resume")?;
             println!(
-                "{} Deploying to {} ({}@{})",
+                "{} Resuming run {} ({} of {} node(s) already done)",
                 ">>".blue().bold(),
+                run.run_id,
+                run.nodes.values().filter(|n| n.status.is_done()).count(),
+                run.nodes.len()
+            );
+            Some(run)
+        }
+        (true, false) => Some(ApplyRun::new()),
+        (false, _) => None,
+    };
+
+    let mut failed = Vec::new();
+    for peer in peers {
+        if let Some(run) = run.as_ref().filter(|r| r.is_done(&peer.name)) {
+            println!(
+                "{} {} already applied in run {}, skipping",
+                "ok".green().bold(),
                 peer.name.bold(),
-                peer.ssh_user,
-                peer.hostname
+                run.run_id
             );
+            continue;
+        }
+        // Best-effort: without a checksum the peer is simply never skipped.
+        let checksum = deploy_checksum(peer, build_locally).ok();
+        if only_changed && is_unchanged(checksum.as_deref(), applied.get(&peer.name)) {
+            println!(
+                "{} {} unchanged since last apply, skipping",
+                "ok".green().bold(),
+                peer.name.bold()
+            );
+            if let Some(run) = run.as_mut() {
+                run.finish(&peer.name, ApplyStatus::Skipped, None);
+                run.save(&runs_dir)?;
+            }
+            continue;
+        }
 
-            // Check connectivity first
-            if !check_ssh_connectivity(&peer.hostname, &peer.ssh_user) {
-                bail!("Cannot reach {} — check SSH connectivity", peer.hostname);
+        if let Some(run) = run.as_mut() {
+            run.start(&peer.name);
+            run.save(&runs_dir)?;
+        }
+        let result = apply_peer(peer, build_locally);
+        if let Some(run) = run.as_mut() {
+            match &result {
+                Ok(()) => run.finish(&peer.name, ApplyStatus::Succeeded, None),
+                Err(e) => run.finish(&peer.name, ApplyStatus::Failed, Some(format!("{:#}", e))),
+            }
+            run.save(&runs_dir)?;
+        }
+        match (&result, checksum) {
+            (Ok(()), Some(checksum)) => {
+                if let Err(e) = controller.record_applied(&peer.name, &checksum) {
+                    println!(
+                        "{} Could not record applied config for {}: {:#}",
+                        "!!".yellow().bold(),
+                        peer.name,
+                        e
+                    );
+                }
+            }
+            (Ok(()), None) => {}
+            (Err(e), _) if all => {
+                println!("{} {}: {:#}", "!!".red().bold(), peer.name, e);
+                failed.push(peer.name.clone());
             }
+            (Err(_), _) => return result,
+        }
+    }
 
+    if !failed.is_empty() {
+        if let Some(run) = &run {
             println!(
-                "{} SSH connectivity confirmed",
-                "ok".green().bold()
+                "{} Run {} recorded in {}; retry the failed nodes with --all --resume",
+                "::".blue().bold(),
+                run.run_id,
+                run.path(&runs_dir).display()
             );
+        }
+        bail!("deploy failed on {}", failed.join(", "));
+    }
+    Ok(())
+}
 
-            // Run remote nixos-rebuild
-            let remote_cmd = format!(
-                "nixos-rebuild switch --flake /etc/nixos#{}",
-                peer.name
-            );
+fn apply_peer(peer: &FleetPeer, build_locally: bool) -> Result<()> {
+    println!(
+        "{} Deploying to {} ({}@{})",
+        ">>".blue().bold(),
+        peer.name.bold(),
+        peer.ssh_user,
+        peer.hostname
+    );
+
+    // Check connectivity first
+    if !check_ssh_connectivity(&peer.hostname, &peer.ssh_user) {
+        bail!("Cannot reach {} — check SSH connectivity", peer.hostname);
+    }
+
+    println!(
+        "{} SSH connectivity confirmed",
+        "ok".green().bold()
+    );
+
+    if build_locally {
+        return push_closure(peer);
+    }
+
+    // Run remote nixos-rebuild
+    let remote_cmd = format!(
+        "nixos-rebuild switch --flake /etc/nixos#{}",
+        peer.name
+    );
+
+    println!(
+        "{} Running: ssh {}@{} {}",
+        ">>".blue().bold(),
+        peer.ssh_user,
+        peer.hostname,
+        remote_cmd
+    );
+
+    let status = Command::new("ssh")
+        .args([
+            &format!("{}@{}", peer.ssh_user, peer.hostname),
+            &remote_cmd,
+        ])
+        .status();
 
+    match status {
+        Ok(s) if s.success() => {
+            println!();
             println!(
-                "{} Running: ssh {}@{} {}",
-                ">>".blue().bold(),
-                peer.ssh_user,
-                peer.hostname,
-                remote_cmd
+                "{} Successfully deployed to {}",
+                "ok".green().bold(),
+                peer.name
             );
+            Ok(())
+        }
+        Ok(s) => {
+            bail!("Remote rebuild failed with status {}", s);
+        }
+        Err(e) => {
+            bail!("Failed to SSH to {}: {}", peer.hostname, e);
+        }
+    }
+}
 
-            let status = Command::new("ssh")
-                .args([
-                    &format!("{}@{}", peer.ssh_user, peer.hostname),
-                    &remote_cmd,
-                ])
-                .status();
+/// The fleet controller `fleet apply` records deployed configs on.
+struct Controller {
+    url: String,
+    client: reqwest::Client,
+    rt: tokio::runtime::Runtime,
+}
 
-            match status {
-                Ok(s) if s.success() => {
-                    println!();
-                    println!(
-                        "{} Successfully deployed to {}",
-                        "ok".green().bold(),
-                        peer.name
-                    );
-                }
-                Ok(s) => {
-                    bail!("Remote rebuild failed with status {}", s);
-                }
-                Err(e) => {
-                    bail!("Failed to SSH to {}: {}", peer.hostname, e);
-                }
+impl Controller {
+    fn connect(url: &str) -> Result<Self> {
+        let tls = ClientTls::from_config(&config::load()?);
+        Ok(Self {
+            url: url.trim_end_matches('/').to_string(),
+            client: api_client_builder(&tls)?.build()?,
+            rt: tokio::runtime::Runtime::new()?,
+        })
+    }
+
+    /// Send `body` (if any) to `path` and parse the JSON response.
+    fn call<T: serde::de::DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<&impl Serialize>,
+    ) -> Result<T> {
+        let url = format!("{}{}", self.url, path);
+        self.rt.block_on(async {
+            let mut request = self.client.request(method.clone(), &url);
+            if let Some(body) = body {
+                request = request.json(body);
             }
+            let resp = request
+                .send()
+                .await
+                .with_context(|| format!("{} {}", method, url))?;
+            if !resp.status().is_success() {
+                bail!(
+                    "{} returned {}: {}",
+                    url,
+                    resp.status(),
+                    resp.text().await.unwrap_or_default()
+                );
+            }
+            resp.json()
+                .await
+                .with_context(|| format!("parsing response from {}", url))
+        })
+    }
+
+    /// The config last applied to each node, by hostname.
+    fn applied(&self) -> Result<BTreeMap<String, AppliedConfig>> {
+        let nodes: Vec<FleetNodeSummary> =
+            self.call(reqwest::Method::GET, "/api/v1/fleet/nodes", None::<&()>)?;
+        Ok(nodes
+            .into_iter()
+            .filter_map(|n| Some((n.hostname, n.applied?)))
+            .collect())
+    }
+
+    fn record_applied(&self, node: &str, checksum: &str) -> Result<AppliedConfig> {
+        self.call(
+            reqwest::Method::PUT,
+            &format!("/api/v1/fleet/nodes/{}/applied", node),
+            Some(&serde_json::json!({ "checksum": checksum })),
+        )
+    }
+}
+
+/// Checksum of what deploying to `peer` would apply: its identity as the
+/// peer resolves it, and the flake lock the build uses (the generated one
+/// here with `build_locally`, the peer's `/etc/nixos` one otherwise).
+fn deploy_checksum(peer: &FleetPeer, build_locally: bool) -> Result<String> {
+    let identity = fetch_remote_identity(peer)?;
+    let flake_lock = if build_locally {
+        std::fs::read_to_string(peer_generated_dir(peer).join("flake.lock")).ok()
+    } else {
+        remote_output(peer, "cat /etc/nixos/flake.lock").ok()
+    };
+    nix_gen::deploy_checksum(&identity, flake_lock.as_deref())
+}
+
+/// Skip only on a known checksum equal to the recorded one; anything
+/// unknown counts as changed.
+fn is_unchanged(checksum: Option<&str>, applied: Option<&AppliedConfig>) -> bool {
+    matches!((checksum, applied), (Some(c), Some(a)) if a.checksum == c)
+}
+
+/// Per-run progress files of `fleet apply --all`, one per run id.
+fn apply_runs_dir() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("~/.config"))
+        .join("kindling")
+        .join("fleet-runs")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApplyStatus {
+    /// Started but never finished: the run was interrupted mid-deploy.
+    Running,
+    Succeeded,
+    Failed,
+    /// Unchanged since the last apply (`--only-changed`).
+    Skipped,
+}
+
+impl ApplyStatus {
+    /// Nothing left to do for the node on resume.
+    fn is_done(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Skipped)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeProgress {
+    pub status: ApplyStatus,
+    pub started_at: DateTime<Utc>,
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Progress of one `fleet apply --all`, rewritten after every node so an
+/// interrupted rollout can be resumed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplyRun {
+    pub run_id: String,
+    pub started_at: DateTime<Utc>,
+    #[serde(default)]
+    pub nodes: BTreeMap<String, NodeProgress>,
+}
+
+impl ApplyRun {
+    fn new() -> Self {
+        let started_at = Utc::now();
+        Self {
+            // Sorts chronologically, which `latest` relies on.
+            run_id: started_at.format("%Y%m%dT%H%M%SZ").to_string(),
+            started_at,
+            nodes: BTreeMap::new(),
         }
-        None => {
-            eprintln!(
-                "{} Unknown fleet node: {}",
-                "!!".red().bold(),
-                node
-            );
-            eprintln!("   Known peers:");
-            for p in &identity.fleet.peers {
-                eprintln!("     - {}", p.name);
+    }
+
+    fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.json", self.run_id))
+    }
+
+    /// The most recent run in `dir`, if any.
+    fn latest(dir: &Path) -> Result<Option<Self>> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Ok(None);
+        };
+        let newest = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+            .max();
+        let Some(path) = newest else {
+            return Ok(None);
+        };
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    /// Write the run file, replacing it atomically.
+    fn save(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create directory {}", dir.display()))?;
+        let path = self.path(dir);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &path)
+            .with_context(|| format!("failed to rename {} to {}", tmp.display(), path.display()))
+    }
+
+    /// Whether `node` needs nothing more from this run.
+    fn is_done(&self, node: &str) -> bool {
+        self.nodes.get(node).is_some_and(|n| n.status.is_done())
+    }
+
+    fn start(&mut self, node: &str) {
+        self.nodes.insert(
+            node.to_string(),
+            NodeProgress {
+                status: ApplyStatus::Running,
+                started_at: Utc::now(),
+                finished_at: None,
+                error: None,
+            },
+        );
+    }
+
+    fn finish(&mut self, node: &str, status: ApplyStatus, error: Option<String>) {
+        let now = Utc::now();
+        let progress = self
+            .nodes
+            .entry(node.to_string())
+            .or_insert_with(|| NodeProgress {
+                status,
+                started_at: now,
+                finished_at: None,
+                error: None,
+            });
+        progress.status = status;
+        progress.finished_at = Some(now);
+        progress.error = error;
+    }
+}
+
+/// The fleet controller state file configured on this host.
+fn controller_state_file() -> Result<String> {
+    let cfg = config::load()?;
+    Ok(cfg
+        .daemon
+        .as_ref()
+        .map(|d| d.fleet_controller.state_file.clone())
+        .unwrap_or_else(|| config::FleetControllerConfig::default().state_file))
+}
+
+/// List the fleet controller's nodes, filtered by declared tags and
+/// `key=value` labels (all must match).
+pub fn list(format: &str, tags: &[String], labels: &[String]) -> Result<()> {
+    let filter = NodeFilter::parse(tags, labels)?;
+    let state_file = controller_state_file()?;
+    let nodes = fleet_store::read_state(&expand_path(&state_file))?;
+    let matching = fleet_store::list_nodes(&nodes, &filter);
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&matching)?);
+        return Ok(());
+    }
+    if matching.is_empty() {
+        println!(
+            "{} No matching nodes ({} known in {})",
+            "::".blue().bold(),
+            nodes.len(),
+            state_file
+        );
+        return Ok(());
+    }
+    for node in &matching {
+        let labels: Vec<String> = node
+            .labels
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        println!(
+            "  {} [{}] {} (reported {})",
+            node.hostname.bold(),
+            node.tags.join(", "),
+            labels.join(" ").dimmed(),
+            node.received_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        for (key, value) in &node.annotations {
+            println!("      {} {}: {}", "note".yellow(), key, value);
+        }
+    }
+    Ok(())
+}
+
+/// Set (or with `key=`, remove) annotations on a node through the fleet
+/// controller's API, so a running controller sees them immediately.
+pub fn annotate(node: &str, annotations: &[String], controller_url: &str) -> Result<()> {
+    let changes = fleet_store::parse_annotations(annotations)?;
+    if changes.is_empty() {
+        bail!("nothing to annotate: pass one or more key=value pairs");
+    }
+    let current: BTreeMap<String, String> = Controller::connect(controller_url)?.call(
+        reqwest::Method::PUT,
+        &format!("/api/v1/fleet/nodes/{}/annotations", node),
+        Some(&changes),
+    )?;
+
+    println!("{} Annotations on {}:", "ok".green().bold(), node.bold());
+    if current.is_empty() {
+        println!("  (none)");
+    }
+    for (key, value) in &current {
+        println!("  {}: {}", key, value);
+    }
+    Ok(())
+}
+
+/// Summarize drift across every node stored by the fleet controller on
+/// this host.
+pub fn drift(format: &str, severity: Option<&str>) -> Result<()> {
+    let min_severity = severity.map(str::parse::<DriftSeverity>).transpose()?;
+    let state_file = controller_state_file()?;
+    let nodes = fleet_store::read_state(&expand_path(&state_file))?;
+    let summary = fleet_drift::summarize(&nodes, min_severity);
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+        return Ok(());
+    }
+
+    if summary.total_nodes == 0 {
+        println!(
+            "{} No nodes in fleet state {} (is this the fleet controller?)",
+            "::".blue().bold(),
+            state_file
+        );
+        return Ok(());
+    }
+
+    println!(
+        "{} Fleet drift: {} critical, {} warning, {} nodes",
+        ">>".blue().bold(),
+        summary.critical_nodes,
+        summary.warning_nodes,
+        summary.total_nodes
+    );
+    for node in &summary.nodes {
+        let icon = match node.severity {
+            Some(DriftSeverity::Critical) => "!!".red().bold(),
+            Some(DriftSeverity::Warning) => "!!".yellow().bold(),
+            None => "ok".green().bold(),
+        };
+        let line = match node.drift.first() {
+            Some(first) if node.drift.len() > 1 => {
+                format!("{} (+{} more)", first.message, node.drift.len() - 1)
             }
-            std::process::exit(1);
+            Some(first) => first.message.clone(),
+            None => "no drift".to_string(),
+        };
+        println!("  {} {} — {}", icon, node.hostname.bold(), line);
+    }
+    Ok(())
+}
+
+/// One node in a fleet export.
+#[derive(Debug, Serialize)]
+struct ExportedNode {
+    hostname: String,
+    first_seen: Option<DateTime<Utc>>,
+    last_seen: DateTime<Utc>,
+    /// The node's node.yaml, when it is a fleet peer that could be reached.
+    identity: Option<NodeIdentity>,
+    report: StoredReport,
+}
+
+/// Pair each stored node with its identity, by hostname.
+fn export_nodes(
+    nodes: BTreeMap<String, FleetNode>,
+    mut identities: BTreeMap<String, NodeIdentity>,
+) -> Vec<ExportedNode> {
+    nodes
+        .into_iter()
+        .map(|(hostname, node)| ExportedNode {
+            identity: identities.remove(&hostname),
+            first_seen: node.first_seen,
+            last_seen: node.received_at,
+            report: node.report,
+            hostname,
+        })
+        .collect()
+}
+
+/// Write `<hostname>/identity.yaml` and `<hostname>/report.json` under
+/// `dir`, returning the hostnames written. The state file is only as
+/// trustworthy as whoever could write it, so hostnames are checked again.
+fn write_export_tree(dir: &Path, nodes: &[ExportedNode]) -> Result<Vec<String>> {
+    let mut hostnames = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        fleet_store::validate_hostname(&node.hostname)?;
+        let node_dir = dir.join(&node.hostname);
+        std::fs::create_dir_all(&node_dir)
+            .with_context(|| format!("creating {}", node_dir.display()))?;
+        if let Some(ref identity) = node.identity {
+            identity.save(&node_dir.join("identity.yaml"))?;
         }
+        let report = serde_json::to_string_pretty(&node.report)?;
+        std::fs::write(node_dir.join("report.json"), report)
+            .with_context(|| format!("writing {}/report.json", node_dir.display()))?;
+        hostnames.push(node.hostname.clone());
+    }
+    Ok(hostnames)
+}
+
+/// Identities of the stored nodes that are fleet peers in this host's
+/// node.yaml, read over SSH. Unreachable peers are skipped with a warning.
+fn peer_identities(nodes: &BTreeMap<String, FleetNode>) -> BTreeMap<String, NodeIdentity> {
+    let node_path = NodeIdentity::default_path();
+    let Ok(local) = NodeIdentity::load(&node_path) else {
+        return BTreeMap::new();
+    };
+    let mut identities = BTreeMap::new();
+    for hostname in nodes.keys() {
+        let Some(peer) = local
+            .fleet
+            .peers
+            .iter()
+            .find(|p| &p.name == hostname || &p.hostname == hostname)
+        else {
+            continue;
+        };
+        match fetch_remote_identity(peer) {
+            Ok(identity) => {
+                identities.insert(hostname.clone(), identity);
+            }
+            Err(e) => println!("{} {}: {}", "!!".yellow().bold(), peer.name, e),
+        }
+    }
+    identities
+}
+
+/// Export every node the fleet controller on this host stores, with its
+/// first/last seen times and, for fleet peers, its node.yaml. `json`
+/// writes one array; `tar` writes per-node directories.
+pub fn export(output: &str, format: &str) -> Result<()> {
+    if format != "json" && format != "tar" {
+        bail!("unknown export format '{}' (expected json or tar)", format);
+    }
+    let state_file = controller_state_file()?;
+    let nodes = fleet_store::read_state(&expand_path(&state_file))?;
+    if nodes.is_empty() {
+        bail!(
+            "No nodes in fleet state {} (is this the fleet controller?)",
+            state_file
+        );
+    }
+    let identities = peer_identities(&nodes);
+    let exported = export_nodes(nodes, identities);
+    let output = expand_path(output);
+
+    if format == "json" {
+        let content = serde_json::to_string_pretty(&exported)?;
+        std::fs::write(&output, content)
+            .with_context(|| format!("writing {}", output.display()))?;
+    } else {
+        let staging = tempfile::Builder::new()
+            .prefix("kindling-fleet-export-")
+            .tempdir()
+            .context("creating export staging directory")?;
+        write_export_tree(staging.path(), &exported).and_then(|hostnames| {
+            // -a picks compression from the archive suffix (.tar.gz, .tar.zst).
+            let mut args = vec![
+                "-caf".to_string(),
+                output.display().to_string(),
+                "-C".to_string(),
+                staging.path().display().to_string(),
+            ];
+            args.extend(hostnames);
+            run_step("tar", &args)
+        })?;
+    }
+
+    let with_identity = exported.iter().filter(|n| n.identity.is_some()).count();
+    println!(
+        "{} Exported {} node(s) ({} with identity) to {}",
+        "ok".green().bold(),
+        exported.len(),
+        with_identity,
+        output.display()
+    );
+    Ok(())
+}
+
+/// `--build-locally`: build the peer's system closure here, `nix copy` it to
+/// the peer, then activate it remotely with `switch-to-configuration`.
+fn push_closure(peer: &FleetPeer) -> Result<()> {
+    let identity = fetch_remote_identity(peer)?;
+    if crate::commands::profile::find_profile(&identity.profile)
+        .is_some_and(|p| p.platform != "linux")
+    {
+        bail!(
+            "{} uses profile '{}'; --build-locally only supports NixOS peers",
+            peer.name,
+            identity.profile
+        );
+    }
+
+    let gen_dir = peer_generated_dir(peer);
+    nix_gen::generate_in(&identity, &gen_dir)?;
+    println!(
+        "{} Generated Nix config in {}",
+        "ok".green().bold(),
+        gen_dir.display()
+    );
+
+    let build = build_args(&gen_dir, &identity.hostname);
+    println!("{} Running: nix {}", ">>".blue().bold(), build.join(" "));
+    let output = Command::new("nix")
+        .args(&build)
+        .stderr(std::process::Stdio::inherit())
+        .output()
+        .context("failed to run nix build")?;
+    if !output.status.success() {
+        bail!("Local build failed with status {}", output.status);
+    }
+    let out_path = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .last()
+        .map(|l| l.trim().to_string())
+        .context("nix build printed no output path")?;
+    println!("{} Built {}", "ok".green().bold(), out_path);
+
+    run_step("nix", &nix_copy_args(peer, &out_path))?;
+    println!("{} Copied closure to {}", "ok".green().bold(), peer.hostname);
+
+    run_step("ssh", &activate_args(peer, &out_path))?;
+    println!();
+    println!(
+        "{} Successfully deployed to {}",
+        "ok".green().bold(),
+        peer.name
+    );
+    Ok(())
+}
+
+/// Where `--build-locally` generates a peer's Nix config on this host.
+fn peer_generated_dir(peer: &FleetPeer) -> PathBuf {
+    nix_gen::generated_dir().join("fleet").join(&peer.name)
+}
+
+/// Run `command` on the peer over SSH and return its stdout.
+fn remote_output(peer: &FleetPeer, command: &str) -> Result<String> {
+    let output = Command::new("ssh")
+        .args([&format!("{}@{}", peer.ssh_user, peer.hostname), command])
+        .output()
+        .with_context(|| format!("failed to SSH to {}", peer.hostname))?;
+    if !output.status.success() {
+        bail!(
+            "`{}` failed on {}: {}",
+            command,
+            peer.hostname,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
     }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The peer's identity as it resolves it: node.yaml merged over its
+/// `extends` bases and the peer's own overlays.
+fn fetch_remote_identity(peer: &FleetPeer) -> Result<NodeIdentity> {
+    let json = remote_output(peer, "kindling identity show --format json")?;
+    serde_json::from_str(&json)
+        .with_context(|| format!("failed to parse node identity from {}", peer.hostname))
+}
 
+fn run_step(program: &str, args: &[String]) -> Result<()> {
+    println!("{} Running: {} {}", ">>".blue().bold(), program, args.join(" "));
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("failed to run {}", program))?;
+    if !status.success() {
+        bail!("{} failed with status {}", program, status);
+    }
     Ok(())
 }
 
+fn build_args(flake_dir: &Path, hostname: &str) -> Vec<String> {
+    vec![
+        "build".to_string(),
+        "--no-link".to_string(),
+        "--print-out-paths".to_string(),
+        format!(
+            "{}#nixosConfigurations.{}.config.system.build.toplevel",
+            flake_dir.display(),
+            hostname
+        ),
+    ]
+}
+
+fn nix_copy_args(peer: &FleetPeer, out_path: &str) -> Vec<String> {
+    vec![
+        "copy".to_string(),
+        "--to".to_string(),
+        format!("ssh://{}@{}", peer.ssh_user, peer.hostname),
+        out_path.to_string(),
+    ]
+}
+
+/// ssh argv that points the system profile at the closure and switches to it.
+fn activate_args(peer: &FleetPeer, out_path: &str) -> Vec<String> {
+    let sudo = if peer.ssh_user == "root" { "" } else { "sudo " };
+    vec![
+        format!("{}@{}", peer.ssh_user, peer.hostname),
+        format!(
+            "{sudo}nix-env -p /nix/var/nix/profiles/system --set {out_path} && \
+             {sudo}{out_path}/bin/switch-to-configuration switch"
+        ),
+    ]
+}
+
 fn check_ssh_connectivity(hostname: &str, user: &str) -> bool {
     Command::new("ssh")
         .args([
@@ -158,3 +900,170 @@ fn check_ssh_connectivity(hostname: &str, user: &str) -> bool {
         .map(|o| o.status.success())
         .unwrap_or(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUT: &str = "/nix/store/abc123-nixos-system-edge-1-25.11";
+
+    fn peer(user: &str) -> FleetPeer {
+        FleetPeer {
+            name: "edge-1".to_string(),
+            hostname: "10.0.0.7".to_string(),
+            ssh_user: user.to_string(),
+        }
+    }
+
+    #[test]
+    fn json_export_is_array_of_nodes() {
+        use crate::domain::node_report::tests::make_test_report;
+
+        let mut nodes = BTreeMap::new();
+        for hostname in ["edge-2", "edge-1"] {
+            let mut report = make_test_report();
+            report.hostname = hostname.to_string();
+            nodes.insert(
+                hostname.to_string(),
+                FleetNode {
+                    report: StoredReport::new(report),
+                    received_at: Utc::now(),
+                    first_seen: Some(Utc::now()),
+                    last_change: Default::default(),
+                    annotations: Default::default(),
+                    applied: None,
+                },
+            );
+        }
+        let mut identities = BTreeMap::new();
+        identities.insert(
+            "edge-1".to_string(),
+            NodeIdentity::from_bootstrap("cloud-server", "edge-1", "ops", None),
+        );
+
+        let exported = export_nodes(nodes, identities);
+        let value = serde_json::to_value(&exported).unwrap();
+        let array = value.as_array().unwrap();
+        assert_eq!(array.len(), 2);
+        assert_eq!(array[0]["hostname"], "edge-1");
+        assert_eq!(array[1]["hostname"], "edge-2");
+        for node in array {
+            let mut keys: Vec<&str> = node
+                .as_object()
+                .unwrap()
+                .keys()
+                .map(String::as_str)
+                .collect();
+            keys.sort_unstable();
+            assert_eq!(
+                keys,
+                ["first_seen", "hostname", "identity", "last_seen", "report"]
+            );
+            assert_eq!(node["report"]["report"]["hostname"], node["hostname"]);
+        }
+        assert_eq!(array[0]["identity"]["hostname"], "edge-1");
+        assert!(array[1]["identity"].is_null());
+
+        let dir = tempfile::tempdir().unwrap();
+        let written = write_export_tree(dir.path(), &exported).unwrap();
+        assert_eq!(written, ["edge-1", "edge-2"]);
+        assert!(dir.path().join("edge-1/identity.yaml").exists());
+        assert!(dir.path().join("edge-1/report.json").exists());
+        assert!(!dir.path().join("edge-2/identity.yaml").exists());
+
+        let mut escaping = exported;
+        escaping[0].hostname = "../outside".to_string();
+        assert!(write_export_tree(dir.path(), &escaping).is_err());
+        assert!(!dir.path().join("../outside").exists());
+    }
+
+    #[test]
+    fn only_changed_skips_matching_checksum() {
+        let applied = AppliedConfig {
+            checksum: "sha256:aaaa".to_string(),
+            applied_at: Utc::now(),
+        };
+        assert!(is_unchanged(Some("sha256:aaaa"), Some(&applied)));
+        assert!(!is_unchanged(Some("sha256:bbbb"), Some(&applied)));
+        assert!(!is_unchanged(Some("sha256:aaaa"), None));
+        assert!(!is_unchanged(None, Some(&applied)));
+    }
+
+    #[test]
+    fn resumed_run_skips_finished_nodes() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(ApplyRun::latest(dir.path()).unwrap().is_none());
+
+        let mut older = ApplyRun::new();
+        older.run_id = "20260101T000000Z".to_string();
+        older.finish("edge-1", ApplyStatus::Failed, None);
+        older.save(dir.path()).unwrap();
+
+        let mut run = ApplyRun::new();
+        run.run_id = "20260102T000000Z".to_string();
+        run.finish("edge-1", ApplyStatus::Succeeded, None);
+        run.finish(
+            "edge-2",
+            ApplyStatus::Failed,
+            Some("unreachable".to_string()),
+        );
+        run.finish("edge-3", ApplyStatus::Skipped, None);
+        run.start("edge-4");
+        run.save(dir.path()).unwrap();
+
+        let resumed = ApplyRun::latest(dir.path()).unwrap().unwrap();
+        assert_eq!(resumed.run_id, "20260102T000000Z");
+        let pending: Vec<&str> = ["edge-1", "edge-2", "edge-3", "edge-4", "edge-5"]
+            .into_iter()
+            .filter(|node| !resumed.is_done(node))
+            .collect();
+        assert_eq!(pending, ["edge-2", "edge-4", "edge-5"]);
+        assert_eq!(
+            resumed.nodes["edge-2"].error.as_deref(),
+            Some("unreachable")
+        );
+        assert_eq!(resumed.nodes["edge-4"].status, ApplyStatus::Running);
+    }
+
+    #[test]
+    fn build_args_target_toplevel() {
+        assert_eq!(
+            build_args(Path::new("/tmp/gen/edge-1"), "edge-1"),
+            vec![
+                "build",
+                "--no-link",
+                "--print-out-paths",
+                "/tmp/gen/edge-1#nixosConfigurations.edge-1.config.system.build.toplevel",
+            ]
+        );
+    }
+
+    #[test]
+    fn nix_copy_args_use_ssh_store() {
+        assert_eq!(
+            nix_copy_args(&peer("root"), OUT),
+            vec!["copy", "--to", "ssh://root@10.0.0.7", OUT]
+        );
+    }
+
+    #[test]
+    fn activate_args_as_root() {
+        let args = activate_args(&peer("root"), OUT);
+        assert_eq!(args[0], "root@10.0.0.7");
+        assert_eq!(
+            args[1],
+            format!(
+                "nix-env -p /nix/var/nix/profiles/system --set {OUT} && \
+                 {OUT}/bin/switch-to-configuration switch"
+            )
+        );
+    }
+
+    #[test]
+    fn activate_args_non_root_uses_sudo() {
+        let args = activate_args(&peer("deploy"), OUT);
+        assert_eq!(args[0], "deploy@10.0.0.7");
+        assert!(args[1].starts_with("sudo nix-env -p /nix/var/nix/profiles/system"));
+        assert!(args[1].contains(&format!("sudo {OUT}/bin/switch-to-configuration switch")));
+    }
+}
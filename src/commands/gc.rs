@@ -0,0 +1,71 @@
+//! `kindling gc` — collect Nix store garbage on this machine.
+//!
+//! Runs the same GC invocation as the daemon's `POST /api/v1/gc`, but
+//! through a local `NixService`, so it works without a running daemon.
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::config;
+use crate::domain::nix_service::NixService;
+use crate::domain::types::GcOptions;
+
+pub fn run(dry_run: bool, older_than: Option<&str>, format: &str) -> Result<()> {
+    let options = GcOptions {
+        older_than: older_than.map(str::to_string),
+        max_freed: None,
+    };
+    let daemon_config = config::load()?.daemon.unwrap_or_default();
+    let service = NixService::new(daemon_config);
+    let rt = tokio::runtime::Runtime::new()?;
+
+    if dry_run {
+        let lines = rt.block_on(service.gc_dry_run(&options))?;
+        if format == "json" {
+            println!("{}", serde_json::to_string_pretty(&lines)?);
+            return Ok(());
+        }
+        for line in &lines {
+            println!("  {}", line);
+        }
+        let paths = lines
+            .iter()
+            .filter(|l| l.starts_with("/nix/store/"))
+            .count();
+        let generations = lines.len() - paths;
+        if generations > 0 {
+            println!(
+                "{} Dry run: {} profile generation(s) would be removed, then {} store path(s) \
+                 already dead would be deleted",
+                "ok".green().bold(),
+                generations,
+                paths
+            );
+            println!("   Paths kept alive only by those generations are not counted.");
+        } else {
+            println!(
+                "{} Dry run: {} store path(s) would be deleted",
+                "ok".green().bold(),
+                paths
+            );
+        }
+        return Ok(());
+    }
+
+    if format != "json" {
+        eprintln!("{} Collecting Nix store garbage...", ">>".blue().bold());
+    }
+    let result = rt.block_on(service.trigger_gc_with(&options))?;
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    println!(
+        "{} Freed {:.1} MiB ({} store paths) in {:.1}s",
+        "ok".green().bold(),
+        result.freed_bytes as f64 / 1_048_576.0,
+        result.freed_paths,
+        result.duration_secs
+    );
+    Ok(())
+}
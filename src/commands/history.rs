@@ -0,0 +1,197 @@
+//! `kindling history` — audit trail of `kindling apply` runs.
+//!
+//! Each apply appends one JSON line to `~/.config/kindling/apply-history.jsonl`
+//! recording what was applied and whether it succeeded.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApplyHistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub profile: String,
+    pub hostname: String,
+    /// SHA-256 of node.yaml at apply time: "sha256:<hex>"
+    pub node_checksum: String,
+    /// `/run/current-system` after the apply, when resolvable.
+    #[serde(default)]
+    pub system_path: Option<String>,
+    pub success: bool,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Default history file location.
+pub fn history_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("~/.config"))
+        .join("kindling")
+        .join("apply-history.jsonl")
+}
+
+/// Checksum of a node.yaml file's raw bytes.
+pub fn file_checksum(path: &Path) -> Result<String> {
+    let content =
+        std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(format!("sha256:{:x}", Sha256::digest(&content)))
+}
+
+/// Resolve `/run/current-system` to its store path.
+pub fn current_system_path() -> Option<String> {
+    std::fs::canonicalize("/run/current-system")
+        .ok()
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+/// Append one entry as a single line. Opening with O_APPEND keeps concurrent
+/// writers from interleaving within a line; the file is created on first use.
+pub fn append(path: &Path, entry: &ApplyHistoryEntry) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {}", parent.display()))?;
+    }
+    let mut line = serde_json::to_string(entry).context("failed to serialize history entry")?;
+    line.push('\n');
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("failed to append to {}", path.display()))?;
+    Ok(())
+}
+
+/// Read all entries, oldest first. Unparseable lines are skipped.
+pub fn read(path: &Path) -> Result<Vec<ApplyHistoryEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect())
+}
+
+pub fn run(format: &str, limit: usize) -> Result<()> {
+    let path = history_path();
+    let entries = read(&path)?;
+    let shown = &entries[entries.len().saturating_sub(limit)..];
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(shown)?);
+        return Ok(());
+    }
+
+    if shown.is_empty() {
+        println!(
+            "{} No apply history yet ({})",
+            "::".blue().bold(),
+            path.display()
+        );
+        return Ok(());
+    }
+
+    for entry in shown.iter().rev() {
+        let status = if entry.success {
+            "ok".green().bold()
+        } else {
+            "!!".red().bold()
+        };
+        println!(
+            "  {} {}  {} @ {}",
+            status,
+            entry.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+            entry.profile.bold(),
+            entry.hostname
+        );
+        println!(
+            "       node.yaml {}",
+            &entry.node_checksum[..std::cmp::min(entry.node_checksum.len(), 24)]
+        );
+        if let Some(ref system) = entry.system_path {
+            println!("       system    {}", system.dimmed());
+        }
+        if let Some(ref err) = entry.error {
+            println!("       error     {}", err.red());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(success: bool) -> ApplyHistoryEntry {
+        ApplyHistoryEntry {
+            timestamp: DateTime::parse_from_rfc3339("2026-01-01T12:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            profile: "k3s-server".to_string(),
+            hostname: "node-1".to_string(),
+            node_checksum: "sha256:abc123".to_string(),
+            system_path: Some("/nix/store/xyz-nixos-system-node-1".to_string()),
+            success,
+            error: (!success).then(|| "nixos-rebuild exited with status 1".to_string()),
+        }
+    }
+
+    #[test]
+    fn entry_serializes_as_single_line() {
+        let json = serde_json::to_string(&entry(true)).unwrap();
+        assert!(!json.contains('\n'));
+        assert!(json.contains("\"timestamp\":\"2026-01-01T12:00:00Z\""));
+        assert!(json.contains("\"profile\":\"k3s-server\""));
+        assert!(json.contains("\"success\":true"));
+        let back: ApplyHistoryEntry = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, entry(true));
+    }
+
+    #[test]
+    fn entry_optional_fields_default() {
+        let json = r#"{"timestamp":"2026-01-01T12:00:00Z","profile":"p","hostname":"h","node_checksum":"sha256:0","success":false}"#;
+        let e: ApplyHistoryEntry = serde_json::from_str(json).unwrap();
+        assert!(e.system_path.is_none());
+        assert!(e.error.is_none());
+    }
+
+    #[test]
+    fn append_creates_file_and_reads_back_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("apply-history.jsonl");
+        append(&path, &entry(true)).unwrap();
+        append(&path, &entry(false)).unwrap();
+
+        let entries = read(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].success);
+        assert!(!entries[1].success);
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn read_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read(&dir.path().join("none.jsonl")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn file_checksum_matches_sha256() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.yaml");
+        std::fs::write(&path, "hostname: node-1\n").unwrap();
+        let sum = file_checksum(&path).unwrap();
+        assert!(sum.starts_with("sha256:"));
+        assert_eq!(sum.len(), "sha256:".len() + 64);
+    }
+}
@@ -0,0 +1,309 @@
+//! `kindling identity init` — guided node.yaml generation.
+//!
+//! Prompts for profile, hostname, user, email, and age key file, then writes
+//! the identity via `NodeIdentity::from_bootstrap`. Without a TTY every value
+//! must be passed as a flag instead.
+//!
+//! `kindling identity validate` — parse node.yaml and check its profile;
+//! with `--against-report`, also check the node conforms to it right now.
+//!
+//! `kindling identity show` — print node.yaml as this node resolves it,
+//! with its `extends` bases and overlays merged.
+
+use std::io::{IsTerminal, Write};
+use std::process::ExitCode;
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+
+use crate::commands::profile::PROFILES;
+use crate::commands::report::{live_report, report_config_of};
+use crate::config;
+use crate::domain::identity_conformance;
+use crate::node_identity::NodeIdentity;
+
+/// Values supplied on the command line; `None` means "prompt for it".
+pub struct InitArgs {
+    pub profile: Option<String>,
+    pub hostname: Option<String>,
+    pub user: Option<String>,
+    pub email: Option<String>,
+    pub age_key_file: Option<String>,
+    pub force: bool,
+}
+
+pub fn init(args: InitArgs) -> Result<()> {
+    let node_path = NodeIdentity::default_path();
+    if node_path.exists() && !args.force {
+        bail!(
+            "node identity already exists at {}\n   \
+             Pass --force to overwrite it.",
+            node_path.display()
+        );
+    }
+
+    let interactive = std::io::stdin().is_terminal();
+    if !interactive {
+        let missing = missing_flags(&args);
+        if !missing.is_empty() {
+            bail!(
+                "stdin is not a terminal; pass all values as flags (missing: {})",
+                missing.join(", ")
+            );
+        }
+    }
+
+    let profile = match args.profile {
+        Some(p) => resolve_profile_choice(&p)?.to_string(),
+        None => prompt_profile()?,
+    };
+    let hostname = match args.hostname {
+        Some(h) => h,
+        None => prompt("Hostname", Some(&detected_hostname()))?,
+    };
+    let user = match args.user {
+        Some(u) => u,
+        None => prompt("User", std::env::var("USER").ok().as_deref())?,
+    };
+    let email = match args.email {
+        Some(e) => e,
+        None => prompt("Email", None)?,
+    };
+    let age_key_file = match args.age_key_file {
+        Some(a) => Some(a),
+        None if interactive => {
+            Some(prompt("Age key file (blank for none)", Some(""))?).filter(|s| !s.is_empty())
+        }
+        None => None,
+    };
+
+    let mut identity =
+        NodeIdentity::from_bootstrap(&profile, &hostname, &user, age_key_file.as_deref());
+    identity.user.email = email.clone();
+    identity.git.user.email = email;
+
+    identity.save(&node_path)?;
+    println!(
+        "{} Node identity saved to {}",
+        "ok".green().bold(),
+        node_path.display()
+    );
+    println!(
+        "{} Run `kindling apply --diff` to preview the generated configuration.",
+        "::".blue().bold()
+    );
+    Ok(())
+}
+
+/// Print the identity merged over its `extends` chain and overlays, the
+/// same one the daemon loads.
+pub fn show(format: &str) -> Result<()> {
+    let cfg = config::load()?;
+    let identity = NodeIdentity::load_with_overlays(
+        &NodeIdentity::default_path(),
+        &cfg.identity.overlay_dirs,
+    )?;
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&identity)?),
+        _ => print!("{}", serde_yaml::to_string(&identity)?),
+    }
+    Ok(())
+}
+
+/// Exit code of `identity validate --against-report` when the live node
+/// doesn't conform. Kept apart from 1, which any other error exits with.
+pub const EXIT_DRIFT: u8 = 2;
+
+/// Validate node.yaml; [`EXIT_DRIFT`] when `against_report` finds a field
+/// the live node doesn't conform to.
+pub fn validate(against_report: bool, fresh: bool, format: &str) -> Result<ExitCode> {
+    let node_path = NodeIdentity::default_path();
+    let identity = NodeIdentity::load(&node_path)?;
+    resolve_profile_choice(&identity.profile)?;
+    if !against_report {
+        println!("{} {} is valid", "ok".green().bold(), node_path.display());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let cfg = config::load()?;
+    let rt = tokio::runtime::Runtime::new()?;
+    let stored = rt.block_on(live_report(&cfg, &report_config_of(&cfg), fresh, None))?;
+    let checks = identity_conformance::check(&identity, &stored.report);
+    let failed = checks.iter().filter(|c| !c.conformant).count();
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&checks)?),
+        _ => {
+            println!(
+                "{} Checking {} against {}",
+                ">>".blue().bold(),
+                stored.report.hostname.bold(),
+                node_path.display()
+            );
+            for c in &checks {
+                if c.conformant {
+                    println!("  {} {} = {}", "ok".green().bold(), c.field, c.actual);
+                } else {
+                    println!(
+                        "  {} {} declared {}, actual {}",
+                        "!!".red().bold(),
+                        c.field,
+                        c.declared,
+                        c.actual
+                    );
+                }
+            }
+            println!();
+            println!(
+                "  {} conformant, {} non-conformant",
+                checks.len() - failed,
+                failed
+            );
+        }
+    }
+
+    Ok(if failed > 0 {
+        ExitCode::from(EXIT_DRIFT)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// Flags that must be present when prompting is impossible.
+fn missing_flags(args: &InitArgs) -> Vec<&'static str> {
+    let mut missing = Vec::new();
+    if args.profile.is_none() {
+        missing.push("--profile");
+    }
+    if args.hostname.is_none() {
+        missing.push("--hostname");
+    }
+    if args.user.is_none() {
+        missing.push("--user");
+    }
+    if args.email.is_none() {
+        missing.push("--email");
+    }
+    missing
+}
+
+/// Accept a profile by name or by its 1-based position in the list.
+fn resolve_profile_choice(input: &str) -> Result<&'static str> {
+    let input = input.trim();
+    if let Ok(n) = input.parse::<usize>() {
+        if let Some(p) = n.checked_sub(1).and_then(|i| PROFILES.get(i)) {
+            return Ok(p.name);
+        }
+    }
+    match PROFILES.iter().find(|p| p.name == input) {
+        Some(p) => Ok(p.name),
+        None => bail!(
+            "unknown profile '{}'. Available profiles: {}",
+            input,
+            PROFILES.iter().map(|p| p.name).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+fn prompt_profile() -> Result<String> {
+    eprintln!("{}", "Available profiles:".bold());
+    for (i, p) in PROFILES.iter().enumerate() {
+        eprintln!("  {}) {} {}", i + 1, p.name, format!("({})", p.platform).dimmed());
+    }
+    loop {
+        let answer = prompt("Profile", None)?;
+        match resolve_profile_choice(&answer) {
+            Ok(name) => return Ok(name.to_string()),
+            Err(e) => eprintln!("{} {}", "!!".yellow().bold(), e),
+        }
+    }
+}
+
+/// Read one line from stdin. Empty input takes `default`; with no default,
+/// keep asking until something is entered.
+fn prompt(label: &str, default: Option<&str>) -> Result<String> {
+    loop {
+        match default {
+            Some(d) if !d.is_empty() => eprint!("{} {} [{}]: ", "??".blue().bold(), label, d),
+            _ => eprint!("{} {}: ", "??".blue().bold(), label),
+        }
+        std::io::stderr().flush()?;
+
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input)? == 0 {
+            bail!("unexpected end of input while reading {}", label);
+        }
+        let input = input.trim();
+        if !input.is_empty() {
+            return Ok(input.to_string());
+        }
+        if let Some(d) = default {
+            return Ok(d.to_string());
+        }
+    }
+}
+
+fn detected_hostname() -> String {
+    hostname::get()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "localhost".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args() -> InitArgs {
+        InitArgs {
+            profile: None,
+            hostname: None,
+            user: None,
+            email: None,
+            age_key_file: None,
+            force: false,
+        }
+    }
+
+    #[test]
+    fn resolve_profile_by_name() {
+        assert_eq!(resolve_profile_choice("k3s-agent").unwrap(), "k3s-agent");
+    }
+
+    #[test]
+    fn resolve_profile_by_number() {
+        assert_eq!(resolve_profile_choice("1").unwrap(), PROFILES[0].name);
+        assert_eq!(
+            resolve_profile_choice(&PROFILES.len().to_string()).unwrap(),
+            PROFILES[PROFILES.len() - 1].name
+        );
+    }
+
+    #[test]
+    fn resolve_profile_rejects_unknown() {
+        assert!(resolve_profile_choice("0").is_err());
+        assert!(resolve_profile_choice("99").is_err());
+        let msg = resolve_profile_choice("windows-gamer").unwrap_err().to_string();
+        assert!(msg.contains("windows-gamer"));
+        assert!(msg.contains("macos-developer"));
+    }
+
+    #[test]
+    fn missing_flags_lists_required_values() {
+        assert_eq!(
+            missing_flags(&args()),
+            vec!["--profile", "--hostname", "--user", "--email"]
+        );
+    }
+
+    #[test]
+    fn missing_flags_age_key_is_optional() {
+        let a = InitArgs {
+            profile: Some("k3s-server".into()),
+            hostname: Some("node-1".into()),
+            user: Some("ops".into()),
+            email: Some("ops@example.com".into()),
+            ..args()
+        };
+        assert!(missing_flags(&a).is_empty());
+    }
+}
@@ -3,6 +3,7 @@ use colored::Colorize;
 use std::process::Command;
 
 use crate::nix;
+use crate::output::{self, progress};
 use crate::platform::{self, Backend};
 
 pub fn run(backend: Backend, no_confirm: bool) -> Result<()> {
@@ -11,7 +12,7 @@ pub fn run(backend: Backend, no_confirm: bool) -> Result<()> {
     let tmp = std::env::temp_dir().join("nix-installer");
     let tmp_str = tmp.to_string_lossy().to_string();
 
-    println!(
+    progress!(
         "{} Downloading nix-installer ({} backend)...",
         "::".blue().bold(),
         backend
@@ -30,7 +31,7 @@ pub fn run(backend: Backend, no_confirm: bool) -> Result<()> {
         .status()
         .context("failed to chmod installer")?;
 
-    println!("{} Running nix-installer...", "::".blue().bold());
+    progress!("{} Running nix-installer...", "::".blue().bold());
 
     let mut cmd = Command::new(&tmp);
     cmd.arg("install");
@@ -41,7 +42,10 @@ pub fn run(backend: Backend, no_confirm: bool) -> Result<()> {
         cmd.args(["--init", "none"]);
     }
 
-    let status = cmd.status().context("failed to run nix-installer")?;
+    let status = cmd
+        .stdout(output::child_stdout())
+        .status()
+        .context("failed to run nix-installer")?;
     if !status.success() {
         bail!("nix-installer exited with status {}", status);
     }
@@ -50,20 +54,20 @@ pub fn run(backend: Backend, no_confirm: bool) -> Result<()> {
     let nix_status = nix::detect();
     if nix_status.installed {
         if let Some(ver) = nix_status.version {
-            println!(
+            progress!(
                 "{} Nix {} installed successfully",
                 "ok".green().bold(),
                 ver
             );
         } else {
-            println!("{} Nix installed successfully", "ok".green().bold());
+            progress!("{} Nix installed successfully", "ok".green().bold());
         }
     } else {
-        println!(
+        eprintln!(
             "{} Installation completed but nix not found on PATH.",
             "!!".yellow().bold()
         );
-        println!("   You may need to restart your shell or source the nix profile.");
+        eprintln!("   You may need to restart your shell or source the nix profile.");
     }
 
     Ok(())
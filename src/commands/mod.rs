@@ -4,16 +4,23 @@ pub mod ami_test;
 pub mod apply;
 pub mod bootstrap;
 pub mod check;
+pub mod config;
 pub mod daemon;
+pub mod discover;
 pub mod ensure;
 pub mod fleet;
+pub mod gc;
 pub mod harden;
+pub mod history;
+pub mod identity;
 pub mod init;
 pub mod install;
 pub mod pki;
 pub mod profile;
 pub mod query;
 pub mod report;
+pub mod report_html;
 pub mod server;
+pub mod store;
 pub mod uninstall;
 pub mod vpn;
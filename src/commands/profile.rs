@@ -7,14 +7,14 @@ use colored::Colorize;
 
 /// Known profiles — mirrors kindling-profiles/lib.profileMeta.
 /// In the future this can be fetched from the flake at runtime.
-struct ProfileInfo {
-    name: &'static str,
-    platform: &'static str,
+pub(crate) struct ProfileInfo {
+    pub(crate) name: &'static str,
+    pub(crate) platform: &'static str,
     description: &'static str,
     components: &'static [&'static str],
 }
 
-const PROFILES: &[ProfileInfo] = &[
+pub(crate) const PROFILES: &[ProfileInfo] = &[
     ProfileInfo {
         name: "macos-developer",
         platform: "darwin",
@@ -63,10 +63,28 @@ pub fn list() -> Result<()> {
 }
 
 /// Look up a profile by name from the built-in registry.
-fn find_profile(name: &str) -> Option<&'static ProfileInfo> {
+pub(crate) fn find_profile(name: &str) -> Option<&'static ProfileInfo> {
     PROFILES.iter().find(|p| p.name == name)
 }
 
+/// System rebuild command for a profile platform (`darwin` / `linux`).
+pub(crate) fn rebuild_command(platform: &str) -> Option<&'static str> {
+    match platform {
+        "darwin" => Some("darwin-rebuild"),
+        "linux" => Some("nixos-rebuild"),
+        _ => None,
+    }
+}
+
+/// Profile platform name for the running OS.
+pub(crate) fn host_platform() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "darwin"
+    } else {
+        "linux"
+    }
+}
+
 pub fn show(name: &str) -> Result<()> {
     match find_profile(name) {
         Some(p) => {
@@ -153,6 +171,24 @@ mod tests {
         assert!(find_profile("nonexistent-profile").is_none());
     }
 
+    #[test]
+    fn rebuild_command_per_platform() {
+        assert_eq!(rebuild_command("darwin"), Some("darwin-rebuild"));
+        assert_eq!(rebuild_command("linux"), Some("nixos-rebuild"));
+        assert_eq!(rebuild_command("windows"), None);
+    }
+
+    #[test]
+    fn all_profiles_have_rebuild_command() {
+        for p in PROFILES {
+            assert!(
+                rebuild_command(p.platform).is_some(),
+                "profile '{}' has no rebuild command",
+                p.name
+            );
+        }
+    }
+
     #[test]
     fn profile_names_are_unique() {
         let mut names: Vec<&str> = PROFILES.iter().map(|p| p.name).collect();
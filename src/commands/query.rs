@@ -1,53 +1,196 @@
 //! `kindling query` — query a kindling daemon via its REST API.
 
-use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Result};
 use clap::Subcommand;
+use colored::Colorize;
 
-use crate::client::KindlingClient;
+use crate::client::{ClientTls, KindlingClient, DEFAULT_TIMEOUT};
 use crate::config;
+use crate::domain::nix_service::{parse_store_path_name, validate_gc_age, validate_store_path};
+use crate::domain::types::{GcOptions, StorePathName};
+use crate::domain::yaml_diff::{self, FieldChange};
+use crate::node_identity::NodeIdentity;
 
 #[derive(Subcommand)]
 pub enum QueryCommands {
     /// Daemon health check
     Health,
+    /// Daemon version, git commit and compiled-in features (e.g. gRPC)
+    Version,
     /// Nix installation status
     Status,
     /// Platform information
     Platform,
     /// Nix store information
-    Store,
+    Store {
+        /// List GC roots (what keeps store paths alive) instead of the summary
+        #[arg(long)]
+        gc_roots: bool,
+
+        /// With --gc-roots, include runtime roots held by running processes
+        #[arg(long, requires = "gc_roots")]
+        include_transient: bool,
+    },
+    /// Show why one store path depends on another
+    WhyDepends {
+        /// Store path that has the dependency
+        from: String,
+        /// Store path it depends on
+        to: String,
+    },
+    /// Every store path in the current system's closure (for SBOMs and
+    /// vulnerability scanners)
+    SystemClosure {
+        /// Print each path's package name and version instead of the path
+        #[arg(long)]
+        names: bool,
+    },
     /// Nix configuration
     NixConfig,
     /// Garbage collection status
     GcStatus,
     /// Trigger garbage collection
-    GcRun,
+    GcRun {
+        /// First delete profile generations older than this many days (e.g. 30d)
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+
+        /// Stop after freeing this many bytes
+        #[arg(long, value_name = "BYTES")]
+        max_freed: Option<u64>,
+    },
     /// Optimise the Nix store
-    Optimise,
+    Optimise {
+        /// Only estimate what deduplication would free; the store is untouched
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Check store paths against their recorded hashes (slow: reads the whole store)
+    StoreVerify {
+        /// Store paths to check (default: every valid path)
+        paths: Vec<String>,
+
+        /// Re-fetch or rebuild corrupted and missing paths
+        #[arg(long)]
+        repair: bool,
+    },
     /// Binary cache reachability
     Caches,
     /// Node identity (from node.yaml)
-    Identity,
+    Identity {
+        /// Compare the daemon's identity against this node.yaml instead of
+        /// printing it: `+` fields only in FILE, `-` only in the daemon's
+        #[arg(long, value_name = "FILE")]
+        diff: Option<PathBuf>,
+    },
     /// Cached runtime report
     Report,
     /// Force-refresh the runtime report
     RefreshReport,
 }
 
-pub fn run(node: Option<&str>, format: &str, command: &QueryCommands) -> Result<()> {
+impl QueryCommands {
+    /// Subcommands that change daemon or store state; never re-run these
+    /// on a timer.
+    fn is_mutating(&self) -> bool {
+        matches!(
+            self,
+            Self::GcRun { .. }
+                | Self::Optimise { dry_run: false }
+                | Self::StoreVerify { .. }
+                | Self::RefreshReport
+        )
+    }
+
+    /// Request timeout when `--timeout` isn't given: store-wide operations
+    /// can run for many minutes, while a health check should fail fast.
+    fn default_timeout(&self) -> Duration {
+        match self {
+            Self::GcRun { .. } | Self::Optimise { .. } | Self::StoreVerify { .. } => {
+                Duration::from_secs(30 * 60)
+            }
+            Self::RefreshReport => Duration::from_secs(2 * 60),
+            Self::Health => Duration::from_secs(3),
+            _ => DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+pub fn run(
+    node: Option<&str>,
+    format: &str,
+    watch: Option<u64>,
+    timeout: Option<u64>,
+    tls: &ClientTls,
+    command: &QueryCommands,
+) -> Result<()> {
+    if let Some(secs) = watch {
+        if command.is_mutating() {
+            bail!("--watch only works with read-only queries; this subcommand modifies state");
+        }
+        if secs == 0 {
+            bail!("--watch interval must be at least 1 second");
+        }
+    }
+    if timeout == Some(0) {
+        bail!("--timeout must be at least 1 second");
+    }
+    let timeout = timeout
+        .map(Duration::from_secs)
+        .unwrap_or_else(|| command.default_timeout());
+
     let rt = tokio::runtime::Runtime::new()?;
-    rt.block_on(run_async(node, format, command))
+    rt.block_on(async {
+        let cfg = config::load()?;
+        let tls = tls.clone().or_config(&cfg);
+        let client = KindlingClient::from_config(node, &cfg, timeout, &tls)?;
+        match watch {
+            Some(secs) => watch_loop(&client, format, command, Duration::from_secs(secs)).await,
+            None => dispatch(&client, format, command).await,
+        }
+    })
 }
 
-async fn run_async(node: Option<&str>, format: &str, command: &QueryCommands) -> Result<()> {
-    let cfg = config::load()?;
-    let client = KindlingClient::from_node(node, &cfg.nodes)?;
+/// Clear the terminal and re-run `command` every `interval` until Ctrl+C.
+/// A failed poll is shown in place of the output rather than ending the watch.
+async fn watch_loop(
+    client: &KindlingClient,
+    format: &str,
+    command: &QueryCommands,
+    interval: Duration,
+) -> Result<()> {
+    loop {
+        print!("\x1b[2J\x1b[H");
+        println!(
+            "{} every {}s, Ctrl+C to stop ({})\n",
+            "::".blue().bold(),
+            interval.as_secs(),
+            chrono::Local::now().format("%H:%M:%S")
+        );
+        if let Err(e) = dispatch(client, format, command).await {
+            println!("{} {:#}", "!!".red().bold(), e);
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+    }
+}
 
+async fn dispatch(client: &KindlingClient, format: &str, command: &QueryCommands) -> Result<()> {
     match command {
         QueryCommands::Health => {
             let data = client.health().await?;
             print_output(format, &data)
         }
+        QueryCommands::Version => {
+            let data = client.version().await?;
+            print_output(format, &data)
+        }
         QueryCommands::Status => {
             let data = client.status().await?;
             print_output(format, &data)
@@ -56,10 +199,38 @@ async fn run_async(node: Option<&str>, format: &str, command: &QueryCommands) ->
             let data = client.platform().await?;
             print_output(format, &data)
         }
-        QueryCommands::Store => {
+        QueryCommands::Store {
+            gc_roots: true,
+            include_transient,
+        } => {
+            let data = client.gc_roots(*include_transient).await?;
+            print_output(format, &data)
+        }
+        QueryCommands::Store { .. } => {
             let data = client.store().await?;
             print_output(format, &data)
         }
+        QueryCommands::WhyDepends { from, to } => {
+            let data = client.why_depends(from, to).await?;
+            print_output(format, &data)
+        }
+        QueryCommands::SystemClosure { names } => {
+            let paths = client.system_closure().await?;
+            if !*names {
+                return print_output(format, &paths);
+            }
+            let parsed: Vec<StorePathName> = paths
+                .iter()
+                .filter_map(|p| parse_store_path_name(p))
+                .collect();
+            if format == "json" {
+                return print_output(format, &parsed);
+            }
+            for p in &parsed {
+                println!("{} {}", p.name, p.version.as_deref().unwrap_or("-"));
+            }
+            Ok(())
+        }
         QueryCommands::NixConfig => {
             let data = client.nix_config().await?;
             print_output(format, &data)
@@ -68,19 +239,52 @@ async fn run_async(node: Option<&str>, format: &str, command: &QueryCommands) ->
             let data = client.gc_status().await?;
             print_output(format, &data)
         }
-        QueryCommands::GcRun => {
-            let data = client.gc_run().await?;
+        QueryCommands::GcRun {
+            older_than,
+            max_freed,
+        } => {
+            if let Some(age) = older_than {
+                validate_gc_age(age)?;
+            }
+            let options = GcOptions {
+                older_than: older_than.clone(),
+                max_freed: *max_freed,
+            };
+            let data = client.gc_run(&options).await?;
+            print_output(format, &data)
+        }
+        QueryCommands::Optimise { dry_run: true } => {
+            let data = client.optimise_estimate().await?;
             print_output(format, &data)
         }
-        QueryCommands::Optimise => {
+        QueryCommands::Optimise { dry_run: false } => {
             let data = client.optimise().await?;
             print_output(format, &data)
         }
+        QueryCommands::StoreVerify { paths, repair } => {
+            for path in paths {
+                validate_store_path(path)?;
+            }
+            if paths.is_empty() {
+                eprintln!(
+                    "{} Verifying the whole store reads every path; this can take a long time",
+                    "!!".yellow().bold()
+                );
+            }
+            let data = client.store_verify(paths, *repair).await?;
+            print_output(format, &data)
+        }
         QueryCommands::Caches => {
             let data = client.caches().await?;
             print_output(format, &data)
         }
-        QueryCommands::Identity => {
+        QueryCommands::Identity { diff: Some(file) } => {
+            let Some(daemon) = client.identity().await? else {
+                bail!("the daemon has no node identity loaded");
+            };
+            print_identity_diff(format, &daemon, file)
+        }
+        QueryCommands::Identity { diff: None } => {
             let data = client.identity().await?;
             print_output(format, &data)
         }
@@ -95,6 +299,49 @@ async fn run_async(node: Option<&str>, format: &str, command: &QueryCommands) ->
     }
 }
 
+/// Diff the daemon's effective identity against a local node.yaml. Both
+/// go through `NodeIdentity`, so defaults the file leaves out don't show
+/// up as differences.
+fn print_identity_diff(format: &str, daemon: &NodeIdentity, file: &Path) -> Result<()> {
+    let local = NodeIdentity::load(file)?;
+    let changes = yaml_diff::diff(
+        &serde_yaml::to_value(daemon)?,
+        &serde_yaml::to_value(&local)?,
+    );
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+        return Ok(());
+    }
+    if changes.is_empty() {
+        println!(
+            "{} Daemon identity matches {}",
+            "ok".green().bold(),
+            file.display()
+        );
+        return Ok(());
+    }
+    println!(
+        "{} {} field(s) differ between the daemon and {}:",
+        "!!".yellow().bold(),
+        changes.len(),
+        file.display()
+    );
+    for change in &changes {
+        match change {
+            FieldChange::Added { path, value } => {
+                println!("  {} {}: {}", "+".green().bold(), path, value)
+            }
+            FieldChange::Removed { path, value } => {
+                println!("  {} {}: {}", "-".red().bold(), path, value)
+            }
+            FieldChange::Changed { path, from, to } => {
+                println!("  {} {}: {} -> {}", "~".yellow().bold(), path, from, to)
+            }
+        }
+    }
+    Ok(())
+}
+
 fn print_output<T: serde::Serialize>(format: &str, data: &T) -> Result<()> {
     match format {
         "json" => {
@@ -171,3 +418,79 @@ fn format_scalar(value: &serde_json::Value) -> String {
         other => other.to_string(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watch_rejects_mutating_subcommands() {
+        for command in [
+            QueryCommands::GcRun {
+                older_than: Some("7d".to_string()),
+                max_freed: None,
+            },
+            QueryCommands::Optimise { dry_run: false },
+            QueryCommands::StoreVerify {
+                paths: vec![],
+                repair: false,
+            },
+            QueryCommands::RefreshReport,
+        ] {
+            let err =
+                run(None, "json", Some(5), None, &ClientTls::default(), &command).unwrap_err();
+            assert!(err.to_string().contains("read-only"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn watch_rejects_zero_interval() {
+        let err = run(
+            None,
+            "json",
+            Some(0),
+            None,
+            &ClientTls::default(),
+            &QueryCommands::Health,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("at least 1 second"));
+    }
+
+    #[test]
+    fn read_only_subcommands_are_watchable() {
+        assert!(!QueryCommands::Health.is_mutating());
+        assert!(!QueryCommands::GcStatus.is_mutating());
+        assert!(!QueryCommands::Caches.is_mutating());
+        assert!(!QueryCommands::Optimise { dry_run: true }.is_mutating());
+        assert!(QueryCommands::GcRun {
+            older_than: None,
+            max_freed: None
+        }
+        .is_mutating());
+    }
+
+    #[test]
+    fn timeout_defaults_follow_the_operation() {
+        assert_eq!(
+            QueryCommands::Health.default_timeout(),
+            Duration::from_secs(3)
+        );
+        assert_eq!(QueryCommands::Status.default_timeout(), DEFAULT_TIMEOUT);
+        assert!(
+            QueryCommands::Optimise { dry_run: false }.default_timeout()
+                >= Duration::from_secs(600)
+        );
+        assert!(QueryCommands::RefreshReport.default_timeout() > DEFAULT_TIMEOUT);
+        let err = run(
+            None,
+            "json",
+            None,
+            Some(0),
+            &ClientTls::default(),
+            &QueryCommands::Health,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("--timeout"));
+    }
+}
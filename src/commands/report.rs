@@ -1,115 +1,449 @@
 //! `kindling report` — generate and display a runtime report for this node.
+//!
+//! With `--fresh` the exit code tells monitoring scripts how collection went:
+//! 0 when every section was collected, 1 when any section fell back to its
+//! defaults, 2 when no report could be produced at all.
 
-use std::path::PathBuf;
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Context, Result};
 use colored::Colorize;
 
-use crate::client::KindlingClient;
+use crate::client::{api_client_builder, ClientTls, KindlingClient, DEFAULT_TIMEOUT};
+use crate::commands::report_html;
 use crate::config;
-use crate::domain::node_report::StoredReport;
+use crate::domain::node_report::{CollectionWarning, FindingSeverity, NodeReport, StoredReport};
+use crate::domain::report_baseline::Baseline;
 use crate::domain::report_collector::ReportCollector;
+use crate::domain::report_health::{self, OverallStatus};
+use crate::domain::report_metrics;
+use crate::domain::report_outbox::{
+    ensure_drained, is_rejected, push, push_endpoint, Outbox, RetryPolicy, PUSH_TIMEOUT,
+};
+use crate::domain::remote_collector;
 use crate::domain::report_store::ReportStore;
+use crate::domain::store_trend;
+use crate::node_identity::NodeIdentity;
+use crate::paths::expand_path;
 
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     format: &str,
     push: bool,
     controller_url: Option<&str>,
     fresh: bool,
     cached: bool,
-) -> Result<()> {
+    compare_baseline: Option<&Path>,
+    redact: bool,
+    remote: Option<&str>,
+    explain_fallbacks: bool,
+    flush_outbox: bool,
+    collect_timeout: Option<u64>,
+    output: Option<&Path>,
+) -> Result<ExitCode> {
+    if output.is_some() && format != "html" {
+        bail!("--output is only supported with --format html");
+    }
     let rt = tokio::runtime::Runtime::new()?;
-    rt.block_on(async { run_async(format, push, controller_url, fresh, cached).await })
+    if let Some(baseline) = compare_baseline {
+        return rt.block_on(compare_against_baseline(format, baseline));
+    }
+    if flush_outbox {
+        rt.block_on(flush_queued_reports(controller_url))?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    rt.block_on(async {
+        run_async(
+            format,
+            push,
+            controller_url,
+            fresh,
+            cached,
+            redact,
+            remote,
+            explain_fallbacks,
+            collect_timeout.map(Duration::from_secs),
+            output,
+        )
+        .await
+    })
+}
+
+/// `--fresh` exit code: every section collected.
+pub const EXIT_COMPLETE: u8 = 0;
+/// `--fresh` exit code: at least one section fell back to defaults.
+pub const EXIT_PARTIAL: u8 = 1;
+/// `--fresh` exit code: collection failed or timed out; no report.
+pub const EXIT_FAILED: u8 = 2;
+
+/// Exit code for a fresh collection, `None` meaning it produced no report.
+pub fn collection_exit_code(report: Option<&NodeReport>) -> u8 {
+    match report {
+        None => EXIT_FAILED,
+        Some(r) if !r.collection_errors.is_empty() => EXIT_PARTIAL,
+        Some(_) => EXIT_COMPLETE,
+    }
+}
+
+/// `--compare-baseline`: collect fresh, check every expectation, exit 1 on any failure.
+async fn compare_against_baseline(format: &str, path: &Path) -> Result<ExitCode> {
+    let baseline = Baseline::load(path)?;
+    let report_config = report_config_of(&config::load()?);
+    let identity = NodeIdentity::load(&NodeIdentity::default_path()).ok();
+    let report = ReportCollector::collect(&report_config, identity.as_ref()).await?;
+    let results = baseline.evaluate(&report)?;
+    let failed = results.iter().filter(|r| !r.passed).count();
+
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&results)?),
+        _ => {
+            println!(
+                "{} Comparing {} against {}",
+                ">>".blue().bold(),
+                report.hostname.bold(),
+                path.display()
+            );
+            for r in &results {
+                if r.passed {
+                    println!("  {} {}", "ok".green().bold(), r.expectation.label());
+                } else {
+                    let actual = r
+                        .actual
+                        .as_ref()
+                        .map(|v| v.to_string())
+                        .unwrap_or_else(|| "(missing)".to_string());
+                    println!(
+                        "  {} {} (actual: {})",
+                        "!!".red().bold(),
+                        r.expectation.label(),
+                        actual
+                    );
+                }
+            }
+            println!();
+            println!(
+                "  {} passed, {} failed",
+                results.len() - failed,
+                failed
+            );
+        }
+    }
+
+    Ok(if failed > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_async(
     format: &str,
     push: bool,
     controller_url: Option<&str>,
     fresh: bool,
     cached: bool,
-) -> Result<()> {
+    redact: bool,
+    remote: Option<&str>,
+    explain_fallbacks: bool,
+    collect_timeout: Option<Duration>,
+    output: Option<&Path>,
+) -> Result<ExitCode> {
     let cfg = config::load()?;
-    let report_config = cfg
-        .daemon
-        .as_ref()
-        .map(|d| d.report.clone())
-        .unwrap_or_default();
-    let store = ReportStore::new(PathBuf::from(&report_config.cache_file));
+    let report_config = report_config_of(&cfg);
+    let store = ReportStore::new(expand_path(&report_config.cache_file));
 
-    let stored = if cached {
+    let stored = if let Some(target) = remote {
+        // --remote: collect over SSH; the local store is left untouched
+        let peers = NodeIdentity::load(&NodeIdentity::default_path())
+            .map(|identity| identity.fleet.peers)
+            .unwrap_or_default();
+        remote_collector::collect(target, &peers).await?
+    } else if cached {
         // --cached: read from persisted file, no collection
         store.read().await?
-    } else if fresh {
-        // --fresh: force live collection, write to store
-        let report = ReportCollector::collect().await?;
-        let stored = StoredReport::new(report);
-        store.write(&stored).await?;
-        stored
     } else {
-        // Default: try daemon HTTP cache first, fall back to fresh collection
-        match try_daemon_cache(&cfg).await {
+        // --fresh forces live collection; by default the daemon cache is
+        // tried first, falling back to fresh collection
+        match live_report(&cfg, &report_config, fresh, collect_timeout).await {
             Ok(stored) => stored,
-            Err(_) => {
-                let report = ReportCollector::collect().await?;
-                let stored = StoredReport::new(report);
-                store.write(&stored).await?;
-                stored
+            Err(e) if fresh => {
+                eprintln!("{} {:#}", "!!".red().bold(), e);
+                return Ok(ExitCode::from(collection_exit_code(None)));
             }
+            Err(e) => return Err(e),
         }
     };
+    // Redact only what is shown here: the on-disk cache and the controller
+    // (which is inside the fleet) keep the full report.
+    let redacted;
+    let shown = if redact {
+        redacted = stored.redact(&report_config.redact_fields)?;
+        &redacted
+    } else {
+        &stored
+    };
 
     match format {
+        _ if explain_fallbacks => print_fallbacks(format, shown)?,
+        "oneline" => println!(
+            "{}",
+            oneline(
+                &shown.report,
+                colored::control::SHOULD_COLORIZE.should_colorize()
+            )
+        ),
+        "prometheus" => print!("{}", report_metrics::render(&shown.report)),
+        "html" => {
+            let html = report_html::render(&shown.report);
+            match output {
+                Some(path) => {
+                    std::fs::write(path, html)
+                        .with_context(|| format!("writing {}", path.display()))?;
+                    println!(
+                        "{} Report written to {}",
+                        "ok".green().bold(),
+                        path.display()
+                    );
+                }
+                None => print!("{}", html),
+            }
+        }
         "json" => {
-            let json = serde_json::to_string_pretty(&stored)?;
-            println!("{}", json);
+            let (status, _) = report_health::classify(&shown.report);
+            let mut json = serde_json::to_value(shown)?;
+            json["overall_status"] = serde_json::to_value(status)?;
+            println!("{}", serde_json::to_string_pretty(&json)?);
         }
         _ => {
-            print_table(&stored.report);
+            print_table(&shown.report);
             println!(
                 "  {} {}  {} {}s",
                 "Checksum:".dimmed(),
-                &stored.checksum[..std::cmp::min(stored.checksum.len(), 24)],
+                &shown.checksum[..std::cmp::min(shown.checksum.len(), 24)],
                 "Age:".dimmed(),
-                stored.age_secs()
+                shown.age_secs()
             );
+            let slowest = shown.report.slowest_sections(3);
+            if !slowest.is_empty() {
+                println!(
+                    "  {} {}",
+                    "Slowest:".dimmed(),
+                    slowest
+                        .iter()
+                        .map(|(section, ms)| format!("{} {}ms", section, ms))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+            let fallbacks = shown.report.collection_errors.len();
+            if fallbacks > 0 {
+                println!(
+                    "  {} {} section(s) used defaults (see --explain-fallbacks)",
+                    "!!".yellow().bold(),
+                    fallbacks
+                );
+            }
+            let warnings = shown.report.collection_warnings.len();
+            if warnings > 0 {
+                println!(
+                    "  {} {} collection warning(s) (see --explain-fallbacks)",
+                    "!!".yellow().bold(),
+                    warnings
+                );
+            }
         }
     }
 
     if push {
-        let url = controller_url.unwrap_or("http://localhost:9100");
-        let endpoint = format!(
-            "{}/api/v1/fleet/nodes/{}/report",
-            url, stored.report.hostname
-        );
+        let tls = ClientTls::from_config(&cfg);
+        push_report(
+            controller_url.unwrap_or(DEFAULT_CONTROLLER_URL),
+            &stored,
+            &tls,
+        )
+        .await?;
+    }
 
-        println!("\n{} to {}...", "Pushing report".cyan(), endpoint);
+    Ok(if fresh {
+        ExitCode::from(collection_exit_code(Some(&stored.report)))
+    } else {
+        ExitCode::SUCCESS
+    })
+}
 
-        let client = reqwest::Client::new();
-        let resp = client.post(&endpoint).json(&stored).send().await?;
+const DEFAULT_CONTROLLER_URL: &str = "http://localhost:9100";
 
-        if resp.status().is_success() {
-            println!("{}", "Report pushed successfully".green());
-        } else {
+/// Push with retries, draining earlier queued reports first so the
+/// controller sees them in order. A report that can't be delivered is
+/// queued in the outbox instead of being dropped, unless the controller
+/// rejected it outright.
+async fn push_report(url: &str, stored: &StoredReport, tls: &ClientTls) -> Result<()> {
+    let outbox = Outbox::new(Outbox::default_dir());
+    let client = api_client_builder(tls)?.timeout(PUSH_TIMEOUT).build()?;
+    let policy = RetryPolicy::default();
+
+    let queued = outbox.pending()?.len();
+    if queued > 0 {
+        println!(
+            "\n{} Flushing {} queued report(s) to {}...",
+            ">>".blue().bold(),
+            queued,
+            url
+        );
+        let outcome = outbox.flush(&client, url, &policy).await?;
+        if outcome.remaining > 0 {
+            outbox.enqueue(stored).await?;
             println!(
-                "{}: {} {}",
-                "Push failed".red(),
-                resp.status(),
-                resp.text().await.unwrap_or_default()
+                "{} Controller unreachable; report queued behind {} others in {}",
+                "!!".yellow().bold(),
+                outcome.remaining,
+                outbox.dir().display()
             );
+            return Ok(());
         }
     }
 
+    let endpoint = push_endpoint(url, &stored.report.hostname);
+    println!("\n{} to {}...", "Pushing report".cyan(), endpoint);
+    match push(&client, url, stored, &policy).await {
+        Ok(()) => println!("{}", "Report pushed successfully".green()),
+        Err(e) if is_rejected(&e) => return Err(e),
+        Err(e) => {
+            let path = outbox.enqueue(stored).await?;
+            println!("{}: {:#}", "Push failed".red(), e);
+            println!(
+                "  queued as {} (sent on the next --push or --flush-outbox)",
+                path.display()
+            );
+        }
+    }
     Ok(())
 }
 
+/// `--flush-outbox`: deliver queued reports in order; fails if any remain.
+async fn flush_queued_reports(controller_url: Option<&str>) -> Result<()> {
+    let url = controller_url.unwrap_or(DEFAULT_CONTROLLER_URL);
+    let outbox = Outbox::new(Outbox::default_dir());
+    let queued = outbox.pending()?.len();
+    if queued == 0 {
+        println!("{} Outbox is empty", "ok".green().bold());
+        return Ok(());
+    }
+    println!(
+        "{} Flushing {} queued report(s) to {}...",
+        ">>".blue().bold(),
+        queued,
+        url
+    );
+    let tls = ClientTls::from_config(&config::load()?);
+    let client = api_client_builder(&tls)?.timeout(PUSH_TIMEOUT).build()?;
+    let outcome = outbox.flush(&client, url, &RetryPolicy::default()).await?;
+    if outcome.sent > 0 {
+        println!("{} Sent {} report(s)", "ok".green().bold(), outcome.sent);
+    }
+    ensure_drained(&outbox, outcome)
+}
+
+/// `--explain-fallbacks`: which sections hold zeroed defaults because
+/// their collector failed, and the error it logged, then any sub-probe
+/// warnings.
+fn print_fallbacks(format: &str, stored: &StoredReport) -> Result<()> {
+    let errors = &stored.report.collection_errors;
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(errors)?);
+        return Ok(());
+    }
+    if errors.is_empty() {
+        println!(
+            "{} Every section was collected; no defaults in use",
+            "ok".green().bold()
+        );
+    } else {
+        println!(
+            "{} {} section(s) fell back to defaults:",
+            "!!".yellow().bold(),
+            errors.len()
+        );
+        for (section, error) in errors {
+            println!("  {} {}", format!("{}:", section).bold(), error);
+        }
+    }
+    print_collection_warnings(&stored.report.collection_warnings);
+    Ok(())
+}
+
+fn print_collection_warnings(warnings: &[CollectionWarning]) {
+    if warnings.is_empty() {
+        return;
+    }
+    println!(
+        "{} {} collection warning(s):",
+        "!!".yellow().bold(),
+        warnings.len()
+    );
+    for warning in warnings {
+        println!(
+            "  {} {}",
+            format!("{}:", warning.section).bold(),
+            warning.message
+        );
+    }
+}
+
+/// The daemon's cached report, or a fresh collection (written to the store)
+/// when `fresh` is set or no daemon answers.
+pub(crate) async fn live_report(
+    cfg: &config::Config,
+    report_config: &config::ReportConfig,
+    fresh: bool,
+    collect_timeout: Option<Duration>,
+) -> Result<StoredReport> {
+    if !fresh {
+        if let Ok(stored) = try_daemon_cache(cfg).await {
+            return Ok(stored);
+        }
+    }
+    let store = ReportStore::new(expand_path(&report_config.cache_file));
+    let identity = NodeIdentity::load(&NodeIdentity::default_path()).ok();
+    let collection = ReportCollector::collect(report_config, identity.as_ref());
+    let report = match collect_timeout {
+        Some(limit) => tokio::time::timeout(limit, collection)
+            .await
+            .map_err(|_| anyhow!("report collection timed out after {}s", limit.as_secs()))??,
+        None => collection.await?,
+    };
+    let stored = StoredReport::with_algorithm(report, report_config.checksum_algorithm);
+    store.write(&stored).await?;
+    record_store_sample(&store, &stored);
+    Ok(stored)
+}
+
+pub(crate) fn report_config_of(cfg: &config::Config) -> config::ReportConfig {
+    cfg.daemon
+        .as_ref()
+        .map(|d| d.report.clone())
+        .unwrap_or_default()
+}
+
+/// Feed `kindling store trend`; a failure here never fails the report.
+fn record_store_sample(store: &ReportStore, stored: &StoredReport) {
+    let path = store_trend::samples_path(store.path());
+    if let Err(e) = store_trend::record(&path, &stored.report) {
+        tracing::warn!(error = %e, "failed to record store trend sample");
+    }
+}
+
 /// Try to fetch the cached report from a running daemon.
 async fn try_daemon_cache(cfg: &config::Config) -> Result<StoredReport> {
-    let client = KindlingClient::from_node(None, &cfg.nodes)?;
+    let client =
+        KindlingClient::from_config(None, cfg, DEFAULT_TIMEOUT, &ClientTls::from_config(cfg))?;
     client.report().await
 }
 
-fn fmt_bytes(bytes: u64) -> String {
+pub(crate) fn fmt_bytes(bytes: u64) -> String {
     if bytes >= 1_099_511_627_776 {
         format!("{:.1} TB", bytes as f64 / 1_099_511_627_776.0)
     } else if bytes >= 1_073_741_824 {
@@ -123,7 +457,7 @@ fn fmt_bytes(bytes: u64) -> String {
     }
 }
 
-fn fmt_uptime(secs: u64) -> String {
+pub(crate) fn fmt_uptime(secs: u64) -> String {
     let days = secs / 86400;
     let hours = (secs % 86400) / 3600;
     let mins = (secs % 3600) / 60;
@@ -136,10 +470,101 @@ fn fmt_uptime(secs: u64) -> String {
     }
 }
 
-fn print_table(report: &crate::domain::node_report::NodeReport) {
+/// `--format oneline`: `host up 3d | cpu 12% | mem 45% | disk 78% | nix
+/// 42GB | k8s ready`, for status bars. Fields are ` | `-separated `name
+/// value` pairs; disk is the root filesystem (else the fullest mount) and
+/// is left out with k8s when the report has nothing for them. Without
+/// `color` the line is plain text.
+fn oneline(report: &NodeReport, color: bool) -> String {
+    let style = |text: colored::ColoredString| if color { text } else { text.clear() };
+    let percent = |value: f64, warn: f64, critical: f64| {
+        let text = format!("{:.0}%", value);
+        if color {
+            by_level(text, report_health::level(value, warn, critical))
+        } else {
+            text
+        }
+    };
+    let uptime = fmt_uptime(report.os.uptime_secs);
+    let mut parts = vec![
+        format!(
+            "{} up {}",
+            style(report.hostname.bold()),
+            uptime.split(' ').next().unwrap_or_default()
+        ),
+        format!(
+            "cpu {}",
+            percent(
+                report.health.cpu_usage_percent,
+                report_health::CPU_WARN_PERCENT,
+                report_health::CPU_CRITICAL_PERCENT
+            )
+        ),
+        format!(
+            "mem {}",
+            percent(
+                report.health.memory_usage_percent,
+                report_health::MEMORY_WARN_PERCENT,
+                report_health::MEMORY_CRITICAL_PERCENT
+            )
+        ),
+    ];
+    let disk = &report.health.disk_usage;
+    let fullest = || {
+        disk.iter()
+            .max_by(|a, b| a.usage_percent.total_cmp(&b.usage_percent))
+    };
+    if let Some(d) = disk.iter().find(|d| d.mount_point == "/").or_else(fullest) {
+        parts.push(format!(
+            "disk {}",
+            percent(
+                d.usage_percent,
+                report_health::DISK_WARN_PERCENT,
+                report_health::DISK_CRITICAL_PERCENT
+            )
+        ));
+    }
+    let store = report.nix.store_size_bytes;
+    parts.push(if store >= 1_073_741_824 {
+        format!("nix {:.0}GB", store as f64 / 1_073_741_824.0)
+    } else {
+        format!("nix {}", fmt_bytes(store).replace(' ', ""))
+    });
+    if let Some(ref k8s) = report.kubernetes {
+        let state = if k8s.node_ready {
+            "ready".green()
+        } else {
+            "not-ready".red()
+        };
+        parts.push(format!("k8s {}", style(state)));
+    }
+    parts.join(" | ")
+}
+
+/// `text` red for Critical, yellow for Degraded, else as is.
+fn by_level(text: String, level: Option<OverallStatus>) -> String {
+    match level {
+        Some(OverallStatus::Critical) => text.red().to_string(),
+        Some(_) => text.yellow().to_string(),
+        None => text,
+    }
+}
+
+fn print_table(report: &NodeReport) {
     println!("{}", "═══ Node Report ═══".cyan().bold());
     println!("  Hostname:      {}", report.hostname.bold());
     println!("  Daemon:        {}", report.daemon_version);
+    let (status, reasons) = report_health::classify(report);
+    let status_str = match status {
+        OverallStatus::Healthy => status.to_string().green().bold(),
+        OverallStatus::Degraded => status.to_string().yellow().bold(),
+        OverallStatus::Critical => status.to_string().red().bold(),
+    };
+    if reasons.is_empty() {
+        println!("  Status:        {}", status_str);
+    } else {
+        println!("  Status:        {} ({})", status_str, reasons.join(", "));
+    }
     println!();
 
     // ── OS ──
@@ -147,6 +572,9 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
     println!("  Distribution:    {}", report.os.distribution);
     println!("  Version:         {}", report.os.version);
     println!("  Kernel:          {}", report.os.kernel_version);
+    if report.os.reboot_required {
+        println!("  Reboot:          {}", "required (newer kernel installed)".red().bold());
+    }
     println!("  Architecture:    {}", report.os.architecture);
     println!("  Platform:        {}", report.os.platform_triple);
     if let Some(ref name) = report.os.product_name {
@@ -155,6 +583,21 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
     if let Some(ref tz) = report.os.timezone {
         println!("  Timezone:        {}", tz);
     }
+    match report.os.time_synced {
+        Some(true) => println!("  Time Sync:       {}", "synced".green()),
+        Some(false) => println!("  Time Sync:       {}", "not synced".red().bold()),
+        None => {}
+    }
+    if let Some(offset) = report.os.clock_offset_ms {
+        let offset_str = by_level(
+            format!("{:+.1} ms", offset),
+            report_health::clock_offset_level(offset),
+        );
+        println!("  Clock Offset:    {}", offset_str);
+    }
+    if let Some(bits) = report.os.entropy_available {
+        println!("  Entropy:         {} bits", bits);
+    }
     println!("  Uptime:          {}", fmt_uptime(report.os.uptime_secs));
     if let Some(ref boot) = report.os.boot_time {
         println!("  Boot Time:       {}", boot.to_rfc3339());
@@ -197,6 +640,12 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
             fmt_bytes(report.hardware.swap_total_bytes)
         );
     }
+    if let Some(limit) = report.hardware.cgroup_memory_limit_bytes {
+        println!("  Cgroup Memory:   {}", fmt_bytes(limit));
+    }
+    if let Some(quota) = report.hardware.cgroup_cpu_quota {
+        println!("  Cgroup CPUs:     {:.2}", quota);
+    }
 
     if !report.hardware.disks.is_empty() {
         println!();
@@ -207,20 +656,28 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
             } else {
                 0.0
             };
-            let pct_str = if pct > 90.0 {
-                format!("{:.0}%", pct).red().to_string()
-            } else if pct > 75.0 {
-                format!("{:.0}%", pct).yellow().to_string()
-            } else {
-                format!("{:.0}%", pct)
+            let pct_str = by_level(
+                format!("{:.0}%", pct),
+                report_health::level(
+                    pct,
+                    report_health::DISK_WARN_PERCENT,
+                    report_health::DISK_CRITICAL_PERCENT,
+                ),
+            );
+            let inodes = match (d.inodes_used, d.inodes_total) {
+                (Some(used), Some(total)) if total > 0 => {
+                    format!(", {:.0}% of inodes", used as f64 / total as f64 * 100.0)
+                }
+                _ => String::new(),
             };
             println!(
-                "    {} → {} ({}) {} used of {}",
+                "    {} → {} ({}) {} used of {}{}",
                 d.device,
                 d.mount_point,
                 d.filesystem,
                 pct_str,
-                fmt_bytes(d.total_bytes)
+                fmt_bytes(d.total_bytes),
+                inodes
             );
         }
     }
@@ -240,17 +697,68 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
         }
     }
 
+    if !report.hardware.pci_devices.is_empty() {
+        println!();
+        println!("  {}", "PCI Devices:".dimmed());
+        for dev in &report.hardware.pci_devices {
+            println!(
+                "    {} {}: {} {}",
+                dev.slot.dimmed(),
+                dev.class,
+                dev.vendor,
+                dev.device
+            );
+        }
+    }
+
+    if !report.hardware.raid_arrays.is_empty() {
+        println!();
+        println!("  {}", "RAID Arrays:".dimmed());
+        for array in &report.hardware.raid_arrays {
+            let status = match (array.degraded, &array.member_status) {
+                (true, Some(s)) => format!("degraded [{}]", s).red().to_string(),
+                (true, None) => "degraded".red().to_string(),
+                (false, Some(s)) => format!("[{}]", s).green().to_string(),
+                (false, None) => String::new(),
+            };
+            let mut line = format!(
+                "    {} {} {} {}",
+                array.name.bold(),
+                array.level.as_deref().unwrap_or("-"),
+                array.state,
+                status
+            );
+            if let Some(ref progress) = array.sync_progress {
+                line.push_str(&format!(" ({})", progress));
+            }
+            println!("{}", line);
+        }
+    }
+
+    if !report.hardware.usb_devices.is_empty() {
+        println!();
+        println!("  {}", "USB Devices:".dimmed());
+        for dev in &report.hardware.usb_devices {
+            let ids = format!("{}:{}", dev.vendor_id, dev.product_id);
+            match dev.vendor {
+                Some(ref vendor) => println!("    {} {} {}", ids.dimmed(), vendor, dev.device),
+                None => println!("    {} {}", ids.dimmed(), dev.device),
+            }
+        }
+    }
+
     if !report.hardware.temperatures.is_empty() {
         println!();
         println!("  {}", "Temperatures:".dimmed());
         for t in &report.hardware.temperatures {
-            let temp_str = if t.celsius > 85.0 {
-                format!("{:.0}°C", t.celsius).red().to_string()
-            } else if t.celsius > 70.0 {
-                format!("{:.0}°C", t.celsius).yellow().to_string()
-            } else {
-                format!("{:.0}°C", t.celsius)
-            };
+            let temp_str = by_level(
+                format!("{:.0}°C", t.celsius),
+                report_health::level(
+                    t.celsius,
+                    report_health::TEMP_WARN_CELSIUS,
+                    report_health::TEMP_CRITICAL_CELSIUS,
+                ),
+            );
             println!("    {}: {}", t.label, temp_str);
         }
     }
@@ -286,37 +794,89 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
         report.health.load_average_5m,
         report.health.load_average_15m
     );
-    let cpu_str = if report.health.cpu_usage_percent > 90.0 {
-        format!("{:.1}%", report.health.cpu_usage_percent).red().to_string()
-    } else if report.health.cpu_usage_percent > 70.0 {
-        format!("{:.1}%", report.health.cpu_usage_percent).yellow().to_string()
-    } else {
-        format!("{:.1}%", report.health.cpu_usage_percent)
-    };
+    if report.hardware.cpu_cores > 0 {
+        let per_core = format!("{:.2}", report.health.load_per_core);
+        let per_core = by_level(
+            per_core,
+            report_health::load_level(report.health.load_per_core),
+        );
+        println!("  Load per Core:   {}", per_core);
+    }
+    let cpu = report.health.cpu_usage_percent;
+    let cpu_str = by_level(
+        format!("{:.1}%", cpu),
+        report_health::level(
+            cpu,
+            report_health::CPU_WARN_PERCENT,
+            report_health::CPU_CRITICAL_PERCENT,
+        ),
+    );
     println!("  CPU Usage:       {}", cpu_str);
-    let mem_str = if report.health.memory_usage_percent > 90.0 {
-        format!("{:.1}%", report.health.memory_usage_percent).red().to_string()
-    } else if report.health.memory_usage_percent > 75.0 {
-        format!("{:.1}%", report.health.memory_usage_percent).yellow().to_string()
-    } else {
-        format!("{:.1}%", report.health.memory_usage_percent)
-    };
+    let mem = report.health.memory_usage_percent;
+    let mem_str = by_level(
+        format!("{:.1}%", mem),
+        report_health::level(
+            mem,
+            report_health::MEMORY_WARN_PERCENT,
+            report_health::MEMORY_CRITICAL_PERCENT,
+        ),
+    );
     println!("  Memory Usage:    {}", mem_str);
     if report.health.swap_usage_percent > 0.0 {
         println!("  Swap Usage:      {:.1}%", report.health.swap_usage_percent);
     }
+    for (label, pressure) in [
+        ("Memory Pressure:", &report.health.memory_pressure),
+        ("CPU Pressure:   ", &report.health.cpu_pressure),
+        ("IO Pressure:    ", &report.health.io_pressure),
+    ] {
+        if let Some(p) = pressure {
+            println!(
+                "  {} {:.2} / {:.2} / {:.2}% stalled (10s / 60s / 300s)",
+                label, p.some.avg10, p.some.avg60, p.some.avg300
+            );
+        }
+    }
     if let (Some(open), Some(max)) = (report.health.open_file_descriptors, report.health.max_file_descriptors) {
         println!("  File Descriptors: {} / {}", open, max);
     }
+    if report.health.degraded {
+        println!("  Services:        {}", "degraded".red().bold());
+    }
+    if let Some(loaded) = report.health.loaded_services {
+        println!("  Loaded Services: {}", loaded);
+    }
+    if !report.health.failed_units.is_empty() {
+        println!(
+            "  Failed Units:    {}",
+            report.health.failed_units.join(", ").red()
+        );
+    }
+    if report.health.recent_oom_kills > 0 {
+        println!(
+            "  OOM Kills (1h):  {} ({})",
+            report.health.recent_oom_kills.to_string().red(),
+            report.health.oom_victims.join(", ")
+        );
+    }
     for du in &report.health.disk_usage {
-        let du_str = if du.usage_percent > 90.0 {
-            format!("{:.1}%", du.usage_percent).red().to_string()
-        } else if du.usage_percent > 75.0 {
-            format!("{:.1}%", du.usage_percent).yellow().to_string()
-        } else {
-            format!("{:.1}%", du.usage_percent)
-        };
-        println!("  Disk {}:  {}", du.mount_point, du_str);
+        let du_str = by_level(
+            format!("{:.1}%", du.usage_percent),
+            report_health::level(
+                du.usage_percent,
+                report_health::DISK_WARN_PERCENT,
+                report_health::DISK_CRITICAL_PERCENT,
+            ),
+        );
+        match du.inode_percent {
+            Some(inodes) if inodes > report_health::INODE_CRITICAL_PERCENT => println!(
+                "  Disk {}:  {} ({} inodes)",
+                du.mount_point,
+                du_str,
+                format!("{:.1}%", inodes).red()
+            ),
+            _ => println!("  Disk {}:  {}", du.mount_point, du_str),
+        }
     }
 
     // ── Processes ──
@@ -337,7 +897,10 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
         for p in &report.processes.top_cpu {
             println!(
                 "    {:>6} {:<20} CPU: {:>5.1}%  MEM: {:>5.1}%",
-                p.pid, p.name, p.cpu_percent, p.memory_percent
+                p.pid,
+                p.display_name(),
+                p.cpu_percent,
+                p.memory_percent
             );
         }
     }
@@ -346,7 +909,22 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
         for p in &report.processes.top_memory {
             println!(
                 "    {:>6} {:<20} MEM: {:>5.1}%  CPU: {:>5.1}%",
-                p.pid, p.name, p.memory_percent, p.cpu_percent
+                p.pid,
+                p.display_name(),
+                p.memory_percent,
+                p.cpu_percent
+            );
+        }
+    }
+    if !report.processes.top_programs.is_empty() {
+        println!("  {}", "Top Programs:".dimmed());
+        for p in &report.processes.top_programs {
+            println!(
+                "    {:>6} {:<20} CPU: {:>5.1}%  MEM: {:>5.1}%",
+                format!("{}x", p.processes),
+                p.program,
+                p.cpu_percent,
+                p.memory_percent
             );
         }
     }
@@ -360,6 +938,19 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
     if !report.network.dns_resolvers.is_empty() {
         println!("  DNS Resolvers:   {}", report.network.dns_resolvers.join(", "));
     }
+    for probe in &report.network.dns_resolver_health {
+        let status = match (probe.ok, probe.latency_ms) {
+            (true, Some(ms)) => format!("ok ({} ms)", ms).green().to_string(),
+            (true, None) => "ok".green().to_string(),
+            (false, _) => probe
+                .error
+                .as_deref()
+                .unwrap_or("failed")
+                .red()
+                .to_string(),
+        };
+        println!("  DNS {}:  {}", probe.resolver, status);
+    }
     println!();
     println!("  {}", "Interfaces:".dimmed());
     for iface in &report.network.interfaces {
@@ -395,6 +986,22 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
         }
     }
 
+    let conns = &report.network.connection_summary;
+    if !conns.tcp_states.is_empty() || conns.udp_sockets > 0 {
+        println!();
+        println!(
+            "  {} {}",
+            "Connections:".dimmed(),
+            conns
+                .tcp_states
+                .iter()
+                .map(|(state, n)| format!("{} {}", state, n))
+                .chain(std::iter::once(format!("UDP {}", conns.udp_sockets)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
     if !report.network.listening_ports.is_empty() {
         println!();
         println!("  {}", "Listening Ports:".dimmed());
@@ -445,6 +1052,42 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
     if let Some(ref ts) = report.nix.last_rebuild_timestamp {
         println!("  Last Rebuild:    {}", ts.to_rfc3339());
     }
+    if let Some(size) = report.nix.eval_cache_size_bytes {
+        println!("  Eval Cache:      {}", fmt_bytes(size));
+    }
+    if let Some(ref flake) = report.nix.system_flake {
+        println!("  System Flake:    {}", flake.url);
+        if let Some(ref rev) = flake.locked_rev {
+            println!("  Locked Rev:      {}", rev);
+        }
+        if let Some(ref rev) = flake.configuration_revision {
+            println!("  Config Rev:      {}", rev);
+        }
+    }
+    if let Some(ref rev) = report.nix.config_source_rev {
+        let short = &rev[..std::cmp::min(rev.len(), 12)];
+        match report.nix.config_source_dirty {
+            Some(true) => println!(
+                "  Config Source:   {} {}",
+                short,
+                "(uncommitted changes)".yellow()
+            ),
+            _ => println!("  Config Source:   {}", short),
+        }
+    }
+    if !report.nix.active_builds.is_empty() {
+        println!(
+            "  Active Builds:   {} ({})",
+            report.nix.active_builds.len(),
+            report.nix.active_builds.join(", ")
+        );
+    }
+    if !report.nix.recent_nix_errors.is_empty() {
+        println!("  {}", "Recent Daemon Errors:".dimmed());
+        for line in &report.nix.recent_nix_errors {
+            println!("    {}", line.red());
+        }
+    }
 
     // ── Kubernetes ──
     if let Some(k8s) = &report.kubernetes {
@@ -489,6 +1132,14 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
         if let Some(hr) = k8s.helm_releases {
             println!("  Helm Releases:   {}", hr);
         }
+        if let Some(bytes) = k8s.image_store_bytes {
+            match k8s.image_count {
+                Some(count) => {
+                    println!("  Images:          {} ({} images)", fmt_bytes(bytes), count)
+                }
+                None => println!("  Images:          {}", fmt_bytes(bytes)),
+            }
+        }
 
         if !k8s.conditions.is_empty() {
             println!("  {}", "Conditions:".dimmed());
@@ -512,6 +1163,15 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
     // ── Security ──
     println!();
     println!("{}", "── Security ──".yellow());
+    let score = report.security.security_score;
+    let score_str = format!("{}/100", score);
+    println!(
+        "  Score:           {}",
+        match report_health::score_level(score) {
+            None => score_str.green().to_string(),
+            level => by_level(score_str, level),
+        }
+    );
     println!(
         "  Firewall:        {}",
         if report.security.firewall_active {
@@ -556,13 +1216,8 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
         for cert in &report.security.tls_certificates {
             let mut line = format!("    {}", cert.domain);
             if let Some(days) = cert.days_until_expiry {
-                let days_str = if days < 14 {
-                    format!(" ({} days)", days).red().to_string()
-                } else if days < 30 {
-                    format!(" ({} days)", days).yellow().to_string()
-                } else {
-                    format!(" ({} days)", days)
-                };
+                let days_str =
+                    by_level(format!(" ({} days)", days), report_health::cert_level(days));
                 line.push_str(&days_str);
             }
             if let Some(ref issuer) = cert.issuer {
@@ -571,6 +1226,17 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
             println!("{}", line);
         }
     }
+    if !report.security.findings.is_empty() {
+        println!("  {}", "Findings:".dimmed());
+        for finding in &report.security.findings {
+            let marker = match finding.severity {
+                FindingSeverity::High => "!!".red().bold(),
+                FindingSeverity::Medium => "!!".yellow().bold(),
+                FindingSeverity::Low => "::".blue().bold(),
+            };
+            println!("    {} {}", marker, finding.message);
+        }
+    }
 
     println!();
     println!(
@@ -579,3 +1245,52 @@ fn print_table(report: &crate::domain::node_report::NodeReport) {
         report.timestamp.to_rfc3339()
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::node_report::tests::make_test_report;
+    use crate::domain::node_report::DiskUsage;
+
+    #[test]
+    fn oneline_has_expected_tokens() {
+        let mut report = make_test_report();
+        report.os.uptime_secs = 3 * 86400 + 7200;
+        report.health.cpu_usage_percent = 12.4;
+        report.health.memory_usage_percent = 45.0;
+        report.health.disk_usage = vec![
+            DiskUsage {
+                mount_point: "/boot".to_string(),
+                usage_percent: 95.0,
+                inode_percent: None,
+            },
+            DiskUsage {
+                mount_point: "/".to_string(),
+                usage_percent: 78.0,
+                inode_percent: None,
+            },
+        ];
+        report.nix.store_size_bytes = 42 * 1_073_741_824;
+
+        let line = oneline(&report, false);
+        assert!(!line.contains('\x1b'));
+        assert_eq!(
+            line,
+            "test-node up 3d | cpu 12% | mem 45% | disk 78% | nix 42GB"
+        );
+    }
+
+    #[test]
+    fn exit_code_reflects_collection_outcome() {
+        let mut report = make_test_report();
+        assert_eq!(collection_exit_code(Some(&report)), EXIT_COMPLETE);
+
+        report
+            .collection_errors
+            .insert("kubernetes".to_string(), "kubectl timed out".to_string());
+        assert_eq!(collection_exit_code(Some(&report)), EXIT_PARTIAL);
+        assert_eq!(EXIT_PARTIAL, 1);
+
+        assert_eq!(collection_exit_code(None), EXIT_FAILED);
+    }
+}
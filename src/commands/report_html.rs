@@ -0,0 +1,728 @@
+//! `kindling report --format html` — a node report as one self-contained
+//! page, for sharing with people who don't use the CLI.
+//!
+//! Sections mirror the table output, each a collapsible `<details>` block.
+//! Values use the table's thresholds, shown as green, amber or red. The CSS
+//! is inline and there is no script, so the file opens anywhere.
+
+use std::fmt::Write;
+
+use crate::commands::report::{fmt_bytes, fmt_uptime};
+use crate::domain::node_report::{FindingSeverity, NodeReport};
+use crate::domain::report_health::{self, OverallStatus};
+
+const STYLE: &str = "\
+body{font-family:system-ui,-apple-system,sans-serif;margin:2rem auto;max-width:60rem;color:#1f2328;background:#f6f8fa}\
+h1{font-size:1.5rem;margin-bottom:.25rem}\
+details{background:#fff;border:1px solid #d0d7de;border-radius:6px;margin:1rem 0;padding:.5rem 1rem}\
+summary{font-weight:600;cursor:pointer}\
+h3{font-size:.9rem;color:#656d76;margin:1rem 0 .25rem}\
+table{border-collapse:collapse;width:100%;margin:.5rem 0}\
+th,td{text-align:left;padding:.2rem .75rem .2rem 0;vertical-align:top}\
+th{color:#656d76;font-weight:500;white-space:nowrap;width:12rem}\
+td{font-family:ui-monospace,monospace;font-size:.9rem}\
+.ok{color:#1a7f37}.warn{color:#9a6700}.crit{color:#cf222e;font-weight:600}\
+footer{color:#656d76;font-size:.8rem}";
+
+/// Colour class for a value against a threshold.
+#[derive(Clone, Copy)]
+enum Level {
+    Ok,
+    Warn,
+    Crit,
+}
+
+impl Level {
+    /// `Crit` above `crit`, `Warn` above `warn`, else `Ok`, by
+    /// [`report_health::level`].
+    fn of(value: f64, warn: f64, crit: f64) -> Self {
+        report_health::level(value, warn, crit).into()
+    }
+
+    fn class(self) -> &'static str {
+        match self {
+            Level::Ok => "ok",
+            Level::Warn => "warn",
+            Level::Crit => "crit",
+        }
+    }
+}
+
+impl From<Option<OverallStatus>> for Level {
+    fn from(status: Option<OverallStatus>) -> Self {
+        match status {
+            Some(OverallStatus::Critical) => Level::Crit,
+            Some(_) => Level::Warn,
+            None => Level::Ok,
+        }
+    }
+}
+
+/// One collapsible section: rows of label and (already escaped) value,
+/// optionally followed by sub-tables.
+struct Section {
+    title: &'static str,
+    body: String,
+}
+
+impl Section {
+    fn new(title: &'static str) -> Self {
+        Self {
+            title,
+            body: String::from("<table>"),
+        }
+    }
+
+    fn row(&mut self, label: &str, value: impl AsRef<str>) -> &mut Self {
+        let _ = write!(
+            self.body,
+            "<tr><th>{}</th><td>{}</td></tr>",
+            escape(label),
+            value.as_ref()
+        );
+        self
+    }
+
+    fn text(&mut self, label: &str, value: impl AsRef<str>) -> &mut Self {
+        self.row(label, escape(value.as_ref()))
+    }
+
+    /// Start a titled sub-table, e.g. "Disks".
+    fn group(&mut self, title: &str) -> &mut Self {
+        let _ = write!(self.body, "</table><h3>{}</h3><table>", escape(title));
+        self
+    }
+
+    fn finish(self, out: &mut String) {
+        let _ = writeln!(
+            out,
+            "<details open><summary>{}</summary>{}</table></details>",
+            self.title, self.body
+        );
+    }
+}
+
+/// Render `report` as a standalone HTML document.
+pub fn render(report: &NodeReport) -> String {
+    let mut out = String::new();
+    let hostname = escape(&report.hostname);
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{} — kindling report</title>\n<style>{}</style>\n</head>\n<body>\n\
+         <h1>{}</h1>\n",
+        hostname, STYLE, hostname
+    );
+
+    let (status, reasons) = report_health::classify(report);
+    let level = match status {
+        OverallStatus::Healthy => Level::Ok,
+        OverallStatus::Degraded => Level::Warn,
+        OverallStatus::Critical => Level::Crit,
+    };
+    let mut status_html = colored(level, &status.to_string());
+    if !reasons.is_empty() {
+        let _ = write!(status_html, " ({})", escape(&reasons.join(", ")));
+    }
+    let _ = writeln!(
+        out,
+        "<p>Status: {} · kindling {}</p>",
+        status_html,
+        escape(&report.daemon_version)
+    );
+
+    os_section(report).finish(&mut out);
+    hardware_section(report).finish(&mut out);
+    health_section(report).finish(&mut out);
+    processes_section(report).finish(&mut out);
+    network_section(report).finish(&mut out);
+    nix_section(report).finish(&mut out);
+    if let Some(section) = kubernetes_section(report) {
+        section.finish(&mut out);
+    }
+    security_section(report).finish(&mut out);
+
+    let _ = write!(
+        out,
+        "<footer>Report generated at {}</footer>\n</body>\n</html>\n",
+        report.timestamp.to_rfc3339()
+    );
+    out
+}
+
+fn os_section(report: &NodeReport) -> Section {
+    let os = &report.os;
+    let mut s = Section::new("OS");
+    s.text("Distribution", &os.distribution)
+        .text("Version", &os.version)
+        .text("Kernel", &os.kernel_version);
+    if os.reboot_required {
+        s.row(
+            "Reboot",
+            colored(Level::Crit, "required (newer kernel installed)"),
+        );
+    }
+    s.text("Architecture", &os.architecture)
+        .text("Platform", &os.platform_triple);
+    if let Some(ref name) = os.product_name {
+        s.text("Product", name);
+    }
+    if let Some(ref tz) = os.timezone {
+        s.text("Timezone", tz);
+    }
+    match os.time_synced {
+        Some(true) => {
+            s.row("Time Sync", colored(Level::Ok, "synced"));
+        }
+        Some(false) => {
+            s.row("Time Sync", colored(Level::Crit, "not synced"));
+        }
+        None => {}
+    }
+    if let Some(offset) = os.clock_offset_ms {
+        let level = Level::from(report_health::clock_offset_level(offset));
+        s.row(
+            "Clock Offset",
+            colored(level, &format!("{:+.1} ms", offset)),
+        );
+    }
+    s.text("Uptime", fmt_uptime(os.uptime_secs));
+    if let Some(ref boot) = os.boot_time {
+        s.text("Boot Time", boot.to_rfc3339());
+    }
+    if let Some(ref sd) = os.systemd_version {
+        s.text("Systemd", sd);
+    }
+    if let Some(ref virt) = os.virtualization {
+        s.text("Virtualization", virt);
+    }
+    s
+}
+
+fn hardware_section(report: &NodeReport) -> Section {
+    let hw = &report.hardware;
+    let mut s = Section::new("Hardware");
+    s.text("CPU Model", &hw.cpu_model)
+        .text("CPU Vendor", &hw.cpu_vendor)
+        .text(
+            "Cores/Threads",
+            format!("{}/{}", hw.cpu_cores, hw.cpu_threads),
+        );
+    if let Some(freq) = hw.cpu_frequency_mhz {
+        s.text("CPU Frequency", format!("{} MHz", freq));
+    }
+    s.text(
+        "RAM",
+        format!(
+            "{} / {}",
+            fmt_bytes(hw.ram_available_bytes),
+            fmt_bytes(hw.ram_total_bytes)
+        ),
+    );
+    if hw.swap_total_bytes > 0 {
+        s.text(
+            "Swap",
+            format!(
+                "{} / {}",
+                fmt_bytes(hw.swap_used_bytes),
+                fmt_bytes(hw.swap_total_bytes)
+            ),
+        );
+    }
+    if let Some(limit) = hw.cgroup_memory_limit_bytes {
+        s.text("Cgroup Memory", fmt_bytes(limit));
+    }
+    if let Some(quota) = hw.cgroup_cpu_quota {
+        s.text("Cgroup CPUs", format!("{:.2}", quota));
+    }
+
+    if !hw.disks.is_empty() {
+        s.group("Disks");
+        for d in &hw.disks {
+            let pct = if d.total_bytes > 0 {
+                d.used_bytes as f64 / d.total_bytes as f64 * 100.0
+            } else {
+                0.0
+            };
+            s.row(
+                &d.mount_point,
+                format!(
+                    "{} used of {} ({}, {})",
+                    colored(
+                        Level::of(
+                            pct,
+                            report_health::DISK_WARN_PERCENT,
+                            report_health::DISK_CRITICAL_PERCENT
+                        ),
+                        &format!("{:.0}%", pct)
+                    ),
+                    fmt_bytes(d.total_bytes),
+                    escape(&d.device),
+                    escape(&d.filesystem)
+                ),
+            );
+        }
+    }
+    if !hw.gpus.is_empty() {
+        s.group("GPUs");
+        for gpu in &hw.gpus {
+            let vram = gpu.vram_bytes.map(fmt_bytes).unwrap_or_default();
+            s.text(&gpu.vendor, format!("{} {}", gpu.name, vram).trim_end());
+        }
+    }
+    if !hw.raid_arrays.is_empty() {
+        s.group("RAID Arrays");
+        for array in &hw.raid_arrays {
+            let level = if array.degraded {
+                Level::Crit
+            } else {
+                Level::Ok
+            };
+            let state = if array.degraded {
+                format!("{} (degraded)", array.state)
+            } else {
+                array.state.clone()
+            };
+            s.row(&array.name, colored(level, &state));
+        }
+    }
+    if !hw.temperatures.is_empty() {
+        s.group("Temperatures");
+        for t in &hw.temperatures {
+            let level = Level::of(
+                t.celsius,
+                report_health::TEMP_WARN_CELSIUS,
+                report_health::TEMP_CRITICAL_CELSIUS,
+            );
+            s.row(&t.label, colored(level, &format!("{:.0}°C", t.celsius)));
+        }
+    }
+    if let Some(ref pwr) = hw.power {
+        s.group("Power");
+        s.text(
+            "Source",
+            if pwr.on_battery {
+                "Battery"
+            } else {
+                "AC Power"
+            },
+        );
+        if let Some(pct) = pwr.charge_percent {
+            let level = if pct < 20.0 { Level::Crit } else { Level::Ok };
+            s.row("Charge", colored(level, &format!("{:.0}%", pct)));
+        }
+    }
+    s
+}
+
+fn health_section(report: &NodeReport) -> Section {
+    let health = &report.health;
+    let mut s = Section::new("Health");
+    s.text(
+        "Load Average",
+        format!(
+            "{:.2} / {:.2} / {:.2}",
+            health.load_average_1m, health.load_average_5m, health.load_average_15m
+        ),
+    );
+    if report.hardware.cpu_cores > 0 {
+        let level = Level::from(report_health::load_level(health.load_per_core));
+        s.row(
+            "Load per Core",
+            colored(level, &format!("{:.2}", health.load_per_core)),
+        );
+    }
+    let cpu = health.cpu_usage_percent;
+    s.row(
+        "CPU Usage",
+        colored(
+            Level::of(
+                cpu,
+                report_health::CPU_WARN_PERCENT,
+                report_health::CPU_CRITICAL_PERCENT,
+            ),
+            &format!("{:.1}%", cpu),
+        ),
+    );
+    let mem = health.memory_usage_percent;
+    s.row(
+        "Memory Usage",
+        colored(
+            Level::of(
+                mem,
+                report_health::MEMORY_WARN_PERCENT,
+                report_health::MEMORY_CRITICAL_PERCENT,
+            ),
+            &format!("{:.1}%", mem),
+        ),
+    );
+    if health.swap_usage_percent > 0.0 {
+        s.text("Swap Usage", format!("{:.1}%", health.swap_usage_percent));
+    }
+    if health.degraded {
+        s.row("Services", colored(Level::Crit, "degraded"));
+    }
+    if !health.failed_units.is_empty() {
+        s.row(
+            "Failed Units",
+            colored(Level::Crit, &health.failed_units.join(", ")),
+        );
+    }
+    if health.recent_oom_kills > 0 {
+        s.row(
+            "OOM Kills (1h)",
+            colored(
+                Level::Crit,
+                &format!(
+                    "{} ({})",
+                    health.recent_oom_kills,
+                    health.oom_victims.join(", ")
+                ),
+            ),
+        );
+    }
+    for du in &health.disk_usage {
+        let level = Level::of(
+            du.usage_percent,
+            report_health::DISK_WARN_PERCENT,
+            report_health::DISK_CRITICAL_PERCENT,
+        );
+        s.row(
+            &format!("Disk {}", du.mount_point),
+            colored(level, &format!("{:.1}%", du.usage_percent)),
+        );
+    }
+    s
+}
+
+fn processes_section(report: &NodeReport) -> Section {
+    let procs = &report.processes;
+    let mut s = Section::new("Processes");
+    s.text("Total", procs.total_processes.to_string())
+        .text("Running", procs.running_processes.to_string());
+    let zombie_level = if procs.zombie_processes > 0 {
+        Level::Crit
+    } else {
+        Level::Ok
+    };
+    s.row(
+        "Zombie",
+        colored(zombie_level, &procs.zombie_processes.to_string()),
+    );
+    for (title, list) in [
+        ("Top CPU", &procs.top_cpu),
+        ("Top Memory", &procs.top_memory),
+    ] {
+        if list.is_empty() {
+            continue;
+        }
+        s.group(title);
+        for p in list {
+            s.text(
+                &p.pid.to_string(),
+                format!(
+                    "{} — CPU {:.1}%, MEM {:.1}%",
+                    p.display_name(),
+                    p.cpu_percent,
+                    p.memory_percent
+                ),
+            );
+        }
+    }
+    if !procs.top_programs.is_empty() {
+        s.group("Top Programs");
+        for p in &procs.top_programs {
+            s.text(
+                &p.program,
+                format!(
+                    "{}x — CPU {:.1}%, MEM {:.1}%",
+                    p.processes, p.cpu_percent, p.memory_percent
+                ),
+            );
+        }
+    }
+    s
+}
+
+fn network_section(report: &NodeReport) -> Section {
+    let net = &report.network;
+    let mut s = Section::new("Network");
+    if let Some(ref gw) = net.default_gateway {
+        s.text("Default Gateway", gw);
+    }
+    if !net.dns_resolvers.is_empty() {
+        s.text("DNS Resolvers", net.dns_resolvers.join(", "));
+    }
+    for probe in &net.dns_resolver_health {
+        let value = match (probe.ok, probe.latency_ms) {
+            (true, Some(ms)) => colored(Level::Ok, &format!("ok ({} ms)", ms)),
+            (true, None) => colored(Level::Ok, "ok"),
+            (false, _) => colored(Level::Crit, probe.error.as_deref().unwrap_or("failed")),
+        };
+        s.row(&format!("DNS {}", probe.resolver), value);
+    }
+
+    let interfaces: Vec<_> = net
+        .interfaces
+        .iter()
+        .filter(|iface| !(iface.addresses.is_empty() && iface.state == "down"))
+        .collect();
+    if !interfaces.is_empty() {
+        s.group("Interfaces");
+        for iface in interfaces {
+            let mut value = iface.state.clone();
+            if !iface.addresses.is_empty() {
+                let _ = write!(value, " — {}", iface.addresses.join(", "));
+            }
+            if iface.rx_bytes > 0 || iface.tx_bytes > 0 {
+                let _ = write!(
+                    value,
+                    " (RX {} / TX {})",
+                    fmt_bytes(iface.rx_bytes),
+                    fmt_bytes(iface.tx_bytes)
+                );
+            }
+            s.text(&iface.name, value);
+        }
+    }
+    if !net.listening_ports.is_empty() {
+        s.group("Listening Ports");
+        for lp in &net.listening_ports {
+            s.text(
+                &format!(
+                    "{}:{} ({})",
+                    lp.address.as_deref().unwrap_or("*"),
+                    lp.port,
+                    lp.protocol
+                ),
+                lp.process.as_deref().unwrap_or("-"),
+            );
+        }
+    }
+    s
+}
+
+fn nix_section(report: &NodeReport) -> Section {
+    let nix = &report.nix;
+    let mut s = Section::new("Nix");
+    s.text("Version", &nix.nix_version)
+        .text("Store Size", fmt_bytes(nix.store_size_bytes))
+        .text("Store Paths", nix.store_path_count.to_string())
+        .text("GC Roots", nix.gc_roots_count.to_string())
+        .text("Generations", nix.system_generations.to_string());
+    s.row(
+        "Sandbox",
+        if nix.sandbox_enabled {
+            colored(Level::Ok, "enabled")
+        } else {
+            colored(Level::Warn, "disabled")
+        },
+    );
+    if let Some(ref jobs) = nix.max_jobs {
+        s.text("Max Jobs", jobs);
+    }
+    if !nix.substituters.is_empty() {
+        s.text("Substituters", nix.substituters.join(", "));
+    }
+    if !nix.trusted_users.is_empty() {
+        s.text("Trusted Users", nix.trusted_users.join(", "));
+    }
+    if let Some(ref path) = nix.current_system_path {
+        s.text("System Path", path);
+    }
+    if let Some(ref ts) = nix.last_rebuild_timestamp {
+        s.text("Last Rebuild", ts.to_rfc3339());
+    }
+    if let Some(ref flake) = nix.system_flake {
+        s.text("System Flake", &flake.url);
+        if let Some(ref rev) = flake.locked_rev {
+            s.text("Locked Rev", rev);
+        }
+    }
+    if !nix.recent_nix_errors.is_empty() {
+        s.group("Recent Daemon Errors");
+        for line in &nix.recent_nix_errors {
+            s.row("", colored(Level::Crit, line));
+        }
+    }
+    s
+}
+
+fn kubernetes_section(report: &NodeReport) -> Option<Section> {
+    let k8s = report.kubernetes.as_ref()?;
+    let mut s = Section::new("Kubernetes");
+    if let Some(ref v) = k8s.k3s_version {
+        s.text("K3s Version", v);
+    }
+    s.row(
+        "Node Ready",
+        if k8s.node_ready {
+            colored(Level::Ok, "yes")
+        } else {
+            colored(Level::Crit, "no")
+        },
+    );
+    s.text("Pods", k8s.pod_count.to_string())
+        .text("Namespaces", k8s.namespace_count.to_string());
+    if k8s.cpu_requests_millis > 0 || k8s.memory_requests_bytes > 0 {
+        s.text(
+            "CPU Requests",
+            format!(
+                "{}m / Limits: {}m",
+                k8s.cpu_requests_millis, k8s.cpu_limits_millis
+            ),
+        );
+        s.text(
+            "Mem Requests",
+            format!(
+                "{} / Limits: {}",
+                fmt_bytes(k8s.memory_requests_bytes),
+                fmt_bytes(k8s.memory_limits_bytes)
+            ),
+        );
+    }
+    if !k8s.conditions.is_empty() {
+        s.group("Conditions");
+        for c in &k8s.conditions {
+            let level = if c.status == "True" {
+                Level::Ok
+            } else {
+                Level::Crit
+            };
+            let mut value = colored(level, &c.status);
+            if let Some(msg) = c.message.as_deref().filter(|m| !m.is_empty()) {
+                let _ = write!(value, " — {}", escape(msg));
+            }
+            s.row(&c.condition_type, value);
+        }
+    }
+    Some(s)
+}
+
+fn security_section(report: &NodeReport) -> Section {
+    let sec = &report.security;
+    let mut s = Section::new("Security");
+    let score_level = Level::from(report_health::score_level(sec.security_score));
+    s.row(
+        "Score",
+        colored(score_level, &format!("{}/100", sec.security_score)),
+    );
+    s.row(
+        "Firewall",
+        if sec.firewall_active {
+            colored(Level::Ok, "active")
+        } else {
+            colored(Level::Crit, "inactive")
+        },
+    );
+    s.text("SSHD Running", if sec.sshd_running { "yes" } else { "no" });
+    s.row(
+        "Root Login",
+        if sec.root_login_allowed {
+            colored(Level::Crit, "allowed")
+        } else {
+            colored(Level::Ok, "prohibited")
+        },
+    );
+    s.row(
+        "Password Auth",
+        if sec.password_auth_enabled {
+            colored(Level::Warn, "enabled")
+        } else {
+            colored(Level::Ok, "disabled")
+        },
+    );
+    s.text("SSH Keys", sec.ssh_keys_deployed.len().to_string());
+    if !sec.tls_certificates.is_empty() {
+        s.group("TLS Certificates");
+        for cert in &sec.tls_certificates {
+            let value = match cert.days_until_expiry {
+                Some(days) => {
+                    let level = Level::from(report_health::cert_level(days));
+                    colored(level, &format!("{} days", days))
+                }
+                None => "-".to_string(),
+            };
+            s.row(&cert.domain, value);
+        }
+    }
+    if !sec.findings.is_empty() {
+        s.group("Findings");
+        for finding in &sec.findings {
+            let (label, level) = match finding.severity {
+                FindingSeverity::High => ("high", Level::Crit),
+                FindingSeverity::Medium => ("medium", Level::Warn),
+                FindingSeverity::Low => ("low", Level::Ok),
+            };
+            s.row(label, colored(level, &finding.message));
+        }
+    }
+    s
+}
+
+/// `text`, escaped, in a span of `level`'s colour.
+fn colored(level: Level, text: &str) -> String {
+    format!("<span class=\"{}\">{}</span>", level.class(), escape(text))
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::node_report::tests::make_test_report;
+    use crate::domain::node_report::{DiskUsage, FindingKind, SecurityFinding};
+
+    #[test]
+    fn html_has_hostname_sections_and_levels() {
+        let mut report = make_test_report();
+        report.hostname = "web-01".to_string();
+        report.health.disk_usage = vec![DiskUsage {
+            mount_point: "/".to_string(),
+            usage_percent: 95.0,
+            inode_percent: None,
+        }];
+        report.security.findings = vec![SecurityFinding {
+            severity: FindingSeverity::High,
+            kind: FindingKind::Posture,
+            message: "sshd allows <root> login".to_string(),
+        }];
+
+        let html = render(&report);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>web-01 — kindling report</title>"));
+        assert!(html.contains("<h1>web-01</h1>"));
+        for section in [
+            "OS",
+            "Hardware",
+            "Health",
+            "Processes",
+            "Network",
+            "Nix",
+            "Security",
+        ] {
+            assert!(
+                html.contains(&format!("<summary>{}</summary>", section)),
+                "missing section {}",
+                section
+            );
+        }
+        assert!(html.contains("<span class=\"crit\">95.0%</span>"));
+        assert!(html.contains("sshd allows &lt;root&gt; login"));
+        assert!(!html.contains("<script"));
+    }
+}
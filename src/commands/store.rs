@@ -0,0 +1,153 @@
+//! `kindling store trend` — Nix store growth and disk exhaustion projection.
+//! `kindling store optimise` — deduplicate the store, or estimate the gain.
+
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+
+use anyhow::Result;
+use colored::Colorize;
+
+use crate::config;
+use crate::domain::nix_service::{self, NixService};
+use crate::domain::store_trend;
+use crate::paths::expand_path;
+
+/// Run `nix store optimise` on this machine, or with `dry_run` only scan
+/// the store for duplicates. Progress goes to stderr when it is a terminal.
+pub fn optimise(dry_run: bool, format: &str) -> Result<()> {
+    let show_progress = format != "json" && std::io::stderr().is_terminal();
+    if dry_run {
+        if show_progress {
+            eprintln!(
+                "{} Scanning /nix/store for duplicate files...",
+                ">>".blue().bold()
+            );
+        }
+        let estimate = nix_service::estimate_dedup(Path::new("/nix/store"), |scanned| {
+            if show_progress {
+                eprint!("\r   {} files scanned", scanned);
+                let _ = std::io::stderr().flush();
+            }
+        })?;
+        if show_progress {
+            eprintln!();
+        }
+        if format == "json" {
+            println!("{}", serde_json::to_string_pretty(&estimate)?);
+            return Ok(());
+        }
+        println!(
+            "{} Optimising would free ~{:.1} MiB by hard-linking {} duplicate files ({} scanned in {:.1}s)",
+            "ok".green().bold(),
+            estimate.deduplicable_bytes as f64 / 1_048_576.0,
+            estimate.duplicate_files,
+            estimate.scanned_files,
+            estimate.duration_secs
+        );
+        return Ok(());
+    }
+
+    if show_progress {
+        eprintln!(
+            "{} Running nix store optimise (this can take a while)...",
+            ">>".blue().bold()
+        );
+    }
+    let daemon_config = config::load()?.daemon.unwrap_or_default();
+    let rt = tokio::runtime::Runtime::new()?;
+    let result = rt.block_on(NixService::new(daemon_config).optimise_store())?;
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    println!(
+        "{} Freed {:.1} MiB in {:.1}s",
+        "ok".green().bold(),
+        result.deduplicated_bytes as f64 / 1_048_576.0,
+        result.duration_secs
+    );
+    Ok(())
+}
+
+pub fn trend(format: &str, threshold_percent: f64) -> Result<()> {
+    let cfg = config::load()?;
+    let cache_file = cfg
+        .daemon
+        .as_ref()
+        .map(|d| d.report.cache_file.clone())
+        .unwrap_or_else(|| config::ReportConfig::default().cache_file);
+    let path = store_trend::samples_path(&expand_path(&cache_file));
+    let samples = store_trend::read(&path)?;
+
+    let Some(trend) = store_trend::compute(&samples, threshold_percent) else {
+        if format == "json" {
+            println!("null");
+        } else {
+            println!(
+                "{} Not enough store samples yet ({} in {}); samples are taken at most hourly by the daemon or `kindling report --fresh`",
+                "::".blue().bold(),
+                samples.len(),
+                path.display()
+            );
+        }
+        return Ok(());
+    };
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&trend)?);
+        return Ok(());
+    }
+
+    println!(
+        "{} Nix store trend over {:.1} days ({} samples)",
+        ">>".blue().bold(),
+        trend.span_days,
+        trend.samples
+    );
+    println!(
+        "  Store Size:      {:.1} GiB ({:+.2} GiB/day)",
+        gib(trend.latest.store_size_bytes as f64),
+        gib(trend.size_bytes_per_day)
+    );
+    println!(
+        "  Store Paths:     {} ({:+.0}/day)",
+        trend.latest.store_path_count, trend.paths_per_day
+    );
+    if let Some(ref mount) = trend.latest.disk_mount {
+        println!(
+            "  Disk ({}):{:width$}{:.1} / {:.1} GiB",
+            mount,
+            "",
+            gib(trend.latest.disk_used_bytes as f64),
+            gib(trend.latest.disk_total_bytes as f64),
+            width = 10usize.saturating_sub(mount.len())
+        );
+    }
+    match trend.days_until_threshold {
+        Some(days) if days <= 0.0 => println!(
+            "  {} disk is already past {:.0}%",
+            "!!".red().bold(),
+            trend.threshold_percent
+        ),
+        Some(days) => {
+            let line = format!(
+                "reaches {:.0}% in ~{:.0} days",
+                trend.threshold_percent, days
+            );
+            if days < 30.0 {
+                println!("  {} {}", "!!".yellow().bold(), line);
+            } else {
+                println!("  {} {}", "ok".green().bold(), line);
+            }
+        }
+        None => println!(
+            "  {} no projected exhaustion (store not growing or disk unknown)",
+            "ok".green().bold()
+        ),
+    }
+    Ok(())
+}
+
+fn gib(bytes: f64) -> f64 {
+    bytes / (1u64 << 30) as f64
+}
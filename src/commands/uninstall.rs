@@ -1,37 +1,89 @@
 use anyhow::{bail, Context, Result};
 use colored::Colorize;
-use std::path::Path;
+use std::io::{BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub fn run() -> Result<()> {
-    // The nix-installer binary is left behind in /nix after install
-    let installer_paths = [
-        "/nix/nix-installer",
-        "/nix/var/nix/profiles/default/bin/nix-installer",
-    ];
+/// The nix-installer binary is left behind in /nix after install.
+const INSTALLER_PATHS: [&str; 2] = [
+    "nix/nix-installer",
+    "nix/var/nix/profiles/default/bin/nix-installer",
+];
 
-    let installer = installer_paths
-        .iter()
-        .map(Path::new)
-        .find(|p| p.exists());
+/// Install receipt written by nix-installer; it drives the uninstall plan.
+const RECEIPT_PATH: &str = "nix/receipt.json";
 
-    let installer = match installer {
+pub fn run(dry_run: bool, no_backup: bool, no_confirm: bool) -> Result<()> {
+    let root = Path::new("/");
+    let installer = match find_installer(root) {
         Some(p) => p,
         None => {
             bail!(
                 "nix-installer not found at {}. Was Nix installed with kindling/nix-installer?",
-                installer_paths.join(" or ")
+                INSTALLER_PATHS
+                    .iter()
+                    .map(|p| format!("/{}", p))
+                    .collect::<Vec<_>>()
+                    .join(" or ")
             );
         }
     };
+    let receipt = find_receipt(root);
+
+    if dry_run {
+        println!("{} Dry run — nothing will be changed", "::".blue().bold());
+        println!("  installer: {}", installer.display());
+        match receipt {
+            Some(ref r) => {
+                println!("  receipt:   {}", r.display());
+                let plan = read_plan(r)?;
+                println!(
+                    "  plan:      nix-installer {}, {} planner, {} action(s) to revert",
+                    plan.version,
+                    plan.planner.as_deref().unwrap_or("unknown"),
+                    plan.actions.len()
+                );
+                for action in plan.actions.iter().rev() {
+                    println!("    - {}", action);
+                }
+            }
+            None => println!(
+                "  receipt:   {} (uninstall will likely fail)",
+                "not found".yellow()
+            ),
+        }
+        if receipt.is_some() && !no_backup {
+            println!("  backup:    {}", backup_path(&backup_dir()).display());
+        }
+        println!(
+            "  would run: {} {}",
+            installer.display(),
+            uninstall_args().join(" ")
+        );
+        return Ok(());
+    }
+
+    let interactive = std::io::stdin().is_terminal();
+    confirm(no_confirm, interactive, &mut std::io::stdin().lock())?;
+
+    if let Some(ref r) = receipt {
+        if !no_backup {
+            let dest = backup_receipt(r, &backup_dir())?;
+            println!(
+                "{} Backed up install receipt to {}",
+                "ok".green().bold(),
+                dest.display()
+            );
+        }
+    }
 
     println!(
         "{} Running nix-installer uninstall...",
         "::".blue().bold()
     );
 
-    let status = Command::new(installer)
-        .args(["uninstall", "--no-confirm"])
+    let status = Command::new(&installer)
+        .args(uninstall_args())
         .status()
         .context("failed to run nix-installer uninstall")?;
 
@@ -42,3 +94,231 @@ pub fn run() -> Result<()> {
     println!("{} Nix uninstalled successfully", "ok".green().bold());
     Ok(())
 }
+
+/// Arguments for the real uninstall. `--no-confirm` is always passed since
+/// kindling has already asked (see [`confirm`]) and runs the installer
+/// non-interactively.
+fn uninstall_args() -> [&'static str; 2] {
+    ["uninstall", "--no-confirm"]
+}
+
+/// Gate the real uninstall: always allowed with `--no-confirm`, otherwise
+/// only when a terminal answers yes. Anything else is an error, so a script
+/// never mistakes a skipped uninstall for a successful one.
+fn confirm(no_confirm: bool, interactive: bool, input: &mut impl BufRead) -> Result<()> {
+    if no_confirm {
+        return Ok(());
+    }
+    if !interactive {
+        bail!("refusing to uninstall non-interactively without --no-confirm");
+    }
+    eprint!(
+        "{} Uninstall Nix and remove /nix? [y/N] ",
+        "??".blue().bold()
+    );
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+        bail!("uninstall cancelled");
+    }
+    Ok(())
+}
+
+/// What the install receipt says the uninstaller will revert.
+#[derive(Debug, PartialEq)]
+struct ReceiptPlan {
+    version: String,
+    planner: Option<String>,
+    /// Action names in install order; uninstall reverts them last to first.
+    actions: Vec<String>,
+}
+
+fn read_plan(receipt: &Path) -> Result<ReceiptPlan> {
+    let content = std::fs::read_to_string(receipt)
+        .with_context(|| format!("failed to read {}", receipt.display()))?;
+    parse_plan(&content).with_context(|| {
+        format!(
+            "{} is not a usable install receipt; the uninstall would fail",
+            receipt.display()
+        )
+    })
+}
+
+fn parse_plan(content: &str) -> Result<ReceiptPlan> {
+    let v: serde_json::Value = serde_json::from_str(content)?;
+    let Some(version) = v.get("version").and_then(|v| v.as_str()) else {
+        bail!("no installer version");
+    };
+    let Some(actions) = v.get("actions").and_then(|a| a.as_array()) else {
+        bail!("no actions");
+    };
+    let actions = actions
+        .iter()
+        .map(|a| {
+            a.pointer("/action/action_name")
+                .and_then(|n| n.as_str())
+                .unwrap_or("unknown action")
+                .to_string()
+        })
+        .collect();
+    Ok(ReceiptPlan {
+        version: version.to_string(),
+        planner: v
+            .pointer("/planner/planner")
+            .and_then(|p| p.as_str())
+            .map(str::to_string),
+        actions,
+    })
+}
+
+fn find_installer(root: &Path) -> Option<PathBuf> {
+    INSTALLER_PATHS
+        .iter()
+        .map(|p| root.join(p))
+        .find(|p| p.exists())
+}
+
+fn find_receipt(root: &Path) -> Option<PathBuf> {
+    Some(root.join(RECEIPT_PATH)).filter(|p| p.is_file())
+}
+
+/// Receipt backups live outside /nix so they survive the uninstall.
+fn backup_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("/tmp"))
+        .join("kindling")
+        .join("receipts")
+}
+
+fn backup_path(dir: &Path) -> PathBuf {
+    dir.join(format!(
+        "receipt-{}.json",
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    ))
+}
+
+fn backup_receipt(receipt: &Path, dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("failed to create directory {}", dir.display()))?;
+    let dest = backup_path(dir);
+    std::fs::copy(receipt, &dest).with_context(|| {
+        format!(
+            "failed to back up {} to {}",
+            receipt.display(),
+            dest.display()
+        )
+    })?;
+    Ok(dest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_receipt_under_root() {
+        let root = tempfile::tempdir().unwrap();
+        assert!(find_receipt(root.path()).is_none());
+
+        std::fs::create_dir_all(root.path().join("nix")).unwrap();
+        std::fs::write(root.path().join("nix/receipt.json"), "{}").unwrap();
+        assert_eq!(
+            find_receipt(root.path()),
+            Some(root.path().join("nix/receipt.json"))
+        );
+    }
+
+    #[test]
+    fn find_receipt_ignores_directory() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("nix/receipt.json")).unwrap();
+        assert!(find_receipt(root.path()).is_none());
+    }
+
+    #[test]
+    fn find_installer_prefers_nix_root() {
+        let root = tempfile::tempdir().unwrap();
+        assert!(find_installer(root.path()).is_none());
+
+        let profile_bin = root.path().join("nix/var/nix/profiles/default/bin");
+        std::fs::create_dir_all(&profile_bin).unwrap();
+        std::fs::write(profile_bin.join("nix-installer"), "").unwrap();
+        assert_eq!(
+            find_installer(root.path()),
+            Some(profile_bin.join("nix-installer"))
+        );
+
+        std::fs::write(root.path().join("nix/nix-installer"), "").unwrap();
+        assert_eq!(
+            find_installer(root.path()),
+            Some(root.path().join("nix/nix-installer"))
+        );
+    }
+
+    #[test]
+    fn backup_receipt_copies_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let receipt = dir.path().join("receipt.json");
+        std::fs::write(&receipt, r#"{"version":"0.30.0"}"#).unwrap();
+
+        let dest = backup_receipt(&receipt, &dir.path().join("backups")).unwrap();
+        assert!(dest.starts_with(dir.path().join("backups")));
+        assert_eq!(
+            std::fs::read_to_string(dest).unwrap(),
+            r#"{"version":"0.30.0"}"#
+        );
+    }
+
+    #[test]
+    fn real_run_passes_no_confirm() {
+        assert_eq!(uninstall_args(), ["uninstall", "--no-confirm"]);
+    }
+
+    #[test]
+    fn real_run_needs_confirmation_without_no_confirm() {
+        assert!(confirm(true, false, &mut "".as_bytes()).is_ok());
+        assert!(confirm(false, true, &mut "y\n".as_bytes()).is_ok());
+        assert!(confirm(false, true, &mut "YES\n".as_bytes()).is_ok());
+        for declined in ["n\n", "\n", ""] {
+            let err = confirm(false, true, &mut declined.as_bytes()).unwrap_err();
+            assert_eq!(err.to_string(), "uninstall cancelled");
+        }
+
+        let err = confirm(false, false, &mut "y\n".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("without --no-confirm"));
+    }
+
+    #[test]
+    fn parse_plan_reads_receipt_actions() {
+        let receipt = r#"{
+            "version": "0.30.0",
+            "actions": [
+                {"action": {"action_name": "create_nix_tree", "path": "/nix"}, "state": "Completed"},
+                {"action": {"action_name": "configure_nix"}, "state": "Completed"},
+                {"action": {}, "state": "Completed"}
+            ],
+            "planner": {"planner": "linux", "settings": {}}
+        }"#;
+        assert_eq!(
+            parse_plan(receipt).unwrap(),
+            ReceiptPlan {
+                version: "0.30.0".to_string(),
+                planner: Some("linux".to_string()),
+                actions: vec![
+                    "create_nix_tree".to_string(),
+                    "configure_nix".to_string(),
+                    "unknown action".to_string(),
+                ],
+            }
+        );
+
+        assert!(parse_plan("not json").is_err());
+        assert!(parse_plan(r#"{"version": "0.30.0"}"#).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let broken = dir.path().join("receipt.json");
+        std::fs::write(&broken, "{").unwrap();
+        let err = read_plan(&broken).unwrap_err();
+        assert!(err.to_string().contains("not a usable install receipt"));
+    }
+}
@@ -1,11 +1,14 @@
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use figment::providers::{Env, Format, Serialized, Yaml};
 use figment::Figment;
 use serde::{Deserialize, Serialize};
 
+use crate::domain::node_report::ChecksumAlgorithm;
+use crate::secret::Secret;
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     pub auto_install: Option<bool>,
@@ -15,6 +18,21 @@ pub struct Config {
     pub daemon: Option<DaemonConfig>,
     #[serde(default)]
     pub nodes: BTreeMap<String, NodeTarget>,
+    /// Fallback URL for nodes not listed in `nodes`, e.g.
+    /// `http://{name}.internal:9100`. `{hostname}` resolves via `fleet.peers`.
+    #[serde(default)]
+    pub node_url_template: Option<String>,
+    /// How this host's clients verify `https://` daemons and controllers.
+    #[serde(default)]
+    pub tls: ClientTlsConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClientTlsConfig {
+    /// PEM CA certificate to trust on top of the system roots, e.g. the
+    /// one that signed the daemons' self-issued certificates.
+    #[serde(default)]
+    pub ca_cert: Option<String>,
 }
 
 /// A named remote node target for `kindling query --node <name>`.
@@ -27,8 +45,39 @@ pub struct NodeTarget {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DaemonConfig {
+    /// TCP listen address. Empty disables the TCP listener.
     #[serde(default = "default_http_addr")]
     pub http_addr: String,
+    /// Also serve the HTTP API on this Unix socket (mode 0660).
+    #[serde(default)]
+    pub unix_socket: Option<String>,
+    /// What to do when `http_addr` is reachable beyond loopback.
+    #[serde(default)]
+    pub insecure_bind: InsecureBindPolicy,
+    /// Write the daemon's PID here while it runs. Startup fails if the
+    /// file names a process that is still alive.
+    #[serde(default)]
+    pub pid_file: Option<String>,
+    /// Origins allowed to call the HTTP API from a browser. Empty (the
+    /// default) allows no cross-origin requests; `*` allows any origin.
+    #[serde(default)]
+    pub cors_allowed_origins: Vec<String>,
+    /// Random delay added to each tick of the report refresh, telemetry
+    /// push and GC loops, as a percentage of the loop's interval, so nodes
+    /// started together drift apart. 0 disables it.
+    #[serde(default = "default_interval_jitter_percent")]
+    pub interval_jitter_percent: u32,
+    /// Announce the daemon on the LAN over mDNS (`_kindling._tcp`) so
+    /// `kindling discover` can find it.
+    #[serde(default)]
+    pub advertise: bool,
+    /// Bearer tokens accepted by the HTTP API. None (the default) leaves
+    /// the API unauthenticated.
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Serve the TCP listener over TLS.
+    #[serde(default)]
+    pub tls: DaemonTlsConfig,
     #[serde(default = "default_grpc_addr")]
     pub grpc_addr: String,
     #[serde(default = "default_log_level")]
@@ -43,12 +92,24 @@ pub struct DaemonConfig {
     pub report: ReportConfig,
     #[serde(default)]
     pub fleet_controller: FleetControllerConfig,
+    /// `experimental-features` that must be enabled in nix.conf; any that
+    /// are absent are reported as `missing_features`.
+    #[serde(default = "default_required_features")]
+    pub required_features: Vec<String>,
 }
 
 impl Default for DaemonConfig {
     fn default() -> Self {
         Self {
             http_addr: default_http_addr(),
+            unix_socket: None,
+            insecure_bind: InsecureBindPolicy::default(),
+            pid_file: None,
+            cors_allowed_origins: Vec::new(),
+            interval_jitter_percent: default_interval_jitter_percent(),
+            advertise: false,
+            auth: AuthConfig::default(),
+            tls: DaemonTlsConfig::default(),
             grpc_addr: default_grpc_addr(),
             log_level: default_log_level(),
             identity: IdentityConfig::default(),
@@ -56,10 +117,82 @@ impl Default for DaemonConfig {
             gc: GcConfig::default(),
             report: ReportConfig::default(),
             fleet_controller: FleetControllerConfig::default(),
+            required_features: default_required_features(),
         }
     }
 }
 
+/// Startup policy for an HTTP listener bound to a non-loopback address.
+/// The REST API has mutating endpoints and, unless `daemon.auth.tokens` is
+/// set, no authentication.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InsecureBindPolicy {
+    /// Start, but log a prominent warning.
+    #[default]
+    Warn,
+    /// Refuse to start (override with `kindling daemon --allow-insecure`).
+    Refuse,
+    /// Start silently.
+    Allow,
+}
+
+/// Certificate for the HTTP API. Explicit `cert_file`/`key_file` win;
+/// otherwise the pair comes from node.yaml `secrets.tls_certificates`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DaemonTlsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `secrets.tls_certificates` entry to use; the first with both a cert
+    /// and a key file when unset.
+    #[serde(default)]
+    pub domain: Option<String>,
+    #[serde(default)]
+    pub cert_file: Option<String>,
+    #[serde(default)]
+    pub key_file: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub tokens: Vec<ApiToken>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiToken {
+    /// Label for logs; the token itself is never logged.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The token itself, or `secret:<name>` to resolve `<name>` through
+    /// node.yaml's `secrets.provider` when the daemon starts.
+    pub token: Secret,
+    pub scope: TokenScope,
+}
+
+/// Marks an [`ApiToken::token`] that names a secret instead of holding one.
+pub const SECRET_REF_PREFIX: &str = "secret:";
+
+impl ApiToken {
+    /// The secret name when the token is a `secret:<name>` reference.
+    pub fn secret_ref(&self) -> Option<&str> {
+        self.token.expose().strip_prefix(SECRET_REF_PREFIX)
+    }
+}
+
+/// What a token may do. Each scope includes the ones before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    /// GET endpoints: status, store, identity, report, metrics.
+    Read,
+    /// Also every non-GET endpoint: GC, optimise, verify, report refresh,
+    /// fleet ingest, identity edits and GraphQL mutations.
+    Write,
+    /// Every endpoint; reserved for routes above `write`.
+    Admin,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelemetryConfig {
     #[serde(default)]
@@ -102,6 +235,21 @@ pub struct ReportConfig {
     /// Maximum age in seconds before a cached report is considered stale.
     #[serde(default = "default_max_age_secs")]
     pub max_age_secs: u64,
+    /// Domain each configured DNS resolver is asked to resolve during
+    /// collection. Empty disables resolver probing.
+    #[serde(default = "default_dns_probe_domain")]
+    pub dns_probe_domain: String,
+    /// Dot-path fields removed by `kindling report --redact`; `*` matches
+    /// every element of a list.
+    #[serde(default = "default_redact_fields")]
+    pub redact_fields: Vec<String>,
+    /// Digest for stored report checksums: sha256, sha512 or blake3.
+    #[serde(default)]
+    pub checksum_algorithm: ChecksumAlgorithm,
+    /// Sum CPU and memory per program into a "top programs" list, so many
+    /// workers of one service show as a single entry.
+    #[serde(default = "default_group_processes")]
+    pub group_processes: bool,
 }
 
 impl Default for ReportConfig {
@@ -110,6 +258,10 @@ impl Default for ReportConfig {
             refresh_interval_secs: default_report_interval(),
             cache_file: default_cache_file(),
             max_age_secs: default_max_age_secs(),
+            dns_probe_domain: default_dns_probe_domain(),
+            redact_fields: default_redact_fields(),
+            checksum_algorithm: ChecksumAlgorithm::default(),
+            group_processes: default_group_processes(),
         }
     }
 }
@@ -134,6 +286,10 @@ pub struct FleetControllerConfig {
     /// Path to persist fleet state.
     #[serde(default = "default_fleet_state_path")]
     pub state_file: String,
+    /// Largest report upload accepted, in bytes, measured after gzip
+    /// decoding. Larger bodies get 413. 0 uses axum's 2 MiB default.
+    #[serde(default = "default_max_report_bytes")]
+    pub max_report_bytes: usize,
 }
 
 impl Default for FleetControllerConfig {
@@ -141,19 +297,30 @@ impl Default for FleetControllerConfig {
         Self {
             enabled: false,
             state_file: default_fleet_state_path(),
+            max_report_bytes: default_max_report_bytes(),
         }
     }
 }
 
+fn default_max_report_bytes() -> usize {
+    8 * 1024 * 1024
+}
+
 fn default_http_addr() -> String {
     "127.0.0.1:9100".to_string()
 }
 fn default_grpc_addr() -> String {
     "127.0.0.1:9101".to_string()
 }
+fn default_interval_jitter_percent() -> u32 {
+    10
+}
 fn default_log_level() -> String {
     "info".to_string()
 }
+pub fn default_required_features() -> Vec<String> {
+    vec!["nix-command".to_string(), "flakes".to_string()]
+}
 fn default_vector_url() -> String {
     "http://localhost:8686".to_string()
 }
@@ -174,6 +341,15 @@ fn default_cache_file() -> String {
 fn default_max_age_secs() -> u64 {
     600 // 10 minutes
 }
+fn default_dns_probe_domain() -> String {
+    "cache.nixos.org".to_string()
+}
+fn default_group_processes() -> bool {
+    true
+}
+fn default_redact_fields() -> Vec<String> {
+    vec!["security.ssh_keys_deployed".to_string()]
+}
 fn default_fleet_state_path() -> String {
     dirs::config_dir()
         .unwrap_or_else(|| std::path::PathBuf::from("~/.config"))
@@ -198,6 +374,8 @@ impl shikumi::TieredConfig for Config {
             identity: IdentityConfig::default(),
             daemon: None,
             nodes: BTreeMap::new(),
+            node_url_template: None,
+            tls: ClientTlsConfig::default(),
         }
     }
     fn prescribed_default() -> Self {
@@ -209,6 +387,14 @@ impl shikumi::TieredConfig for DaemonConfig {
     fn bare() -> Self {
         Self {
             http_addr: String::new(),
+            unix_socket: None,
+            insecure_bind: InsecureBindPolicy::default(),
+            pid_file: None,
+            cors_allowed_origins: Vec::new(),
+            interval_jitter_percent: 0,
+            advertise: false,
+            auth: AuthConfig::default(),
+            tls: DaemonTlsConfig::default(),
             grpc_addr: String::new(),
             log_level: String::new(),
             identity: IdentityConfig::default(),
@@ -223,11 +409,17 @@ impl shikumi::TieredConfig for DaemonConfig {
                 refresh_interval_secs: 0,
                 cache_file: String::new(),
                 max_age_secs: 0,
+                dns_probe_domain: String::new(),
+                redact_fields: Vec::new(),
+                checksum_algorithm: ChecksumAlgorithm::default(),
+                group_processes: false,
             },
             fleet_controller: FleetControllerConfig {
                 enabled: false,
                 state_file: String::new(),
+                max_report_bytes: 0,
             },
+            required_features: Vec::new(),
         }
     }
     fn prescribed_default() -> Self {
@@ -255,6 +447,10 @@ impl shikumi::TieredConfig for ReportConfig {
             refresh_interval_secs: 0,
             cache_file: String::new(),
             max_age_secs: 0,
+            dns_probe_domain: String::new(),
+            redact_fields: Vec::new(),
+            checksum_algorithm: ChecksumAlgorithm::default(),
+            group_processes: false,
         }
     }
     fn prescribed_default() -> Self {
@@ -267,6 +463,7 @@ impl shikumi::TieredConfig for FleetControllerConfig {
         Self {
             enabled: false,
             state_file: String::new(),
+            max_report_bytes: 0,
         }
     }
     fn prescribed_default() -> Self {
@@ -293,12 +490,17 @@ fn local_config_path() -> PathBuf {
 
 // ── Figment loading ────────────────────────────────────────
 
+/// Prefix of config environment variables; `__` separates nested keys,
+/// e.g. `KINDLING_DAEMON__LOG_LEVEL`.
+const ENV_PREFIX: &str = "KINDLING_";
+const ENV_SEPARATOR: &str = "__";
+
 /// Build the figment provider chain:
 /// defaults → system YAML → env vars → user YAML → local YAML
 fn figment() -> Figment {
     Figment::from(Serialized::defaults(Config::default()))
         .merge(Yaml::file(system_config_path()))
-        .merge(Env::prefixed("KINDLING_").split("__"))
+        .merge(Env::prefixed(ENV_PREFIX).split(ENV_SEPARATOR))
         .merge(Yaml::file(user_config_path()))
         .merge(Yaml::file(local_config_path()))
 }
@@ -318,27 +520,156 @@ pub fn load_with_path(path: &str) -> Result<Config> {
         .map_err(|e| anyhow::anyhow!("config error: {}", e))
 }
 
+/// One merged config value and the layer that set it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigValue {
+    pub value: serde_json::Value,
+    /// `default`, the config file path, or `env <VAR>`.
+    pub source: String,
+}
+
+/// Every leaf of the merged config, keyed by dotted path (`daemon.http_addr`),
+/// with the layer it came from. Fails like [`load`] on an invalid config.
+pub fn provenance() -> Result<BTreeMap<String, ConfigValue>> {
+    let figment = figment();
+    figment
+        .extract::<Config>()
+        .map_err(|e| anyhow::anyhow!("config error: {}", e))?;
+    provenance_of(&figment, ENV_PREFIX)
+}
+
+/// Config fields of type `Secret`, as dot paths; `[]` steps into every
+/// element of a list. `config show` prints these as `[redacted]`.
+const SECRET_PATHS: &[&str] = &["daemon.auth.tokens.[].token"];
+
+/// Redact the secrets inside the value at dot path `key`.
+fn redact_secrets(key: &str, value: &mut serde_json::Value) {
+    fn redact_at(value: &mut serde_json::Value, rest: &str) {
+        if rest.is_empty() {
+            *value = serde_json::Value::from(crate::secret::REDACTED);
+            return;
+        }
+        let (head, tail) = rest.split_once('.').unwrap_or((rest, ""));
+        match (head, value) {
+            ("[]", serde_json::Value::Array(items)) => {
+                items.iter_mut().for_each(|item| redact_at(item, tail))
+            }
+            (field, serde_json::Value::Object(map)) => {
+                if let Some(child) = map.get_mut(field) {
+                    redact_at(child, tail);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for secret in SECRET_PATHS {
+        if *secret == key {
+            redact_at(value, "");
+        } else if let Some(rest) = secret.strip_prefix(key).and_then(|r| r.strip_prefix('.')) {
+            redact_at(value, rest);
+        }
+    }
+}
+
+fn provenance_of(figment: &Figment, env_prefix: &str) -> Result<BTreeMap<String, ConfigValue>> {
+    fn walk(
+        figment: &Figment,
+        env_prefix: &str,
+        path: &mut Vec<String>,
+        value: &figment::value::Value,
+        out: &mut BTreeMap<String, ConfigValue>,
+    ) -> Result<()> {
+        if let Some(dict) = value.as_dict().filter(|d| !d.is_empty()) {
+            for (key, child) in dict {
+                path.push(key.clone());
+                walk(figment, env_prefix, path, child, out)?;
+                path.pop();
+            }
+            return Ok(());
+        }
+        let source = match figment.get_metadata(value.tag()) {
+            Some(md) => match md.source {
+                Some(figment::Source::File(ref file)) => file.display().to_string(),
+                Some(figment::Source::Code(_)) => "default".to_string(),
+                Some(ref other) => other.to_string(),
+                None if md.name.contains("environment") => format!(
+                    "env {}{}",
+                    env_prefix,
+                    path.join(ENV_SEPARATOR).to_ascii_uppercase()
+                ),
+                None => md.name.to_string(),
+            },
+            None => "unknown".to_string(),
+        };
+        let key = path.join(".");
+        let mut value = serde_json::to_value(value)?;
+        redact_secrets(&key, &mut value);
+        out.insert(key, ConfigValue { value, source });
+        Ok(())
+    }
+
+    let merged = figment
+        .find_value("")
+        .map_err(|e| anyhow::anyhow!("config error: {}", e))?;
+    let mut out = BTreeMap::new();
+    walk(figment, env_prefix, &mut Vec::new(), &merged, &mut out)?;
+    Ok(out)
+}
+
 /// Persist the auto_install flag to the user config file.
 pub fn save_auto_install(value: bool) -> Result<()> {
-    let path = user_config_path();
-
-    let mut config = if path.exists() {
-        let content = std::fs::read_to_string(&path)
-            .with_context(|| format!("reading {}", path.display()))?;
-        serde_yaml::from_str(&content).unwrap_or_default()
-    } else {
-        Config::default()
+    update_user_config(&user_config_path(), |doc| {
+        doc.insert("auto_install".into(), value.into());
+        Ok(())
+    })
+}
+
+/// Add or replace entries in the user config's `nodes` map.
+pub fn save_nodes(nodes: BTreeMap<String, NodeTarget>) -> Result<()> {
+    update_user_config(&user_config_path(), |doc| {
+        let entry = doc
+            .entry("nodes".into())
+            .or_insert_with(|| serde_yaml::Mapping::new().into());
+        let Some(map) = entry.as_mapping_mut() else {
+            bail!("`nodes` in the user config is not a map");
+        };
+        for (name, target) in nodes {
+            map.insert(name.into(), serde_yaml::to_value(target)?);
+        }
+        Ok(())
+    })
+}
+
+/// Read the user config file at `path`, apply `update` to its top-level
+/// mapping and write it back. Only the keys `update` touches change, so
+/// settings this version doesn't know about survive; a file that doesn't
+/// parse is an error rather than being overwritten.
+fn update_user_config(
+    path: &Path,
+    update: impl FnOnce(&mut serde_yaml::Mapping) -> Result<()>,
+) -> Result<()> {
+    let mut doc = match std::fs::read_to_string(path) {
+        Ok(content) => match serde_yaml::from_str(&content)
+            .with_context(|| format!("parsing {}", path.display()))?
+        {
+            serde_yaml::Value::Null => serde_yaml::Mapping::new(),
+            serde_yaml::Value::Mapping(map) => map,
+            _ => bail!("{} is not a YAML mapping", path.display()),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => serde_yaml::Mapping::new(),
+        Err(e) => return Err(e).with_context(|| format!("reading {}", path.display())),
     };
 
-    config.auto_install = Some(value);
+    update(&mut doc)?;
 
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)
             .with_context(|| format!("creating {}", parent.display()))?;
     }
 
-    let content = serde_yaml::to_string(&config).context("serializing config")?;
-    std::fs::write(&path, content).with_context(|| format!("writing {}", path.display()))?;
+    let content = serde_yaml::to_string(&doc).context("serializing config")?;
+    std::fs::write(path, content).with_context(|| format!("writing {}", path.display()))?;
     Ok(())
 }
 
@@ -396,6 +727,7 @@ mod tests {
     fn fleet_controller_config_default_disabled() {
         let fc = FleetControllerConfig::default();
         assert!(!fc.enabled);
+        assert_eq!(fc.max_report_bytes, 8 * 1024 * 1024);
     }
 
     #[test]
@@ -455,6 +787,72 @@ nodes:
         let config: Config = serde_yaml::from_str(yaml).unwrap();
         assert!(config.nodes.contains_key("staging"));
     }
+
+    #[test]
+    fn provenance_attributes_env_and_file_layers() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("config.yaml");
+        std::fs::write(
+            &file,
+            "daemon:\n  log_level: debug\n  auth:\n    tokens:\n      - token: s3cret\n        scope: read\n",
+        )
+        .unwrap();
+        std::env::set_var("KINDLING_PROVENANCE_TEST_BACKEND", "determinate");
+
+        let figment = Figment::from(Serialized::defaults(Config::default()))
+            .merge(Env::prefixed("KINDLING_PROVENANCE_TEST_").split(ENV_SEPARATOR))
+            .merge(Yaml::file(&file));
+        let values = provenance_of(&figment, "KINDLING_PROVENANCE_TEST_").unwrap();
+        std::env::remove_var("KINDLING_PROVENANCE_TEST_BACKEND");
+
+        let backend = &values["backend"];
+        assert_eq!(backend.value, "determinate");
+        assert_eq!(backend.source, "env KINDLING_PROVENANCE_TEST_BACKEND");
+        assert_eq!(values["daemon.log_level"].source, file.display().to_string());
+        assert_eq!(values["nodes"].source, "default");
+        let tokens = &values["daemon.auth.tokens"].value;
+        assert_eq!(tokens[0]["token"], "[redacted]");
+        assert_eq!(tokens[0]["scope"], "read");
+    }
+
+    #[test]
+    fn insecure_bind_defaults_to_warn() {
+        assert_eq!(DaemonConfig::default().insecure_bind, InsecureBindPolicy::Warn);
+        let dc: DaemonConfig = serde_yaml::from_str("insecure_bind: refuse").unwrap();
+        assert_eq!(dc.insecure_bind, InsecureBindPolicy::Refuse);
+    }
+
+    #[test]
+    fn update_user_config_only_touches_the_changed_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("kindling.yaml");
+        std::fs::write(
+            &file,
+            "backend: upstream\nfuture_setting: keep-me\nnodes:\n  a:\n    url: http://a:9100\n",
+        )
+        .unwrap();
+
+        update_user_config(&file, |doc| {
+            doc.insert("auto_install".into(), true.into());
+            Ok(())
+        })
+        .unwrap();
+        let doc: serde_yaml::Value =
+            serde_yaml::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+        assert_eq!(doc["auto_install"], true);
+        assert_eq!(doc["backend"], "upstream");
+        assert_eq!(doc["future_setting"], "keep-me");
+        assert_eq!(doc["nodes"]["a"]["url"], "http://a:9100");
+        assert!(doc.get("daemon").is_none());
+
+        std::fs::write(&file, "backend: [unclosed\n").unwrap();
+        let err = update_user_config(&file, |_| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("parsing"));
+        assert_eq!(
+            std::fs::read_to_string(&file).unwrap(),
+            "backend: [unclosed\n"
+        );
+    }
 }
 
 #[cfg(test)]
@@ -469,6 +867,7 @@ mod tiered_tests {
         assert!(b.backend.is_none());
         assert!(b.daemon.is_none());
         assert!(b.nodes.is_empty());
+        assert!(b.node_url_template.is_none());
     }
 
     #[test]
@@ -0,0 +1,155 @@
+//! Age key rotation — is the deployed age identity still a recipient?
+//!
+//! After a key rotation the secrets are re-encrypted to the new recipients;
+//! a node still holding the old identity can no longer decrypt them, which
+//! only shows up once a service restarts and fails. The recipients are
+//! those recorded in the sops file's metadata, which is what the secrets
+//! really are encrypted to; only when that can't be read do the `age1…`
+//! entries of `secrets.age_keys` stand in. The deployed identity's
+//! recipient comes from `age-keygen -y`.
+
+use std::process::Stdio;
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::node_report::{FindingKind, FindingSeverity, SecurityFinding};
+use crate::secret::Secret;
+
+/// Message prefix for the rotation finding.
+pub const ROTATION_PREFIX: &str = "age key rotation needed: ";
+
+/// Recipients listed under `sops.age[].recipient` in a sops-encrypted file
+/// (YAML or JSON).
+pub fn sops_recipients(content: &str) -> Vec<String> {
+    let Ok(doc) = serde_yaml::from_str::<serde_yaml::Value>(content) else {
+        return Vec::new();
+    };
+    doc.get("sops")
+        .and_then(|s| s.get("age"))
+        .and_then(|a| a.as_sequence())
+        .map(|entries| {
+            entries
+                .iter()
+                .filter_map(|e| e.get("recipient")?.as_str())
+                .map(|r| r.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Public keys (`age1…`) from `secrets.age_keys`; anything else is skipped.
+pub fn declared_recipients(age_keys: &[String]) -> Vec<String> {
+    age_keys
+        .iter()
+        .map(|k| k.trim())
+        .filter(|k| k.starts_with("age1"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Recipients to check against: the sops metadata's when `sops_content` is
+/// readable and lists any, otherwise the declared `age_keys`.
+pub fn recipients(sops_content: Option<&str>, age_keys: &[String]) -> Vec<String> {
+    let from_sops = sops_content.map(sops_recipients).unwrap_or_default();
+    if from_sops.is_empty() {
+        declared_recipients(age_keys)
+    } else {
+        from_sops
+    }
+}
+
+/// Recipients printed by `age-keygen -y`, one per identity in the key file.
+pub fn parse_public_keys(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|l| l.starts_with("age1"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Recipients of the identities in `key`, an age key file's content. The
+/// key goes to `age-keygen -y` on stdin. `None` if age-keygen fails.
+pub async fn public_keys(key: &Secret) -> Option<Vec<String>> {
+    let mut child = Command::new("age-keygen")
+        .arg("-y")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let mut stdin = child.stdin.take()?;
+    stdin.write_all(key.expose().as_bytes()).await.ok()?;
+    drop(stdin);
+    let output = child.wait_with_output().await.ok()?;
+    output
+        .status
+        .success()
+        .then(|| parse_public_keys(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Flag a deployed identity none of whose recipients the secrets were
+/// encrypted to. Either side being unknown yields nothing.
+pub fn check(deployed: &[String], recipients: &[String]) -> Option<SecurityFinding> {
+    if deployed.is_empty() || recipients.is_empty() {
+        return None;
+    }
+    if deployed.iter().any(|d| recipients.contains(d)) {
+        return None;
+    }
+    Some(SecurityFinding {
+        severity: FindingSeverity::High,
+        kind: FindingKind::AgeRotation,
+        message: format!(
+            "{}deployed age identity {} is not among the {} secrets recipient(s)",
+            ROTATION_PREFIX,
+            deployed.join(", "),
+            recipients.len()
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+    const NEW: &str = "age1lggyhqrw2nlhcxprm67z43rta597azn8gknawjehu9d9dl0jq3yqqvfafg";
+
+    #[test]
+    fn deployed_recipient_membership() {
+        let recipients = vec![NEW.to_string()];
+        assert!(check(&[NEW.to_string()], &recipients).is_none());
+        assert!(check(&[OLD.to_string(), NEW.to_string()], &recipients).is_none());
+
+        let finding = check(&[OLD.to_string()], &recipients).unwrap();
+        assert_eq!(finding.severity, FindingSeverity::High);
+        assert!(finding.message.starts_with(ROTATION_PREFIX));
+        assert!(finding.message.contains(OLD));
+
+        assert!(check(&[], &recipients).is_none());
+        assert!(check(&[OLD.to_string()], &[]).is_none());
+    }
+
+    #[test]
+    fn reads_recipients_from_sops_metadata_and_age_keys() {
+        let sops = format!(
+            "db_password: ENC[AES256_GCM,data:abc]\nsops:\n  age:\n    - recipient: {}\n      enc: |\n        -----BEGIN AGE ENCRYPTED FILE-----\n  lastmodified: \"2026-01-01T00:00:00Z\"\n",
+            NEW
+        );
+        assert_eq!(sops_recipients(&sops), vec![NEW]);
+        assert!(sops_recipients("not: [valid").is_empty());
+
+        let keys = vec![format!(" {} ", OLD), "AGE-SECRET-KEY-1QQQQ".to_string()];
+        assert_eq!(declared_recipients(&keys), vec![OLD]);
+
+        // The sops metadata wins; a stale age_keys entry can't mask a rotation.
+        assert_eq!(recipients(Some(&sops), &keys), vec![NEW]);
+        assert_eq!(recipients(None, &keys), vec![OLD]);
+        assert_eq!(recipients(Some("plain: yaml"), &keys), vec![OLD]);
+
+        let output = format!("# comment\n{}\n", NEW);
+        assert_eq!(parse_public_keys(&output), vec![NEW]);
+    }
+}
@@ -0,0 +1,236 @@
+//! cgroup CPU and memory limits — what a container may actually use.
+//!
+//! Inside a container `/proc/cpuinfo` and `/proc/meminfo` describe the host,
+//! so a pod limited to 2 CPUs and 1 GiB would report the whole machine. The
+//! limits come from cgroup v2 (`memory.max`, `cpu.max`) when
+//! `cgroup.controllers` is present, else from the v1 `memory` and `cpu`
+//! hierarchies. An unlimited cgroup, or a host without cgroups, yields no
+//! limits.
+
+use std::path::Path;
+
+/// Where the current process's cgroup is mounted (its own cgroup inside a
+/// container with a cgroup namespace).
+pub const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// cgroup v1 reports "no limit" as a page-aligned `i64::MAX`; anything this
+/// large is unlimited.
+const V1_UNLIMITED: u64 = 1 << 62;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CgroupLimits {
+    pub memory_limit_bytes: Option<u64>,
+    /// Memory in use minus inactive page cache, the figure the kernel's
+    /// OOM killer and kubelet eviction work from.
+    pub memory_working_set_bytes: Option<u64>,
+    /// CPUs' worth of time per period, e.g. 1.5.
+    pub cpu_quota: Option<f64>,
+}
+
+impl CgroupLimits {
+    /// Memory usage in percent of the limit, when there is one.
+    #[cfg(any(not(target_os = "macos"), test))]
+    pub fn memory_usage_percent(&self) -> Option<f64> {
+        let limit = self.memory_limit_bytes.filter(|&l| l > 0)?;
+        let used = self.memory_working_set_bytes?;
+        Some(used as f64 / limit as f64 * 100.0)
+    }
+}
+
+/// Limits of the cgroup mounted at `root`.
+pub async fn read_limits(root: &Path) -> CgroupLimits {
+    if is_v2(root).await {
+        let (max, current, stat, cpu_max) = tokio::join!(
+            read(root, "memory.max"),
+            read(root, "memory.current"),
+            read(root, "memory.stat"),
+            read(root, "cpu.max"),
+        );
+        CgroupLimits {
+            memory_limit_bytes: max.and_then(|s| parse_v2_max(&s)),
+            memory_working_set_bytes: working_set(current, stat, "inactive_file"),
+            cpu_quota: cpu_max.and_then(|s| parse_v2_cpu_max(&s)),
+        }
+    } else {
+        let (limit, usage, stat, quota, period) = tokio::join!(
+            read(root, "memory/memory.limit_in_bytes"),
+            read(root, "memory/memory.usage_in_bytes"),
+            read(root, "memory/memory.stat"),
+            read(root, "cpu/cpu.cfs_quota_us"),
+            read(root, "cpu/cpu.cfs_period_us"),
+        );
+        CgroupLimits {
+            memory_limit_bytes: limit.and_then(|s| parse_v1_limit(&s)),
+            memory_working_set_bytes: working_set(usage, stat, "total_inactive_file"),
+            cpu_quota: quota
+                .zip(period)
+                .and_then(|(q, p)| parse_v1_cpu_quota(&q, &p)),
+        }
+    }
+}
+
+/// CPU usage in percent of `quota` CPUs, sampled over `window`; `None`
+/// without a readable usage counter.
+#[cfg(not(target_os = "macos"))]
+pub async fn sample_cpu_percent(
+    root: &Path,
+    quota: f64,
+    window: std::time::Duration,
+) -> Option<f64> {
+    let before = cpu_usage_usec(root).await?;
+    tokio::time::sleep(window).await;
+    let after = cpu_usage_usec(root).await?;
+    let budget = quota * window.as_micros() as f64;
+    if budget <= 0.0 {
+        return None;
+    }
+    Some((after.saturating_sub(before) as f64 / budget * 100.0).min(100.0))
+}
+
+/// Total CPU time the cgroup has used, in microseconds.
+#[cfg(any(not(target_os = "macos"), test))]
+async fn cpu_usage_usec(root: &Path) -> Option<u64> {
+    if is_v2(root).await {
+        stat_field(&read(root, "cpu.stat").await?, "usage_usec")
+    } else {
+        // cpuacct counts nanoseconds.
+        let ns: u64 = read(root, "cpuacct/cpuacct.usage")
+            .await?
+            .trim()
+            .parse()
+            .ok()?;
+        Some(ns / 1000)
+    }
+}
+
+async fn is_v2(root: &Path) -> bool {
+    tokio::fs::try_exists(root.join("cgroup.controllers"))
+        .await
+        .unwrap_or(false)
+}
+
+async fn read(root: &Path, file: &str) -> Option<String> {
+    tokio::fs::read_to_string(root.join(file)).await.ok()
+}
+
+fn working_set(usage: Option<String>, stat: Option<String>, inactive_key: &str) -> Option<u64> {
+    let usage: u64 = usage?.trim().parse().ok()?;
+    let inactive = stat.and_then(|s| stat_field(&s, inactive_key)).unwrap_or(0);
+    Some(usage.saturating_sub(inactive))
+}
+
+/// `memory.max`: a byte count, or `max` for none.
+fn parse_v2_max(content: &str) -> Option<u64> {
+    content.trim().parse().ok()
+}
+
+/// `cpu.max`: `$QUOTA $PERIOD` in microseconds, quota `max` for none.
+fn parse_v2_cpu_max(content: &str) -> Option<f64> {
+    let mut fields = content.split_whitespace();
+    let quota: f64 = fields.next()?.parse().ok()?;
+    let period: f64 = fields.next()?.parse().ok()?;
+    (quota > 0.0 && period > 0.0).then(|| quota / period)
+}
+
+/// `memory.limit_in_bytes`, where unlimited is a near-`i64::MAX` value.
+fn parse_v1_limit(content: &str) -> Option<u64> {
+    content
+        .trim()
+        .parse()
+        .ok()
+        .filter(|&limit: &u64| limit < V1_UNLIMITED)
+}
+
+/// `cpu.cfs_quota_us` (`-1` for none) over `cpu.cfs_period_us`.
+fn parse_v1_cpu_quota(quota: &str, period: &str) -> Option<f64> {
+    let quota: i64 = quota.trim().parse().ok()?;
+    let period: i64 = period.trim().parse().ok()?;
+    (quota > 0 && period > 0).then(|| quota as f64 / period as f64)
+}
+
+/// Value of `key` in a flat-keyed stat file (`key value` per line).
+fn stat_field(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let (k, v) = line.split_once(' ')?;
+        (k == key).then(|| v.trim().parse().ok()).flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, file: &str, content: &str) {
+        let path = root.join(file);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reads_cgroup_v2_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "cgroup.controllers", "cpuset cpu io memory pids\n");
+        write(root, "memory.max", "1073741824\n");
+        write(root, "memory.current", "600000000\n");
+        write(
+            root,
+            "memory.stat",
+            "anon 400000000\ninactive_file 63129088\n",
+        );
+        write(root, "cpu.max", "150000 100000\n");
+        write(root, "cpu.stat", "usage_usec 2500000\nuser_usec 2000000\n");
+
+        let limits = read_limits(root).await;
+        assert_eq!(limits.memory_limit_bytes, Some(1_073_741_824));
+        assert_eq!(limits.memory_working_set_bytes, Some(536_870_912));
+        assert_eq!(limits.cpu_quota, Some(1.5));
+        assert_eq!(limits.memory_usage_percent(), Some(50.0));
+        assert_eq!(cpu_usage_usec(root).await, Some(2_500_000));
+
+        write(root, "memory.max", "max\n");
+        write(root, "cpu.max", "max 100000\n");
+        let unlimited = read_limits(root).await;
+        assert_eq!(unlimited.memory_limit_bytes, None);
+        assert_eq!(unlimited.cpu_quota, None);
+        assert_eq!(unlimited.memory_usage_percent(), None);
+    }
+
+    #[tokio::test]
+    async fn reads_cgroup_v1_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "memory/memory.limit_in_bytes", "536870912\n");
+        write(root, "memory/memory.usage_in_bytes", "300000000\n");
+        write(
+            root,
+            "memory/memory.stat",
+            "cache 100\ntotal_inactive_file 31564544\n",
+        );
+        write(root, "cpu/cpu.cfs_quota_us", "50000\n");
+        write(root, "cpu/cpu.cfs_period_us", "100000\n");
+        write(root, "cpuacct/cpuacct.usage", "7000000000\n");
+
+        let limits = read_limits(root).await;
+        assert_eq!(limits.memory_limit_bytes, Some(536_870_912));
+        assert_eq!(limits.memory_working_set_bytes, Some(268_435_456));
+        assert_eq!(limits.cpu_quota, Some(0.5));
+        assert_eq!(cpu_usage_usec(root).await, Some(7_000_000));
+
+        write(
+            root,
+            "memory/memory.limit_in_bytes",
+            "9223372036854771712\n",
+        );
+        write(root, "cpu/cpu.cfs_quota_us", "-1\n");
+        let unlimited = read_limits(root).await;
+        assert_eq!(unlimited.memory_limit_bytes, None);
+        assert_eq!(unlimited.cpu_quota, None);
+    }
+
+    #[tokio::test]
+    async fn no_cgroup_means_no_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(read_limits(dir.path()).await, CgroupLimits::default());
+    }
+}
@@ -0,0 +1,171 @@
+//! Firewall drift — declared allowlist vs. what is actually listening.
+//!
+//! `network.firewall` in node.yaml states which ports should be reachable;
+//! the report's listening ports say which are. Listeners bound to loopback
+//! are never reachable from outside, so they are ignored in both directions.
+
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+
+use super::node_report::{FindingKind, FindingSeverity, ListeningPort, SecurityFinding};
+use crate::node_identity::FirewallConfig;
+
+/// Message prefix for listeners missing from the allowlist.
+const EXPOSURE_PREFIX: &str = "unexpected exposure: ";
+/// Message prefix for allowlisted ports nobody listens on.
+const STALE_RULE_PREFIX: &str = "stale rule: ";
+
+/// Compare the declared firewall allowlist with the listening ports.
+///
+/// Flags listeners missing from the allowlist ("unexpected exposure") and
+/// allowlisted ports nobody listens on ("stale rule"). A config with no
+/// allowed ports declares no intent, so it yields nothing.
+pub fn check(firewall: &FirewallConfig, listening: &[ListeningPort]) -> Vec<SecurityFinding> {
+    let mut findings = Vec::new();
+    if firewall.allowed_tcp_ports.is_empty() && firewall.allowed_udp_ports.is_empty() {
+        return findings;
+    }
+    for (protocol, allowed) in [
+        ("tcp", &firewall.allowed_tcp_ports),
+        ("udp", &firewall.allowed_udp_ports),
+    ] {
+        let allowed: BTreeSet<u32> = allowed.iter().copied().collect();
+        let exposed: BTreeSet<u32> = listening
+            .iter()
+            .filter(|p| p.protocol == protocol && !is_loopback(p.address.as_deref()))
+            .map(|p| u32::from(p.port))
+            .collect();
+
+        for port in exposed.difference(&allowed) {
+            let process = listening
+                .iter()
+                .find(|p| u32::from(p.port) == *port && p.protocol == protocol)
+                .and_then(|p| p.process.as_deref())
+                .unwrap_or("unknown process");
+            findings.push(SecurityFinding {
+                severity: FindingSeverity::Medium,
+                kind: FindingKind::FirewallExposure,
+                message: format!(
+                    "{}{} listening on {}/{} which is not in the firewall allowlist",
+                    EXPOSURE_PREFIX, process, port, protocol
+                ),
+            });
+        }
+        for port in allowed.difference(&exposed) {
+            findings.push(SecurityFinding {
+                severity: FindingSeverity::Low,
+                kind: FindingKind::FirewallStaleRule,
+                message: format!(
+                    "{}{}/{} is allowed by the firewall but nothing is listening",
+                    STALE_RULE_PREFIX, port, protocol
+                ),
+            });
+        }
+    }
+    findings
+}
+
+/// Whether a listener address (as reported by `ss`/`lsof`) is loopback-only.
+/// IPv4-mapped IPv6 addresses count by their IPv4 part.
+fn is_loopback(address: Option<&str>) -> bool {
+    let Some(address) = address else {
+        return false;
+    };
+    let address = address.trim_matches(|c| c == '[' || c == ']');
+    // ss appends the interface scope, e.g. `127.0.0.53%lo`.
+    let address = address.split('%').next().unwrap_or(address);
+    match address.parse::<IpAddr>() {
+        Ok(IpAddr::V6(v6)) => v6
+            .to_ipv4_mapped()
+            .map_or(v6.is_loopback(), |v4| v4.is_loopback()),
+        Ok(ip) => ip.is_loopback(),
+        Err(_) => address == "localhost",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn firewall(tcp: &[u32], udp: &[u32]) -> FirewallConfig {
+        FirewallConfig {
+            allowed_tcp_ports: tcp.to_vec(),
+            allowed_udp_ports: udp.to_vec(),
+            rules: vec![],
+        }
+    }
+
+    fn listen(port: u16, protocol: &str, address: &str, process: &str) -> ListeningPort {
+        ListeningPort {
+            port,
+            protocol: protocol.to_string(),
+            address: Some(address.to_string()),
+            process: Some(process.to_string()),
+        }
+    }
+
+    fn messages(findings: &[SecurityFinding]) -> Vec<&str> {
+        findings.iter().map(|f| f.message.as_str()).collect()
+    }
+
+    #[test]
+    fn matching_allowlist_has_no_findings() {
+        let ports = vec![
+            listen(22, "tcp", "0.0.0.0", "sshd"),
+            listen(443, "tcp", "*", "nginx"),
+            listen(51820, "udp", "0.0.0.0", "wireguard"),
+            // Loopback-only listeners are not exposed.
+            listen(9100, "tcp", "127.0.0.1", "kindling"),
+            listen(53, "udp", "[::1]", "unbound"),
+            listen(8080, "tcp", "[::ffff:127.0.0.1]", "devserver"),
+            listen(5353, "udp", "127.0.0.53%lo", "systemd-resolve"),
+        ];
+        assert!(check(&firewall(&[22, 443], &[51820]), &ports).is_empty());
+    }
+
+    #[test]
+    fn unlisted_listener_is_unexpected_exposure() {
+        let ports = vec![
+            listen(22, "tcp", "0.0.0.0", "sshd"),
+            listen(5432, "tcp", "0.0.0.0", "postgres"),
+        ];
+        let findings = check(&firewall(&[22], &[]), &ports);
+        assert_eq!(
+            messages(&findings),
+            vec!["unexpected exposure: postgres listening on 5432/tcp which is not in the firewall allowlist"]
+        );
+        assert_eq!(findings[0].severity, FindingSeverity::Medium);
+    }
+
+    #[test]
+    fn allowlisted_port_without_listener_is_stale_rule() {
+        let ports = vec![listen(22, "tcp", "0.0.0.0", "sshd")];
+        let findings = check(&firewall(&[22, 8080], &[51820]), &ports);
+        assert_eq!(
+            messages(&findings),
+            vec![
+                "stale rule: 8080/tcp is allowed by the firewall but nothing is listening",
+                "stale rule: 51820/udp is allowed by the firewall but nothing is listening",
+            ]
+        );
+        assert!(findings.iter().all(|f| f.severity == FindingSeverity::Low));
+    }
+
+    #[test]
+    fn protocols_are_checked_separately() {
+        // 53/udp being allowed does not cover a 53/tcp listener.
+        let ports = vec![listen(53, "tcp", "0.0.0.0", "dnsmasq")];
+        let findings = check(&firewall(&[], &[53]), &ports);
+        assert_eq!(findings.len(), 2);
+        assert!(findings[0].message.starts_with("unexpected exposure: dnsmasq listening on 53/tcp"));
+        assert!(findings[1].message.starts_with("stale rule: 53/udp"));
+        assert_eq!(findings[0].kind, FindingKind::FirewallExposure);
+        assert_eq!(findings[1].kind, FindingKind::FirewallStaleRule);
+    }
+
+    #[test]
+    fn empty_allowlist_declares_no_intent() {
+        let ports = vec![listen(5432, "tcp", "0.0.0.0", "postgres")];
+        assert!(check(&FirewallConfig::default(), &ports).is_empty());
+    }
+}
@@ -0,0 +1,235 @@
+//! Fleet-wide drift — where each node's live state departs from what it
+//! declares, summarized across the controller's stored nodes.
+//!
+//! The controller only holds each node's latest report, so drift comes from
+//! what the report already carries: firewall drift findings (computed on the
+//! node against its node.yaml) and uncommitted changes in the config source.
+
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::fleet_store::FleetNode;
+use super::node_report::{FindingKind, NodeReport};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftSeverity {
+    Warning,
+    Critical,
+}
+
+impl std::str::FromStr for DriftSeverity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "warning" => Ok(Self::Warning),
+            "critical" => Ok(Self::Critical),
+            other => bail!(
+                "unknown drift severity '{}' (expected warning or critical)",
+                other
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DriftItem {
+    pub severity: DriftSeverity,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeDrift {
+    pub hostname: String,
+    pub received_at: DateTime<Utc>,
+    /// Highest severity among `drift`, `None` when the node is clean.
+    pub severity: Option<DriftSeverity>,
+    pub drift: Vec<DriftItem>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FleetDrift {
+    pub total_nodes: usize,
+    pub critical_nodes: usize,
+    pub warning_nodes: usize,
+    pub nodes: Vec<NodeDrift>,
+}
+
+/// Drift carried by one report. Exposed listeners are critical; stale
+/// firewall rules and a dirty config source are warnings.
+pub fn report_drift(report: &NodeReport) -> Vec<DriftItem> {
+    let mut drift: Vec<DriftItem> = report
+        .security
+        .findings
+        .iter()
+        .filter_map(|f| {
+            let severity = match f.kind {
+                FindingKind::FirewallExposure => DriftSeverity::Critical,
+                FindingKind::FirewallStaleRule => DriftSeverity::Warning,
+                _ => return None,
+            };
+            Some(DriftItem {
+                severity,
+                message: f.message.clone(),
+            })
+        })
+        .collect();
+    if report.nix.config_source_dirty == Some(true) {
+        drift.push(DriftItem {
+            severity: DriftSeverity::Warning,
+            message: match report.nix.config_source_rev {
+                Some(ref rev) => format!("config source has uncommitted changes on top of {}", rev),
+                None => "config source has uncommitted changes".to_string(),
+            },
+        });
+    }
+    drift.sort_by_key(|d| std::cmp::Reverse(d.severity));
+    drift
+}
+
+/// Summarize drift over the controller's nodes. With `min_severity`, only
+/// items at or above it are kept and nodes left without any are dropped
+/// from the per-node list; the counts are taken after filtering.
+pub fn summarize(
+    nodes: &BTreeMap<String, FleetNode>,
+    min_severity: Option<DriftSeverity>,
+) -> FleetDrift {
+    let nodes: Vec<NodeDrift> = nodes
+        .iter()
+        .map(|(hostname, node)| {
+            let drift: Vec<DriftItem> = report_drift(&node.report.report)
+                .into_iter()
+                .filter(|d| min_severity.is_none_or(|min| d.severity >= min))
+                .collect();
+            NodeDrift {
+                hostname: hostname.clone(),
+                received_at: node.received_at,
+                severity: drift.iter().map(|d| d.severity).max(),
+                drift,
+            }
+        })
+        .collect();
+    let count = |severity| {
+        nodes
+            .iter()
+            .filter(|n| n.severity == Some(severity))
+            .count()
+    };
+    FleetDrift {
+        total_nodes: nodes.len(),
+        critical_nodes: count(DriftSeverity::Critical),
+        warning_nodes: count(DriftSeverity::Warning),
+        nodes: nodes
+            .into_iter()
+            .filter(|n| min_severity.is_none() || n.severity.is_some())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::fleet_store::{read_state, FleetStore};
+    use crate::domain::node_report::tests::make_test_report;
+    use crate::domain::node_report::{FindingSeverity, SecurityFinding, StoredReport};
+
+    fn finding(kind: FindingKind, message: &str) -> SecurityFinding {
+        SecurityFinding {
+            severity: FindingSeverity::Medium,
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn report_drift_classifies_findings() {
+        let mut report = make_test_report();
+        report.security.findings = vec![
+            finding(
+                FindingKind::FirewallStaleRule,
+                "stale rule: 8080/tcp is allowed by the firewall but nothing is listening",
+            ),
+            finding(
+                FindingKind::Posture,
+                "unexpected exposure: SSH password authentication is enabled",
+            ),
+            finding(
+                FindingKind::FirewallExposure,
+                "redis listening on 6379/tcp which is not in the firewall allowlist",
+            ),
+        ];
+        report.nix.config_source_dirty = Some(true);
+        report.nix.config_source_rev = Some("a1b2c3d".to_string());
+
+        let drift = report_drift(&report);
+        assert_eq!(drift.len(), 3);
+        assert_eq!(drift[0].severity, DriftSeverity::Critical);
+        assert!(drift[0].message.contains("redis"));
+        assert!(drift[1..]
+            .iter()
+            .all(|d| d.severity == DriftSeverity::Warning));
+        assert!(drift
+            .iter()
+            .any(|d| d.message == "config source has uncommitted changes on top of a1b2c3d"));
+    }
+
+    #[tokio::test]
+    async fn summarizes_stored_nodes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fleet.json");
+        let store = FleetStore::open(path.clone()).await.unwrap();
+
+        let mut exposed = make_test_report();
+        exposed.hostname = "edge-1".to_string();
+        exposed.security.findings = vec![
+            finding(
+                FindingKind::FirewallExposure,
+                "unexpected exposure: postgres listening on 5432/tcp which is not in the firewall allowlist",
+            ),
+            finding(
+                FindingKind::FirewallStaleRule,
+                "stale rule: 443/tcp is allowed by the firewall but nothing is listening",
+            ),
+        ];
+        store
+            .ingest("edge-1", StoredReport::new(exposed))
+            .await
+            .unwrap();
+
+        let mut dirty = make_test_report();
+        dirty.hostname = "edge-2".to_string();
+        dirty.nix.config_source_dirty = Some(true);
+        store
+            .ingest("edge-2", StoredReport::new(dirty))
+            .await
+            .unwrap();
+
+        let nodes = read_state(&path).unwrap();
+        let all = summarize(&nodes, None);
+        assert_eq!(all.total_nodes, 2);
+        assert_eq!(all.critical_nodes, 1);
+        assert_eq!(all.warning_nodes, 1);
+        assert_eq!(all.nodes[0].hostname, "edge-1");
+        assert_eq!(all.nodes[0].drift.len(), 2);
+        assert_eq!(all.nodes[1].severity, Some(DriftSeverity::Warning));
+
+        let critical = summarize(&nodes, Some(DriftSeverity::Critical));
+        assert_eq!(critical.critical_nodes, 1);
+        assert_eq!(critical.warning_nodes, 0);
+        assert_eq!(critical.nodes.len(), 1);
+        assert_eq!(critical.nodes[0].drift.len(), 1);
+    }
+
+    #[test]
+    fn parses_severity() {
+        assert_eq!(
+            "critical".parse::<DriftSeverity>().unwrap(),
+            DriftSeverity::Critical
+        );
+        assert!("high".parse::<DriftSeverity>().is_err());
+    }
+}
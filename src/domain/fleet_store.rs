@@ -0,0 +1,573 @@
+//! FleetStore — reports pushed by remote nodes to the fleet controller.
+//!
+//! Each node's latest report is kept in memory and persisted to the
+//! controller's state file (`fleet_controller.state_file`). Ingesting a
+//! report computes a ReportDelta against the node's previous one.
+//! Operators can attach annotations (free-form `key=value` notes such as
+//! `decommission=2026-11`) to a node; they live beside its reports and are
+//! kept when new reports arrive.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+
+use super::node_report::StoredReport;
+use super::report_delta::ReportDelta;
+
+/// Everything the controller knows about one node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetNode {
+    pub report: StoredReport,
+    pub received_at: DateTime<Utc>,
+    /// When the controller first heard from the node. Unset in state
+    /// written before it was tracked.
+    #[serde(default)]
+    pub first_seen: Option<DateTime<Utc>>,
+    /// Changes since the node's previous report (empty for the first).
+    #[serde(default)]
+    pub last_change: ReportDelta,
+    /// Operator notes, set through the controller rather than node.yaml.
+    #[serde(default)]
+    pub annotations: BTreeMap<String, String>,
+    /// The config `kindling fleet apply` last deployed to the node.
+    #[serde(default)]
+    pub applied: Option<AppliedConfig>,
+}
+
+/// A config successfully deployed by `kindling fleet apply`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppliedConfig {
+    /// [`deploy_checksum`](crate::node_identity::nix_gen::deploy_checksum)
+    /// of the deployed identity and flake lock.
+    pub checksum: String,
+    pub applied_at: DateTime<Utc>,
+}
+
+/// Tag and label filters over the tags/labels each node declares in its
+/// node.yaml (carried in `NodeReport::declared`). Every filter must match.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeFilter {
+    pub tags: Vec<String>,
+    pub labels: Vec<(String, String)>,
+}
+
+impl NodeFilter {
+    /// Build a filter from `--tag` values and `key=value` `--label` values.
+    pub fn parse(tags: &[String], labels: &[String]) -> Result<Self> {
+        let labels = labels
+            .iter()
+            .map(|l| match l.split_once('=') {
+                Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+                _ => bail!("invalid label filter '{}' (expected key=value)", l),
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            tags: tags.to_vec(),
+            labels,
+        })
+    }
+
+    pub fn matches(&self, node: &FleetNode) -> bool {
+        let declared = &node.report.report.declared;
+        self.tags.iter().all(|t| declared.tags.contains(t))
+            && self
+                .labels
+                .iter()
+                .all(|(k, v)| declared.labels.get(k) == Some(v))
+    }
+}
+
+/// Parse `key=value` annotation arguments. An empty value (`key=`)
+/// removes the annotation.
+pub fn parse_annotations(args: &[String]) -> Result<BTreeMap<String, String>> {
+    args.iter()
+        .map(|a| match a.split_once('=') {
+            Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+            _ => bail!("invalid annotation '{}' (expected key=value)", a),
+        })
+        .collect()
+}
+
+/// Reject hostnames that can't safely name a directory: exports write
+/// `<hostname>/report.json`, so `/`, `..` or NUL would escape the tree.
+pub fn validate_hostname(hostname: &str) -> Result<()> {
+    if hostname.is_empty()
+        || hostname == "."
+        || hostname.contains("..")
+        || hostname.contains(['/', '\\', '\0'])
+    {
+        bail!("invalid hostname {:?}", hostname);
+    }
+    Ok(())
+}
+
+/// One row of the fleet node list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FleetNodeSummary {
+    pub hostname: String,
+    pub received_at: DateTime<Utc>,
+    pub tags: Vec<String>,
+    pub labels: BTreeMap<String, String>,
+    pub annotations: BTreeMap<String, String>,
+    #[serde(default)]
+    pub applied: Option<AppliedConfig>,
+}
+
+/// Nodes matching `filter`, by hostname.
+pub fn list_nodes(
+    nodes: &BTreeMap<String, FleetNode>,
+    filter: &NodeFilter,
+) -> Vec<FleetNodeSummary> {
+    nodes
+        .iter()
+        .filter(|(_, node)| filter.matches(node))
+        .map(|(hostname, node)| FleetNodeSummary {
+            hostname: hostname.clone(),
+            received_at: node.received_at,
+            tags: node.report.report.declared.tags.clone(),
+            labels: node.report.report.declared.labels.clone(),
+            annotations: node.annotations.clone(),
+            applied: node.applied.clone(),
+        })
+        .collect()
+}
+
+pub struct FleetStore {
+    path: PathBuf,
+    nodes: RwLock<BTreeMap<String, FleetNode>>,
+    write_lock: Mutex<()>,
+}
+
+impl FleetStore {
+    /// Open the store, loading persisted state. A missing state file starts
+    /// the controller with no nodes; one that doesn't parse is moved aside
+    /// to `<file>.corrupt-<timestamp>` first, so the next write can't
+    /// destroy what might still be recovered from it.
+    pub async fn open(path: PathBuf) -> Result<Self> {
+        let nodes = match tokio::fs::read_to_string(&path).await {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(nodes) => nodes,
+                Err(e) => {
+                    let aside = corrupt_path(&path, Utc::now());
+                    tokio::fs::rename(&path, &aside).await.with_context(|| {
+                        format!(
+                            "fleet state {} does not parse ({}) and could not be moved aside",
+                            path.display(),
+                            e
+                        )
+                    })?;
+                    warn!(
+                        path = %path.display(),
+                        moved_to = %aside.display(),
+                        error = %e,
+                        "fleet state does not parse; moved it aside and starting empty"
+                    );
+                    BTreeMap::new()
+                }
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("reading fleet state {}", path.display()))
+            }
+        };
+        Ok(Self {
+            path,
+            nodes: RwLock::new(nodes),
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    /// Store a node's new report and return what changed since its last one.
+    pub async fn ingest(&self, hostname: &str, report: StoredReport) -> Result<ReportDelta> {
+        validate_hostname(hostname)?;
+        let delta = {
+            let mut nodes = self.nodes.write().await;
+            let prev = nodes.get(hostname);
+            let delta = prev
+                .map(|prev| ReportDelta::compute(&prev.report.report, &report.report))
+                .unwrap_or_default();
+            let now = Utc::now();
+            let first_seen = prev.map_or(now, |p| p.first_seen.unwrap_or(p.received_at));
+            let annotations = prev.map(|p| p.annotations.clone()).unwrap_or_default();
+            let applied = prev.and_then(|p| p.applied.clone());
+            nodes.insert(
+                hostname.to_string(),
+                FleetNode {
+                    report,
+                    received_at: now,
+                    first_seen: Some(first_seen),
+                    last_change: delta.clone(),
+                    annotations,
+                    applied,
+                },
+            );
+            delta
+        };
+        if !delta.is_empty() {
+            info!(hostname, delta = ?delta, "node report changed");
+        }
+        self.persist().await?;
+        Ok(delta)
+    }
+
+    pub async fn node(&self, hostname: &str) -> Option<FleetNode> {
+        self.nodes.read().await.get(hostname).cloned()
+    }
+
+    /// Every stored node, by hostname.
+    pub async fn nodes(&self) -> BTreeMap<String, FleetNode> {
+        self.nodes.read().await.clone()
+    }
+
+    /// Nodes matching `filter`.
+    pub async fn list(&self, filter: &NodeFilter) -> Vec<FleetNodeSummary> {
+        list_nodes(&*self.nodes.read().await, filter)
+    }
+
+    pub async fn last_change(&self, hostname: &str) -> Option<ReportDelta> {
+        self.node(hostname).await.map(|n| n.last_change)
+    }
+
+    /// Merge `changes` into a node's annotations (empty values remove keys)
+    /// and return the result. `None` for a node that has never reported.
+    pub async fn annotate(
+        &self,
+        hostname: &str,
+        changes: BTreeMap<String, String>,
+    ) -> Result<Option<BTreeMap<String, String>>> {
+        let annotations = {
+            let mut nodes = self.nodes.write().await;
+            let Some(node) = nodes.get_mut(hostname) else {
+                return Ok(None);
+            };
+            for (key, value) in changes {
+                if value.is_empty() {
+                    node.annotations.remove(&key);
+                } else {
+                    node.annotations.insert(key, value);
+                }
+            }
+            node.annotations.clone()
+        };
+        self.persist().await?;
+        Ok(Some(annotations))
+    }
+
+    /// Record that `checksum` was just deployed to a node. `None` for a node
+    /// that has never reported.
+    pub async fn record_applied(
+        &self,
+        hostname: &str,
+        checksum: &str,
+    ) -> Result<Option<AppliedConfig>> {
+        let applied = {
+            let mut nodes = self.nodes.write().await;
+            let Some(node) = nodes.get_mut(hostname) else {
+                return Ok(None);
+            };
+            let applied = AppliedConfig {
+                checksum: checksum.to_string(),
+                applied_at: Utc::now(),
+            };
+            node.applied = Some(applied.clone());
+            applied
+        };
+        self.persist().await?;
+        Ok(Some(applied))
+    }
+
+    /// Atomically write all nodes to the state file (tmp + rename).
+    async fn persist(&self) -> Result<()> {
+        let _guard = self.write_lock.lock().await;
+        let content = serde_json::to_string_pretty(&*self.nodes.read().await)
+            .context("failed to serialize fleet state")?;
+
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("creating directory {}", parent.display()))?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        tokio::fs::write(&tmp_path, &content)
+            .await
+            .with_context(|| format!("writing temp file {}", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, &self.path)
+            .await
+            .with_context(|| {
+                format!(
+                    "renaming {} to {}",
+                    tmp_path.display(),
+                    self.path.display()
+                )
+            })?;
+        Ok(())
+    }
+}
+
+/// Where an unparseable state file at `path` is moved at `now`.
+fn corrupt_path(path: &Path, now: DateTime<Utc>) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".corrupt-{}", now.format("%Y%m%dT%H%M%SZ")));
+    path.with_file_name(name)
+}
+
+/// Read a controller state file without opening a store, for CLI commands
+/// running on the controller host. A missing file means no nodes.
+pub fn read_state(path: &Path) -> Result<BTreeMap<String, FleetNode>> {
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("failed to parse fleet state {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::node_report::tests::make_test_report;
+
+    #[tokio::test]
+    async fn first_report_has_empty_delta() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FleetStore::open(dir.path().join("fleet.json"))
+            .await
+            .unwrap();
+
+        let delta = store
+            .ingest("test-node", StoredReport::new(make_test_report()))
+            .await
+            .unwrap();
+        assert!(delta.is_empty());
+        assert!(store.last_change("test-node").await.unwrap().is_empty());
+        assert!(store.last_change("other-node").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn second_report_records_delta() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FleetStore::open(dir.path().join("fleet.json"))
+            .await
+            .unwrap();
+        store
+            .ingest("test-node", StoredReport::new(make_test_report()))
+            .await
+            .unwrap();
+
+        let mut next = make_test_report();
+        next.os.kernel_version = "6.12.99".to_string();
+        next.nix.store_size_bytes += 1024;
+        let delta = store
+            .ingest("test-node", StoredReport::new(next))
+            .await
+            .unwrap();
+
+        assert_eq!(delta.store_size_change_bytes, 1024);
+        assert_eq!(delta.kernel_change.as_ref().unwrap().to, "6.12.99");
+        assert_eq!(store.last_change("test-node").await, Some(delta));
+    }
+
+    #[tokio::test]
+    async fn state_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("fleet.json");
+        {
+            let store = FleetStore::open(path.clone()).await.unwrap();
+            store
+                .ingest("test-node", StoredReport::new(make_test_report()))
+                .await
+                .unwrap();
+        }
+        let reopened = FleetStore::open(path).await.unwrap();
+        let node = reopened.node("test-node").await.unwrap();
+        assert_eq!(node.report.report.hostname, "test-node");
+    }
+
+    #[tokio::test]
+    async fn list_filters_by_declared_tags_and_labels() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FleetStore::open(dir.path().join("fleet.json"))
+            .await
+            .unwrap();
+        for (host, tags, role) in [
+            ("prod-server", vec!["prod", "k3s"], "server"),
+            ("prod-agent", vec!["prod", "k3s"], "agent"),
+            ("staging-server", vec!["staging", "k3s"], "server"),
+            ("laptop", vec![], ""),
+        ] {
+            let mut report = make_test_report();
+            report.hostname = host.to_string();
+            report.declared.tags = tags.into_iter().map(String::from).collect();
+            if !role.is_empty() {
+                report
+                    .declared
+                    .labels
+                    .insert("role".to_string(), role.to_string());
+            }
+            store.ingest(host, StoredReport::new(report)).await.unwrap();
+        }
+        let all = store.list(&NodeFilter::default()).await;
+        assert_eq!(all.len(), 4);
+
+        let filter = NodeFilter::parse(&["prod".into()], &["role=server".into()]).unwrap();
+        let names: Vec<_> = store
+            .list(&filter)
+            .await
+            .into_iter()
+            .map(|n| n.hostname)
+            .collect();
+        assert_eq!(names, vec!["prod-server"]);
+
+        let filter = NodeFilter::parse(&["k3s".into()], &["role=server".into()]).unwrap();
+        let names: Vec<_> = store
+            .list(&filter)
+            .await
+            .into_iter()
+            .map(|n| n.hostname)
+            .collect();
+        assert_eq!(names, vec!["prod-server", "staging-server"]);
+
+        let filter = NodeFilter::parse(&["prod".into(), "staging".into()], &[]).unwrap();
+        assert!(store.list(&filter).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn annotations_survive_restart_and_new_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fleet.json");
+        {
+            let store = FleetStore::open(path.clone()).await.unwrap();
+            assert_eq!(
+                store.annotate("test-node", BTreeMap::new()).await.unwrap(),
+                None
+            );
+            store
+                .ingest("test-node", StoredReport::new(make_test_report()))
+                .await
+                .unwrap();
+            let changes = parse_annotations(&[
+                "status=scheduled for decommission".into(),
+                "disk=flaky".into(),
+            ])
+            .unwrap();
+            store.annotate("test-node", changes).await.unwrap();
+        }
+
+        let reopened = FleetStore::open(path).await.unwrap();
+        reopened
+            .ingest("test-node", StoredReport::new(make_test_report()))
+            .await
+            .unwrap();
+        let removed = parse_annotations(&["disk=".into()]).unwrap();
+        let annotations = reopened
+            .annotate("test-node", removed)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            annotations,
+            BTreeMap::from([(
+                "status".to_string(),
+                "scheduled for decommission".to_string()
+            )])
+        );
+        let listed = reopened.list(&NodeFilter::default()).await;
+        assert_eq!(listed[0].annotations, annotations);
+        assert!(parse_annotations(&["=x".into()]).is_err());
+    }
+
+    #[tokio::test]
+    async fn applied_config_survives_restart_and_new_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fleet.json");
+        {
+            let store = FleetStore::open(path.clone()).await.unwrap();
+            assert_eq!(
+                store
+                    .record_applied("test-node", "sha256:aaaa")
+                    .await
+                    .unwrap(),
+                None
+            );
+            store
+                .ingest("test-node", StoredReport::new(make_test_report()))
+                .await
+                .unwrap();
+            store
+                .record_applied("test-node", "sha256:aaaa")
+                .await
+                .unwrap();
+            store
+                .record_applied("test-node", "sha256:bbbb")
+                .await
+                .unwrap();
+        }
+
+        let reopened = FleetStore::open(path).await.unwrap();
+        reopened
+            .ingest("test-node", StoredReport::new(make_test_report()))
+            .await
+            .unwrap();
+        let listed = reopened.list(&NodeFilter::default()).await;
+        assert_eq!(listed[0].applied.as_ref().unwrap().checksum, "sha256:bbbb");
+    }
+
+    #[test]
+    fn node_filter_rejects_malformed_label() {
+        assert!(NodeFilter::parse(&[], &["role".into()]).is_err());
+        assert!(NodeFilter::parse(&[], &["=server".into()]).is_err());
+        let filter = NodeFilter::parse(&[], &["tier=".into()]).unwrap();
+        assert_eq!(filter.labels, vec![("tier".to_string(), String::new())]);
+    }
+
+    #[tokio::test]
+    async fn ingest_rejects_path_like_hostnames() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FleetStore::open(dir.path().join("fleet.json"))
+            .await
+            .unwrap();
+        for hostname in ["", "..", "../etc", "a/b", "nul\0byte"] {
+            let report = StoredReport::new(make_test_report());
+            assert!(
+                store.ingest(hostname, report).await.is_err(),
+                "{hostname:?}"
+            );
+        }
+        assert!(validate_hostname("edge-1.example.com").is_ok());
+        assert!(store.nodes().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn corrupt_state_is_moved_aside() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fleet.json");
+        std::fs::write(&path, "not json").unwrap();
+        let store = FleetStore::open(path.clone()).await.unwrap();
+        assert!(store.node("test-node").await.is_none());
+        assert!(!path.exists());
+
+        let aside: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(aside.len(), 1);
+        assert!(aside[0].starts_with("fleet.json.corrupt-"), "{:?}", aside);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(&aside[0])).unwrap(),
+            "not json"
+        );
+    }
+
+    #[tokio::test]
+    async fn unreadable_state_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        // A directory where the state file should be can't be read as one.
+        assert!(FleetStore::open(dir.path().to_path_buf()).await.is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Identity conformance — whether a node matches its node.yaml right now.
+//!
+//! Each declared field that a report can observe (CPU cores, memory size,
+//! the firewall allowlist, Nix trusted users, the Kubernetes role) is
+//! checked against a live report and marked pass or fail. Fields node.yaml
+//! leaves undeclared are not checked. Firewall conformance is the firewall
+//! drift check: any exposure or stale rule fails it.
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+
+use super::firewall_drift;
+use super::node_report::NodeReport;
+use crate::node_identity::NodeIdentity;
+
+/// Declared memory is a nominal size; the kernel and firmware reserve part
+/// of it, so the reported total may fall short by up to this fraction.
+const MEMORY_TOLERANCE: f64 = 0.10;
+
+/// API server port a k3s server node listens on; agents don't.
+const K8S_API_PORT: u16 = 6443;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldCheck {
+    /// node.yaml field path, e.g. `hardware.cpu.cores`.
+    pub field: String,
+    pub declared: String,
+    pub actual: String,
+    pub conformant: bool,
+}
+
+impl FieldCheck {
+    fn new(field: &str, declared: String, actual: String, conformant: bool) -> Self {
+        Self {
+            field: field.to_string(),
+            declared,
+            actual,
+            conformant,
+        }
+    }
+}
+
+/// Check every observable declared field of `identity` against `report`.
+pub fn check(identity: &NodeIdentity, report: &NodeReport) -> Vec<FieldCheck> {
+    let mut checks = Vec::new();
+
+    if let Some(cores) = identity.hardware.cpu.cores {
+        checks.push(FieldCheck::new(
+            "hardware.cpu.cores",
+            cores.to_string(),
+            report.hardware.cpu_cores.to_string(),
+            report.hardware.cpu_cores == cores,
+        ));
+    }
+
+    if let Some(ref memory) = identity.hardware.memory {
+        let actual_gb = report.hardware.ram_total_bytes as f64 / 1e9;
+        let deviation = (actual_gb - memory.size_gb).abs() / memory.size_gb;
+        checks.push(FieldCheck::new(
+            "hardware.memory.size_gb",
+            format!("{} GB", memory.size_gb),
+            format!("{:.1} GB", actual_gb),
+            deviation <= MEMORY_TOLERANCE,
+        ));
+    }
+
+    let firewall = &identity.network.firewall;
+    if !firewall.allowed_tcp_ports.is_empty() || !firewall.allowed_udp_ports.is_empty() {
+        let findings = firewall_drift::check(firewall, &report.network.listening_ports);
+        checks.push(FieldCheck::new(
+            "network.firewall",
+            format!(
+                "tcp {:?}, udp {:?}",
+                firewall.allowed_tcp_ports, firewall.allowed_udp_ports
+            ),
+            if findings.is_empty() {
+                "matches listeners".to_string()
+            } else {
+                findings
+                    .iter()
+                    .map(|f| f.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            },
+            findings.is_empty(),
+        ));
+    }
+
+    if !identity.nix.trusted_users.is_empty() {
+        let declared: BTreeSet<&str> = identity
+            .nix
+            .trusted_users
+            .iter()
+            .map(String::as_str)
+            .collect();
+        let actual: BTreeSet<&str> = report
+            .nix
+            .trusted_users
+            .iter()
+            .map(String::as_str)
+            .collect();
+        checks.push(FieldCheck::new(
+            "nix.trusted_users",
+            declared.iter().copied().collect::<Vec<_>>().join(", "),
+            actual.iter().copied().collect::<Vec<_>>().join(", "),
+            declared == actual,
+        ));
+    }
+
+    if let Some(ref role) = identity.kubernetes.role {
+        let api_listening = report
+            .network
+            .listening_ports
+            .iter()
+            .any(|p| p.port == K8S_API_PORT && p.protocol == "tcp");
+        let (actual, conformant) = match report.kubernetes {
+            None => ("no Kubernetes node".to_string(), false),
+            Some(_) if role == "server" && !api_listening => {
+                (format!("nothing listening on {}/tcp", K8S_API_PORT), false)
+            }
+            Some(ref k8s) if !k8s.node_ready => ("node not ready".to_string(), false),
+            Some(_) => (role.clone(), true),
+        };
+        checks.push(FieldCheck::new(
+            "kubernetes.role",
+            role.clone(),
+            actual,
+            conformant,
+        ));
+    }
+
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::node_report::tests::make_test_report;
+    use crate::domain::node_report::{K8sSnapshot, ListeningPort};
+    use crate::node_identity::MemoryConfig;
+
+    fn listener(port: u16) -> ListeningPort {
+        ListeningPort {
+            port,
+            protocol: "tcp".to_string(),
+            address: Some("0.0.0.0".to_string()),
+            process: None,
+        }
+    }
+
+    fn declared_identity() -> NodeIdentity {
+        let mut identity =
+            NodeIdentity::from_bootstrap("k8s-cloud-server", "test-node", "ops", None);
+        identity.hardware.cpu.cores = Some(4);
+        identity.hardware.memory = Some(MemoryConfig { size_gb: 16.0 });
+        identity.network.firewall.allowed_tcp_ports = vec![22, 6443];
+        identity.kubernetes.role = Some("server".to_string());
+        identity
+    }
+
+    fn conformant_report() -> NodeReport {
+        let mut report = make_test_report();
+        report.hardware.cpu_cores = 4;
+        report.hardware.ram_total_bytes = 15_600_000_000;
+        report.network.listening_ports = vec![listener(22), listener(6443)];
+        report.nix.trusted_users = vec!["ops".to_string(), "root".to_string()];
+        report.kubernetes = Some(K8sSnapshot {
+            k3s_version: Some("v1.30.0+k3s1".to_string()),
+            node_ready: true,
+            pod_count: 10,
+            namespace_count: 4,
+            conditions: vec![],
+            cpu_requests_millis: 0,
+            cpu_limits_millis: 0,
+            memory_requests_bytes: 0,
+            memory_limits_bytes: 0,
+            flux_installed: None,
+            helm_releases: None,
+            image_store_bytes: None,
+            image_count: None,
+        });
+        report
+    }
+
+    #[test]
+    fn conformant_node_passes_every_field() {
+        let checks = check(&declared_identity(), &conformant_report());
+        let fields: Vec<&str> = checks.iter().map(|c| c.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "hardware.cpu.cores",
+                "hardware.memory.size_gb",
+                "network.firewall",
+                "nix.trusted_users",
+                "kubernetes.role"
+            ]
+        );
+        assert!(checks.iter().all(|c| c.conformant), "{:?}", checks);
+    }
+
+    #[test]
+    fn non_conformant_node_fails_each_drifted_field() {
+        let mut report = conformant_report();
+        report.hardware.cpu_cores = 2;
+        report.hardware.ram_total_bytes = 8_000_000_000;
+        report.network.listening_ports = vec![listener(22), listener(5432)];
+        report.nix.trusted_users = vec!["root".to_string()];
+
+        let checks = check(&declared_identity(), &report);
+        let failed: Vec<&str> = checks
+            .iter()
+            .filter(|c| !c.conformant)
+            .map(|c| c.field.as_str())
+            .collect();
+        assert_eq!(
+            failed,
+            [
+                "hardware.cpu.cores",
+                "hardware.memory.size_gb",
+                "network.firewall",
+                "nix.trusted_users",
+                "kubernetes.role"
+            ]
+        );
+        let firewall = checks
+            .iter()
+            .find(|c| c.field == "network.firewall")
+            .unwrap();
+        assert!(firewall.actual.contains("5432/tcp"));
+    }
+
+    #[test]
+    fn undeclared_fields_are_not_checked() {
+        let mut identity = NodeIdentity::from_bootstrap("cloud-server", "test-node", "ops", None);
+        identity.nix.trusted_users.clear();
+        assert!(check(&identity, &make_test_report()).is_empty());
+    }
+}
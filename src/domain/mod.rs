@@ -1,6 +1,21 @@
+pub mod age_rotation;
+pub mod cgroup_limits;
+pub mod firewall_drift;
+pub mod fleet_drift;
+pub mod fleet_store;
+pub mod identity_conformance;
 pub mod nix_service;
 pub mod node_report;
 pub mod node_service;
+pub mod remote_collector;
+pub mod report_baseline;
 pub mod report_collector;
+pub mod report_delta;
+pub mod report_health;
+pub mod report_metrics;
+pub mod report_outbox;
 pub mod report_store;
+pub mod security_score;
+pub mod store_trend;
 pub mod types;
+pub mod yaml_diff;
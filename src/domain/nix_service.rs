@@ -1,27 +1,40 @@
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Instant;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
+use tracing::warn;
 
 use crate::config::DaemonConfig;
 use crate::domain::types::*;
 
 pub struct NixService {
     nix_path: RwLock<Option<PathBuf>>,
+    conflicting_installs: Vec<String>,
     platform: PlatformInfo,
     start_time: Instant,
     gc_status: RwLock<GcStatus>,
+    /// Held while an optimise estimate scans the store.
+    estimate_lock: Mutex<()>,
     config: DaemonConfig,
 }
 
 impl NixService {
     pub fn new(config: DaemonConfig) -> Arc<Self> {
         let platform = detect_platform();
-        let nix_path = crate::nix::detect().nix_path;
+        let detected = crate::nix::detect();
+        if !detected.conflicting_installs.is_empty() {
+            warn!(
+                installs = ?detected.conflicting_installs,
+                "multiple nix installations found; the first one is used"
+            );
+        }
 
         Arc::new(Self {
-            nix_path: RwLock::new(nix_path),
+            nix_path: RwLock::new(detected.nix_path),
+            conflicting_installs: detected.conflicting_installs,
             platform,
             start_time: Instant::now(),
             gc_status: RwLock::new(GcStatus {
@@ -30,6 +43,7 @@ impl NixService {
                 last_gc_at: None,
                 last_gc_freed_bytes: None,
             }),
+            estimate_lock: Mutex::new(()),
             config,
         })
     }
@@ -45,6 +59,7 @@ impl NixService {
                     version,
                     nix_path: Some(path.to_string_lossy().to_string()),
                     install_method,
+                    conflicting_installs: self.conflicting_installs.clone(),
                 }
             }
             None => NixStatus {
@@ -52,6 +67,7 @@ impl NixService {
                 version: None,
                 nix_path: None,
                 install_method: None,
+                conflicting_installs: Vec::new(),
             },
         }
     }
@@ -121,6 +137,93 @@ impl NixService {
         })
     }
 
+    /// Enumerate GC roots. Runtime roots (`/proc/...`, `{censored}`, ...)
+    /// are dropped unless `include_transient` is set.
+    pub async fn gc_roots(&self, include_transient: bool) -> Result<Vec<GcRoot>> {
+        let nix_path = self.nix_path.read().await;
+        let nix = nix_path
+            .as_ref()
+            .context("nix not installed")?;
+
+        let output = tokio::process::Command::new(nix.with_file_name("nix-store"))
+            .args(["--gc", "--print-roots"])
+            .output()
+            .await
+            .context("failed to run nix-store --gc --print-roots")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "nix-store --gc --print-roots failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(parse_gc_roots(&String::from_utf8_lossy(&output.stdout))
+            .into_iter()
+            .filter(|root| include_transient || !root.is_transient())
+            .collect())
+    }
+
+    /// The chain of store paths through which `from` depends on `to`,
+    /// starting at `from`. Empty when there is no dependency.
+    pub async fn why_depends(&self, from: &str, to: &str) -> Result<Vec<String>> {
+        validate_store_path(from)?;
+        validate_store_path(to)?;
+
+        let nix_path = self.nix_path.read().await;
+        let nix = nix_path
+            .as_ref()
+            .context("nix not installed")?;
+
+        // why-depends has no --json mode, so walk the closure's reference
+        // graph from path-info instead of parsing its tree drawing.
+        let output = tokio::process::Command::new(nix)
+            .args(["path-info", "--json", "--recursive", "--", from])
+            .output()
+            .await
+            .context("failed to run nix path-info")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "nix path-info failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let json: serde_json::Value =
+            serde_json::from_slice(&output.stdout).context("parsing nix path-info output")?;
+        let graph = parse_path_info_references(&json);
+        Ok(dependency_chain(&graph, from, to))
+    }
+
+    /// Every store path in the closure of `/run/current-system`, sorted.
+    pub async fn system_closure(&self) -> Result<Vec<String>> {
+        let nix_path = self.nix_path.read().await;
+        let nix = nix_path.as_ref().context("nix not installed")?;
+
+        let output = tokio::process::Command::new(nix.with_file_name("nix-store"))
+            .args(["--query", "--requisites", "/run/current-system"])
+            .output()
+            .await
+            .context("failed to run nix-store --query --requisites")?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "nix-store --query --requisites /run/current-system failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        let mut paths: Vec<String> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|l| l.starts_with("/nix/store/"))
+            .map(str::to_string)
+            .collect();
+        paths.sort();
+        Ok(paths)
+    }
+
     pub async fn nix_config(&self) -> Result<NixConfig> {
         let nix_path = self.nix_path.read().await;
         let nix = nix_path
@@ -140,45 +243,15 @@ impl NixService {
         let json: serde_json::Value =
             serde_json::from_slice(&output.stdout).context("parsing nix show-config output")?;
 
-        let get_str = |key: &str| -> Option<String> {
-            json.get(key)
-                .and_then(|v| v.get("value"))
-                .map(|v| {
-                    if let Some(s) = v.as_str() {
-                        s.to_string()
-                    } else {
-                        v.to_string()
-                    }
-                })
-        };
-
-        let get_str_list = |key: &str| -> Vec<String> {
-            json.get(key)
-                .and_then(|v| v.get("value"))
-                .and_then(|v| v.as_array())
-                .map(|arr| {
-                    arr.iter()
-                        .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                        .collect()
-                })
-                .or_else(|| {
-                    // Some config values are space-separated strings
-                    json.get(key)
-                        .and_then(|v| v.get("value"))
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.split_whitespace().map(|s| s.to_string()).collect())
-                })
-                .unwrap_or_default()
-        };
-
-        Ok(NixConfig {
-            substituters: get_str_list("substituters"),
-            trusted_public_keys: get_str_list("trusted-public-keys"),
-            max_jobs: get_str("max-jobs"),
-            cores: get_str("cores"),
-            experimental_features: get_str_list("experimental-features"),
-            sandbox: get_str("sandbox"),
-        })
+        let mut config = parse_nix_config(&json);
+        config.missing_features = config.features_missing(&self.config.required_features);
+        if !config.missing_features.is_empty() {
+            warn!(
+                missing = ?config.missing_features,
+                "nix.conf experimental-features is missing required features"
+            );
+        }
+        Ok(config)
     }
 
     pub async fn gc_status(&self) -> GcStatus {
@@ -186,45 +259,36 @@ impl NixService {
     }
 
     pub async fn trigger_gc(&self) -> Result<GcResult> {
+        self.trigger_gc_with(&GcOptions::default()).await
+    }
+
+    /// Collect garbage within `options`' age and size limits.
+    pub async fn trigger_gc_with(&self, options: &GcOptions) -> Result<GcResult> {
         let nix_path = self.nix_path.read().await;
         let nix = nix_path
             .as_ref()
             .context("nix not installed")?;
+        let (program, args) = gc_command(nix, options)?;
+        let program_name = program
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
 
         let start = Instant::now();
 
-        let output = tokio::process::Command::new(nix)
-            .args(["store", "gc"])
+        let output = tokio::process::Command::new(&program)
+            .args(&args)
             .output()
             .await
-            .context("failed to run nix store gc")?;
+            .with_context(|| format!("failed to run {}", program_name))?;
 
         let duration_secs = start.elapsed().as_secs_f64();
 
         if !output.status.success() {
-            anyhow::bail!("nix store gc failed");
+            anyhow::bail!("{} failed", program_name);
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
-
-        // Parse output: count deleted paths and freed bytes
-        // nix store gc outputs lines like: "deleting '/nix/store/...' (X bytes)"
-        // and ends with "Y store paths deleted, Z bytes freed"
-        let mut freed_bytes: u64 = 0;
-        let mut freed_paths: u64 = 0;
-
-        for line in stdout.lines() {
-            if let Some(rest) = line.strip_suffix(" bytes freed") {
-                if let Some((paths_str, bytes_str)) = rest.rsplit_once(", ") {
-                    freed_bytes = bytes_str.parse().unwrap_or(0);
-                    freed_paths = paths_str
-                        .split_whitespace()
-                        .next()
-                        .and_then(|s| s.parse().ok())
-                        .unwrap_or(0);
-                }
-            }
-        }
+        let (freed_paths, freed_bytes) = parse_gc_summary(&String::from_utf8_lossy(&output.stdout));
 
         // Update GC status
         {
@@ -240,6 +304,80 @@ impl NixService {
         })
     }
 
+    /// What GC within `options` would delete, deleting nothing: with an
+    /// age limit the profile generations that would be removed first, then
+    /// the store paths that are dead now. Paths only those generations keep
+    /// alive are not listed, since they aren't dead until the generations go.
+    pub async fn gc_dry_run(&self, options: &GcOptions) -> Result<Vec<String>> {
+        let nix_path = self.nix_path.read().await;
+        let nix = nix_path
+            .as_ref()
+            .context("nix not installed")?;
+
+        let mut lines = Vec::new();
+        for (program, args) in gc_dry_run_commands(nix, options)? {
+            let program_name = program
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let output = tokio::process::Command::new(&program)
+                .args(&args)
+                .output()
+                .await
+                .with_context(|| format!("failed to run {}", program_name))?;
+            if !output.status.success() {
+                anyhow::bail!(
+                    "{} failed: {}",
+                    program_name,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+            lines.extend(parse_gc_dry_run(&String::from_utf8_lossy(&output.stdout)));
+            lines.extend(parse_gc_dry_run(&String::from_utf8_lossy(&output.stderr)));
+        }
+        Ok(lines)
+    }
+
+    /// Check store contents against their recorded hashes: every valid
+    /// path when `paths` is `None`. Signatures are not checked. Reads the
+    /// whole store, so expect it to take minutes.
+    pub async fn verify_store(
+        &self,
+        paths: Option<Vec<String>>,
+        repair: bool,
+    ) -> Result<StoreVerifyResult> {
+        let nix_path = self.nix_path.read().await;
+        let nix = nix_path
+            .as_ref()
+            .context("nix not installed")?;
+        let args = verify_args(paths.as_deref(), repair)?;
+
+        let start = Instant::now();
+
+        let output = tokio::process::Command::new(nix)
+            .args(&args)
+            .output()
+            .await
+            .context("failed to run nix store verify")?;
+
+        let duration_secs = start.elapsed().as_secs_f64();
+
+        // Exits non-zero whenever it finds a problem; only an empty report
+        // alongside a failure means verify itself didn't run.
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let (corrupted_paths, missing_paths) = parse_verify_output(&stderr);
+        if !output.status.success() && corrupted_paths.is_empty() && missing_paths.is_empty() {
+            anyhow::bail!("nix store verify failed: {}", stderr.trim());
+        }
+
+        Ok(StoreVerifyResult {
+            corrupted_paths,
+            missing_paths,
+            repair,
+            duration_secs,
+        })
+    }
+
     pub async fn optimise_store(&self) -> Result<OptimiseResult> {
         let nix_path = self.nix_path.read().await;
         let nix = nix_path
@@ -260,16 +398,13 @@ impl NixService {
             anyhow::bail!("nix store optimise failed");
         }
 
-        // Parse output for deduplicated bytes
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let deduplicated_bytes = stdout
-            .lines()
-            .find_map(|line| {
-                // Format varies; try to extract byte count
-                line.split_whitespace()
-                    .find_map(|word| word.parse::<u64>().ok())
-            })
-            .unwrap_or(0);
+        // The summary goes to stderr; older releases printed it on stdout.
+        let combined = format!(
+            "{}\n{}",
+            String::from_utf8_lossy(&output.stderr),
+            String::from_utf8_lossy(&output.stdout)
+        );
+        let deduplicated_bytes = parse_optimise_output(&combined).unwrap_or(0);
 
         Ok(OptimiseResult {
             deduplicated_bytes,
@@ -277,6 +412,19 @@ impl NixService {
         })
     }
 
+    /// What `optimise_store` would free, estimated by scanning the store.
+    /// The scan reads the whole store, so only one runs at a time; `None`
+    /// when another is still in progress.
+    pub async fn estimate_optimise(&self) -> Result<Option<OptimiseEstimate>> {
+        let Ok(_scan) = self.estimate_lock.try_lock() else {
+            return Ok(None);
+        };
+        tokio::task::spawn_blocking(|| estimate_dedup(Path::new("/nix/store"), |_| {}))
+            .await
+            .context("store scan task failed")?
+            .map(Some)
+    }
+
     pub async fn cache_info(&self) -> Result<Vec<CacheInfo>> {
         let config = self.nix_config().await?;
         let client = reqwest::Client::builder()
@@ -374,6 +522,505 @@ impl NixService {
     }
 }
 
+/// Parse `nix-store --gc --print-roots`: one `<link> -> <store path>` per line.
+fn parse_gc_roots(output: &str) -> Vec<GcRoot> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (link, path) = line.rsplit_once(" -> ")?;
+            Some(GcRoot {
+                root_link: link.trim().to_string(),
+                store_path: path.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Validate a `--delete-older-than` age: a positive number of days, `<N>d`.
+pub(crate) fn validate_gc_age(age: &str) -> Result<()> {
+    match age.strip_suffix('d').map(str::parse::<u32>) {
+        Some(Ok(days)) if days > 0 => Ok(()),
+        _ => anyhow::bail!(
+            "invalid GC age '{}' (expected a number of days such as 30d)",
+            age
+        ),
+    }
+}
+
+/// The GC invocation for `options`. An age limit needs
+/// `nix-collect-garbage --delete-older-than` (installed next to `nix`),
+/// which also drops old profile generations; otherwise `nix store gc`.
+fn gc_command(nix: &std::path::Path, options: &GcOptions) -> Result<(PathBuf, Vec<String>)> {
+    let (program, mut args) = match options.older_than {
+        Some(ref age) => {
+            validate_gc_age(age)?;
+            (
+                nix.with_file_name("nix-collect-garbage"),
+                vec!["--delete-older-than".to_string(), age.clone()],
+            )
+        }
+        None => (
+            nix.to_path_buf(),
+            vec!["store".to_string(), "gc".to_string()],
+        ),
+    };
+    if let Some(max) = options.max_freed {
+        let flag = if options.older_than.is_some() {
+            "--max-freed"
+        } else {
+            "--max"
+        };
+        args.push(flag.to_string());
+        args.push(max.to_string());
+    }
+    Ok((program, args))
+}
+
+/// The dry-run counterpart of `gc_command`, run in order: with an age
+/// limit, `nix-collect-garbage --dry-run` lists the generations it would
+/// drop; `nix-store --gc --print-dead` then lists what is garbage now.
+fn gc_dry_run_commands(
+    nix: &std::path::Path,
+    options: &GcOptions,
+) -> Result<Vec<(PathBuf, Vec<String>)>> {
+    let mut commands = Vec::new();
+    if let Some(ref age) = options.older_than {
+        validate_gc_age(age)?;
+        commands.push((
+            nix.with_file_name("nix-collect-garbage"),
+            vec![
+                "--delete-older-than".to_string(),
+                age.clone(),
+                "--dry-run".to_string(),
+            ],
+        ));
+    }
+    commands.push((
+        nix.with_file_name("nix-store"),
+        vec!["--gc".to_string(), "--print-dead".to_string()],
+    ));
+    Ok(commands)
+}
+
+/// Dead store paths and `would remove ...` lines from a GC dry run; the
+/// progress chatter around them is dropped.
+fn parse_gc_dry_run(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|l| l.starts_with("/nix/store/") || l.starts_with("would "))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Arguments for `nix store verify`. `--no-trust` limits it to content
+/// hashes; locally built paths carry no signatures and would all be
+/// reported as untrusted otherwise.
+fn verify_args(paths: Option<&[String]>, repair: bool) -> Result<Vec<String>> {
+    let mut args: Vec<String> = ["store", "verify", "--no-trust"]
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if repair {
+        args.push("--repair".to_string());
+    }
+    match paths {
+        None => args.push("--all".to_string()),
+        Some([]) => anyhow::bail!("no store paths given to verify"),
+        Some(paths) => {
+            for path in paths {
+                validate_store_path(path)?;
+            }
+            args.push("--".to_string());
+            args.extend(paths.iter().cloned());
+        }
+    }
+    Ok(args)
+}
+
+/// `(corrupted, missing)` store paths from `nix store verify` stderr.
+/// Corruption reads `path '<p>' was modified! expected hash ...`; a path
+/// deleted from disk fails with `getting status of '<p>': No such file or
+/// directory`.
+fn parse_verify_output(output: &str) -> (Vec<String>, Vec<String>) {
+    fn quoted_store_path(line: &str) -> Option<String> {
+        let start = line.find("'/nix/store/")? + 1;
+        let len = line[start..].find('\'')?;
+        let path = &line[start..start + len];
+        // Keep the store path itself, not a file inside it.
+        let end = path["/nix/store/".len()..]
+            .find('/')
+            .map(|i| i + "/nix/store/".len())
+            .unwrap_or(path.len());
+        Some(path[..end].to_string())
+    }
+
+    let mut corrupted = Vec::new();
+    let mut missing = Vec::new();
+    for line in output.lines() {
+        let target = if line.contains("was modified!") {
+            &mut corrupted
+        } else if line.contains("No such file or directory") {
+            &mut missing
+        } else {
+            continue;
+        };
+        if let Some(path) = quoted_store_path(line) {
+            if !target.contains(&path) {
+                target.push(path);
+            }
+        }
+    }
+    (corrupted, missing)
+}
+
+/// `(paths, bytes)` from the GC summary line, e.g.
+/// `"12 store paths deleted, 34567 bytes freed"` or, from newer Nix,
+/// `"12 store paths deleted, 1.50 MiB freed"`.
+fn parse_gc_summary(output: &str) -> (u64, u64) {
+    for line in output.lines() {
+        let Some(rest) = line.trim().strip_suffix(" freed") else {
+            continue;
+        };
+        let Some((paths_str, size_str)) = rest.rsplit_once(", ") else {
+            continue;
+        };
+        let paths = paths_str
+            .split_whitespace()
+            .next()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        let mut size = size_str.split_whitespace();
+        let bytes = size
+            .next()
+            .and_then(|value| size_bytes(value, size.next()))
+            .unwrap_or(0);
+        return (paths, bytes);
+    }
+    (0, 0)
+}
+
+/// A size as Nix prints it (`34567`, `34567 bytes`, `1.50 MiB`) in bytes.
+fn size_bytes(value: &str, unit: Option<&str>) -> Option<u64> {
+    let value: f64 = value.parse().ok()?;
+    let multiplier: u64 = match unit {
+        Some("KiB") => 1 << 10,
+        Some("MiB") => 1 << 20,
+        Some("GiB") => 1 << 30,
+        Some("TiB") => 1 << 40,
+        _ => 1,
+    };
+    Some((value * multiplier as f64) as u64)
+}
+
+/// Bytes freed by `nix store optimise`, which reports on stderr:
+/// `"12.34 MiB freed by hard-linking 56 files"` (Nix 2.4+) or
+/// `"1234 bytes freed by hard-linking 56 files"` (`nix-store --optimise`).
+/// Without such a line, falls back to the `"currently hard linking saves
+/// 12.34 MiB"` note. `None` when neither appears.
+fn parse_optimise_output(output: &str) -> Option<u64> {
+    let mut saves = None;
+    for line in output.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if let Some(i) = words.iter().position(|w| *w == "freed") {
+            let parsed = match i {
+                0 => None,
+                1 => size_bytes(words[0], None),
+                _ => size_bytes(words[i - 2], Some(words[i - 1]))
+                    .or_else(|| size_bytes(words[i - 1], None)),
+            };
+            if parsed.is_some() {
+                return parsed;
+            }
+        }
+        if let Some(i) = words.iter().position(|w| *w == "saves") {
+            if let Some(value) = words.get(i + 1) {
+                saves = saves.or_else(|| size_bytes(value, words.get(i + 2).copied()));
+            }
+        }
+    }
+    saves
+}
+
+/// Estimate what `nix store optimise` would free under `root` without
+/// touching it: regular files with identical contents but separate inodes
+/// would be hard-linked together, freeing all but one copy. Only files
+/// sharing a size with another inode are hashed. `progress` is called with
+/// the running file count every 10,000 files.
+pub fn estimate_dedup(root: &Path, mut progress: impl FnMut(u64)) -> Result<OptimiseEstimate> {
+    let start = Instant::now();
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    let mut seen_inodes = HashSet::new();
+    let mut scanned_files = 0u64;
+
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if dir == root => {
+                return Err(e).with_context(|| format!("reading {}", root.display()))
+            }
+            Err(_) => continue,
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                // The hard-link pool itself; its inodes are shared with the
+                // store files already deduplicated.
+                if dir != root || entry.file_name() != ".links" {
+                    dirs.push(entry.path());
+                }
+                continue;
+            }
+            if !file_type.is_file() {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            scanned_files += 1;
+            if scanned_files.is_multiple_of(10_000) {
+                progress(scanned_files);
+            }
+            if meta.len() > 0 && seen_inodes.insert((meta.dev(), meta.ino())) {
+                by_size.entry(meta.len()).or_default().push(entry.path());
+            }
+        }
+    }
+
+    let mut deduplicable_bytes = 0;
+    let mut duplicate_files = 0;
+    for (size, paths) in by_size.into_iter().filter(|(_, p)| p.len() > 1) {
+        let mut by_hash: HashMap<blake3::Hash, u64> = HashMap::new();
+        for path in paths {
+            // Streamed, so a multi-GB store file isn't read into memory.
+            let hash = std::fs::File::open(&path).and_then(|file| {
+                blake3::Hasher::new()
+                    .update_reader(file)
+                    .map(|h| h.finalize())
+            });
+            if let Ok(hash) = hash {
+                *by_hash.entry(hash).or_default() += 1;
+            }
+        }
+        for copies in by_hash.into_values().filter(|&n| n > 1) {
+            duplicate_files += copies - 1;
+            deduplicable_bytes += size * (copies - 1);
+        }
+    }
+
+    Ok(OptimiseEstimate {
+        deduplicable_bytes,
+        duplicate_files,
+        scanned_files,
+        duration_secs: start.elapsed().as_secs_f64(),
+    })
+}
+
+/// Accept only `/nix/store/<hash>-<name>` paths (no subpaths), so request
+/// input can't smuggle flags or installable expressions into nix.
+pub(crate) fn validate_store_path(path: &str) -> Result<()> {
+    let name = path
+        .strip_prefix("/nix/store/")
+        .with_context(|| format!("'{}' is not a /nix/store path", path))?;
+    let valid = name.len() > 33
+        && name.as_bytes()[32] == b'-'
+        && name[..32].bytes().all(|b| b.is_ascii_alphanumeric())
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"+-._?=".contains(&b))
+        && !name.starts_with('.');
+    if !valid {
+        anyhow::bail!("'{}' is not a valid store path", path);
+    }
+    Ok(())
+}
+
+/// References of each path in `nix path-info --json` output. Nix 2.19+
+/// prints an object keyed by path; older versions an array of objects
+/// carrying a `path` field. References are made absolute in case a
+/// version prints bare store path names.
+fn parse_path_info_references(json: &serde_json::Value) -> HashMap<String, Vec<String>> {
+    let references = |info: &serde_json::Value| -> Vec<String> {
+        info.get("references")
+            .and_then(|r| r.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|r| r.as_str())
+            .map(|r| {
+                if r.starts_with("/nix/store/") {
+                    r.to_string()
+                } else {
+                    format!("/nix/store/{}", r)
+                }
+            })
+            .collect()
+    };
+    match json {
+        serde_json::Value::Object(paths) => paths
+            .iter()
+            .map(|(path, info)| (path.clone(), references(info)))
+            .collect(),
+        serde_json::Value::Array(infos) => infos
+            .iter()
+            .filter_map(|info| {
+                let path = info.get("path")?.as_str()?;
+                Some((path.to_string(), references(info)))
+            })
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+/// Shortest chain of references from `from` to `to`, both included.
+/// Empty when `to` is not reachable.
+fn dependency_chain(graph: &HashMap<String, Vec<String>>, from: &str, to: &str) -> Vec<String> {
+    let mut parent: HashMap<&str, &str> = HashMap::new();
+    let mut queue = VecDeque::from([from]);
+    let mut seen = HashSet::from([from]);
+    while let Some(path) = queue.pop_front() {
+        if path == to {
+            let mut chain = vec![to.to_string()];
+            let mut current = to;
+            while let Some(&prev) = parent.get(current) {
+                chain.push(prev.to_string());
+                current = prev;
+            }
+            chain.reverse();
+            return chain;
+        }
+        for next in graph.get(path).into_iter().flatten() {
+            if seen.insert(next.as_str()) {
+                parent.insert(next.as_str(), path);
+                queue.push_back(next.as_str());
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// Split a store path into package name and version. The version starts
+/// at the first `-` followed by something other than a letter, so
+/// `python3.11-requests-2.31.0` is `python3.11-requests` at `2.31.0`.
+/// `None` when `path` is not a store path.
+pub(crate) fn parse_store_path_name(path: &str) -> Option<StorePathName> {
+    let base = path.strip_prefix("/nix/store/")?;
+    let (_hash, full_name) = base.split_once('-')?;
+    let split = full_name
+        .char_indices()
+        .find(|&(i, c)| {
+            c == '-'
+                && full_name[i + 1..]
+                    .chars()
+                    .next()
+                    .is_some_and(|next| !next.is_ascii_alphabetic())
+        })
+        .map(|(i, _)| i);
+    let (name, version) = match split {
+        Some(i) => (&full_name[..i], Some(full_name[i + 1..].to_string())),
+        None => (full_name, None),
+    };
+    Some(StorePathName {
+        store_path: path.to_string(),
+        name: name.to_string(),
+        version,
+    })
+}
+
+/// Parse `nix show-config --json` output. `missing_features` is left empty;
+/// callers fill it in against their required set.
+pub(crate) fn parse_nix_config(json: &serde_json::Value) -> NixConfig {
+    let get_str = |key: &str| -> Option<String> {
+        json.get(key)
+            .and_then(|v| v.get("value"))
+            .map(|v| {
+                if let Some(s) = v.as_str() {
+                    s.to_string()
+                } else {
+                    v.to_string()
+                }
+            })
+    };
+
+    let get_str_list = |key: &str| -> Vec<String> {
+        json.get(key)
+            .and_then(|v| v.get("value"))
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .or_else(|| {
+                // Some config values are space-separated strings
+                json.get(key)
+                    .and_then(|v| v.get("value"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.split_whitespace().map(|s| s.to_string()).collect())
+            })
+            .unwrap_or_default()
+    };
+
+    NixConfig {
+        substituters: get_str_list("substituters"),
+        trusted_public_keys: get_str_list("trusted-public-keys"),
+        max_jobs: get_str("max-jobs"),
+        cores: get_str("cores"),
+        experimental_features: get_str_list("experimental-features"),
+        sandbox: get_str("sandbox"),
+        missing_features: Vec::new(),
+        overrides: config_overrides(json),
+    }
+}
+
+/// Settings whose values are credentials or point at them; listed with
+/// their value redacted, since overrides end up in reports and the API.
+const SECRET_SETTINGS: &[&str] = &["access-tokens", "netrc-file", "secret-key-files"];
+
+fn is_secret_setting(name: &str) -> bool {
+    SECRET_SETTINGS.contains(&name)
+        || ["token", "password", "secret"]
+            .iter()
+            .any(|word| name.contains(word))
+}
+
+/// Settings from `nix show-config --json` whose `value` differs from their
+/// `defaultValue`. Settings without a `defaultValue` (older Nix) are skipped.
+fn config_overrides(json: &serde_json::Value) -> HashMap<String, String> {
+    let Some(settings) = json.as_object() else {
+        return HashMap::new();
+    };
+    settings
+        .iter()
+        .filter_map(|(name, setting)| {
+            let value = setting.get("value")?;
+            let default = setting.get("defaultValue")?;
+            (value != default).then(|| {
+                let shown = if is_secret_setting(name) {
+                    crate::secret::REDACTED.to_string()
+                } else {
+                    conf_value(value)
+                };
+                (name.clone(), shown)
+            })
+        })
+        .collect()
+}
+
+/// A show-config value as nix.conf writes it: lists space-separated,
+/// strings bare.
+fn conf_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(items) => {
+            items.iter().map(conf_value).collect::<Vec<_>>().join(" ")
+        }
+        other => other.to_string(),
+    }
+}
+
 fn detect_platform() -> PlatformInfo {
     let os = std::env::consts::OS.to_string();
     let arch = std::env::consts::ARCH.to_string();
@@ -407,3 +1054,380 @@ fn detect_platform() -> PlatformInfo {
         has_systemd,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gc_roots_with_censored_and_runtime_roots() {
+        let output = "\
+/home/alice/project/result -> /nix/store/8a9b-hello-2.12.1
+/nix/var/nix/profiles/per-user/root/profile-3-link -> /nix/store/kq2x-user-environment
+/nix/var/nix/profiles/system-42-link -> /nix/store/z1y2-nixos-system-node-1-25.05
+/proc/1234/maps -> /nix/store/m3n4-glibc-2.40
+{censored} -> /nix/store/p5q6-openssl-3.3.2
+{memory:7} -> /nix/store/r7s8-bash-5.2
+/run/booted-system -> /nix/store/z1y2-nixos-system-node-1-25.05
+";
+        let roots = parse_gc_roots(output);
+        assert_eq!(roots.len(), 7);
+        assert_eq!(
+            roots[2],
+            GcRoot {
+                root_link: "/nix/var/nix/profiles/system-42-link".to_string(),
+                store_path: "/nix/store/z1y2-nixos-system-node-1-25.05".to_string(),
+            }
+        );
+        assert_eq!(roots[4].root_link, "{censored}");
+
+        let transient: Vec<_> = roots
+            .iter()
+            .filter(|r| r.is_transient())
+            .map(|r| r.root_link.as_str())
+            .collect();
+        assert_eq!(transient, vec!["/proc/1234/maps", "{censored}", "{memory:7}"]);
+    }
+
+    #[test]
+    fn parse_gc_roots_skips_noise() {
+        let output = "finding garbage collector roots...\n\n/run/current-system -> /nix/store/a-sys\n";
+        let roots = parse_gc_roots(output);
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0].root_link, "/run/current-system");
+    }
+
+    #[test]
+    fn why_depends_chain_from_path_info() {
+        let hello = "/nix/store/v5sv61sszx301i0x6xysaqzla09nksnd-hello-2.12.1";
+        let glibc = "/nix/store/9l06v7fc38c1x3r2iydl15ksgz0ysb82-glibc-2.40-66";
+        let libidn2 = "/nix/store/3s0xn6ivlmlh7z6b0jafi1hv09ykbnxs-libidn2-2.3.7";
+        let unistring = "/nix/store/qv9n5rgapf5qasy7pqj3vl5n76rq1cw1-libunistring-1.2";
+        // Nix 2.19+ keys by path; self-references are common.
+        let keyed = serde_json::json!({
+            hello: { "references": [glibc, hello] },
+            glibc: { "references": [glibc, libidn2] },
+            libidn2: { "references": [unistring] },
+            unistring: { "references": [] },
+        });
+        let graph = parse_path_info_references(&keyed);
+        assert_eq!(
+            dependency_chain(&graph, hello, unistring),
+            vec![hello, glibc, libidn2, unistring]
+        );
+        assert!(dependency_chain(&graph, unistring, hello).is_empty());
+
+        // Older Nix prints an array with a `path` field per entry.
+        let listed = serde_json::json!([
+            { "path": hello, "references": [glibc] },
+            { "path": glibc, "references": [] },
+        ]);
+        let graph = parse_path_info_references(&listed);
+        assert_eq!(dependency_chain(&graph, hello, glibc), vec![hello, glibc]);
+    }
+
+    #[test]
+    fn store_path_names_split_like_parse_drv_name() {
+        let pairs: Vec<(String, Option<String>)> = [
+            "/nix/store/v5sv61sszx301i0x6xysaqzla09nksnd-hello-2.12.1",
+            "/nix/store/9l06v7fc38c1x3r2iydl15ksgz0ysb82-glibc-2.40-66",
+            "/nix/store/0c3v1mbkwqdb39n4xcqa6n48b2bk0jpr-python3.11-requests-2.31.0",
+            "/nix/store/1b9p07z77phvv2hf6gm9f28syp39f1ag-nixos-system-edge-24.05.20240601",
+            "/nix/store/2a8yrzbmgvcg2lq8mrdm5h5w3lyr6hp9-linux-6.6.30-modules",
+            "/nix/store/xbnvnm5h3dlm6m0xyr1nc6b1ma7acfyd-source",
+            "/nix/store/zz6vmmkp3ncd0r0f4zzmxzi1rzdmrwfd-etc-os-release",
+        ]
+        .iter()
+        .map(|p| {
+            let parsed = parse_store_path_name(p).unwrap();
+            assert_eq!(parsed.store_path, *p);
+            (parsed.name, parsed.version)
+        })
+        .collect();
+        let expected = [
+            ("hello", Some("2.12.1")),
+            ("glibc", Some("2.40-66")),
+            ("python3.11-requests", Some("2.31.0")),
+            ("nixos-system-edge", Some("24.05.20240601")),
+            ("linux", Some("6.6.30-modules")),
+            ("source", None),
+            ("etc-os-release", None),
+        ];
+        assert_eq!(
+            pairs,
+            expected
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.map(str::to_string)))
+                .collect::<Vec<_>>()
+        );
+        assert!(parse_store_path_name("/usr/bin/hello").is_none());
+    }
+
+    #[test]
+    fn gc_command_with_age_uses_nix_collect_garbage() {
+        let options = GcOptions {
+            older_than: Some("7d".to_string()),
+            max_freed: Some(1 << 30),
+        };
+        let nix = std::path::Path::new("/run/current-system/sw/bin/nix");
+        let (program, args) = gc_command(nix, &options).unwrap();
+        assert_eq!(
+            program,
+            PathBuf::from("/run/current-system/sw/bin/nix-collect-garbage")
+        );
+        assert_eq!(
+            args,
+            vec!["--delete-older-than", "7d", "--max-freed", "1073741824"]
+        );
+    }
+
+    #[test]
+    fn gc_command_without_age_uses_nix_store_gc() {
+        let nix = std::path::Path::new("/usr/bin/nix");
+        let (program, args) = gc_command(nix, &GcOptions::default()).unwrap();
+        assert_eq!(program, PathBuf::from("/usr/bin/nix"));
+        assert_eq!(args, vec!["store", "gc"]);
+
+        let capped = GcOptions {
+            older_than: None,
+            max_freed: Some(500),
+        };
+        let (_, args) = gc_command(nix, &capped).unwrap();
+        assert_eq!(args, vec!["store", "gc", "--max", "500"]);
+    }
+
+    #[test]
+    fn gc_dry_run_command_deletes_nothing() {
+        let nix = std::path::Path::new("/usr/bin/nix");
+        let print_dead = (
+            PathBuf::from("/usr/bin/nix-store"),
+            vec!["--gc".to_string(), "--print-dead".to_string()],
+        );
+        assert_eq!(
+            gc_dry_run_commands(nix, &GcOptions::default()).unwrap(),
+            vec![print_dead.clone()]
+        );
+
+        let aged = GcOptions {
+            older_than: Some("30d".to_string()),
+            max_freed: None,
+        };
+        let commands = gc_dry_run_commands(nix, &aged).unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].0, PathBuf::from("/usr/bin/nix-collect-garbage"));
+        assert_eq!(
+            commands[0].1,
+            vec!["--delete-older-than", "30d", "--dry-run"]
+        );
+        assert_eq!(commands[1], print_dead);
+
+        let output = "finding garbage collector roots...\n\
+                      /nix/store/aaaa-hello-2.12\n\
+                      removing old generations of profile /nix/var/nix/profiles/system\n\
+                      would remove profile version 41\n";
+        assert_eq!(
+            parse_gc_dry_run(output),
+            vec![
+                "/nix/store/aaaa-hello-2.12",
+                "would remove profile version 41"
+            ]
+        );
+    }
+
+    #[test]
+    fn gc_age_must_be_days() {
+        assert!(validate_gc_age("30d").is_ok());
+        for bad in ["", "d", "0d", "7", "7h", "-3d", "7d; rm -rf /", "--help"] {
+            assert!(validate_gc_age(bad).is_err(), "{:?} accepted", bad);
+        }
+        let options = GcOptions {
+            older_than: Some("1w".to_string()),
+            max_freed: None,
+        };
+        assert!(gc_command(std::path::Path::new("/usr/bin/nix"), &options).is_err());
+    }
+
+    #[test]
+    fn parse_verify_output_corrupted_and_missing() {
+        let output = "\
+checking path '/nix/store/0c2i4pcn5ks2n0s8qmfxh4ahy3rmhzl6-hello-2.12.1'...
+path '/nix/store/0c2i4pcn5ks2n0s8qmfxh4ahy3rmhzl6-hello-2.12.1' was modified! expected hash 'sha256:1bpz6pq3vql4m5r0wz1kqsdzqfgf5ylc7p2phly5ps6g1gwfs8cq', got 'sha256:0lswdj1sh1n4gq2zqsr2qz7xk4g7b4j2a9l3wnpkq3nkf7cq1p8c'
+error: getting status of '/nix/store/7b7g9kz1i3yp2c4z4b0jdn1n9gk5n1nq-openssl-3.0.13/lib/libssl.so.3': No such file or directory
+error: getting status of '/nix/store/7b7g9kz1i3yp2c4z4b0jdn1n9gk5n1nq-openssl-3.0.13/lib/libcrypto.so.3': No such file or directory
+error: getting status of '/nix/store/d0dj1y3d5m2sbmlq7jhy7x6h3dc8wjgz-zlib-1.3.1': No such file or directory
+2 paths checked, 1 corrupted, 2 failed
+";
+        let (corrupted, missing) = parse_verify_output(output);
+        assert_eq!(
+            corrupted,
+            vec!["/nix/store/0c2i4pcn5ks2n0s8qmfxh4ahy3rmhzl6-hello-2.12.1"]
+        );
+        assert_eq!(
+            missing,
+            vec![
+                "/nix/store/7b7g9kz1i3yp2c4z4b0jdn1n9gk5n1nq-openssl-3.0.13",
+                "/nix/store/d0dj1y3d5m2sbmlq7jhy7x6h3dc8wjgz-zlib-1.3.1",
+            ]
+        );
+        assert_eq!(parse_verify_output(""), (vec![], vec![]));
+    }
+
+    #[test]
+    fn parse_optimise_output_across_nix_versions() {
+        // Nix 2.4+ `nix store optimise`
+        assert_eq!(
+            parse_optimise_output("1.50 MiB freed by hard-linking 1234 files\n"),
+            Some(1_572_864)
+        );
+        // `nix-store --optimise` printing plain bytes
+        assert_eq!(
+            parse_optimise_output("3456 bytes freed by hard-linking 12 files"),
+            Some(3456)
+        );
+        // Nothing to do; the leading count must not be taken as the size
+        assert_eq!(
+            parse_optimise_output("0.00 MiB freed by hard-linking 0 files"),
+            Some(0)
+        );
+        // Only the cumulative note, after progress lines with other numbers
+        let output = "\
+[12/300 paths] optimising '/nix/store/abc-foo'
+note: currently hard linking saves 2.00 GiB
+";
+        assert_eq!(parse_optimise_output(output), Some(2 << 30));
+        assert_eq!(parse_optimise_output("building 42 paths\n"), None);
+    }
+
+    #[test]
+    fn estimate_dedup_counts_unlinked_duplicates() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path();
+        let pkg_a = store.join("aaaa-foo");
+        let pkg_b = store.join("bbbb-bar");
+        std::fs::create_dir_all(pkg_a.join("share")).unwrap();
+        std::fs::create_dir_all(&pkg_b).unwrap();
+        std::fs::create_dir_all(store.join(".links")).unwrap();
+
+        let license = "MIT License ".repeat(100);
+        std::fs::write(pkg_a.join("share/LICENSE"), &license).unwrap();
+        std::fs::write(pkg_b.join("LICENSE"), &license).unwrap();
+        std::fs::write(pkg_b.join("COPYING"), &license).unwrap();
+        // Same size, different contents: not a duplicate
+        std::fs::write(pkg_b.join("NOTICE"), "x".repeat(license.len())).unwrap();
+        // Already hard-linked copies (and the pool) free nothing more
+        std::fs::hard_link(pkg_b.join("NOTICE"), pkg_a.join("NOTICE")).unwrap();
+        std::fs::hard_link(pkg_b.join("NOTICE"), store.join(".links/notice")).unwrap();
+
+        let mut calls = 0;
+        let estimate = estimate_dedup(store, |_| calls += 1).unwrap();
+        assert_eq!(estimate.scanned_files, 5);
+        assert_eq!(estimate.duplicate_files, 2);
+        assert_eq!(estimate.deduplicable_bytes, 2 * license.len() as u64);
+        assert_eq!(calls, 0);
+
+        assert!(estimate_dedup(&store.join("missing"), |_| {}).is_err());
+    }
+
+    #[tokio::test]
+    async fn estimate_optimise_runs_one_scan_at_a_time() {
+        let svc = NixService::new(DaemonConfig::default());
+        let _running = svc.estimate_lock.lock().await;
+        assert!(svc.estimate_optimise().await.unwrap().is_none());
+    }
+
+    #[test]
+    fn verify_args_all_or_validated_paths() {
+        assert_eq!(
+            verify_args(None, true).unwrap(),
+            vec!["store", "verify", "--no-trust", "--repair", "--all"]
+        );
+        let path = "/nix/store/0c2i4pcn5ks2n0s8qmfxh4ahy3rmhzl6-hello-2.12.1".to_string();
+        assert_eq!(
+            verify_args(Some(std::slice::from_ref(&path)), false).unwrap(),
+            vec!["store", "verify", "--no-trust", "--", path.as_str()]
+        );
+        assert!(verify_args(Some(&["--all".to_string()]), false).is_err());
+        assert!(verify_args(Some(&[]), false).is_err());
+    }
+
+    #[test]
+    fn parse_gc_summary_bytes_and_units() {
+        assert_eq!(
+            parse_gc_summary(
+                "deleting '/nix/store/abc-foo'\n3 store paths deleted, 4096 bytes freed\n"
+            ),
+            (3, 4096)
+        );
+        assert_eq!(
+            parse_gc_summary("1204 store paths deleted, 1.50 MiB freed"),
+            (1204, 1_572_864)
+        );
+        assert_eq!(parse_gc_summary("nothing to do"), (0, 0));
+    }
+
+    #[test]
+    fn validate_store_path_accepts_store_paths_only() {
+        assert!(
+            validate_store_path("/nix/store/v5sv61sszx301i0x6xysaqzla09nksnd-hello-2.12.1").is_ok()
+        );
+        assert!(validate_store_path("nixpkgs#hello").is_err());
+        assert!(validate_store_path("/etc/passwd").is_err());
+        assert!(validate_store_path("/nix/store/short-hello").is_err());
+        assert!(validate_store_path(
+            "/nix/store/v5sv61sszx301i0x6xysaqzla09nksnd-hello/bin/hello"
+        )
+        .is_err());
+        assert!(
+            validate_store_path("/nix/store/v5sv61sszx301i0x6xysaqzla09nksnd-a b").is_err()
+        );
+    }
+
+    #[test]
+    fn parse_nix_config_values_and_lists() {
+        let json = serde_json::json!({
+            "substituters": { "value": ["https://cache.nixos.org/"] },
+            "max-jobs": { "value": 8 },
+            "experimental-features": { "value": "nix-command flakes" },
+            "sandbox": { "value": true },
+        });
+        let config = parse_nix_config(&json);
+        assert_eq!(config.substituters, vec!["https://cache.nixos.org/"]);
+        assert_eq!(config.max_jobs.as_deref(), Some("8"));
+        assert_eq!(config.sandbox.as_deref(), Some("true"));
+        assert_eq!(config.experimental_features, vec!["nix-command", "flakes"]);
+        assert!(config.cores.is_none());
+        assert!(config.missing_features.is_empty());
+        assert!(config.overrides.is_empty());
+    }
+
+    #[test]
+    fn nix_config_overrides_list_only_changed_settings() {
+        // Trimmed from `nix show-config --json` on a NixOS host.
+        let json: serde_json::Value = serde_json::from_str(
+            r#"{
+              "auto-optimise-store": {"aliases": [], "defaultValue": false, "description": "", "documentDefault": true, "value": true},
+              "cores": {"aliases": [], "defaultValue": 0, "description": "", "documentDefault": false, "value": 0},
+              "max-jobs": {"aliases": [], "defaultValue": 1, "description": "", "documentDefault": false, "value": 8},
+              "substituters": {"aliases": [], "defaultValue": ["https://cache.nixos.org/"], "description": "", "documentDefault": true, "value": ["https://cache.nixos.org/", "https://nix.example.com"]},
+              "sandbox": {"aliases": [], "defaultValue": true, "description": "", "documentDefault": true, "value": true},
+              "trusted-users": {"aliases": [], "defaultValue": ["root"], "description": "", "documentDefault": true, "value": ["root"]},
+              "legacy-setting": {"value": "on"},
+              "access-tokens": {"aliases": [], "defaultValue": {}, "description": "", "documentDefault": true, "value": {"github.com": "ghp_abc123"}},
+              "netrc-file": {"aliases": [], "defaultValue": "/etc/nix/netrc", "description": "", "documentDefault": true, "value": "/run/secrets/netrc"}
+            }"#,
+        )
+        .unwrap();
+        let overrides = parse_nix_config(&json).overrides;
+        assert_eq!(overrides.len(), 5);
+        assert_eq!(overrides["access-tokens"], "[redacted]");
+        assert_eq!(overrides["netrc-file"], "[redacted]");
+        assert!(is_secret_setting("some-plugin-password"));
+        assert!(!is_secret_setting("max-jobs"));
+        assert_eq!(overrides["auto-optimise-store"], "true");
+        assert_eq!(overrides["max-jobs"], "8");
+        assert_eq!(
+            overrides["substituters"],
+            "https://cache.nixos.org/ https://nix.example.com"
+        );
+    }
+}
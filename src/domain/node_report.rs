@@ -3,15 +3,21 @@
 //! Unlike NodeIdentity (declared in YAML), a NodeReport is generated at runtime
 //! by inspecting the actual hardware, OS, network, and service state of a node.
 
-use async_graphql::SimpleObject;
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use async_graphql::{Enum, SimpleObject};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::node_identity::remove_field_path;
 
 /// A report wrapped with integrity metadata for storage and caching.
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct StoredReport {
-    /// SHA-256 checksum of the serialized report: "sha256:<hex>"
+    /// Checksum of the serialized report: "<algorithm>:<hex>", e.g.
+    /// "sha256:…". The prefix selects the algorithm `verify` uses.
     pub checksum: String,
     /// When the report was collected.
     pub collected_at: DateTime<Utc>,
@@ -24,12 +30,13 @@ pub struct StoredReport {
 impl StoredReport {
     /// Create a new StoredReport from a NodeReport, computing the SHA-256 checksum.
     pub fn new(report: NodeReport) -> Self {
-        let serialized = serde_json::to_string(&report).unwrap_or_default();
-        let hash = Sha256::digest(serialized.as_bytes());
-        let checksum = format!("sha256:{:x}", hash);
+        Self::with_algorithm(report, ChecksumAlgorithm::default())
+    }
 
+    /// Create a new StoredReport checksummed with `algorithm`.
+    pub fn with_algorithm(report: NodeReport, algorithm: ChecksumAlgorithm) -> Self {
         Self {
-            checksum,
+            checksum: algorithm.checksum(&report),
             collected_at: Utc::now(),
             collector_version: env!("CARGO_PKG_VERSION").to_string(),
             report,
@@ -48,12 +55,188 @@ impl StoredReport {
         self.age_secs() > max_age_secs as i64
     }
 
-    /// Verify the checksum matches the report data. Returns true if valid.
+    /// Verify the checksum matches the report data, using the algorithm
+    /// named by its prefix. Returns true if valid; an unknown prefix is
+    /// never valid.
     pub fn verify(&self) -> bool {
-        let serialized = serde_json::to_string(&self.report).unwrap_or_default();
-        let hash = Sha256::digest(serialized.as_bytes());
-        let expected = format!("sha256:{:x}", hash);
-        self.checksum == expected
+        self.algorithm()
+            .is_some_and(|algorithm| self.checksum == algorithm.checksum(&self.report))
+    }
+
+    /// The algorithm named by the checksum prefix.
+    pub fn algorithm(&self) -> Option<ChecksumAlgorithm> {
+        let (prefix, _) = self.checksum.split_once(':')?;
+        ChecksumAlgorithm::from_prefix(prefix)
+    }
+
+    /// Redact the inner report (see `NodeReport::redact`), keeping the
+    /// collection metadata and recomputing the checksum with the same
+    /// algorithm.
+    pub fn redact(&self, private_fields: &[impl AsRef<str>]) -> Result<Self> {
+        let report = self.report.redact(private_fields)?;
+        Ok(Self {
+            checksum: self.algorithm().unwrap_or_default().checksum(&report),
+            collected_at: self.collected_at,
+            collector_version: self.collector_version.clone(),
+            report,
+        })
+    }
+}
+
+/// Digest used for `StoredReport::checksum` (`report.checksum_algorithm`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumAlgorithm {
+    #[default]
+    Sha256,
+    /// For FIPS environments that require SHA-512.
+    Sha512,
+    /// Faster on large reports.
+    Blake3,
+}
+
+impl ChecksumAlgorithm {
+    /// The checksum prefix, also the config spelling.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+            Self::Blake3 => "blake3",
+        }
+    }
+
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        [Self::Sha256, Self::Sha512, Self::Blake3]
+            .into_iter()
+            .find(|a| a.prefix() == prefix)
+    }
+
+    /// "<prefix>:<hex>" over the report's JSON serialization.
+    pub fn checksum(self, report: &NodeReport) -> String {
+        let serialized = serde_json::to_string(report).unwrap_or_default();
+        let bytes = serialized.as_bytes();
+        let hex = match self {
+            Self::Sha256 => format!("{:x}", Sha256::digest(bytes)),
+            Self::Sha512 => format!("{:x}", Sha512::digest(bytes)),
+            Self::Blake3 => blake3::hash(bytes).to_hex().to_string(),
+        };
+        format!("{}:{}", self.prefix(), hex)
+    }
+}
+
+impl NodeReport {
+    /// The `n` slowest sections by collection time, slowest first.
+    pub fn slowest_sections(&self, n: usize) -> Vec<(&str, u64)> {
+        let mut sections: Vec<_> = self
+            .collection_durations_ms
+            .iter()
+            .map(|(name, ms)| (name.as_str(), *ms))
+            .collect();
+        sections.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        sections.truncate(n);
+        sections
+    }
+
+    /// Return a copy safe to share outside the fleet.
+    ///
+    /// Removes each dot-path in `private_fields` (same semantics as identity
+    /// redaction), then always strips process arguments and masks public
+    /// IP addresses. Private, loopback and link-local addresses are kept.
+    pub fn redact(&self, private_fields: &[impl AsRef<str>]) -> Result<Self> {
+        let mut val =
+            serde_yaml::to_value(self).context("failed to serialize report for redaction")?;
+        for field_path in private_fields {
+            remove_field_path(&mut val, field_path.as_ref());
+        }
+        let mut redacted: NodeReport =
+            serde_yaml::from_value(val).context("failed to deserialize redacted report")?;
+
+        let processes = &mut redacted.processes;
+        for p in processes
+            .top_cpu
+            .iter_mut()
+            .chain(processes.top_memory.iter_mut())
+        {
+            p.name = strip_process_args(&p.name);
+            p.program = strip_process_args(&p.program);
+        }
+        for usage in &mut processes.top_programs {
+            usage.program = strip_process_args(&usage.program);
+        }
+
+        let net = &mut redacted.network;
+        for iface in &mut net.interfaces {
+            for addr in &mut iface.addresses {
+                *addr = mask_public_address(addr);
+            }
+        }
+        for route in &mut net.routes {
+            route.destination = mask_public_address(&route.destination);
+            if let Some(ref mut gw) = route.gateway {
+                *gw = mask_public_address(gw);
+            }
+        }
+        if let Some(ref mut gw) = net.default_gateway {
+            *gw = mask_public_address(gw);
+        }
+        for resolver in &mut net.dns_resolvers {
+            *resolver = mask_public_address(resolver);
+        }
+        for probe in &mut net.dns_resolver_health {
+            probe.resolver = mask_public_address(&probe.resolver);
+        }
+        for port in &mut net.listening_ports {
+            if let Some(ref mut addr) = port.address {
+                *addr = mask_public_address(addr);
+            }
+        }
+
+        Ok(redacted)
+    }
+}
+
+/// Keep only the executable of a `ps` command line; arguments can carry tokens.
+fn strip_process_args(command: &str) -> String {
+    command.split_whitespace().next().unwrap_or("").to_string()
+}
+
+/// Replace a public IP (optionally with `/prefix`) by `redacted`, keeping the
+/// prefix length. Anything that isn't an IP address is returned unchanged.
+fn mask_public_address(addr: &str) -> String {
+    let (ip, prefix) = match addr.split_once('/') {
+        Some((ip, prefix)) => (ip, Some(prefix)),
+        None => (addr, None),
+    };
+    // Strip an IPv6 zone ("fe80::1%eth0") before parsing.
+    let Ok(parsed) = ip.split('%').next().unwrap_or(ip).parse::<std::net::IpAddr>() else {
+        return addr.to_string();
+    };
+    if !is_public_ip(&parsed) {
+        return addr.to_string();
+    }
+    match prefix {
+        Some(prefix) => format!("redacted/{}", prefix),
+        None => "redacted".to_string(),
+    }
+}
+
+fn is_public_ip(ip: &std::net::IpAddr) -> bool {
+    match ip {
+        std::net::IpAddr::V4(v4) => {
+            !(v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                // 100.64.0.0/10 carrier-grade NAT (Tailscale and friends)
+                || (v4.octets()[0] == 100 && (v4.octets()[1] & 0xc0) == 64))
+        }
+        std::net::IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || (first & 0xffc0) == 0xfe80 // link-local
+                || (first & 0xfe00) == 0xfc00) // unique local
+        }
     }
 }
 
@@ -71,6 +254,36 @@ pub struct NodeReport {
     pub health: HealthMetrics,
     pub security: SecuritySnapshot,
     pub processes: ProcessSnapshot,
+    /// Wall-clock time each section collector took, keyed by section name.
+    #[serde(default)]
+    pub collection_durations_ms: BTreeMap<String, u64>,
+    /// Sections whose collector failed, keyed by section name, with the
+    /// error. Those sections hold zeroed defaults, not real data.
+    #[serde(default)]
+    pub collection_errors: BTreeMap<String, String>,
+    /// Sub-probes that failed inside a section that was otherwise
+    /// collected, e.g. GPU detection within hardware.
+    #[serde(default)]
+    pub collection_warnings: Vec<CollectionWarning>,
+    /// Tags and labels declared in node.yaml, carried so the fleet
+    /// controller can filter nodes by them.
+    #[serde(default)]
+    pub declared: DeclaredLabels,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct DeclaredLabels {
+    /// `fleet.tags`.
+    pub tags: Vec<String>,
+    /// `kubernetes.node_labels`.
+    pub labels: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct CollectionWarning {
+    /// Section whose collector raised it, e.g. `hardware`.
+    pub section: String,
+    pub message: String,
 }
 
 // ── Hardware ───────────────────────────────────────────────
@@ -92,6 +305,20 @@ pub struct HardwareSnapshot {
     pub gpus: Vec<GpuSnapshot>,
     pub temperatures: Vec<TemperatureReading>,
     pub power: Option<PowerSnapshot>,
+    #[serde(default)]
+    pub pci_devices: Vec<PciDevice>,
+    #[serde(default)]
+    pub usb_devices: Vec<UsbDevice>,
+    /// Linux software RAID (md) arrays; empty without `/proc/mdstat`.
+    #[serde(default)]
+    pub raid_arrays: Vec<RaidArray>,
+    /// Memory limit of the cgroup the collector runs in (container or
+    /// pod); `None` when unlimited.
+    #[serde(default)]
+    pub cgroup_memory_limit_bytes: Option<u64>,
+    /// CPU quota of that cgroup in CPUs, e.g. 1.5; `None` when unlimited.
+    #[serde(default)]
+    pub cgroup_cpu_quota: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -104,6 +331,55 @@ pub struct DiskSnapshot {
     pub available_bytes: u64,
     #[serde(default)]
     pub smart_healthy: Option<bool>,
+    /// From `df -i`; `None` where the filesystem has no fixed inode table
+    /// (btrfs, ZFS).
+    #[serde(default)]
+    pub inodes_total: Option<u64>,
+    #[serde(default)]
+    pub inodes_used: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct PciDevice {
+    /// Bus address, e.g. "00:02.0".
+    pub slot: String,
+    /// Device class, e.g. "VGA compatible controller".
+    pub class: String,
+    pub vendor: String,
+    pub device: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct UsbDevice {
+    /// Hex vendor ID without prefix, e.g. "8087".
+    pub vendor_id: String,
+    /// Hex product ID without prefix, e.g. "0024".
+    pub product_id: String,
+    /// Vendor name, when the source reports it separately.
+    #[serde(default)]
+    pub vendor: Option<String>,
+    pub device: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct RaidArray {
+    /// Array device name, e.g. "md0".
+    pub name: String,
+    /// e.g. "raid1"; absent for inactive arrays.
+    #[serde(default)]
+    pub level: Option<String>,
+    /// "active" or "inactive", with any qualifier like "(auto-read-only)".
+    pub state: String,
+    /// Member partitions, e.g. "sda1"; failed ones keep their "(F)" mark.
+    pub devices: Vec<String>,
+    /// Per-member status from mdstat, e.g. "UU" or "U_U".
+    #[serde(default)]
+    pub member_status: Option<String>,
+    /// A member is missing or failed.
+    pub degraded: bool,
+    /// Running resync/recovery/check, e.g. "recovery 8.5%".
+    #[serde(default)]
+    pub sync_progress: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -155,6 +431,20 @@ pub struct OsSnapshot {
     pub is_wsl: bool,
     #[serde(default)]
     pub virtualization: Option<String>,
+    /// A newer kernel is installed than the one running (reboot pending).
+    #[serde(default)]
+    pub reboot_required: bool,
+    /// The system clock is synchronized to a time source. `None` when it
+    /// could not be determined.
+    #[serde(default)]
+    pub time_synced: Option<bool>,
+    /// Measured offset from NTP time in milliseconds; positive when the
+    /// local clock is ahead.
+    #[serde(default)]
+    pub clock_offset_ms: Option<f64>,
+    /// Bits in the kernel entropy pool (Linux only).
+    #[serde(default)]
+    pub entropy_available: Option<u64>,
 }
 
 // ── Network ────────────────────────────────────────────────
@@ -168,6 +458,30 @@ pub struct NetworkSnapshot {
     #[serde(default)]
     pub default_gateway: Option<String>,
     pub listening_ports: Vec<ListeningPort>,
+    /// Result of probing each resolver with an A-record lookup.
+    #[serde(default)]
+    pub dns_resolver_health: Vec<ResolverHealth>,
+    /// Socket counts by state, for spotting leaks and TIME_WAIT storms.
+    #[serde(default)]
+    pub connection_summary: ConnectionSummary,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct ConnectionSummary {
+    /// TCP sockets keyed by netstat-style state ("ESTABLISHED",
+    /// "TIME_WAIT", …).
+    pub tcp_states: BTreeMap<String, u32>,
+    pub udp_sockets: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct ResolverHealth {
+    pub resolver: String,
+    pub ok: bool,
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    #[serde(default)]
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -223,6 +537,44 @@ pub struct NixSnapshot {
     pub trusted_users: Vec<String>,
     pub max_jobs: Option<String>,
     pub sandbox_enabled: bool,
+    /// Flake the current system was built from (best-effort); `None` when
+    /// the generated flake has changed since `kindling apply` switched to it.
+    #[serde(default)]
+    pub system_flake: Option<FlakeInfo>,
+    /// Total size of the flake evaluation cache (`~/.cache/nix/eval-cache-*`).
+    #[serde(default)]
+    pub eval_cache_size_bytes: Option<u64>,
+    /// `eval-cache` setting from nix config.
+    #[serde(default)]
+    pub eval_cache_enabled: Option<bool>,
+    /// Last error lines from the nix-daemon log over the past hour.
+    #[serde(default)]
+    pub recent_nix_errors: Vec<String>,
+    /// Whether the config source repo (the system flake dir, else
+    /// `/etc/nixos`) has uncommitted changes; `None` when it isn't a git repo.
+    #[serde(default)]
+    pub config_source_dirty: Option<bool>,
+    /// `HEAD` commit of the config source repo.
+    #[serde(default)]
+    pub config_source_rev: Option<String>,
+    /// Derivations being built right now (store names without the hash),
+    /// at most 20. Empty when nothing is building or it can't be determined.
+    #[serde(default)]
+    pub active_builds: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct FlakeInfo {
+    /// Resolved flake URL (e.g. "path:/root/.config/kindling/generated").
+    pub url: String,
+    /// Locked revision of the flake, when it is a git flake.
+    #[serde(default)]
+    pub locked_rev: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<DateTime<Utc>>,
+    /// `configurationRevision` baked into the running system, if set.
+    #[serde(default)]
+    pub configuration_revision: Option<String>,
 }
 
 // ── Kubernetes ─────────────────────────────────────────────
@@ -243,6 +595,11 @@ pub struct K8sSnapshot {
     pub flux_installed: Option<bool>,
     #[serde(default)]
     pub helm_releases: Option<u32>,
+    /// Disk used by the container runtime's image filesystem.
+    #[serde(default)]
+    pub image_store_bytes: Option<u64>,
+    #[serde(default)]
+    pub image_count: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -260,18 +617,69 @@ pub struct HealthMetrics {
     pub load_average_1m: f64,
     pub load_average_5m: f64,
     pub load_average_15m: f64,
+    /// 1-minute load average divided by `hardware.cpu_cores`, so load is
+    /// comparable across machines. 0 when the core count is unknown.
+    #[serde(default)]
+    pub load_per_core: f64,
     pub memory_usage_percent: f64,
     pub swap_usage_percent: f64,
     pub cpu_usage_percent: f64,
     pub disk_usage: Vec<DiskUsage>,
     pub open_file_descriptors: Option<u64>,
     pub max_file_descriptors: Option<u64>,
+    /// OOM-killer events in the last hour (Linux only).
+    #[serde(default)]
+    pub recent_oom_kills: u32,
+    /// Process names killed by the OOM killer in the last hour.
+    #[serde(default)]
+    pub oom_victims: Vec<String>,
+    /// Service units in a failed state (systemd), or launchd jobs whose
+    /// last exit status was nonzero (macOS).
+    #[serde(default)]
+    pub failed_units: Vec<String>,
+    /// Number of loaded launchd jobs (macOS only).
+    #[serde(default)]
+    pub loaded_services: Option<u32>,
+    /// Service manager reports degraded, or any unit has failed.
+    #[serde(default)]
+    pub degraded: bool,
+    /// Pressure stall information from `/proc/pressure/*` (Linux 4.20+).
+    /// `None` where the kernel doesn't expose PSI.
+    #[serde(default)]
+    pub memory_pressure: Option<PressureStall>,
+    #[serde(default)]
+    pub cpu_pressure: Option<PressureStall>,
+    #[serde(default)]
+    pub io_pressure: Option<PressureStall>,
+}
+
+/// Share of wall time, in percent, that tasks were stalled waiting on a
+/// resource. Rising stall time predicts thrashing and OOM well before usage
+/// reaches 100%.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct PressureStall {
+    /// At least one task stalled.
+    pub some: StallAverages,
+    /// All non-idle tasks stalled at once. Absent for CPU on older kernels.
+    #[serde(default)]
+    pub full: Option<StallAverages>,
+}
+
+/// Stall percentages averaged over 10 s, 60 s and 300 s.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct StallAverages {
+    pub avg10: f64,
+    pub avg60: f64,
+    pub avg300: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct DiskUsage {
     pub mount_point: String,
     pub usage_percent: f64,
+    /// Inodes in use, as a percentage; `None` when `df -i` has no figure.
+    #[serde(default)]
+    pub inode_percent: Option<f64>,
 }
 
 // ── Processes ──────────────────────────────────────────────
@@ -283,12 +691,41 @@ pub struct ProcessSnapshot {
     pub zombie_processes: u32,
     pub top_cpu: Vec<ProcessInfo>,
     pub top_memory: Vec<ProcessInfo>,
+    /// Processes summed per program, busiest CPU first. Empty when
+    /// `report.group_processes` is off.
+    #[serde(default)]
+    pub top_programs: Vec<ProgramUsage>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct ProcessInfo {
     pub pid: u32,
+    /// Full command line.
     pub name: String,
+    /// Friendly program name: the command's basename with any Nix store
+    /// hash and wrapper naming stripped, e.g. `python3.11`.
+    #[serde(default)]
+    pub program: String,
+    pub cpu_percent: f64,
+    pub memory_percent: f64,
+}
+
+impl ProcessInfo {
+    /// What to show for this process: the program name where known.
+    pub fn display_name(&self) -> &str {
+        if self.program.is_empty() {
+            &self.name
+        } else {
+            &self.program
+        }
+    }
+}
+
+/// Every process of one program taken together.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct ProgramUsage {
+    pub program: String,
+    pub processes: u32,
     pub cpu_percent: f64,
     pub memory_percent: f64,
 }
@@ -297,6 +734,7 @@ pub struct ProcessInfo {
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct SecuritySnapshot {
+    #[serde(default)]
     pub ssh_keys_deployed: Vec<String>,
     pub tls_certificates: Vec<CertStatus>,
     pub firewall_active: bool,
@@ -306,6 +744,43 @@ pub struct SecuritySnapshot {
     pub sshd_running: bool,
     pub root_login_allowed: bool,
     pub password_auth_enabled: bool,
+    /// 0–100 posture summary computed from the fields above.
+    #[serde(default)]
+    pub security_score: u8,
+    /// Issues that lowered `security_score`.
+    #[serde(default)]
+    pub findings: Vec<SecurityFinding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct SecurityFinding {
+    pub severity: FindingSeverity,
+    /// What raised the finding, for consumers that act on it.
+    #[serde(default)]
+    pub kind: FindingKind,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+    /// Host hardening checks behind the security score.
+    #[default]
+    Posture,
+    /// A listener missing from the firewall allowlist.
+    FirewallExposure,
+    /// An allowlisted port nothing listens on.
+    FirewallStaleRule,
+    /// Secrets no longer encrypted to the deployed age identity.
+    AgeRotation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingSeverity {
+    Low,
+    Medium,
+    High,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -320,10 +795,10 @@ pub struct CertStatus {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
-    fn make_test_report() -> NodeReport {
+    pub(crate) fn make_test_report() -> NodeReport {
         NodeReport {
             timestamp: Utc::now(),
             daemon_version: "0.3.0".to_string(),
@@ -344,6 +819,11 @@ mod tests {
                 gpus: vec![],
                 temperatures: vec![],
                 power: None,
+                pci_devices: vec![],
+                usb_devices: vec![],
+                raid_arrays: vec![],
+                cgroup_memory_limit_bytes: None,
+                cgroup_cpu_quota: None,
             },
             os: OsSnapshot {
                 distribution: "NixOS".to_string(),
@@ -360,6 +840,10 @@ mod tests {
                 timezone: None,
                 is_wsl: false,
                 virtualization: None,
+                reboot_required: false,
+                time_synced: Some(true),
+                clock_offset_ms: Some(0.4),
+                entropy_available: Some(256),
             },
             network: NetworkSnapshot {
                 hostname: "test-node".to_string(),
@@ -368,6 +852,8 @@ mod tests {
                 dns_resolvers: vec![],
                 default_gateway: None,
                 listening_ports: vec![],
+                dns_resolver_health: vec![],
+                connection_summary: ConnectionSummary::default(),
             },
             nix: NixSnapshot {
                 nix_version: "2.24.12".to_string(),
@@ -382,18 +868,34 @@ mod tests {
                 trusted_users: vec!["root".to_string()],
                 max_jobs: None,
                 sandbox_enabled: true,
+                system_flake: None,
+                eval_cache_size_bytes: None,
+                eval_cache_enabled: None,
+                recent_nix_errors: vec![],
+                config_source_dirty: None,
+                config_source_rev: None,
+                active_builds: Vec::new(),
             },
             kubernetes: None,
             health: HealthMetrics {
                 load_average_1m: 0.5,
                 load_average_5m: 0.3,
                 load_average_15m: 0.2,
+                load_per_core: 0.125,
                 memory_usage_percent: 50.0,
                 swap_usage_percent: 0.0,
                 cpu_usage_percent: 10.0,
                 disk_usage: vec![],
                 open_file_descriptors: None,
                 max_file_descriptors: None,
+                recent_oom_kills: 0,
+                oom_victims: vec![],
+                failed_units: vec![],
+                loaded_services: None,
+                degraded: false,
+                memory_pressure: None,
+                cpu_pressure: None,
+                io_pressure: None,
             },
             security: SecuritySnapshot {
                 ssh_keys_deployed: vec![],
@@ -404,6 +906,8 @@ mod tests {
                 sshd_running: true,
                 root_login_allowed: false,
                 password_auth_enabled: false,
+                security_score: 0,
+                findings: vec![],
             },
             processes: ProcessSnapshot {
                 total_processes: 100,
@@ -411,10 +915,31 @@ mod tests {
                 zombie_processes: 0,
                 top_cpu: vec![],
                 top_memory: vec![],
+                top_programs: vec![],
             },
+            collection_durations_ms: BTreeMap::new(),
+            collection_errors: BTreeMap::new(),
+            collection_warnings: Vec::new(),
+            declared: DeclaredLabels::default(),
         }
     }
 
+    #[test]
+    fn slowest_sections_sorted_descending() {
+        let mut report = make_test_report();
+        report.collection_durations_ms = BTreeMap::from([
+            ("hardware".to_string(), 120),
+            ("nix".to_string(), 2300),
+            ("os".to_string(), 15),
+            ("network".to_string(), 900),
+        ]);
+        assert_eq!(
+            report.slowest_sections(2),
+            vec![("nix", 2300), ("network", 900)]
+        );
+        assert_eq!(report.slowest_sections(10).len(), 4);
+    }
+
     #[test]
     fn stored_report_new_computes_checksum() {
         let report = make_test_report();
@@ -446,6 +971,53 @@ mod tests {
         assert!(!stored.verify(), "wrong checksum should fail verification");
     }
 
+    #[test]
+    fn stored_report_blake3_verifies() {
+        let stored = StoredReport::with_algorithm(make_test_report(), ChecksumAlgorithm::Blake3);
+        assert!(stored.checksum.starts_with("blake3:"));
+        assert_eq!(stored.checksum.len(), "blake3:".len() + 64);
+        assert_eq!(stored.algorithm(), Some(ChecksumAlgorithm::Blake3));
+        assert!(stored.verify());
+
+        let json = serde_json::to_string(&stored).unwrap();
+        let roundtrip: StoredReport = serde_json::from_str(&json).unwrap();
+        assert!(roundtrip.verify());
+    }
+
+    #[test]
+    fn stored_report_sha512_verifies() {
+        let stored = StoredReport::with_algorithm(make_test_report(), ChecksumAlgorithm::Sha512);
+        assert_eq!(stored.checksum.len(), "sha512:".len() + 128);
+        assert!(stored.verify());
+    }
+
+    #[test]
+    fn stored_report_cross_algorithm_verify_fails() {
+        let blake3 = StoredReport::with_algorithm(make_test_report(), ChecksumAlgorithm::Blake3);
+        let hex = blake3.checksum.strip_prefix("blake3:").unwrap().to_string();
+
+        let mut relabeled = blake3.clone();
+        relabeled.checksum = format!("sha256:{}", hex);
+        assert!(!relabeled.verify());
+
+        let mut unknown = blake3.clone();
+        unknown.checksum = format!("md5:{}", hex);
+        assert_eq!(unknown.algorithm(), None);
+        assert!(!unknown.verify());
+
+        let mut bare = blake3;
+        bare.checksum = hex;
+        assert!(!bare.verify());
+    }
+
+    #[test]
+    fn stored_report_redact_keeps_algorithm() {
+        let stored = StoredReport::with_algorithm(make_test_report(), ChecksumAlgorithm::Blake3);
+        let redacted = stored.redact(&["security.ssh_keys_deployed"]).unwrap();
+        assert!(redacted.checksum.starts_with("blake3:"));
+        assert!(redacted.verify());
+    }
+
     #[test]
     fn stored_report_age_is_non_negative() {
         let report = make_test_report();
@@ -496,4 +1068,105 @@ mod tests {
         let s2 = StoredReport::new(r2);
         assert_ne!(s1.checksum, s2.checksum);
     }
+
+    // ── redaction tests ──────────────────────────────
+
+    fn report_with_sensitive_data() -> NodeReport {
+        let mut report = make_test_report();
+        report.security.ssh_keys_deployed = vec!["alice@laptop".to_string()];
+        report.processes.top_cpu = vec![ProcessInfo {
+            pid: 42,
+            name: "/usr/bin/curl -H Authorization:secret-token https://api".to_string(),
+            program: "curl".to_string(),
+            cpu_percent: 12.0,
+            memory_percent: 1.0,
+        }];
+        // Older collectors and foreign reports may carry a whole command
+        // line as the program name.
+        report.processes.top_programs = vec![ProgramUsage {
+            program: "backup.sh --password=hunter2".to_string(),
+            processes: 1,
+            cpu_percent: 3.0,
+            memory_percent: 0.5,
+        }];
+        report.network.interfaces = vec![InterfaceSnapshot {
+            name: "eth0".to_string(),
+            state: "up".to_string(),
+            addresses: vec![
+                "203.0.113.7/24".to_string(),
+                "192.168.1.10/24".to_string(),
+                "2001:db8::1/64".to_string(),
+                "fe80::1/64".to_string(),
+            ],
+            mac: None,
+            mtu: None,
+            rx_bytes: 0,
+            tx_bytes: 0,
+            speed_mbps: None,
+            interface_type: None,
+        }];
+        report.network.default_gateway = Some("203.0.113.1".to_string());
+        report.network.dns_resolvers = vec!["1.1.1.1".to_string(), "10.0.0.53".to_string()];
+        report
+    }
+
+    #[test]
+    fn redact_removes_and_masks_sensitive_fields() {
+        let report = report_with_sensitive_data();
+        let redacted = report.redact(&["security.ssh_keys_deployed"]).unwrap();
+
+        assert!(redacted.security.ssh_keys_deployed.is_empty());
+        assert_eq!(redacted.processes.top_cpu[0].name, "/usr/bin/curl");
+        assert_eq!(redacted.processes.top_cpu[0].program, "curl");
+        assert_eq!(redacted.processes.top_programs[0].program, "backup.sh");
+        assert_eq!(
+            redacted.network.interfaces[0].addresses,
+            vec!["redacted/24", "192.168.1.10/24", "redacted/64", "fe80::1/64"]
+        );
+        assert_eq!(redacted.network.default_gateway.as_deref(), Some("redacted"));
+        assert_eq!(redacted.network.dns_resolvers, vec!["redacted", "10.0.0.53"]);
+        assert_eq!(redacted.hostname, report.hostname);
+
+        let json = serde_json::to_string(&redacted).unwrap();
+        assert!(!json.contains("alice@laptop"));
+        assert!(!json.contains("secret-token"));
+        assert!(!json.contains("hunter2"));
+        assert!(!json.contains("203.0.113"));
+        let back: NodeReport = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.hostname, "test-node");
+    }
+
+    #[test]
+    fn redact_wildcard_path_over_lists() {
+        let mut report = make_test_report();
+        report.network.listening_ports = vec![ListeningPort {
+            port: 22,
+            protocol: "tcp".to_string(),
+            address: Some("0.0.0.0".to_string()),
+            process: Some("sshd".to_string()),
+        }];
+        let redacted = report.redact(&["network.listening_ports.*.process"]).unwrap();
+        assert_eq!(redacted.network.listening_ports[0].port, 22);
+        assert!(redacted.network.listening_ports[0].process.is_none());
+    }
+
+    #[test]
+    fn stored_report_redact_recomputes_checksum() {
+        let stored = StoredReport::new(report_with_sensitive_data());
+        let redacted = stored.redact(&["security.ssh_keys_deployed"]).unwrap();
+        assert_ne!(redacted.checksum, stored.checksum);
+        assert_eq!(redacted.collected_at, stored.collected_at);
+        assert!(redacted.verify());
+    }
+
+    #[test]
+    fn mask_public_address_cases() {
+        assert_eq!(mask_public_address("8.8.8.8"), "redacted");
+        assert_eq!(mask_public_address("172.16.5.4/12"), "172.16.5.4/12");
+        assert_eq!(mask_public_address("100.100.1.1"), "100.100.1.1");
+        assert_eq!(mask_public_address("127.0.0.1"), "127.0.0.1");
+        assert_eq!(mask_public_address("fd00::1"), "fd00::1");
+        assert_eq!(mask_public_address("fe80::1%eth0"), "fe80::1%eth0");
+        assert_eq!(mask_public_address("default"), "default");
+    }
 }
@@ -5,34 +5,54 @@
 //!
 //! API endpoints read ONLY from the memory cache and never trigger discovery.
 //! `refresh()` drives the full pipeline: collect → store → cache.
+//! Concurrent refreshes are coalesced: callers that arrive while a collection
+//! is in flight wait for it and share its result.
 
+use std::future::Future;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 
-use anyhow::Result;
-use tokio::sync::RwLock;
+use anyhow::{bail, Context, Result};
+use tokio::sync::{Mutex, RwLock};
 use tracing::{info, warn};
 
 use crate::config::{IdentityConfig, ReportConfig};
-use crate::node_identity::NodeIdentity;
+use crate::node_identity::{resolve_extends, set_field_path, NodeIdentity};
+use crate::paths::expand_path;
 
-use super::node_report::StoredReport;
-use super::report_collector::ReportCollector;
+use super::node_report::{NodeReport, StoredReport};
+use super::report_collector::{CollectorRegistry, ReportCollector};
 use super::report_store::ReportStore;
+use super::store_trend;
 
 pub struct NodeService {
     identity: RwLock<Option<NodeIdentity>>,
+    /// Base node.yaml the identity is loaded from and edits are saved to.
+    identity_path: PathBuf,
     cache: RwLock<Option<StoredReport>>,
     store: ReportStore,
     identity_config: IdentityConfig,
     report_config: ReportConfig,
+    /// Held for the duration of a refresh; serializes collections.
+    refresh_lock: Mutex<()>,
+    /// Bumped after every successful refresh.
+    refresh_generation: AtomicU64,
 }
 
 impl NodeService {
     /// Create a new NodeService, loading identity (with overlays) and
     /// populating the memory cache from the persisted report file if valid.
     pub fn new(identity_config: IdentityConfig, report_config: ReportConfig) -> Self {
+        Self::with_identity_path(identity_config, report_config, NodeIdentity::default_path())
+    }
+
+    /// Like [`NodeService::new`], with node.yaml at `base_path`.
+    pub fn with_identity_path(
+        identity_config: IdentityConfig,
+        report_config: ReportConfig,
+        base_path: PathBuf,
+    ) -> Self {
         // Load identity with overlay support
-        let base_path = NodeIdentity::default_path();
         let identity = if base_path.exists() {
             match NodeIdentity::load_with_overlays(&base_path, &identity_config.overlay_dirs) {
                 Ok(id) => {
@@ -48,17 +68,30 @@ impl NodeService {
             None
         };
 
-        let store = ReportStore::new(PathBuf::from(&report_config.cache_file));
+        let store = ReportStore::new(expand_path(&report_config.cache_file));
 
         Self {
             identity: RwLock::new(identity),
+            identity_path: base_path,
             cache: RwLock::new(None),
             store,
             identity_config,
             report_config,
+            refresh_lock: Mutex::new(()),
+            refresh_generation: AtomicU64::new(0),
         }
     }
 
+    /// Verify the report cache location is writable (startup).
+    pub async fn check_cache_writable(&self) -> Result<()> {
+        self.store.probe_writable().await.with_context(|| {
+            format!(
+                "report cache {} is not writable",
+                self.store.path().display()
+            )
+        })
+    }
+
     /// Load the persisted report from disk into the memory cache (startup).
     ///
     /// If the file exists and the checksum verifies, the cache is populated.
@@ -90,9 +123,33 @@ impl NodeService {
     /// 1. Collect a fresh report via ReportCollector
     /// 2. Write the StoredReport to disk (atomic, hash-verified)
     /// 3. Update the in-memory cache
+    ///
+    /// If another refresh completes while this call is waiting, its result is
+    /// returned instead of collecting again. A failed refresh publishes
+    /// nothing, so waiters fall through and collect themselves.
     pub async fn refresh(&self) -> Result<StoredReport> {
-        let report = ReportCollector::collect().await?;
-        let stored = StoredReport::new(report);
+        let identity = self.identity.read().await.clone();
+        self.refresh_with(|| ReportCollector::collect(&self.report_config, identity.as_ref()))
+            .await
+    }
+
+    async fn refresh_with<F, Fut>(&self, collect: F) -> Result<StoredReport>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<NodeReport>>,
+    {
+        let requested_at = self.refresh_generation.load(Ordering::Acquire);
+        let _guard = self.refresh_lock.lock().await;
+
+        if self.refresh_generation.load(Ordering::Acquire) != requested_at {
+            if let Some(stored) = self.cache.read().await.clone() {
+                return Ok(stored);
+            }
+        }
+
+        let report = collect().await?;
+        let stored =
+            StoredReport::with_algorithm(report, self.report_config.checksum_algorithm);
 
         // Write to file store
         self.store.write(&stored).await?;
@@ -101,12 +158,58 @@ impl NodeService {
             "report written to disk"
         );
 
+        self.record_store_sample(&stored.report);
+
         // Update memory cache
         *self.cache.write().await = Some(stored.clone());
+        self.refresh_generation.fetch_add(1, Ordering::Release);
+
+        Ok(stored)
+    }
+
+    /// Re-collect only the named sections (e.g. "health", "network") and
+    /// merge them into the cached report, skipping the expensive ones.
+    /// Falls back to a full refresh when nothing is cached yet.
+    pub async fn refresh_sections(&self, sections: &[String]) -> Result<StoredReport> {
+        let mut registry = CollectorRegistry::platform(&self.report_config).only(sections)?;
+        if let Some(identity) = self.identity.read().await.as_ref() {
+            registry = registry.with_ntp_servers(&identity.network.ntp_servers);
+        }
+        self.refresh_partial_with(&registry).await
+    }
+
+    async fn refresh_partial_with(&self, registry: &CollectorRegistry) -> Result<StoredReport> {
+        let guard = self.refresh_lock.lock().await;
+        let Some(current) = self.cache.read().await.clone() else {
+            drop(guard);
+            return self.refresh().await;
+        };
 
+        let mut report = current.report;
+        let identity = self.identity.read().await.clone();
+        ReportCollector::refresh_into(registry, &mut report, identity.as_ref()).await;
+        let stored =
+            StoredReport::with_algorithm(report, self.report_config.checksum_algorithm);
+        self.store.write(&stored).await?;
+        info!(
+            checksum = %stored.checksum,
+            sections = ?registry.names(),
+            "partial report written to disk"
+        );
+        // The generation is left alone: callers waiting on a full refresh
+        // must not be handed a partially refreshed report.
+        *self.cache.write().await = Some(stored.clone());
         Ok(stored)
     }
 
+    /// Best-effort store growth sample; never fails a refresh.
+    fn record_store_sample(&self, report: &NodeReport) {
+        let path = store_trend::samples_path(self.store.path());
+        if let Err(e) = store_trend::record(&path, report) {
+            warn!(error = %e, "failed to record store trend sample");
+        }
+    }
+
     /// Get the cached StoredReport from memory. Never triggers discovery.
     pub async fn cached_report(&self) -> Option<StoredReport> {
         self.cache.read().await.clone()
@@ -139,16 +242,196 @@ impl NodeService {
 
     /// Reload identity from disk, re-applying overlays.
     pub async fn reload_identity(&self) -> Result<()> {
-        let base_path = NodeIdentity::default_path();
-        let identity =
-            NodeIdentity::load_with_overlays(&base_path, &self.identity_config.overlay_dirs)?;
+        let identity = NodeIdentity::load_with_overlays(
+            &self.identity_path,
+            &self.identity_config.overlay_dirs,
+        )?;
         *self.identity.write().await = Some(identity);
         info!("reloaded node identity with overlays");
         Ok(())
     }
 
+    /// Set the dot-path `field` of node.yaml to `value` (parsed as YAML),
+    /// save the file and reload the identity. The edit is rejected, and the
+    /// file left alone, if the result is no longer a valid `NodeIdentity`.
+    pub async fn set_identity_field(&self, field: &str, value: &str) -> Result<NodeIdentity> {
+        // Held across read-modify-write so concurrent edits don't lose each other.
+        let mut current = self.identity.write().await;
+        let path = &self.identity_path;
+        if !path.exists() {
+            bail!("no node identity at {}", path.display());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read node identity from {}", path.display()))?;
+        let mut doc: serde_yaml::Value = serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse node identity from {}", path.display()))?;
+        let value: serde_yaml::Value = serde_yaml::from_str(value)
+            .with_context(|| format!("invalid YAML value for {}", field))?;
+        set_field_path(&mut doc, field, value)?;
+        serde_yaml::from_value::<NodeIdentity>(resolve_extends(doc.clone(), path)?)
+            .with_context(|| format!("setting {} would make node.yaml invalid", field))?;
+
+        let content =
+            serde_yaml::to_string(&doc).context("failed to serialize node identity to YAML")?;
+        // tmp + rename, so a crash mid-write can't leave node.yaml truncated.
+        let tmp_path = path.with_extension("yaml.tmp");
+        tokio::fs::write(&tmp_path, content)
+            .await
+            .with_context(|| format!("writing temp file {}", tmp_path.display()))?;
+        tokio::fs::rename(&tmp_path, path)
+            .await
+            .with_context(|| format!("renaming {} to {}", tmp_path.display(), path.display()))?;
+        let identity = NodeIdentity::load_with_overlays(path, &self.identity_config.overlay_dirs)?;
+        *current = Some(identity.clone());
+        info!(field, "updated node identity field");
+        Ok(identity)
+    }
+
     /// Get the report config (for use by server loop).
     pub fn report_config(&self) -> &ReportConfig {
         &self.report_config
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::node_report::tests::make_test_report;
+    use crate::domain::report_collector::{Section, SectionCollector, SectionFuture};
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn make_service(dir: &tempfile::TempDir) -> Arc<NodeService> {
+        let report_config = ReportConfig {
+            cache_file: dir.path().join("report.json").to_string_lossy().into_owned(),
+            ..ReportConfig::default()
+        };
+        Arc::new(NodeService::new(IdentityConfig::default(), report_config))
+    }
+
+    #[tokio::test]
+    async fn concurrent_refreshes_share_one_collection() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = make_service(&dir);
+        let collections = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let service = Arc::clone(&service);
+                let collections = Arc::clone(&collections);
+                tokio::spawn(async move {
+                    service
+                        .refresh_with(|| async move {
+                            collections.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(100)).await;
+                            Ok(make_test_report())
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        let mut checksums = Vec::new();
+        for handle in handles {
+            checksums.push(handle.await.unwrap().unwrap().checksum);
+        }
+
+        assert_eq!(collections.load(Ordering::SeqCst), 1);
+        assert!(checksums.windows(2).all(|w| w[0] == w[1]));
+    }
+
+    #[tokio::test]
+    async fn failed_refresh_does_not_poison_next() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = make_service(&dir);
+
+        let failed = service
+            .refresh_with(|| async { anyhow::bail!("collector exploded") })
+            .await;
+        assert!(failed.is_err());
+        assert!(service.cached_report().await.is_none());
+
+        let stored = service
+            .refresh_with(|| async { Ok(make_test_report()) })
+            .await
+            .unwrap();
+        assert_eq!(service.cached_report().await.unwrap().checksum, stored.checksum);
+    }
+
+    #[tokio::test]
+    async fn sequential_refreshes_collect_each_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = make_service(&dir);
+        let collections = AtomicUsize::new(0);
+
+        for _ in 0..3 {
+            service
+                .refresh_with(|| async {
+                    collections.fetch_add(1, Ordering::SeqCst);
+                    Ok(make_test_report())
+                })
+                .await
+                .unwrap();
+        }
+        assert_eq!(collections.load(Ordering::SeqCst), 3);
+    }
+
+    struct FakeHealth;
+
+    impl SectionCollector for FakeHealth {
+        fn name(&self) -> &'static str {
+            "health"
+        }
+        fn collect(&self) -> SectionFuture<'_> {
+            Box::pin(async {
+                let mut health = make_test_report().health;
+                health.load_average_1m = 7.5;
+                Ok(Section::Health(health))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn partial_refresh_only_touches_named_sections() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = make_service(&dir);
+        let before = service
+            .refresh_with(|| async { Ok(make_test_report()) })
+            .await
+            .unwrap();
+
+        let after = service
+            .refresh_partial_with(&CollectorRegistry::new().with(FakeHealth))
+            .await
+            .unwrap();
+
+        assert_eq!(after.report.health.load_average_1m, 7.5);
+        assert_ne!(after.checksum, before.checksum);
+        assert_eq!(
+            serde_json::to_value(&after.report.hardware).unwrap(),
+            serde_json::to_value(&before.report.hardware).unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&after.report.nix).unwrap(),
+            serde_json::to_value(&before.report.nix).unwrap()
+        );
+        assert_eq!(after.report.os.hostname, before.report.os.hostname);
+        assert!(after.report.collection_durations_ms.contains_key("health"));
+        // Cache and disk both carry the merged report.
+        assert_eq!(service.cached_report().await.unwrap().checksum, after.checksum);
+        assert_eq!(service.store.read().await.unwrap().checksum, after.checksum);
+    }
+
+    #[tokio::test]
+    async fn partial_refresh_rejects_unknown_section() {
+        let dir = tempfile::tempdir().unwrap();
+        let service = make_service(&dir);
+        let err = service
+            .refresh_sections(&["health".to_string(), "bogus".to_string()])
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown report section 'bogus'"));
+        assert!(service.cached_report().await.is_none());
+    }
+}
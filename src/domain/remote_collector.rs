@@ -0,0 +1,392 @@
+//! Remote (agentless) report collection over SSH.
+//!
+//! Runs `kindling report --fresh --format json` on the target when kindling
+//! is installed there. Otherwise a fixed shell script dumps the raw inputs
+//! for the key sections (OS, memory, load, Nix) and the report is assembled
+//! locally; every other section keeps its zeroed default and is listed in
+//! `collection_errors`, as is any key section whose input came back empty.
+
+use std::collections::BTreeMap;
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use tokio::process::Command;
+
+use super::node_report::{NodeReport, StoredReport};
+use super::report_collector::{
+    default_hardware, default_health, default_network, default_nix, default_os,
+    default_processes, default_security, parse_meminfo_kb, parse_os_release_field,
+};
+use crate::commands::report::{EXIT_COMPLETE, EXIT_PARTIAL};
+use crate::node_identity::FleetPeer;
+
+/// Exit status the remote shell uses to signal that kindling is absent.
+const KINDLING_MISSING: i32 = 127;
+
+/// Runs kindling when present, otherwise exits 127 without output.
+const KINDLING_SCRIPT: &str = "command -v kindling >/dev/null 2>&1 || exit 127; \
+                               exec kindling report --fresh --format json";
+
+/// Raw inputs for the fallback path, one `@@<section>` marker per command.
+const RAW_SCRIPT: &str = "echo @@hostname; hostname; \
+                          echo @@uname; uname -rm; \
+                          echo @@os-release; cat /etc/os-release 2>/dev/null; \
+                          echo @@meminfo; cat /proc/meminfo 2>/dev/null; \
+                          echo @@loadavg; cat /proc/loadavg 2>/dev/null; \
+                          echo @@uptime; cat /proc/uptime 2>/dev/null; \
+                          echo @@nproc; nproc 2>/dev/null; \
+                          echo @@nix; nix --version 2>/dev/null; \
+                          echo @@current-system; readlink -f /run/current-system 2>/dev/null; \
+                          true";
+
+/// Report section each RAW_SCRIPT section feeds.
+const RAW_SECTION_TARGETS: &[(&str, &str)] = &[
+    ("hostname", "os"),
+    ("uname", "os"),
+    ("os-release", "os"),
+    ("uptime", "os"),
+    ("meminfo", "hardware"),
+    ("nproc", "hardware"),
+    ("loadavg", "health"),
+    ("nix", "nix"),
+    ("current-system", "nix"),
+];
+
+/// Report sections RAW_SCRIPT has no input for at all.
+const RAW_UNCOLLECTED: &[&str] = &["network", "processes", "security"];
+
+/// Collect a report from `target` (`user@host`, or a fleet peer name).
+pub async fn collect(target: &str, peers: &[FleetPeer]) -> Result<StoredReport> {
+    let destination = resolve_target(target, peers)?;
+
+    let output = run_ssh(&destination, KINDLING_SCRIPT).await?;
+    if printed_report(output.status.code(), &output.stdout) {
+        return serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("parsing kindling report from {}", destination));
+    }
+    if output.status.code() != Some(KINDLING_MISSING) {
+        bail!(
+            "remote kindling report on {} failed: {}",
+            destination,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    tracing::info!(%destination, "kindling not installed remotely; collecting raw sections");
+    let output = run_ssh(&destination, RAW_SCRIPT).await?;
+    if !output.status.success() {
+        bail!(
+            "raw collection on {} failed: {}",
+            destination,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(StoredReport::new(report_from_raw(&String::from_utf8_lossy(
+        &output.stdout,
+    ))))
+}
+
+/// Whether a remote `kindling report --fresh` run left a report on stdout:
+/// a complete one, or a partial one with some sections defaulted. Partial
+/// shares exit code 1 with every other error, which prints nothing.
+fn printed_report(code: Option<i32>, stdout: &[u8]) -> bool {
+    match code.and_then(|c| u8::try_from(c).ok()) {
+        Some(EXIT_COMPLETE) => true,
+        Some(EXIT_PARTIAL) => stdout.iter().any(|b| !b.is_ascii_whitespace()),
+        _ => false,
+    }
+}
+
+/// Map a fleet peer name to `ssh_user@hostname`; anything else is used as
+/// an SSH destination as-is.
+fn resolve_target(target: &str, peers: &[FleetPeer]) -> Result<String> {
+    if target.is_empty() || target.starts_with('-') {
+        bail!("invalid SSH destination '{}'", target);
+    }
+    Ok(peers
+        .iter()
+        .find(|p| p.name == target)
+        .map(|p| format!("{}@{}", p.ssh_user, p.hostname))
+        .unwrap_or_else(|| target.to_string()))
+}
+
+/// Non-interactive ssh argv; `--` keeps the destination from being read
+/// as an option.
+fn ssh_args(destination: &str, script: &str) -> Vec<String> {
+    vec![
+        "-o".to_string(),
+        "BatchMode=yes".to_string(),
+        "-o".to_string(),
+        "ConnectTimeout=10".to_string(),
+        "--".to_string(),
+        destination.to_string(),
+        script.to_string(),
+    ]
+}
+
+async fn run_ssh(destination: &str, script: &str) -> Result<std::process::Output> {
+    Command::new("ssh")
+        .args(ssh_args(destination, script))
+        .output()
+        .await
+        .with_context(|| format!("failed to SSH to {}", destination))
+}
+
+/// Split RAW_SCRIPT output into its `@@` sections.
+fn raw_sections(output: &str) -> std::collections::HashMap<&str, String> {
+    let mut sections = std::collections::HashMap::new();
+    let mut current: Option<(&str, String)> = None;
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("@@") {
+            if let Some((prev, body)) = current.take() {
+                sections.insert(prev, body);
+            }
+            current = Some((name.trim(), String::new()));
+        } else if let Some((_, ref mut body)) = current {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    if let Some((prev, body)) = current {
+        sections.insert(prev, body);
+    }
+    sections
+}
+
+/// `collection_errors` for a raw report: each report section whose raw
+/// inputs are missing or empty, plus the sections RAW_SCRIPT never collects.
+fn raw_collection_errors(
+    sections: &std::collections::HashMap<&str, String>,
+) -> BTreeMap<String, String> {
+    let mut missing: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (raw, target) in RAW_SECTION_TARGETS {
+        if sections.get(raw).is_none_or(|s| s.trim().is_empty()) {
+            missing.entry(target).or_default().push(raw);
+        }
+    }
+    let mut errors: BTreeMap<String, String> = missing
+        .into_iter()
+        .map(|(target, raws)| {
+            let reason = format!("no {} in raw SSH output", raws.join(", "));
+            (target.to_string(), reason)
+        })
+        .collect();
+    for section in RAW_UNCOLLECTED {
+        errors.insert(
+            section.to_string(),
+            "not collected over raw SSH".to_string(),
+        );
+    }
+    errors
+}
+
+/// Assemble a partial NodeReport from RAW_SCRIPT output.
+fn report_from_raw(output: &str) -> NodeReport {
+    let sections = raw_sections(output);
+    let section = |name: &str| sections.get(name).map(|s| s.trim()).unwrap_or("");
+
+    let hostname = match section("hostname") {
+        "" => "unknown".to_string(),
+        h => h.to_string(),
+    };
+    let mut uname = section("uname").split_whitespace();
+    let kernel = uname.next().unwrap_or("unknown").to_string();
+    let arch = uname.next().unwrap_or("unknown").to_string();
+
+    let mut os = default_os();
+    let os_release = section("os-release");
+    os.distribution = parse_os_release_field(os_release, "NAME").unwrap_or(os.distribution);
+    os.version = parse_os_release_field(os_release, "VERSION_ID").unwrap_or(os.version);
+    os.product_name = parse_os_release_field(os_release, "PRETTY_NAME");
+    os.kernel_version = kernel;
+    os.architecture = arch.clone();
+    os.hostname = hostname.clone();
+    os.uptime_secs = section("uptime")
+        .split_whitespace()
+        .next()
+        .and_then(|s| s.parse::<f64>().ok())
+        .map(|s| s as u64)
+        .unwrap_or(0);
+
+    let mut hardware = default_hardware();
+    let meminfo = section("meminfo");
+    hardware.cpu_architecture = arch;
+    hardware.cpu_threads = section("nproc").parse().unwrap_or(0);
+    hardware.ram_total_bytes = parse_meminfo_kb(meminfo, "MemTotal") * 1024;
+    hardware.ram_available_bytes = parse_meminfo_kb(meminfo, "MemAvailable") * 1024;
+    hardware.swap_total_bytes = parse_meminfo_kb(meminfo, "SwapTotal") * 1024;
+    hardware.swap_used_bytes = hardware
+        .swap_total_bytes
+        .saturating_sub(parse_meminfo_kb(meminfo, "SwapFree") * 1024);
+
+    let mut health = default_health();
+    let loads: Vec<f64> = section("loadavg")
+        .split_whitespace()
+        .take(3)
+        .filter_map(|s| s.parse().ok())
+        .collect();
+    if let [one, five, fifteen] = loads[..] {
+        health.load_average_1m = one;
+        health.load_average_5m = five;
+        health.load_average_15m = fifteen;
+    }
+    if hardware.ram_total_bytes > 0 {
+        health.memory_usage_percent = (hardware.ram_total_bytes - hardware.ram_available_bytes)
+            as f64
+            / hardware.ram_total_bytes as f64
+            * 100.0;
+    }
+
+    let mut nix = default_nix();
+    if let Some(version) = section("nix").split_whitespace().last() {
+        nix.nix_version = version.to_string();
+    }
+    nix.current_system_path = Some(section("current-system"))
+        .filter(|p| !p.is_empty())
+        .map(str::to_string);
+
+    let mut network = default_network();
+    network.hostname = hostname.clone();
+
+    NodeReport {
+        timestamp: Utc::now(),
+        daemon_version: "remote".to_string(),
+        hostname,
+        hardware,
+        os,
+        network,
+        nix,
+        kubernetes: None,
+        health,
+        security: default_security(),
+        processes: default_processes(),
+        collection_durations_ms: Default::default(),
+        collection_errors: raw_collection_errors(&sections),
+        collection_warnings: Vec::new(),
+        declared: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::report::EXIT_FAILED;
+
+    fn peer(name: &str, hostname: &str, user: &str) -> FleetPeer {
+        FleetPeer {
+            name: name.to_string(),
+            hostname: hostname.to_string(),
+            ssh_user: user.to_string(),
+        }
+    }
+
+    #[test]
+    fn ssh_args_are_non_interactive() {
+        assert_eq!(
+            ssh_args("deploy@10.0.0.7", KINDLING_SCRIPT),
+            vec![
+                "-o",
+                "BatchMode=yes",
+                "-o",
+                "ConnectTimeout=10",
+                "--",
+                "deploy@10.0.0.7",
+                KINDLING_SCRIPT,
+            ]
+        );
+        assert!(KINDLING_SCRIPT.contains("kindling report --fresh --format json"));
+        assert!(KINDLING_SCRIPT.contains("exit 127"));
+    }
+
+    #[test]
+    fn partial_remote_reports_are_still_parsed() {
+        let report = b"{\"checksum\": \"abc\"}\n";
+        assert!(printed_report(Some(0), report));
+        assert!(printed_report(Some(1), report));
+        // Exit 1 without output is an ordinary error.
+        assert!(!printed_report(Some(1), b"\n"));
+        assert!(!printed_report(Some(i32::from(EXIT_FAILED)), report));
+        assert!(!printed_report(None, report));
+    }
+
+    #[test]
+    fn resolve_target_uses_fleet_peers() {
+        let peers = vec![peer("edge-1", "10.0.0.7", "deploy")];
+        assert_eq!(resolve_target("edge-1", &peers).unwrap(), "deploy@10.0.0.7");
+        assert_eq!(
+            resolve_target("root@db.internal", &peers).unwrap(),
+            "root@db.internal"
+        );
+        assert!(resolve_target("-oProxyCommand=evil", &peers).is_err());
+        assert!(resolve_target("", &peers).is_err());
+    }
+
+    #[test]
+    fn report_from_raw_fills_key_sections() {
+        let output = "\
+@@hostname
+edge-1
+@@uname
+6.12.4 x86_64
+@@os-release
+NAME=NixOS
+VERSION_ID=\"25.05\"
+PRETTY_NAME=\"NixOS 25.05 (Warbler)\"
+@@meminfo
+MemTotal:       16000000 kB
+MemAvailable:    4000000 kB
+SwapTotal:       2000000 kB
+SwapFree:        1500000 kB
+@@loadavg
+0.52 0.41 0.30 1/612 12345
+@@uptime
+86400.25 170000.10
+@@nproc
+8
+@@nix
+nix (Nix) 2.24.10
+@@current-system
+/nix/store/z1y2-nixos-system-edge-1-25.05
+";
+        let report = report_from_raw(output);
+        assert_eq!(report.hostname, "edge-1");
+        assert_eq!(report.os.distribution, "NixOS");
+        assert_eq!(report.os.version, "25.05");
+        assert_eq!(report.os.kernel_version, "6.12.4");
+        assert_eq!(report.os.architecture, "x86_64");
+        assert_eq!(report.os.uptime_secs, 86400);
+        assert_eq!(report.hardware.cpu_threads, 8);
+        assert_eq!(report.hardware.ram_total_bytes, 16_000_000 * 1024);
+        assert_eq!(report.hardware.swap_used_bytes, 500_000 * 1024);
+        assert_eq!(report.health.load_average_5m, 0.41);
+        assert_eq!(report.health.memory_usage_percent, 75.0);
+        assert_eq!(report.nix.nix_version, "2.24.10");
+        assert_eq!(
+            report.nix.current_system_path.as_deref(),
+            Some("/nix/store/z1y2-nixos-system-edge-1-25.05")
+        );
+        assert_eq!(
+            report.collection_errors.keys().collect::<Vec<_>>(),
+            vec!["network", "processes", "security"]
+        );
+    }
+
+    #[test]
+    fn report_from_raw_tolerates_missing_sections() {
+        let report = report_from_raw("@@hostname\nmac-1\n@@uname\n@@nix\n");
+        assert_eq!(report.hostname, "mac-1");
+        assert_eq!(report.os.kernel_version, "unknown");
+        assert_eq!(report.nix.nix_version, "unknown");
+        assert!(report.nix.current_system_path.is_none());
+
+        let errors = &report.collection_errors;
+        assert_eq!(
+            errors["os"],
+            "no uname, os-release, uptime in raw SSH output"
+        );
+        assert_eq!(errors["hardware"], "no meminfo, nproc in raw SSH output");
+        assert_eq!(errors["health"], "no loadavg in raw SSH output");
+        assert_eq!(errors["nix"], "no nix, current-system in raw SSH output");
+        assert_eq!(errors["security"], "not collected over raw SSH");
+    }
+}
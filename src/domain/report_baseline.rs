@@ -0,0 +1,304 @@
+//! Report baselines — assert that a collected NodeReport meets expectations.
+//!
+//! A baseline is a small YAML file of expectations, each a field path into
+//! the serialized report, an operator, and a value:
+//!
+//! ```yaml
+//! expectations:
+//!   - path: security.firewall_active
+//!     op: eq
+//!     value: true
+//!   - path: network.listening_ports.*.port
+//!     op: contains
+//!     value: 22
+//!   - path: health.memory_usage_percent
+//!     op: lt
+//!     value: 90
+//! ```
+//!
+//! Path segments are object keys or array indices; `*` maps the rest of the
+//! path over every element of an array.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::node_report::NodeReport;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    #[serde(default)]
+    pub expectations: Vec<Expectation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Expectation {
+    /// Dot-separated path into the report, e.g. `security.firewall_active`.
+    pub path: String,
+    pub op: Operator,
+    /// Expected value. Ignored by `exists` / `absent`.
+    #[serde(default)]
+    pub value: Value,
+    /// Optional human-readable label shown instead of the raw expectation.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// Array contains the value, or string contains the substring.
+    Contains,
+    NotContains,
+    Exists,
+    Absent,
+}
+
+/// Outcome of evaluating a single expectation.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpectationResult {
+    pub expectation: Expectation,
+    pub passed: bool,
+    /// The value found at the path (`None` when the path doesn't resolve).
+    pub actual: Option<Value>,
+}
+
+impl Expectation {
+    /// Label for output: the description, or `path op value`.
+    pub fn label(&self) -> String {
+        if let Some(ref desc) = self.description {
+            return desc.clone();
+        }
+        match self.op {
+            Operator::Exists | Operator::Absent => format!("{} {:?}", self.path, self.op),
+            _ => format!("{} {:?} {}", self.path, self.op, self.value),
+        }
+    }
+
+    /// Evaluate against a report already serialized to JSON.
+    pub fn evaluate(&self, report: &Value) -> ExpectationResult {
+        let actual = lookup(report, &self.path);
+        let passed = match (&actual, self.op) {
+            (None, Operator::Absent) => true,
+            (None, _) => false,
+            (Some(_), Operator::Exists) => true,
+            (Some(_), Operator::Absent) => false,
+            (Some(a), op) => compare(a, op, &self.value),
+        };
+        ExpectationResult {
+            expectation: self.clone(),
+            passed,
+            actual,
+        }
+    }
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read baseline from {}", path.display()))?;
+        serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse baseline from {}", path.display()))
+    }
+
+    /// Evaluate every expectation against a report.
+    pub fn evaluate(&self, report: &NodeReport) -> Result<Vec<ExpectationResult>> {
+        let value = serde_json::to_value(report).context("failed to serialize report")?;
+        Ok(self.expectations.iter().map(|e| e.evaluate(&value)).collect())
+    }
+}
+
+/// Resolve a dot-path. `*` over an array yields an array of the sub-results.
+fn lookup(value: &Value, path: &str) -> Option<Value> {
+    let parts: Vec<&str> = path.split('.').filter(|p| !p.is_empty()).collect();
+    lookup_parts(value, &parts)
+}
+
+fn lookup_parts(value: &Value, parts: &[&str]) -> Option<Value> {
+    let Some((head, rest)) = parts.split_first() else {
+        return Some(value.clone());
+    };
+    match (value, *head) {
+        (Value::Array(items), "*") => Some(Value::Array(
+            items
+                .iter()
+                .filter_map(|item| lookup_parts(item, rest))
+                .collect(),
+        )),
+        (Value::Array(items), idx) => {
+            let idx: usize = idx.parse().ok()?;
+            lookup_parts(items.get(idx)?, rest)
+        }
+        (Value::Object(map), key) => match map.get(key)? {
+            Value::Null => None,
+            v => lookup_parts(v, rest),
+        },
+        _ => None,
+    }
+}
+
+fn compare(actual: &Value, op: Operator, expected: &Value) -> bool {
+    match op {
+        Operator::Eq => values_equal(actual, expected),
+        Operator::Ne => !values_equal(actual, expected),
+        Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge => {
+            match (actual.as_f64(), expected.as_f64()) {
+                (Some(a), Some(e)) => match op {
+                    Operator::Lt => a < e,
+                    Operator::Le => a <= e,
+                    Operator::Gt => a > e,
+                    _ => a >= e,
+                },
+                _ => false,
+            }
+        }
+        Operator::Contains => contains(actual, expected),
+        Operator::NotContains => !contains(actual, expected),
+        Operator::Exists | Operator::Absent => unreachable!("handled by caller"),
+    }
+}
+
+/// Equality that treats `22` and `22.0` as the same number.
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) if a.is_number() && b.is_number() => x == y,
+        _ => a == b,
+    }
+}
+
+fn contains(haystack: &Value, needle: &Value) -> bool {
+    match haystack {
+        Value::Array(items) => items.iter().any(|item| values_equal(item, needle)),
+        Value::String(s) => needle.as_str().is_some_and(|n| s.contains(n)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> Value {
+        json!({
+            "hostname": "test-node",
+            "os": { "kernel_version": "6.12.0", "virtualization": null },
+            "security": { "firewall_active": true, "password_auth_enabled": false },
+            "network": {
+                "listening_ports": [
+                    { "port": 22, "protocol": "tcp" },
+                    { "port": 9100, "protocol": "tcp" }
+                ]
+            },
+            "health": { "memory_usage_percent": 42.5 }
+        })
+    }
+
+    fn expect(path: &str, op: Operator, value: Value) -> Expectation {
+        Expectation {
+            path: path.to_string(),
+            op,
+            value,
+            description: None,
+        }
+    }
+
+    #[test]
+    fn lookup_nested_and_indexed() {
+        let v = sample();
+        assert_eq!(lookup(&v, "security.firewall_active"), Some(json!(true)));
+        assert_eq!(lookup(&v, "network.listening_ports.1.port"), Some(json!(9100)));
+        assert_eq!(lookup(&v, "network.listening_ports.5.port"), None);
+        assert_eq!(lookup(&v, "nope.field"), None);
+    }
+
+    #[test]
+    fn lookup_wildcard_maps_over_array() {
+        assert_eq!(
+            lookup(&sample(), "network.listening_ports.*.port"),
+            Some(json!([22, 9100]))
+        );
+    }
+
+    #[test]
+    fn lookup_null_is_absent() {
+        assert_eq!(lookup(&sample(), "os.virtualization"), None);
+    }
+
+    #[test]
+    fn eq_and_ne() {
+        let v = sample();
+        assert!(expect("security.firewall_active", Operator::Eq, json!(true)).evaluate(&v).passed);
+        assert!(!expect("security.password_auth_enabled", Operator::Eq, json!(true)).evaluate(&v).passed);
+        assert!(expect("hostname", Operator::Ne, json!("other")).evaluate(&v).passed);
+    }
+
+    #[test]
+    fn numeric_comparisons() {
+        let v = sample();
+        assert!(expect("health.memory_usage_percent", Operator::Lt, json!(90)).evaluate(&v).passed);
+        assert!(expect("health.memory_usage_percent", Operator::Ge, json!(42.5)).evaluate(&v).passed);
+        assert!(!expect("health.memory_usage_percent", Operator::Gt, json!(50)).evaluate(&v).passed);
+        // Non-numeric operands never satisfy an ordering.
+        assert!(!expect("hostname", Operator::Lt, json!(5)).evaluate(&v).passed);
+    }
+
+    #[test]
+    fn contains_array_and_string() {
+        let v = sample();
+        assert!(expect("network.listening_ports.*.port", Operator::Contains, json!(22)).evaluate(&v).passed);
+        assert!(expect("network.listening_ports.*.port", Operator::NotContains, json!(23)).evaluate(&v).passed);
+        assert!(expect("os.kernel_version", Operator::Contains, json!("6.12")).evaluate(&v).passed);
+    }
+
+    #[test]
+    fn exists_and_absent() {
+        let v = sample();
+        assert!(expect("os.kernel_version", Operator::Exists, Value::Null).evaluate(&v).passed);
+        assert!(expect("os.virtualization", Operator::Absent, Value::Null).evaluate(&v).passed);
+        assert!(!expect("os.kernel_version", Operator::Absent, Value::Null).evaluate(&v).passed);
+    }
+
+    #[test]
+    fn missing_path_fails_value_operators() {
+        let result = expect("security.nope", Operator::Eq, json!(true)).evaluate(&sample());
+        assert!(!result.passed);
+        assert!(result.actual.is_none());
+    }
+
+    #[test]
+    fn baseline_parses_from_yaml() {
+        let yaml = r#"
+expectations:
+  - path: security.firewall_active
+    op: eq
+    value: true
+  - path: network.listening_ports.*.port
+    op: contains
+    value: 22
+    description: sshd listening
+  - path: os.kernel_version
+    op: exists
+"#;
+        let baseline: Baseline = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(baseline.expectations.len(), 3);
+        assert_eq!(baseline.expectations[1].op, Operator::Contains);
+        assert_eq!(baseline.expectations[1].label(), "sshd listening");
+        let v = sample();
+        assert!(baseline.expectations.iter().all(|e| e.evaluate(&v).passed));
+    }
+
+    #[test]
+    fn unknown_operator_rejected() {
+        let yaml = "expectations:\n  - path: a\n    op: approx\n    value: 1\n";
+        assert!(serde_yaml::from_str::<Baseline>(yaml).is_err());
+    }
+}
@@ -3,83 +3,514 @@
 //! Deep platform-specific probing:
 //! - macOS: sysctl, system_profiler, vm_stat, sw_vers, networksetup, pmset, lsof
 //! - Linux: /proc/*, /sys/*, ip, ss, lspci, systemctl, uname
+//!
+//! Each report section is produced by a SectionCollector; a
+//! CollectorRegistry runs them and assembles the NodeReport.
 
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use chrono::Utc;
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tracing::warn;
 
+use super::age_rotation;
+use super::cgroup_limits;
+use super::firewall_drift;
 use super::node_report::*;
+use super::report_health;
+use super::security_score;
+use crate::config::ReportConfig;
+use crate::node_identity::secrets as node_secrets;
+use crate::node_identity::{NodeIdentity, NodeRole};
+use crate::paths::expand_path;
+
+/// One section of a NodeReport, as produced by a SectionCollector.
+#[derive(Debug, Clone)]
+pub enum Section {
+    Hardware(HardwareSnapshot),
+    Os(OsSnapshot),
+    Network(NetworkSnapshot),
+    Nix(NixSnapshot),
+    /// `None` when the node is not part of a cluster.
+    Kubernetes(Option<K8sSnapshot>),
+    Health(HealthMetrics),
+    Security(SecuritySnapshot),
+    Processes(ProcessSnapshot),
+}
 
-pub struct ReportCollector;
+pub type SectionFuture<'a> = Pin<Box<dyn Future<Output = Result<Section>> + Send + 'a>>;
 
-impl ReportCollector {
-    /// Collect a complete runtime report from this machine.
-    pub async fn collect() -> Result<NodeReport> {
-        let hostname = gethostname();
+/// Produces one report section. Collectors in a registry run concurrently;
+/// a failing collector leaves its section at the zeroed default.
+pub trait SectionCollector: Send + Sync {
+    /// Short name used in logs, e.g. "hardware".
+    fn name(&self) -> &'static str;
+    fn collect(&self) -> SectionFuture<'_>;
+}
 
-        let (hardware, os, network, nix, health, security, processes) = tokio::join!(
-            Self::collect_hardware(),
-            Self::collect_os(),
-            Self::collect_network(),
-            Self::collect_nix(),
-            Self::collect_health(),
-            Self::collect_security(),
-            Self::collect_processes(),
-        );
+tokio::task_local! {
+    /// Warnings raised by the collector running in the current task.
+    static SECTION_WARNINGS: RefCell<Vec<String>>;
+}
 
-        let kubernetes = Self::collect_kubernetes().await.ok();
+/// Record a failed sub-probe against the section being collected, so it
+/// shows up in `collection_warnings` as well as the log. Outside a
+/// registry run it is only logged.
+pub fn probe_warning(message: impl Into<String>) {
+    let message = message.into();
+    warn!(warning = %message, "report sub-probe failed");
+    let _ = SECTION_WARNINGS.try_with(|w| w.borrow_mut().push(message));
+}
 
-        Ok(NodeReport {
-            timestamp: Utc::now(),
-            daemon_version: env!("CARGO_PKG_VERSION").to_string(),
-            hostname: hostname.clone(),
-            hardware: hardware.unwrap_or_else(|e| {
-                warn!(error = %e, "failed to collect hardware info");
-                default_hardware()
-            }),
-            os: os.unwrap_or_else(|e| {
-                warn!(error = %e, "failed to collect OS info");
-                default_os()
-            }),
-            network: network.unwrap_or_else(|e| {
-                warn!(error = %e, "failed to collect network info");
-                default_network()
-            }),
-            nix: nix.unwrap_or_else(|e| {
-                warn!(error = %e, "failed to collect Nix info");
-                default_nix()
-            }),
-            kubernetes,
-            health: health.unwrap_or_else(|e| {
-                warn!(error = %e, "failed to collect health metrics");
-                default_health()
-            }),
-            security: security.unwrap_or_else(|e| {
-                warn!(error = %e, "failed to collect security info");
-                default_security()
-            }),
-            processes: processes.unwrap_or_else(|e| {
-                warn!(error = %e, "failed to collect process info");
-                default_processes()
-            }),
+/// The set of collectors that make up a report. When two collectors emit
+/// the same section, the one registered last wins, so extra collectors can
+/// override the platform defaults.
+#[derive(Default, Clone)]
+pub struct CollectorRegistry {
+    collectors: Vec<Arc<dyn SectionCollector>>,
+}
+
+impl CollectorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in collectors for this platform.
+    pub fn platform(config: &ReportConfig) -> Self {
+        Self::new()
+            .with(HardwareCollector)
+            .with(OsCollector {
+                ntp_servers: Vec::new(),
+            })
+            .with(NetworkCollector {
+                dns_probe_domain: config.dns_probe_domain.clone(),
+            })
+            .with(NixCollector)
+            .with(KubernetesCollector)
+            .with(HealthCollector)
+            .with(SecurityCollector)
+            .with(ProcessCollector {
+                group_by_program: config.group_processes,
+            })
+    }
+
+    /// Drop the collectors a node in `role` has no use for, e.g. Kubernetes
+    /// probing on a workstation.
+    pub fn for_role(self, role: NodeRole) -> Self {
+        let skipped: &[&str] = match role {
+            NodeRole::Workstation => &["kubernetes"],
+            NodeRole::Kubernetes | NodeRole::Server => &[],
+        };
+        Self {
+            collectors: self
+                .collectors
+                .into_iter()
+                .filter(|c| !skipped.contains(&c.name()))
+                .collect(),
+        }
+    }
+
+    /// Measure clock offset against `servers` (node.yaml
+    /// `network.ntp_servers`) where the platform queries a server itself.
+    pub fn with_ntp_servers(self, servers: &[String]) -> Self {
+        Self {
+            collectors: self
+                .collectors
+                .into_iter()
+                .map(|c| -> Arc<dyn SectionCollector> {
+                    if c.name() == "os" {
+                        Arc::new(OsCollector {
+                            ntp_servers: servers.to_vec(),
+                        })
+                    } else {
+                        c
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn with(mut self, collector: impl SectionCollector + 'static) -> Self {
+        self.collectors.push(Arc::new(collector));
+        self
+    }
+
+    /// Names of the registered collectors, in registration order.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.collectors.iter().map(|c| c.name()).collect()
+    }
+
+    /// Keep only the named collectors. Fails on a name no collector has,
+    /// so a typo can't silently refresh nothing.
+    pub fn only(self, names: &[String]) -> Result<Self> {
+        let known = self.names();
+        if let Some(unknown) = names.iter().find(|n| !known.contains(&n.as_str())) {
+            anyhow::bail!(
+                "unknown report section '{}' (expected one of: {})",
+                unknown,
+                known.join(", ")
+            );
+        }
+        Ok(Self {
+            collectors: self
+                .collectors
+                .into_iter()
+                .filter(|c| names.iter().any(|n| n == c.name()))
+                .collect(),
+        })
+    }
+
+    /// Run every collector concurrently and assemble the report.
+    pub async fn collect(&self) -> NodeReport {
+        let mut report = empty_report(gethostname());
+        self.collect_into(&mut report).await;
+        report
+    }
+
+    /// Run every collector concurrently and replace their sections in
+    /// `report`. A failing collector leaves its section as it was and
+    /// records its error in `collection_errors`.
+    pub async fn collect_into(&self, report: &mut NodeReport) {
+        let mut tasks = tokio::task::JoinSet::new();
+        for (index, collector) in self.collectors.iter().enumerate() {
+            let collector = Arc::clone(collector);
+            let task = async move {
+                let started = std::time::Instant::now();
+                let result = collector.collect().await;
+                let warnings = SECTION_WARNINGS.with(|w| w.take());
+                (index, collector.name(), result, warnings, started.elapsed())
+            };
+            tasks.spawn(SECTION_WARNINGS.scope(RefCell::new(Vec::new()), task));
+        }
+
+        let mut results = Vec::with_capacity(self.collectors.len());
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok(result) => results.push(result),
+                Err(e) => warn!(error = %e, "report collector task failed"),
+            }
+        }
+        // Apply in registration order so later collectors override earlier ones.
+        results.sort_by_key(|(index, _, _, _, _)| *index);
+
+        for (_, name, result, warnings, elapsed) in results {
+            report
+                .collection_durations_ms
+                .insert(name.to_string(), elapsed.as_millis() as u64);
+            // Warnings from an earlier run of this section are stale now.
+            report.collection_warnings.retain(|w| w.section != name);
+            report
+                .collection_warnings
+                .extend(warnings.into_iter().map(|message| CollectionWarning {
+                    section: name.to_string(),
+                    message,
+                }));
+            match result {
+                Ok(section) => {
+                    apply_section(report, section);
+                    report.collection_errors.remove(name);
+                }
+                Err(e) => {
+                    warn!(section = name, error = %e, "failed to collect report section");
+                    report
+                        .collection_errors
+                        .insert(name.to_string(), format!("{:#}", e));
+                }
+            }
+        }
+        // Health and hardware come from separate collectors.
+        report.health.load_per_core =
+            report_health::load_per_core(report.health.load_average_1m, report.hardware.cpu_cores);
+    }
+}
+
+/// Cross-check declared firewall intent.
+fn add_firewall_drift(report: &mut NodeReport, identity: &NodeIdentity) {
+    report.security.findings.retain(|f| !is_firewall_drift(f));
+    report.security.findings.extend(firewall_drift::check(
+        &identity.network.firewall,
+        &report.network.listening_ports,
+    ));
+}
+
+fn is_firewall_drift(finding: &SecurityFinding) -> bool {
+    matches!(
+        finding.kind,
+        FindingKind::FirewallExposure | FindingKind::FirewallStaleRule
+    )
+}
+
+/// Flag a deployed age identity the secrets are no longer encrypted to.
+async fn add_age_rotation_check(report: &mut NodeReport, identity: &NodeIdentity) {
+    let secrets = identity.with_expanded_paths().secrets;
+    let config = secrets.clone();
+    let key = tokio::task::spawn_blocking(move || node_secrets::age_key(&config))
+        .await
+        .ok()
+        .flatten();
+    let Some(key) = key else {
+        return;
+    };
+    let deployed = match key {
+        Ok(key) => age_rotation::public_keys(&key).await.unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+
+    let sops_content = match secrets.sops_file {
+        Some(ref file) => tokio::fs::read_to_string(expand_path(file)).await.ok(),
+        None => None,
+    };
+    let recipients = age_rotation::recipients(sops_content.as_deref(), &secrets.age_keys);
+
+    if let Some(finding) = age_rotation::check(&deployed, &recipients) {
+        report.security.findings.push(finding);
+    }
+}
+
+/// Copy fleet tags and Kubernetes node labels from node.yaml.
+fn add_declared_labels(report: &mut NodeReport, identity: &NodeIdentity) {
+    report.declared = DeclaredLabels {
+        tags: identity.fleet.tags.clone(),
+        labels: identity
+            .kubernetes
+            .node_labels
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect(),
+    };
+}
+
+fn empty_report(hostname: String) -> NodeReport {
+    NodeReport {
+        timestamp: Utc::now(),
+        daemon_version: env!("CARGO_PKG_VERSION").to_string(),
+        hostname,
+        hardware: default_hardware(),
+        os: default_os(),
+        network: default_network(),
+        nix: default_nix(),
+        kubernetes: None,
+        health: default_health(),
+        security: default_security(),
+        processes: default_processes(),
+        collection_durations_ms: BTreeMap::new(),
+        collection_errors: BTreeMap::new(),
+        collection_warnings: Vec::new(),
+        declared: DeclaredLabels::default(),
+    }
+}
+
+fn apply_section(report: &mut NodeReport, section: Section) {
+    match section {
+        Section::Hardware(s) => report.hardware = s,
+        Section::Os(s) => report.os = s,
+        Section::Network(s) => report.network = s,
+        Section::Nix(s) => report.nix = s,
+        Section::Kubernetes(s) => report.kubernetes = s,
+        Section::Health(s) => report.health = s,
+        Section::Security(s) => report.security = s,
+        Section::Processes(s) => report.processes = s,
+    }
+}
+
+// ── Platform collectors ────────────────────────────────────
+
+struct HardwareCollector;
+struct OsCollector {
+    ntp_servers: Vec<String>,
+}
+struct NetworkCollector {
+    dns_probe_domain: String,
+}
+struct NixCollector;
+struct KubernetesCollector;
+struct HealthCollector;
+struct SecurityCollector;
+struct ProcessCollector {
+    group_by_program: bool,
+}
+
+impl SectionCollector for HardwareCollector {
+    fn name(&self) -> &'static str {
+        "hardware"
+    }
+    fn collect(&self) -> SectionFuture<'_> {
+        Box::pin(async {
+            ReportCollector::collect_hardware()
+                .await
+                .map(Section::Hardware)
+        })
+    }
+}
+
+impl SectionCollector for OsCollector {
+    fn name(&self) -> &'static str {
+        "os"
+    }
+    fn collect(&self) -> SectionFuture<'_> {
+        Box::pin(async {
+            ReportCollector::collect_os(&self.ntp_servers)
+                .await
+                .map(Section::Os)
+        })
+    }
+}
+
+impl SectionCollector for NetworkCollector {
+    fn name(&self) -> &'static str {
+        "network"
+    }
+    fn collect(&self) -> SectionFuture<'_> {
+        Box::pin(async {
+            ReportCollector::collect_network(&self.dns_probe_domain)
+                .await
+                .map(Section::Network)
+        })
+    }
+}
+
+impl SectionCollector for NixCollector {
+    fn name(&self) -> &'static str {
+        "nix"
+    }
+    fn collect(&self) -> SectionFuture<'_> {
+        Box::pin(async { ReportCollector::collect_nix().await.map(Section::Nix) })
+    }
+}
+
+impl SectionCollector for KubernetesCollector {
+    fn name(&self) -> &'static str {
+        "kubernetes"
+    }
+    /// Most nodes have no cluster, so failure means "not a k8s node".
+    fn collect(&self) -> SectionFuture<'_> {
+        Box::pin(async {
+            Ok(Section::Kubernetes(
+                ReportCollector::collect_kubernetes().await.ok(),
+            ))
+        })
+    }
+}
+
+impl SectionCollector for HealthCollector {
+    fn name(&self) -> &'static str {
+        "health"
+    }
+    fn collect(&self) -> SectionFuture<'_> {
+        Box::pin(async { ReportCollector::collect_health().await.map(Section::Health) })
+    }
+}
+
+impl SectionCollector for SecurityCollector {
+    fn name(&self) -> &'static str {
+        "security"
+    }
+    fn collect(&self) -> SectionFuture<'_> {
+        Box::pin(async {
+            ReportCollector::collect_security()
+                .await
+                .map(Section::Security)
+        })
+    }
+}
+
+impl SectionCollector for ProcessCollector {
+    fn name(&self) -> &'static str {
+        "processes"
+    }
+    fn collect(&self) -> SectionFuture<'_> {
+        Box::pin(async {
+            ReportCollector::collect_processes(self.group_by_program)
+                .await
+                .map(Section::Processes)
         })
     }
+}
+
+pub struct ReportCollector;
+
+impl ReportCollector {
+    /// Collect a complete runtime report from this machine. The node's
+    /// identity decides which sections its role needs and is checked
+    /// against what was found; without one every section is collected.
+    pub async fn collect(
+        config: &ReportConfig,
+        identity: Option<&NodeIdentity>,
+    ) -> Result<NodeReport> {
+        let mut registry = CollectorRegistry::platform(config);
+        if let Some(identity) = identity {
+            registry = registry
+                .for_role(identity.node_role())
+                .with_ntp_servers(&identity.network.ntp_servers);
+        }
+        let mut report = registry.collect().await;
+        if let Some(identity) = identity {
+            add_firewall_drift(&mut report, identity);
+            add_age_rotation_check(&mut report, identity).await;
+            add_declared_labels(&mut report, identity);
+        }
+        Ok(report)
+    }
+
+    /// Re-collect only `registry`'s sections into an existing report.
+    pub async fn refresh_into(
+        registry: &CollectorRegistry,
+        report: &mut NodeReport,
+        identity: Option<&NodeIdentity>,
+    ) {
+        registry.collect_into(report).await;
+        let names = registry.names();
+        // Drift compares the listening ports against node.yaml, so it goes
+        // stale with either section; fresh security findings lost it too.
+        if names.contains(&"network") || names.contains(&"security") {
+            match identity {
+                Some(identity) => add_firewall_drift(report, identity),
+                None => report.security.findings.retain(|f| !is_firewall_drift(f)),
+            }
+        }
+        if let Some(identity) = identity.filter(|_| names.contains(&"security")) {
+            add_age_rotation_check(report, identity).await;
+        }
+        report.timestamp = Utc::now();
+    }
 
     // ═══════════════════════════════════════════════════════════
     // HARDWARE
     // ═══════════════════════════════════════════════════════════
 
     async fn collect_hardware() -> Result<HardwareSnapshot> {
-        let (cpu_info, mem_info, swap_info, disks, gpus, power) = tokio::join!(
+        let (
+            cpu_info,
+            mem_info,
+            swap_info,
+            disks,
+            gpus,
+            power,
+            pci_devices,
+            usb_devices,
+            raid_arrays,
+            cgroup,
+        ) = tokio::join!(
             Self::collect_cpu_info(),
             Self::collect_memory_info(),
             Self::collect_swap_info(),
             Self::collect_disk_info(),
             Self::collect_gpu_info(),
             Self::collect_power_info(),
+            Self::collect_pci_devices(),
+            Self::collect_usb_devices(),
+            Self::collect_raid_arrays(),
+            cgroup_limits::read_limits(std::path::Path::new(cgroup_limits::CGROUP_ROOT)),
         );
 
         let (cpu_model, cpu_vendor, cpu_arch, cpu_cores, cpu_threads, cpu_freq, cpu_cache) =
@@ -99,10 +530,24 @@ impl ReportCollector {
             ram_available_bytes: ram_available,
             swap_total_bytes: swap_total,
             swap_used_bytes: swap_used,
-            disks: disks.unwrap_or_default(),
-            gpus: gpus.unwrap_or_default(),
+            disks: disks.unwrap_or_else(|e| {
+                probe_warning(format!("disk detection failed: {:#}", e));
+                Vec::new()
+            }),
+            gpus: gpus.unwrap_or_else(|e| {
+                probe_warning(format!("GPU detection failed: {:#}", e));
+                Vec::new()
+            }),
             temperatures: Vec::new(), // requires SMC/hwmon access
-            power: power.ok().flatten(),
+            power: power.unwrap_or_else(|e| {
+                probe_warning(format!("power detection failed: {:#}", e));
+                None
+            }),
+            pci_devices,
+            usb_devices,
+            raid_arrays,
+            cgroup_memory_limit_bytes: cgroup.memory_limit_bytes,
+            cgroup_cpu_quota: cgroup.cpu_quota,
         })
     }
 
@@ -270,9 +715,14 @@ impl ReportCollector {
 
     #[cfg(target_os = "macos")]
     async fn collect_disk_info() -> Result<Vec<DiskSnapshot>> {
-        // macOS df -kT doesn't exist; use df -k + mount for fs types
-        let df_output = run_cmd("df", &["-k"]).await.unwrap_or_default();
-        let mount_output = run_cmd("mount", &[]).await.unwrap_or_default();
+        // macOS df -kT doesn't exist; use df -kP + mount for fs types
+        let (df_output, mount_output, inodes) = tokio::join!(
+            run_cmd("df", &["-kP"]),
+            run_cmd("mount", &[]),
+            collect_inode_counts(),
+        );
+        let df_output = df_output.unwrap_or_default();
+        let mount_output = mount_output.unwrap_or_default();
 
         // Build mount_point → filesystem map from mount output
         let mut fs_map: HashMap<String, String> = HashMap::new();
@@ -292,8 +742,7 @@ impl ReportCollector {
         }
 
         let mut disks = Vec::new();
-        for line in df_output.lines().skip(1) {
-            let parts: Vec<&str> = line.split_whitespace().collect();
+        for parts in df_rows(&df_output) {
             if parts.len() < 6 {
                 continue;
             }
@@ -324,6 +773,7 @@ impl ReportCollector {
 
             let filesystem = fs_map.get(&mount_point).cloned().unwrap_or_default();
 
+            let inode_counts = inodes.get(&mount_point).copied();
             disks.push(DiskSnapshot {
                 device,
                 mount_point,
@@ -332,6 +782,8 @@ impl ReportCollector {
                 used_bytes: used_kb * 1024,
                 available_bytes: available_kb * 1024,
                 smart_healthy: None,
+                inodes_total: inode_counts.map(|(total, _)| total),
+                inodes_used: inode_counts.map(|(_, used)| used),
             });
         }
         Ok(disks)
@@ -340,11 +792,11 @@ impl ReportCollector {
     #[cfg(not(target_os = "macos"))]
     async fn collect_disk_info() -> Result<Vec<DiskSnapshot>> {
         // Linux: df -kT gives filesystem type
-        let output = run_cmd("df", &["-kT"]).await.unwrap_or_default();
+        let (output, inodes) = tokio::join!(run_cmd("df", &["-kPT"]), collect_inode_counts());
+        let output = output.unwrap_or_default();
         let mut disks = Vec::new();
 
-        for line in output.lines().skip(1) {
-            let parts: Vec<&str> = line.split_whitespace().collect();
+        for parts in df_rows(&output) {
             if parts.len() < 7 {
                 continue;
             }
@@ -371,6 +823,7 @@ impl ReportCollector {
             let used_kb: u64 = parts[3].parse().unwrap_or(0);
             let available_kb: u64 = parts[4].parse().unwrap_or(0);
 
+            let inode_counts = inodes.get(&mount_point).copied();
             disks.push(DiskSnapshot {
                 device,
                 mount_point,
@@ -379,6 +832,8 @@ impl ReportCollector {
                 used_bytes: used_kb * 1024,
                 available_bytes: available_kb * 1024,
                 smart_healthy: None,
+                inodes_total: inode_counts.map(|(total, _)| total),
+                inodes_used: inode_counts.map(|(_, used)| used),
             });
         }
         Ok(disks)
@@ -456,32 +911,25 @@ impl ReportCollector {
         let output = run_cmd("lspci", &["-mm"]).await.unwrap_or_default();
         let mut gpus = Vec::new();
 
-        for line in output.lines() {
-            let lower = line.to_lowercase();
-            if lower.contains("vga") || lower.contains("3d") || lower.contains("display") {
-                // lspci -mm format: Slot "Class" "Vendor" "Device" ...
-                let parts: Vec<&str> = line.split('"').collect();
-                if parts.len() >= 6 {
-                    let vendor = parts[3].to_string();
-                    let name = parts[5].to_string();
-
-                    let vendor_short = if vendor.contains("NVIDIA") {
-                        "NVIDIA".into()
-                    } else if vendor.contains("Advanced Micro") || vendor.contains("AMD") {
-                        "AMD".into()
-                    } else if vendor.contains("Intel") {
-                        "Intel".into()
-                    } else {
-                        vendor.clone()
-                    };
+        for dev in parse_lspci_mm(&output) {
+            let class = dev.class.to_lowercase();
+            if class.contains("vga") || class.contains("3d") || class.contains("display") {
+                let vendor_short = if dev.vendor.contains("NVIDIA") {
+                    "NVIDIA".into()
+                } else if dev.vendor.contains("Advanced Micro") || dev.vendor.contains("AMD") {
+                    "AMD".into()
+                } else if dev.vendor.contains("Intel") {
+                    "Intel".into()
+                } else {
+                    dev.vendor.clone()
+                };
 
-                    gpus.push(GpuSnapshot {
-                        name,
-                        vendor: vendor_short,
-                        vram_bytes: None,
-                        metal_support: None,
-                    });
-                }
+                gpus.push(GpuSnapshot {
+                    name: dev.device,
+                    vendor: vendor_short,
+                    vram_bytes: None,
+                    metal_support: None,
+                });
             }
         }
 
@@ -511,6 +959,54 @@ impl ReportCollector {
         Ok(gpus)
     }
 
+    // ── PCI / USB devices ──────────────────────────────────
+
+    #[cfg(target_os = "macos")]
+    async fn collect_pci_devices() -> Vec<PciDevice> {
+        // No lspci on macOS; Apple Silicon has no user-visible PCI bus.
+        Vec::new()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    async fn collect_pci_devices() -> Vec<PciDevice> {
+        run_cmd("lspci", &["-mm"])
+            .await
+            .map(|out| parse_lspci_mm(&out))
+            .unwrap_or_default()
+    }
+
+    #[cfg(target_os = "macos")]
+    async fn collect_usb_devices() -> Vec<UsbDevice> {
+        run_cmd("system_profiler", &["SPUSBDataType", "-json"])
+            .await
+            .map(|out| parse_system_profiler_usb(&out))
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    async fn collect_usb_devices() -> Vec<UsbDevice> {
+        run_cmd("lsusb", &[])
+            .await
+            .map(|out| parse_lsusb(&out))
+            .unwrap_or_default()
+    }
+
+    // ── Software RAID ──────────────────────────────────────
+
+    #[cfg(target_os = "macos")]
+    async fn collect_raid_arrays() -> Vec<RaidArray> {
+        Vec::new()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    async fn collect_raid_arrays() -> Vec<RaidArray> {
+        // Absent when the md driver isn't loaded, i.e. no software RAID.
+        tokio::fs::read_to_string("/proc/mdstat")
+            .await
+            .map(|out| parse_mdstat(&out))
+            .unwrap_or_default()
+    }
+
     // ── Power / Battery ────────────────────────────────────
 
     #[cfg(target_os = "macos")]
@@ -595,7 +1091,7 @@ impl ReportCollector {
     // ═══════════════════════════════════════════════════════════
 
     #[cfg(target_os = "macos")]
-    async fn collect_os() -> Result<OsSnapshot> {
+    async fn collect_os(ntp_servers: &[String]) -> Result<OsSnapshot> {
         let (version, build, product_name, kernel, arch, boottime, tz) = tokio::join!(
             run_cmd("sw_vers", &["-productVersion"]),
             run_cmd("sw_vers", &["-buildVersion"]),
@@ -632,6 +1128,9 @@ impl ReportCollector {
                 }
             });
 
+        let clock_offset_ms = Self::measure_clock_offset_ms(ntp_servers).await;
+        let time_synced = Self::detect_time_sync(clock_offset_ms).await;
+
         Ok(OsSnapshot {
             distribution: "macOS".to_string(),
             version: version.trim().to_string(),
@@ -647,11 +1146,15 @@ impl ReportCollector {
             timezone: tz,
             is_wsl: false,
             virtualization,
+            reboot_required: false,
+            time_synced,
+            clock_offset_ms,
+            entropy_available: None,
         })
     }
 
     #[cfg(not(target_os = "macos"))]
-    async fn collect_os() -> Result<OsSnapshot> {
+    async fn collect_os(_ntp_servers: &[String]) -> Result<OsSnapshot> {
         let (os_release_str, kernel, arch, uptime_str, tz) = tokio::join!(
             tokio::fs::read_to_string("/etc/os-release"),
             run_cmd("uname", &["-r"]),
@@ -702,6 +1205,12 @@ impl ReportCollector {
 
         let hostname = gethostname();
         let triple = format!("{}-linux", arch_str.trim());
+        let reboot_required = detect_reboot_required(std::path::Path::new("/"), kernel.trim());
+        let (time_synced, clock_offset_ms, entropy_available) = tokio::join!(
+            Self::detect_time_sync(),
+            Self::measure_clock_offset_ms(),
+            read_sys_file("/proc/sys/kernel/random/entropy_avail"),
+        );
 
         Ok(OsSnapshot {
             distribution,
@@ -718,9 +1227,66 @@ impl ReportCollector {
             timezone: tz,
             is_wsl,
             virtualization,
+            reboot_required,
+            time_synced,
+            clock_offset_ms,
+            entropy_available: entropy_available.and_then(|s| parse_entropy_avail(&s)),
         })
     }
 
+    /// Offset from the first of node.yaml's `ntp_servers`, else the
+    /// configured network time server. sntp only queries; it never sets
+    /// the clock.
+    #[cfg(target_os = "macos")]
+    async fn measure_clock_offset_ms(ntp_servers: &[String]) -> Option<f64> {
+        let server = match ntp_servers.first() {
+            Some(server) => server.clone(),
+            None => run_cmd("systemsetup", &["-getnetworktimeserver"])
+                .await
+                .and_then(|s| s.rsplit(':').next().map(|v| v.trim().to_string()))
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| "time.apple.com".to_string()),
+        };
+        run_cmd("sntp", &["-t", "2", &server])
+            .await
+            .and_then(|s| parse_sntp_offset(&s))
+            .map(|secs| secs * 1000.0)
+    }
+
+    /// chrony's view of the offset, else systemd-timesyncd's.
+    #[cfg(not(target_os = "macos"))]
+    async fn measure_clock_offset_ms() -> Option<f64> {
+        if let Some(offset) = run_cmd("chronyc", &["tracking"])
+            .await
+            .and_then(|s| parse_chrony_offset_ms(&s))
+        {
+            return Some(offset);
+        }
+        run_cmd("timedatectl", &["timesync-status"])
+            .await
+            .and_then(|s| parse_timesync_offset_ms(&s))
+    }
+
+    /// An offset under a second counts as synced. Without one, only a
+    /// disabled network time setting is conclusive.
+    #[cfg(target_os = "macos")]
+    async fn detect_time_sync(clock_offset_ms: Option<f64>) -> Option<bool> {
+        if let Some(offset) = clock_offset_ms {
+            return Some(offset.abs() < MAX_CLOCK_OFFSET_SECS * 1000.0);
+        }
+        run_cmd("systemsetup", &["-getusingnetworktime"])
+            .await
+            .and_then(|s| parse_network_time_setting(&s))
+            .filter(|enabled| !enabled)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    async fn detect_time_sync() -> Option<bool> {
+        run_cmd("timedatectl", &["show", "-p", "NTPSynchronized"])
+            .await
+            .and_then(|s| parse_ntp_synchronized(&s))
+    }
+
     async fn detect_timezone() -> Option<String> {
         // Try TZ env, then /etc/localtime symlink, then date
         if let Ok(tz) = std::env::var("TZ") {
@@ -804,14 +1370,15 @@ impl ReportCollector {
     // ═══════════════════════════════════════════════════════════
 
     #[cfg(target_os = "macos")]
-    async fn collect_network() -> Result<NetworkSnapshot> {
+    async fn collect_network(dns_probe_domain: &str) -> Result<NetworkSnapshot> {
         let hostname = gethostname();
 
-        let (ifconfig, netstat, resolv, listening) = tokio::join!(
+        let (ifconfig, netstat, resolv, listening, sockets) = tokio::join!(
             run_cmd("ifconfig", &[]),
             run_cmd("netstat", &["-rn"]),
             tokio::fs::read_to_string("/etc/resolv.conf"),
             Self::collect_listening_ports(),
+            run_cmd("netstat", &["-an"]),
         );
 
         let ifconfig = ifconfig.unwrap_or_default();
@@ -831,6 +1398,7 @@ impl ReportCollector {
 
         let resolv = resolv.unwrap_or_default();
         let dns_resolvers = parse_resolv_conf(&resolv);
+        let dns_resolver_health = probe_resolvers(&dns_resolvers, dns_probe_domain).await;
 
         Ok(NetworkSnapshot {
             hostname,
@@ -838,19 +1406,25 @@ impl ReportCollector {
             routes,
             dns_resolvers,
             default_gateway: default_gw,
-            listening_ports: listening.unwrap_or_default(),
+            listening_ports: listening.unwrap_or_else(|e| {
+                probe_warning(format!("listening port detection failed: {:#}", e));
+                Vec::new()
+            }),
+            dns_resolver_health,
+            connection_summary: parse_netstat_connections(&sockets.unwrap_or_default()),
         })
     }
 
     #[cfg(not(target_os = "macos"))]
-    async fn collect_network() -> Result<NetworkSnapshot> {
+    async fn collect_network(dns_probe_domain: &str) -> Result<NetworkSnapshot> {
         let hostname = gethostname();
 
-        let (ip_addr, ip_route, resolv, listening) = tokio::join!(
+        let (ip_addr, ip_route, resolv, listening, sockets) = tokio::join!(
             run_cmd("ip", &["-j", "addr"]),
             run_cmd("ip", &["-j", "route"]),
             tokio::fs::read_to_string("/etc/resolv.conf"),
             Self::collect_listening_ports(),
+            run_cmd("ss", &["-s"]),
         );
 
         let ip_addr = ip_addr.unwrap_or_default();
@@ -869,6 +1443,7 @@ impl ReportCollector {
 
         let resolv = resolv.unwrap_or_default();
         let dns_resolvers = parse_resolv_conf(&resolv);
+        let dns_resolver_health = probe_resolvers(&dns_resolvers, dns_probe_domain).await;
 
         Ok(NetworkSnapshot {
             hostname,
@@ -876,7 +1451,12 @@ impl ReportCollector {
             routes,
             dns_resolvers,
             default_gateway: default_gw,
-            listening_ports: listening.unwrap_or_default(),
+            listening_ports: listening.unwrap_or_else(|e| {
+                probe_warning(format!("listening port detection failed: {:#}", e));
+                Vec::new()
+            }),
+            dns_resolver_health,
+            connection_summary: parse_ss_summary(&sockets.unwrap_or_default()),
         })
     }
 
@@ -902,7 +1482,9 @@ impl ReportCollector {
                 // n*:8080 or n127.0.0.1:9100
                 if let Some(port_str) = name_field.rsplit(':').next() {
                     if let Ok(port) = port_str.parse::<u16>() {
-                        let addr = name_field.rsplit(':').nth(1).map(|s| s.to_string());
+                        let addr = name_field
+                            .rsplit_once(':')
+                            .map(|(host, _)| host.to_string());
                         // Avoid duplicates
                         if !ports.iter().any(|p: &ListeningPort| p.port == port) {
                             ports.push(ListeningPort {
@@ -936,7 +1518,9 @@ impl ReportCollector {
             } else if let Some(name_field) = line.strip_prefix('n') {
                 if let Some(port_str) = name_field.rsplit(':').next() {
                     if let Ok(port) = port_str.parse::<u16>() {
-                        let addr = name_field.rsplit(':').nth(1).map(|s| s.to_string());
+                        let addr = name_field
+                            .rsplit_once(':')
+                            .map(|(host, _)| host.to_string());
                         if !ports.iter().any(|p: &ListeningPort| p.port == port && p.protocol == "udp") {
                             ports.push(ListeningPort {
                                 port,
@@ -970,7 +1554,7 @@ impl ReportCollector {
                 let local = parts[3];
                 if let Some(port_str) = local.rsplit(':').next() {
                     if let Ok(port) = port_str.parse::<u16>() {
-                        let addr = local.rsplit(':').nth(1).map(|s| s.to_string());
+                        let addr = local.rsplit_once(':').map(|(host, _)| host.to_string());
                         let process = parts.get(5).map(|s| {
                             // users:(("process",pid=123,fd=4))
                             s.split('"')
@@ -998,7 +1582,7 @@ impl ReportCollector {
                 let local = parts[3];
                 if let Some(port_str) = local.rsplit(':').next() {
                     if let Ok(port) = port_str.parse::<u16>() {
-                        let addr = local.rsplit(':').nth(1).map(|s| s.to_string());
+                        let addr = local.rsplit_once(':').map(|(host, _)| host.to_string());
                         let process = parts.get(5).map(|s| {
                             s.split('"')
                                 .nth(1)
@@ -1056,11 +1640,20 @@ impl ReportCollector {
             .map(|s| s.lines().count() as u64)
             .unwrap_or(0);
 
-        // GC roots count
-        let gc_roots_count = run_cmd("nix-store", &["--gc", "--print-roots"])
-            .await
+        // GC roots count; temporary roots among them mark running builds
+        let gc_roots = run_cmd("nix-store", &["--gc", "--print-roots"]).await;
+        let gc_roots_count = gc_roots
+            .as_deref()
             .map(|s| s.lines().count() as u64)
             .unwrap_or(0);
+        let mut temp_drvs = gc_roots
+            .as_deref()
+            .map(parse_temp_root_drvs)
+            .unwrap_or_default();
+        if temp_drvs.is_empty() {
+            temp_drvs = temproots_drvs(std::path::Path::new("/nix/var/nix/temproots"));
+        }
+        let active_builds = building(temp_drvs);
 
         // Nix config
         let nix_config_json = run_cmd("nix", &["show-config", "--json"]).await;
@@ -1106,6 +1699,22 @@ impl ReportCollector {
             })
             .unwrap_or(false);
 
+        let eval_cache_enabled = nix_config
+            .get("eval-cache")
+            .and_then(|s| s.get("value"))
+            .and_then(|s| {
+                if s.is_boolean() {
+                    s.as_bool()
+                } else {
+                    s.as_str().map(|s| s == "true")
+                }
+            });
+
+        let eval_cache_size_bytes = std::env::var_os("XDG_CACHE_HOME")
+            .map(std::path::PathBuf::from)
+            .or_else(|| dirs::home_dir().map(|h| h.join(".cache")))
+            .and_then(|cache_home| eval_cache_size(&cache_home.join("nix")));
+
         // Current system path
         let current_system_path = run_cmd("readlink", &["-f", "/run/current-system"])
             .await
@@ -1133,18 +1742,25 @@ impl ReportCollector {
                 .unwrap_or(0)
         };
 
+        let last_rebuild_timestamp = last_rebuild_timestamp(std::path::Path::new("/"));
+
         // Channels
         let channels = run_cmd("nix-channel", &["--list"])
             .await
             .map(|s| s.lines().map(|l| l.to_string()).collect())
             .unwrap_or_default();
 
+        let system_flake = Self::collect_system_flake().await;
+        let recent_nix_errors = Self::collect_nix_daemon_errors().await;
+        let (config_source_dirty, config_source_rev) =
+            Self::collect_config_source(system_flake.as_ref()).await;
+
         Ok(NixSnapshot {
             nix_version,
             store_size_bytes,
             store_path_count,
             gc_roots_count,
-            last_rebuild_timestamp: None,
+            last_rebuild_timestamp,
             current_system_path,
             substituters,
             system_generations,
@@ -1152,9 +1768,124 @@ impl ReportCollector {
             trusted_users,
             max_jobs,
             sandbox_enabled,
+            system_flake,
+            eval_cache_size_bytes,
+            eval_cache_enabled,
+            recent_nix_errors,
+            config_source_dirty,
+            config_source_rev,
+            active_builds,
         })
     }
 
+    #[cfg(target_os = "macos")]
+    async fn collect_nix_daemon_errors() -> Vec<String> {
+        // launchd's org.nixos.nix-daemon writes stderr here; no priorities,
+        // so keep lines that look like errors.
+        run_cmd("tail", &["-n", "500", "/var/log/nix-daemon.log"])
+            .await
+            .map(|log| recent_error_lines(&log, true, NIX_ERROR_LINES))
+            .unwrap_or_default()
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    async fn collect_nix_daemon_errors() -> Vec<String> {
+        run_cmd(
+            "journalctl",
+            &[
+                "-u", "nix-daemon", "--since", "-1h", "-p", "err", "--no-pager", "-q",
+            ],
+        )
+        .await
+        .map(|log| recent_error_lines(&log, false, NIX_ERROR_LINES))
+        .unwrap_or_default()
+    }
+
+    /// Best-effort: lock metadata of the kindling-generated flake, plus the
+    /// `configurationRevision` the running system reports. `None` unless
+    /// `kindling apply` switched `/run/current-system` to the flake as it is
+    /// now, so a later build, diff or regenerate can't pass its lock off as
+    /// the running one. `nix flake metadata` evaluates the flake, so its
+    /// result is reused until `flake.nix` or `flake.lock` change.
+    async fn collect_system_flake() -> Option<FlakeInfo> {
+        static METADATA: std::sync::Mutex<Option<(FlakeStamp, FlakeInfo)>> =
+            std::sync::Mutex::new(None);
+
+        let gen_dir = crate::node_identity::nix_gen::generated_dir();
+        let current = std::fs::canonicalize("/run/current-system").ok()?;
+        if !crate::node_identity::nix_gen::is_activated_as(&gen_dir, &current) {
+            return None;
+        }
+        let stamp = flake_stamp(&gen_dir)?;
+        let cached = METADATA
+            .lock()
+            .ok()
+            .and_then(|m| m.clone())
+            .filter(|(s, _)| *s == stamp)
+            .map(|(_, info)| info);
+        let mut info = match cached {
+            Some(info) => info,
+            None => {
+                let flake_ref = gen_dir.to_string_lossy();
+                let metadata_args = [
+                    "flake",
+                    "metadata",
+                    "--json",
+                    "--no-write-lock-file",
+                    &flake_ref,
+                ];
+                let info = parse_flake_metadata(&run_cmd("nix", &metadata_args).await?)?;
+                if let Ok(mut m) = METADATA.lock() {
+                    *m = Some((stamp, info.clone()));
+                }
+                info
+            }
+        };
+        info.configuration_revision = Self::collect_configuration_revision().await;
+        Some(info)
+    }
+
+    /// `configurationRevision` of the running system, from the version file
+    /// in `/run/current-system`, falling back to `nixos-version --json` /
+    /// `darwin-version --json`.
+    async fn collect_configuration_revision() -> Option<String> {
+        if let Ok(json) = tokio::fs::read_to_string("/run/current-system/nixos-version.json").await
+        {
+            if let Some(rev) = parse_configuration_revision(&json) {
+                return Some(rev);
+            }
+        }
+        let cmd = if cfg!(target_os = "macos") {
+            "darwin-version"
+        } else {
+            "nixos-version"
+        };
+        run_cmd(cmd, &["--json"])
+            .await
+            .as_deref()
+            .and_then(parse_configuration_revision)
+    }
+
+    /// Dirty state and `HEAD` of the config source: the local system flake
+    /// dir when it is a git repo, else `/etc/nixos`. `(None, None)` when
+    /// neither is.
+    async fn collect_config_source(flake: Option<&FlakeInfo>) -> (Option<bool>, Option<String>) {
+        let candidates = flake
+            .and_then(|f| flake_source_dir(&f.url))
+            .into_iter()
+            .chain(std::iter::once("/etc/nixos".to_string()));
+        for dir in candidates {
+            let Some(rev) = run_cmd("git", &["-C", &dir, "rev-parse", "HEAD"]).await else {
+                continue;
+            };
+            let dirty = run_cmd("git", &["-C", &dir, "status", "--porcelain"])
+                .await
+                .map(|status| git_status_is_dirty(&status));
+            return (dirty, Some(rev.trim().to_string()));
+        }
+        (None, None)
+    }
+
     // ═══════════════════════════════════════════════════════════
     // KUBERNETES
     // ═══════════════════════════════════════════════════════════
@@ -1240,6 +1971,7 @@ impl ReportCollector {
         );
 
         let (cpu_req, cpu_lim, mem_req, mem_lim) = resource_info;
+        let (image_store_bytes, image_count) = Self::collect_image_store().await;
 
         // FluxCD detection
         let flux_installed = run_cmd("kubectl", &["get", "ns", "flux-system", "--request-timeout=3s"])
@@ -1263,15 +1995,62 @@ impl ReportCollector {
             memory_limits_bytes: mem_lim,
             flux_installed,
             helm_releases,
+            image_store_bytes,
+            image_count,
         })
     }
 
-    async fn collect_k8s_resources() -> (u64, u64, u64, u64) {
-        // kubectl top node gives resource usage; describe node gives requests/limits
-        let output = run_cmd(
-            "kubectl",
-            &["describe", "nodes", "--request-timeout=5s"],
-        )
+    /// Image filesystem usage and image count from crictl (standalone or
+    /// k3s-bundled). Without crictl, usage falls back to `du` on the
+    /// containerd snapshotter directory when one exists.
+    async fn collect_image_store() -> (Option<u64>, Option<u32>) {
+        async fn crictl(args: &[&str]) -> Option<String> {
+            match run_cmd("crictl", args).await {
+                Some(out) => Some(out),
+                None => {
+                    let mut k3s_args = vec!["crictl"];
+                    k3s_args.extend_from_slice(args);
+                    run_cmd("k3s", &k3s_args).await
+                }
+            }
+        }
+
+        let (fsinfo, images) = tokio::join!(
+            crictl(&["imagefsinfo", "-o", "json"]),
+            crictl(&["images", "-q"]),
+        );
+        let image_count = images.map(|out| {
+            out.lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .collect::<std::collections::BTreeSet<_>>()
+                .len() as u32
+        });
+
+        let mut bytes = fsinfo.as_deref().and_then(parse_crictl_imagefsinfo);
+        if bytes.is_none() {
+            for dir in CONTAINERD_SNAPSHOTTER_DIRS {
+                if !std::path::Path::new(dir).is_dir() {
+                    continue;
+                }
+                bytes = run_cmd("du", &["-sk", dir]).await.and_then(|out| {
+                    out.split_whitespace()
+                        .next()
+                        .and_then(|kb| kb.parse::<u64>().ok())
+                        .map(|kb| kb * 1024)
+                });
+                break;
+            }
+        }
+        (bytes, image_count)
+    }
+
+    async fn collect_k8s_resources() -> (u64, u64, u64, u64) {
+        // kubectl top node gives resource usage; describe node gives requests/limits
+        let output = run_cmd(
+            "kubectl",
+            &["describe", "nodes", "--request-timeout=5s"],
+        )
         .await
         .unwrap_or_default();
 
@@ -1374,16 +2153,35 @@ impl ReportCollector {
             .await
             .and_then(|s| s.trim().parse().ok());
 
+        // launchd jobs: loaded count and those with a nonzero last exit status
+        let (failed_units, loaded_services) = match run_cmd("launchctl", &["list"]).await {
+            Some(out) => {
+                let (failed, loaded) = parse_launchctl_list(&out);
+                (failed, Some(loaded))
+            }
+            None => (Vec::new(), None),
+        };
+
         Ok(HealthMetrics {
             load_average_1m: loads.first().copied().unwrap_or(0.0),
             load_average_5m: loads.get(1).copied().unwrap_or(0.0),
             load_average_15m: loads.get(2).copied().unwrap_or(0.0),
+            // Needs the hardware section; filled in once both are collected.
+            load_per_core: 0.0,
             memory_usage_percent,
             swap_usage_percent,
             cpu_usage_percent: cpu_usage,
             disk_usage,
             open_file_descriptors: None,
             max_file_descriptors: max_fds,
+            recent_oom_kills: 0,
+            oom_victims: Vec::new(),
+            degraded: !failed_units.is_empty(),
+            failed_units,
+            loaded_services,
+            memory_pressure: None,
+            cpu_pressure: None,
+            io_pressure: None,
         })
     }
 
@@ -1398,11 +2196,18 @@ impl ReportCollector {
             .filter_map(|s| s.parse().ok())
             .collect();
 
+        // Inside a container, usage is measured against the cgroup's limits
+        // rather than the host's totals.
+        let cgroup_root = std::path::Path::new(cgroup_limits::CGROUP_ROOT);
+        let cgroup = cgroup_limits::read_limits(cgroup_root).await;
+
         let (ram_total, ram_available) = Self::collect_memory_info().await;
-        let memory_usage_percent = if ram_total > 0 {
-            ((ram_total - ram_available) as f64 / ram_total as f64) * 100.0
-        } else {
-            0.0
+        let memory_usage_percent = match cgroup.memory_usage_percent() {
+            Some(percent) => percent,
+            None if ram_total > 0 => {
+                ((ram_total - ram_available) as f64 / ram_total as f64) * 100.0
+            }
+            None => 0.0,
         };
 
         let (swap_total, swap_used) = Self::collect_swap_info().await;
@@ -1413,7 +2218,20 @@ impl ReportCollector {
         };
 
         // CPU usage from /proc/stat (instantaneous snapshot — delta between two reads)
-        let cpu_usage = Self::sample_cpu_usage_linux().await;
+        let cpu_usage = match cgroup.cpu_quota {
+            Some(quota) => {
+                let (host, limited) = tokio::join!(
+                    Self::sample_cpu_usage_linux(),
+                    cgroup_limits::sample_cpu_percent(
+                        cgroup_root,
+                        quota,
+                        std::time::Duration::from_millis(200)
+                    )
+                );
+                limited.unwrap_or(host)
+            }
+            None => Self::sample_cpu_usage_linux().await,
+        };
 
         let disk_usage = Self::collect_disk_usage().await;
 
@@ -1429,19 +2247,68 @@ impl ReportCollector {
                 })
                 .unwrap_or((None, None));
 
+        // OOM-killer events from the kernel ring buffer over the last hour
+        let (recent_oom_kills, oom_victims) =
+            run_cmd("journalctl", &["-k", "--since", "-1h", "--no-pager", "-q"])
+                .await
+                .map(|log| parse_oom_kills(&log))
+                .unwrap_or_default();
+
+        let (failed_units, degraded) = Self::collect_systemd_state().await;
+
+        let (memory_pressure, cpu_pressure, io_pressure) = tokio::join!(
+            read_pressure("memory"),
+            read_pressure("cpu"),
+            read_pressure("io")
+        );
+
         Ok(HealthMetrics {
             load_average_1m: loads.first().copied().unwrap_or(0.0),
             load_average_5m: loads.get(1).copied().unwrap_or(0.0),
             load_average_15m: loads.get(2).copied().unwrap_or(0.0),
+            // Needs the hardware section; filled in once both are collected.
+            load_per_core: 0.0,
             memory_usage_percent,
             swap_usage_percent,
             cpu_usage_percent: cpu_usage,
             disk_usage,
             open_file_descriptors: open_fds,
             max_file_descriptors: max_fds,
+            recent_oom_kills,
+            oom_victims,
+            failed_units,
+            loaded_services: None,
+            degraded,
+            memory_pressure,
+            cpu_pressure,
+            io_pressure,
         })
     }
 
+    /// Failed units and degraded state from systemd. Empty on non-systemd hosts.
+    #[cfg(not(target_os = "macos"))]
+    async fn collect_systemd_state() -> (Vec<String>, bool) {
+        if !std::path::Path::new("/run/systemd/system").exists() {
+            return (Vec::new(), false);
+        }
+
+        let (failed, state) = tokio::join!(
+            run_cmd("systemctl", &["--failed", "--no-legend", "--plain"]),
+            // `is-system-running` exits non-zero when degraded, so read stdout directly
+            Command::new("systemctl").arg("is-system-running").output(),
+        );
+
+        let failed_units = failed
+            .map(|s| parse_systemctl_failed(&s))
+            .unwrap_or_default();
+        let system_degraded = state
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim() == "degraded")
+            .unwrap_or(false);
+
+        let degraded = system_degraded || !failed_units.is_empty();
+        (failed_units, degraded)
+    }
+
     #[cfg(not(target_os = "macos"))]
     async fn sample_cpu_usage_linux() -> f64 {
         // Read /proc/stat twice with 200ms gap
@@ -1486,11 +2353,11 @@ impl ReportCollector {
     }
 
     async fn collect_disk_usage() -> Vec<DiskUsage> {
-        let output = run_cmd("df", &["-k"]).await.unwrap_or_default();
+        let (output, inodes) = tokio::join!(run_cmd("df", &["-kP"]), collect_inode_counts());
+        let output = output.unwrap_or_default();
         let mut usage = Vec::new();
 
-        for line in output.lines().skip(1) {
-            let parts: Vec<&str> = line.split_whitespace().collect();
+        for parts in df_rows(&output) {
             if parts.len() < 5 {
                 continue;
             }
@@ -1508,9 +2375,14 @@ impl ReportCollector {
 
             if let Some(pct_str) = parts.get(4) {
                 if let Ok(pct) = pct_str.trim_end_matches('%').parse::<f64>() {
+                    let inode_percent = inodes
+                        .get(&mount)
+                        .filter(|(total, _)| *total > 0)
+                        .map(|(total, used)| *used as f64 / *total as f64 * 100.0);
                     usage.push(DiskUsage {
                         mount_point: mount,
                         usage_percent: pct,
+                        inode_percent,
                     });
                 }
             }
@@ -1522,65 +2394,65 @@ impl ReportCollector {
     // PROCESSES
     // ═══════════════════════════════════════════════════════════
 
-    async fn collect_processes() -> Result<ProcessSnapshot> {
-        // ps aux gives us everything we need cross-platform
-        let output = run_cmd("ps", &["aux"]).await.unwrap_or_default();
+    async fn collect_processes(group_by_program: bool) -> Result<ProcessSnapshot> {
+        // Explicit columns work on both procps and BSD ps; -ww stops long
+        // command lines from being cut at the terminal width.
+        let output = run_cmd("ps", PS_ARGS).await.unwrap_or_default();
 
         let mut total: u32 = 0;
         let mut running: u32 = 0;
         let mut zombie: u32 = 0;
-        let mut procs: Vec<(u32, String, f64, f64)> = Vec::new();
-
-        for line in output.lines().skip(1) {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 11 {
-                continue;
-            }
+        let mut procs: Vec<ProcessInfo> = Vec::new();
 
+        for row in output.lines().filter_map(parse_ps_line) {
             total += 1;
 
-            let pid: u32 = parts[1].parse().unwrap_or(0);
-            let cpu_pct: f64 = parts[2].parse().unwrap_or(0.0);
-            let mem_pct: f64 = parts[3].parse().unwrap_or(0.0);
-            let stat = parts[7];
-            let name = parts[10..].join(" ");
-
-            if stat.starts_with('R') {
+            if row.stat.starts_with('R') {
                 running += 1;
             }
-            if stat.starts_with('Z') {
+            if row.stat.starts_with('Z') {
                 zombie += 1;
             }
 
-            procs.push((pid, name, cpu_pct, mem_pct));
+            procs.push(ProcessInfo {
+                pid: row.pid,
+                program: program_name(&row.command),
+                name: row.command,
+                cpu_percent: row.cpu_percent,
+                memory_percent: row.memory_percent,
+            });
         }
 
+        let top_programs = if group_by_program {
+            group_by_program_name(&procs).into_iter().take(5).collect()
+        } else {
+            Vec::new()
+        };
+
         // Top 5 by CPU
-        procs.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+        procs.sort_by(|a, b| {
+            b.cpu_percent
+                .partial_cmp(&a.cpu_percent)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
         let top_cpu: Vec<ProcessInfo> = procs
             .iter()
             .take(5)
-            .filter(|p| p.2 > 0.0)
-            .map(|(pid, name, cpu, mem)| ProcessInfo {
-                pid: *pid,
-                name: name.clone(),
-                cpu_percent: *cpu,
-                memory_percent: *mem,
-            })
+            .filter(|p| p.cpu_percent > 0.0)
+            .cloned()
             .collect();
 
         // Top 5 by memory
-        procs.sort_by(|a, b| b.3.partial_cmp(&a.3).unwrap_or(std::cmp::Ordering::Equal));
+        procs.sort_by(|a, b| {
+            b.memory_percent
+                .partial_cmp(&a.memory_percent)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
         let top_memory: Vec<ProcessInfo> = procs
             .iter()
             .take(5)
-            .filter(|p| p.3 > 0.0)
-            .map(|(pid, name, cpu, mem)| ProcessInfo {
-                pid: *pid,
-                name: name.clone(),
-                cpu_percent: *cpu,
-                memory_percent: *mem,
-            })
+            .filter(|p| p.memory_percent > 0.0)
+            .cloned()
             .collect();
 
         Ok(ProcessSnapshot {
@@ -1589,6 +2461,7 @@ impl ReportCollector {
             zombie_processes: zombie,
             top_cpu,
             top_memory,
+            top_programs,
         })
     }
 
@@ -1606,7 +2479,7 @@ impl ReportCollector {
         let (firewall_active, firewall_rules_count, firewall_backend) = firewall;
         let (sshd_running, root_login_allowed, password_auth_enabled) = sshd_info;
 
-        Ok(SecuritySnapshot {
+        let mut snapshot = SecuritySnapshot {
             ssh_keys_deployed: ssh_keys,
             tls_certificates: Vec::new(),
             firewall_active,
@@ -1615,7 +2488,11 @@ impl ReportCollector {
             sshd_running,
             root_login_allowed,
             password_auth_enabled,
-        })
+            security_score: 0,
+            findings: Vec::new(),
+        };
+        (snapshot.security_score, snapshot.findings) = security_score::assess(&snapshot);
+        Ok(snapshot)
     }
 
     async fn collect_ssh_keys() -> Vec<String> {
@@ -1788,8 +2665,8 @@ fn parse_vm_stat_field(output: &str, field: &str) -> u64 {
         .unwrap_or(0)
 }
 
-#[cfg(not(target_os = "macos"))]
-fn parse_meminfo_kb(meminfo: &str, field: &str) -> u64 {
+/// Also used for remote collection, so not gated to Linux builds.
+pub(super) fn parse_meminfo_kb(meminfo: &str, field: &str) -> u64 {
     meminfo
         .lines()
         .find(|l| l.starts_with(field))
@@ -2138,6 +3015,429 @@ fn parse_linux_routes(json_str: &str) -> Vec<RouteSnapshot> {
         .collect()
 }
 
+/// When the system was last rebuilt, from symlink mtimes under `root`.
+/// NixOS re-creates `/run/current-system` on every activation; nix-darwin
+/// is read from its newest generation link, then the `system` profile.
+fn last_rebuild_timestamp(root: &std::path::Path) -> Option<chrono::DateTime<Utc>> {
+    let profiles = root.join("nix/var/nix/profiles");
+    if cfg!(target_os = "macos") {
+        newest_generation_link(&profiles).or_else(|| symlink_mtime(&profiles.join("system")))
+    } else {
+        symlink_mtime(&root.join("run/current-system"))
+            .or_else(|| newest_generation_link(&profiles))
+    }
+}
+
+/// Mtime of the newest `system-<n>-link` in a profiles directory.
+fn newest_generation_link(profiles_dir: &std::path::Path) -> Option<chrono::DateTime<Utc>> {
+    std::fs::read_dir(profiles_dir)
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with("system-") && name.ends_with("-link")
+        })
+        .filter_map(|e| symlink_mtime(&e.path()))
+        .max()
+}
+
+/// Mtime of the link itself, not its target (store paths all have mtime 1).
+fn symlink_mtime(path: &std::path::Path) -> Option<chrono::DateTime<Utc>> {
+    std::fs::symlink_metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .map(chrono::DateTime::<Utc>::from)
+}
+
+/// How many running builds a report lists.
+const MAX_ACTIVE_BUILDS: usize = 20;
+
+/// Derivations held by temporary roots in `nix-store --gc --print-roots`
+/// output (`{temp:<pid>} -> /nix/store/…drv`). Nix registers a temp root
+/// for each derivation it builds, but also for ones it merely evaluates,
+/// so [`building`] narrows these down.
+fn parse_temp_root_drvs(print_roots: &str) -> Vec<String> {
+    print_roots
+        .lines()
+        .filter_map(|line| line.split_once(" -> "))
+        .filter(|(root, _)| root.starts_with("{temp:"))
+        .map(|(_, path)| path.trim())
+        .filter(|path| path.ends_with(".drv"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Same as [`parse_temp_root_drvs`], read straight from the per-process
+/// files in `/nix/var/nix/temproots` (NUL-separated store paths). Files
+/// left behind by processes that have exited are skipped.
+fn temproots_drvs(dir: &std::path::Path) -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let proc = std::path::Path::new("/proc");
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|e| !proc.is_dir() || proc.join(e.file_name()).exists())
+        .filter_map(|e| std::fs::read(e.path()).ok())
+        .flat_map(|content| parse_temproots(&content))
+        .collect()
+}
+
+/// Derivation paths in one temproots file.
+fn parse_temproots(content: &[u8]) -> Vec<String> {
+    content
+        .split(|b| *b == 0)
+        .filter_map(|path| std::str::from_utf8(path).ok())
+        .filter(|path| path.ends_with(".drv"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Names of the derivations among `drvs` that are being built: a builder
+/// holds `<output>.lock` in the store for as long as it runs. At most
+/// [`MAX_ACTIVE_BUILDS`], by name.
+fn building(drvs: Vec<String>) -> Vec<String> {
+    let mut builds: Vec<String> = drvs
+        .into_iter()
+        .filter(|drv| is_building(std::path::Path::new(drv)))
+        .filter_map(|drv| drv_name(&drv))
+        .collect();
+    builds.sort();
+    builds.dedup();
+    builds.truncate(MAX_ACTIVE_BUILDS);
+    builds
+}
+
+/// Whether an output of the derivation at `drv` has its build lock held.
+fn is_building(drv: &std::path::Path) -> bool {
+    std::fs::read_to_string(drv).is_ok_and(|aterm| {
+        drv_outputs(&aterm)
+            .iter()
+            .any(|out| std::path::Path::new(&format!("{}.lock", out)).exists())
+    })
+}
+
+/// Output paths declared in a derivation's ATerm
+/// (`Derive([("out","/nix/store/…","",""),…],…)`). Content-addressed
+/// outputs have no path until built and are left out.
+fn drv_outputs(aterm: &str) -> Vec<&str> {
+    let Some(rest) = aterm.strip_prefix("Derive([") else {
+        return Vec::new();
+    };
+    let outputs = rest.find(")]").map_or("", |end| &rest[..end]);
+    outputs
+        .split("),(")
+        .filter_map(|output| output.split(',').nth(1))
+        .map(|path| path.trim_matches('"'))
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+/// `/nix/store/<hash>-hello-2.12.drv` → `hello-2.12`; `None` for anything
+/// that isn't a derivation.
+fn drv_name(path: &str) -> Option<String> {
+    let base = path.strip_prefix("/nix/store/")?.strip_suffix(".drv")?;
+    let (_hash, name) = base.split_once('-')?;
+    Some(name.to_string())
+}
+
+/// Total bytes of the `eval-cache-v*` entries under nix's cache dir.
+/// `None` when there is no eval cache at all.
+fn eval_cache_size(nix_cache_dir: &std::path::Path) -> Option<u64> {
+    let entries = std::fs::read_dir(nix_cache_dir).ok()?;
+    let caches: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().starts_with("eval-cache-"))
+        .collect();
+    if caches.is_empty() {
+        return None;
+    }
+    Some(caches.iter().map(|e| dir_size_bytes(&e.path())).sum())
+}
+
+/// Recursive size of a file or directory. Symlinks are not followed.
+fn dir_size_bytes(path: &std::path::Path) -> u64 {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| dir_size_bytes(&e.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+/// Parse `nix flake metadata --json` output.
+fn parse_flake_metadata(json: &str) -> Option<FlakeInfo> {
+    let v: serde_json::Value = serde_json::from_str(json).ok()?;
+    let url = v
+        .get("resolvedUrl")
+        .or_else(|| v.get("url"))
+        .and_then(|u| u.as_str())?
+        .to_string();
+    let locked_rev = v
+        .get("revision")
+        .or_else(|| v.get("locked").and_then(|l| l.get("rev")))
+        .and_then(|r| r.as_str())
+        .map(|r| r.to_string());
+    let last_modified = v
+        .get("lastModified")
+        .and_then(|t| t.as_i64())
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0));
+    Some(FlakeInfo {
+        url,
+        locked_rev,
+        last_modified,
+        configuration_revision: None,
+    })
+}
+
+/// Modification times of a flake's `flake.nix` and `flake.lock`; `None`
+/// when `dir` holds no flake.
+type FlakeStamp = (std::time::SystemTime, Option<std::time::SystemTime>);
+
+fn flake_stamp(dir: &std::path::Path) -> Option<FlakeStamp> {
+    let modified = |name| {
+        std::fs::metadata(dir.join(name))
+            .and_then(|m| m.modified())
+            .ok()
+    };
+    Some((modified("flake.nix")?, modified("flake.lock")))
+}
+
+/// Extract `configurationRevision` from `nixos-version --json` / `darwin-version --json`.
+fn parse_configuration_revision(json: &str) -> Option<String> {
+    let v: serde_json::Value = serde_json::from_str(json).ok()?;
+    v.get("configurationRevision")
+        .and_then(|r| r.as_str())
+        .map(|r| r.to_string())
+}
+
+/// Local directory behind a `path:` or `git+file://` flake URL.
+fn flake_source_dir(url: &str) -> Option<String> {
+    let rest = url
+        .strip_prefix("path:")
+        .or_else(|| url.strip_prefix("git+file://"))?;
+    let dir = rest.split('?').next().unwrap_or(rest);
+    (!dir.is_empty()).then(|| dir.to_string())
+}
+
+/// Whether `git status --porcelain` output shows changes a flake build
+/// would pick up. Untracked files (`??`) are ignored — flakes only see
+/// tracked files.
+fn git_status_is_dirty(status: &str) -> bool {
+    status
+        .lines()
+        .any(|line| !line.trim().is_empty() && !line.starts_with("??"))
+}
+
+// ── DNS resolver probes ────────────────────────────────────
+
+const DNS_PROBE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+/// resolv.conf has no port syntax; nameservers always listen on 53.
+const DNS_PORT: u16 = 53;
+
+/// Probe every resolver concurrently with an A-record lookup for `domain`.
+/// Returns nothing when there are no resolvers or probing is disabled.
+async fn probe_resolvers(resolvers: &[String], domain: &str) -> Vec<ResolverHealth> {
+    if domain.is_empty() || resolvers.is_empty() {
+        return Vec::new();
+    }
+    let mut probes = tokio::task::JoinSet::new();
+    for (idx, resolver) in resolvers.iter().enumerate() {
+        let resolver = resolver.clone();
+        let domain = domain.to_string();
+        probes.spawn(async move { (idx, probe_resolver(resolver, DNS_PORT, &domain).await) });
+    }
+    let mut results: Vec<(usize, ResolverHealth)> = probes.join_all().await;
+    results.sort_by_key(|(idx, _)| *idx);
+    results.into_iter().map(|(_, health)| health).collect()
+}
+
+async fn probe_resolver(resolver: String, port: u16, domain: &str) -> ResolverHealth {
+    let started = std::time::Instant::now();
+    let outcome =
+        tokio::time::timeout(DNS_PROBE_TIMEOUT, dns_query_a(&resolver, port, domain)).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    match outcome {
+        Ok(Ok(_answers)) => ResolverHealth {
+            resolver,
+            ok: true,
+            latency_ms: Some(latency_ms),
+            error: None,
+        },
+        Ok(Err(e)) => ResolverHealth {
+            resolver,
+            ok: false,
+            latency_ms: None,
+            error: Some(e.to_string()),
+        },
+        Err(_) => ResolverHealth {
+            resolver,
+            ok: false,
+            latency_ms: None,
+            error: Some(format!("timed out after {}s", DNS_PROBE_TIMEOUT.as_secs())),
+        },
+    }
+}
+
+/// Send a single A query over UDP and return the answer count.
+async fn dns_query_a(resolver: &str, port: u16, domain: &str) -> Result<u16> {
+    let addr = resolver_addr(resolver, port)?;
+    let bind = if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = tokio::net::UdpSocket::bind(bind).await?;
+    socket.connect(addr).await?;
+
+    let id: u16 = rand::random();
+    socket.send(&build_dns_query(id, domain)?).await?;
+    let mut buf = [0u8; 512];
+    let len = socket.recv(&mut buf).await?;
+    parse_dns_response(id, &buf[..len])
+}
+
+/// Socket address of a resolv.conf nameserver. A link-local IPv6 resolver
+/// carries its zone ("fe80::1%eth0"), which std can't parse; it becomes the
+/// scope id so the query leaves through that interface.
+fn resolver_addr(resolver: &str, port: u16) -> Result<std::net::SocketAddr> {
+    let (ip, zone) = match resolver.split_once('%') {
+        Some((ip, zone)) => (ip, Some(zone)),
+        None => (resolver, None),
+    };
+    let ip: std::net::IpAddr = ip
+        .parse()
+        .map_err(|_| anyhow::anyhow!("invalid resolver address"))?;
+    let (std::net::IpAddr::V6(v6), Some(zone)) = (ip, zone) else {
+        return Ok(std::net::SocketAddr::new(ip, port));
+    };
+    let scope_id = match zone.parse::<u32>() {
+        Ok(index) => index,
+        Err(_) => {
+            let name = std::ffi::CString::new(zone)
+                .map_err(|_| anyhow::anyhow!("invalid interface '{}'", zone))?;
+            // SAFETY: `name` is a valid NUL-terminated string.
+            match unsafe { libc::if_nametoindex(name.as_ptr()) } {
+                0 => anyhow::bail!("unknown interface '{}'", zone),
+                index => index,
+            }
+        }
+    };
+    Ok(std::net::SocketAddrV6::new(v6, port, 0, scope_id).into())
+}
+
+/// Build a recursive DNS query for the A record of `domain`.
+fn build_dns_query(id: u16, domain: &str) -> Result<Vec<u8>> {
+    let mut packet = Vec::with_capacity(12 + domain.len() + 6);
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&[0x01, 0x00]); // flags: RD
+    packet.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]); // QDCOUNT=1
+    for label in domain.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            anyhow::bail!("invalid probe domain '{}'", domain);
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&[0, 1, 0, 1]); // QTYPE=A, QCLASS=IN
+    Ok(packet)
+}
+
+/// Validate a DNS response to query `id`; returns the answer count.
+fn parse_dns_response(id: u16, packet: &[u8]) -> Result<u16> {
+    if packet.len() < 12 {
+        anyhow::bail!("short DNS response ({} bytes)", packet.len());
+    }
+    if u16::from_be_bytes([packet[0], packet[1]]) != id {
+        anyhow::bail!("DNS response ID mismatch");
+    }
+    if packet[2] & 0x80 == 0 {
+        anyhow::bail!("DNS packet is not a response");
+    }
+    let rcode = packet[3] & 0x0f;
+    let answers = u16::from_be_bytes([packet[6], packet[7]]);
+    match rcode {
+        0 if answers > 0 => Ok(answers),
+        0 => anyhow::bail!("no answers"),
+        2 => anyhow::bail!("SERVFAIL"),
+        3 => anyhow::bail!("NXDOMAIN"),
+        5 => anyhow::bail!("REFUSED"),
+        other => anyhow::bail!("DNS error rcode {}", other),
+    }
+}
+
+/// `ss -s`: the `TCP:` line carries per-state counts, e.g.
+/// `TCP:   1023 (estab 412, closed 580, orphaned 0, timewait 578)`
+/// (older versions print `timewait 578/0`), and the transport table's
+/// `UDP` row the UDP socket total. `closed` and `orphaned` are sockets no
+/// longer attached to a connection, so they are left out.
+#[cfg(any(not(target_os = "macos"), test))]
+fn parse_ss_summary(output: &str) -> ConnectionSummary {
+    let mut summary = ConnectionSummary::default();
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("TCP:") {
+            let counts = rest
+                .split_once('(')
+                .and_then(|(_, c)| c.split_once(')'))
+                .map(|(c, _)| c)
+                .unwrap_or("");
+            for entry in counts.split(',') {
+                let mut words = entry.split_whitespace();
+                let (Some(name), Some(count)) = (words.next(), words.next()) else {
+                    continue;
+                };
+                let Ok(count) = count.split('/').next().unwrap_or("").parse::<u32>() else {
+                    continue;
+                };
+                let state = match name {
+                    "closed" | "orphaned" => continue,
+                    "estab" => "ESTABLISHED".to_string(),
+                    "timewait" => "TIME_WAIT".to_string(),
+                    "synrecv" => "SYN_RECV".to_string(),
+                    other => other.to_uppercase(),
+                };
+                summary.tcp_states.insert(state, count);
+            }
+        } else if let Some(rest) = line.strip_prefix("UDP") {
+            if let Some(Ok(total)) = rest.split_whitespace().next().map(str::parse) {
+                summary.udp_sockets = total;
+            }
+        }
+    }
+    summary
+}
+
+/// `netstat -an` (macOS): one row per socket; TCP rows end in their state.
+#[cfg(any(target_os = "macos", test))]
+fn parse_netstat_connections(output: &str) -> ConnectionSummary {
+    let mut summary = ConnectionSummary::default();
+    for line in output.lines() {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        match parts.first() {
+            Some(proto) if proto.starts_with("tcp") && parts.len() >= 6 => {
+                *summary
+                    .tcp_states
+                    .entry(parts[parts.len() - 1].to_string())
+                    .or_insert(0) += 1;
+            }
+            Some(proto) if proto.starts_with("udp") => summary.udp_sockets += 1,
+            _ => {}
+        }
+    }
+    summary
+}
+
 fn parse_resolv_conf(content: &str) -> Vec<String> {
     content
         .lines()
@@ -2147,8 +3447,8 @@ fn parse_resolv_conf(content: &str) -> Vec<String> {
         .collect()
 }
 
-#[cfg(not(target_os = "macos"))]
-fn parse_os_release_field(content: &str, field: &str) -> Option<String> {
+/// Also used for remote collection, so not gated to Linux builds.
+pub(super) fn parse_os_release_field(content: &str, field: &str) -> Option<String> {
     content
         .lines()
         .find(|l| l.starts_with(&format!("{}=", field)))
@@ -2186,115 +3486,761 @@ async fn detect_wsl() -> bool {
         .unwrap_or(false)
 }
 
-fn parse_k8s_cpu(s: &str) -> u64 {
-    // "250m" → 250, "1" → 1000
-    if let Some(millis) = s.strip_suffix('m') {
-        millis.parse().unwrap_or(0)
-    } else {
-        s.parse::<u64>().unwrap_or(0) * 1000
+/// Largest clock offset from the time server still reported as synced.
+#[cfg(any(target_os = "macos", test))]
+const MAX_CLOCK_OFFSET_SECS: f64 = 1.0;
+
+/// `timedatectl show -p NTPSynchronized` prints `NTPSynchronized=yes`.
+#[cfg(any(not(target_os = "macos"), test))]
+fn parse_ntp_synchronized(output: &str) -> Option<bool> {
+    let value = output
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("NTPSynchronized="))?;
+    match value.trim() {
+        "yes" => Some(true),
+        "no" => Some(false),
+        _ => None,
     }
 }
 
-fn parse_k8s_memory(s: &str) -> u64 {
-    if let Some(gi) = s.strip_suffix("Gi") {
-        gi.parse::<u64>().unwrap_or(0) * 1024 * 1024 * 1024
-    } else if let Some(mi) = s.strip_suffix("Mi") {
-        mi.parse::<u64>().unwrap_or(0) * 1024 * 1024
-    } else if let Some(ki) = s.strip_suffix("Ki") {
-        ki.parse::<u64>().unwrap_or(0) * 1024
-    } else {
-        s.parse().unwrap_or(0)
-    }
+/// Offset in seconds from `sntp` output, e.g.
+/// `+0.012345 +/- 0.028290 time.apple.com 17.253.4.125`.
+#[cfg(any(target_os = "macos", test))]
+fn parse_sntp_offset(output: &str) -> Option<f64> {
+    output
+        .lines()
+        .find(|l| l.contains("+/-"))
+        .and_then(|l| l.split_whitespace().next())
+        .and_then(|v| v.parse().ok())
 }
 
-// ═══════════════════════════════════════════════════════════════
-// Defaults for error fallback
-// ═══════════════════════════════════════════════════════════════
+/// Offset in milliseconds from the `System time` line of `chronyc
+/// tracking`, e.g. `System time : 0.000123456 seconds fast of NTP time`.
+/// A clock running fast is a positive offset.
+#[cfg(any(not(target_os = "macos"), test))]
+fn parse_chrony_offset_ms(output: &str) -> Option<f64> {
+    let value = output
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("System time"))?
+        .trim_start()
+        .strip_prefix(':')?;
+    let mut parts = value.split_whitespace();
+    let secs: f64 = parts.next()?.parse().ok()?;
+    let sign = match (parts.next()?, parts.next()?) {
+        ("seconds", "fast") => 1.0,
+        ("seconds", "slow") => -1.0,
+        _ => return None,
+    };
+    Some(sign * secs * 1000.0)
+}
 
-fn default_hardware() -> HardwareSnapshot {
-    HardwareSnapshot {
-        cpu_model: "unknown".into(),
-        cpu_vendor: "unknown".into(),
-        cpu_architecture: "unknown".into(),
-        cpu_cores: 0,
-        cpu_threads: 0,
-        cpu_frequency_mhz: None,
-        cpu_cache_bytes: None,
-        ram_total_bytes: 0,
-        ram_available_bytes: 0,
-        swap_total_bytes: 0,
-        swap_used_bytes: 0,
-        disks: Vec::new(),
-        gpus: Vec::new(),
-        temperatures: Vec::new(),
-        power: None,
-    }
+/// Offset in milliseconds from `timedatectl timesync-status`, e.g.
+/// `Offset: -1.234ms` (systemd picks us, ms or s as the unit).
+#[cfg(any(not(target_os = "macos"), test))]
+fn parse_timesync_offset_ms(output: &str) -> Option<f64> {
+    let value = output
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("Offset:"))?
+        .trim();
+    let (number, scale) = if let Some(v) = value.strip_suffix("us") {
+        (v, 0.001)
+    } else if let Some(v) = value.strip_suffix("ms") {
+        (v, 1.0)
+    } else {
+        (value.strip_suffix('s')?, 1000.0)
+    };
+    number.trim().parse::<f64>().ok().map(|n| n * scale)
 }
 
-fn default_os() -> OsSnapshot {
-    OsSnapshot {
-        distribution: "unknown".into(),
-        version: "unknown".into(),
-        kernel_version: "unknown".into(),
-        architecture: "unknown".into(),
-        platform_triple: "unknown".into(),
-        hostname: "unknown".into(),
-        product_name: None,
-        build_id: None,
-        systemd_version: None,
-        boot_time: None,
-        uptime_secs: 0,
-        timezone: None,
-        is_wsl: false,
-        virtualization: None,
+/// `systemsetup -getusingnetworktime` prints `Network Time: On`.
+#[cfg(any(target_os = "macos", test))]
+fn parse_network_time_setting(output: &str) -> Option<bool> {
+    match output.trim().strip_prefix("Network Time:")?.trim() {
+        "On" => Some(true),
+        "Off" => Some(false),
+        _ => None,
     }
 }
 
-fn default_network() -> NetworkSnapshot {
-    NetworkSnapshot {
-        hostname: "unknown".into(),
-        interfaces: Vec::new(),
-        routes: Vec::new(),
-        dns_resolvers: Vec::new(),
-        default_gateway: None,
-        listening_ports: Vec::new(),
-    }
+/// Contents of `/proc/sys/kernel/random/entropy_avail`.
+#[cfg(any(not(target_os = "macos"), test))]
+fn parse_entropy_avail(content: &str) -> Option<u64> {
+    content.trim().parse().ok()
 }
 
-fn default_nix() -> NixSnapshot {
-    NixSnapshot {
-        nix_version: "unknown".into(),
-        store_size_bytes: 0,
-        store_path_count: 0,
-        gc_roots_count: 0,
-        last_rebuild_timestamp: None,
-        current_system_path: None,
-        substituters: Vec::new(),
-        system_generations: 0,
-        channels: Vec::new(),
-        trusted_users: Vec::new(),
-        max_jobs: None,
-        sandbox_enabled: false,
+/// Detect a pending reboot after a kernel update. `root` is the filesystem
+/// root (`/` in production) so the checks can be exercised against a fixture.
+///
+/// In order: the Debian/Ubuntu `/run/reboot-required` marker, NixOS
+/// `/run/booted-system` vs `/run/current-system` kernel, then the running
+/// kernel against the newest entry in `/lib/modules`.
+#[cfg(not(target_os = "macos"))]
+fn detect_reboot_required(root: &std::path::Path, running_kernel: &str) -> bool {
+    if root.join("run/reboot-required").exists() {
+        return true;
     }
-}
 
-fn default_health() -> HealthMetrics {
-    HealthMetrics {
-        load_average_1m: 0.0,
-        load_average_5m: 0.0,
-        load_average_15m: 0.0,
-        memory_usage_percent: 0.0,
-        swap_usage_percent: 0.0,
-        cpu_usage_percent: 0.0,
-        disk_usage: Vec::new(),
-        open_file_descriptors: None,
-        max_file_descriptors: None,
+    let booted = std::fs::canonicalize(root.join("run/booted-system/kernel"));
+    let current = std::fs::canonicalize(root.join("run/current-system/kernel"));
+    if let (Ok(booted), Ok(current)) = (booted, current) {
+        return booted != current;
+    }
+
+    let Ok(entries) = std::fs::read_dir(root.join("lib/modules")) else {
+        return false;
+    };
+    let newest = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .max_by_key(|name| kernel_version_key(name));
+    match newest {
+        Some(newest) if !running_kernel.is_empty() && running_kernel != "unknown" => {
+            kernel_version_key(&newest) > kernel_version_key(running_kernel)
+        }
+        _ => false,
     }
 }
 
-fn default_security() -> SecuritySnapshot {
-    SecuritySnapshot {
-        ssh_keys_deployed: Vec::new(),
+/// Numeric components of a kernel release ("6.12.3-arch1-1" → [6, 12, 3, 1, 1]).
+#[cfg(not(target_os = "macos"))]
+fn kernel_version_key(release: &str) -> Vec<u64> {
+    release
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|part| part.parse().ok())
+        .collect()
+}
+
+/// PSI for one resource (`memory`, `cpu`, `io`); `None` on kernels without
+/// `/proc/pressure` or with PSI disabled.
+#[cfg(not(target_os = "macos"))]
+async fn read_pressure(resource: &str) -> Option<PressureStall> {
+    let content = tokio::fs::read_to_string(format!("/proc/pressure/{}", resource))
+        .await
+        .ok()?;
+    parse_pressure(&content)
+}
+
+/// Parse a `/proc/pressure/*` file:
+/// `some avg10=1.53 avg60=0.87 avg300=0.22 total=1234567` plus, except for
+/// CPU on older kernels, a matching `full` line.
+#[cfg(not(target_os = "macos"))]
+fn parse_pressure(content: &str) -> Option<PressureStall> {
+    let mut some = None;
+    let mut full = None;
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let slot = match fields.next() {
+            Some("some") => &mut some,
+            Some("full") => &mut full,
+            _ => continue,
+        };
+        let avg = |key: &str| -> Option<f64> {
+            fields
+                .clone()
+                .find_map(|f| f.strip_prefix(key)?.strip_prefix('=')?.parse().ok())
+        };
+        *slot = Some(StallAverages {
+            avg10: avg("avg10")?,
+            avg60: avg("avg60")?,
+            avg300: avg("avg300")?,
+        });
+    }
+    Some(PressureStall { some: some?, full })
+}
+
+/// Count OOM-killer kills in kernel log output and collect the victim names.
+///
+/// Matches both "Out of memory: Killed process 1234 (java) ..." and the
+/// cgroup variant "Memory cgroup out of memory: Killed process ...".
+#[cfg(not(target_os = "macos"))]
+fn parse_oom_kills(log: &str) -> (u32, Vec<String>) {
+    let mut kills = 0;
+    let mut victims: Vec<String> = Vec::new();
+    for line in log.lines() {
+        if !line.to_lowercase().contains("out of memory") {
+            continue;
+        }
+        let Some(rest) = line
+            .split_once("Killed process ")
+            .or_else(|| line.split_once("Kill process "))
+            .map(|(_, rest)| rest)
+        else {
+            continue;
+        };
+        kills += 1;
+        let name = rest
+            .split_once('(')
+            .and_then(|(_, after)| after.split_once(')'))
+            .map(|(name, _)| name.to_string());
+        if let Some(name) = name {
+            if !victims.contains(&name) {
+                victims.push(name);
+            }
+        }
+    }
+    (kills, victims)
+}
+
+/// `ps` invocation read by [`parse_ps_line`]; `=` suppresses the header.
+const PS_ARGS: &[&str] = &[
+    "-ww",
+    "-eo",
+    "pid=,ppid=,user=,pcpu=,pmem=,rss=,stat=,args=",
+];
+
+/// One process as listed by `ps`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessEntry {
+    pub pid: u32,
+    pub ppid: u32,
+    pub user: String,
+    pub cpu_percent: f64,
+    pub memory_percent: f64,
+    pub rss_kb: u64,
+    #[serde(skip)]
+    stat: String,
+    pub command: String,
+}
+
+/// One line of `ps -eo pid=,ppid=,user=,pcpu=,pmem=,rss=,stat=,args=`. The
+/// command is the rest of the line with its own spacing intact.
+fn parse_ps_line(line: &str) -> Option<ProcessEntry> {
+    let mut rest = line.trim_start();
+    let mut fields = [""; 7];
+    for field in &mut fields {
+        let (value, tail) = rest.split_once(char::is_whitespace)?;
+        *field = value;
+        rest = tail.trim_start();
+    }
+    let command = rest.trim_end();
+    if command.is_empty() {
+        return None;
+    }
+    Some(ProcessEntry {
+        pid: fields[0].parse().ok()?,
+        ppid: fields[1].parse().unwrap_or(0),
+        user: fields[2].to_string(),
+        cpu_percent: fields[3].parse().unwrap_or(0.0),
+        memory_percent: fields[4].parse().unwrap_or(0.0),
+        rss_kb: fields[5].parse().unwrap_or(0),
+        stat: fields[6].to_string(),
+        command: command.to_string(),
+    })
+}
+
+/// Friendly name for a `ps` command line: the basename of its executable.
+/// A bare `/nix/store/<hash>-<name>` loses its hash, and Nix wrapper
+/// naming (`.python3.11-wrapped`) is undone. Kernel threads (`[kworker/0:1]`)
+/// are kept as they are.
+fn program_name(command: &str) -> String {
+    let command = command.trim();
+    if command.starts_with('[') {
+        return command.to_string();
+    }
+    let exe = command.split_whitespace().next().unwrap_or_default();
+    // Processes that retitle themselves ("nginx: worker process").
+    let exe = exe.trim_end_matches(':');
+    let base = match exe.strip_prefix("/nix/store/") {
+        Some(entry) if !entry.contains('/') => {
+            entry.split_once('-').map_or(entry, |(_, name)| name)
+        }
+        _ => exe.rsplit('/').next().unwrap_or(exe),
+    };
+    let unwrapped = base
+        .strip_prefix('.')
+        .and_then(|b| b.strip_suffix("-wrapped"));
+    unwrapped.unwrap_or(base).to_string()
+}
+
+/// Sum CPU and memory per program, busiest CPU first (then memory, then name).
+fn group_by_program_name(procs: &[ProcessInfo]) -> Vec<ProgramUsage> {
+    let mut programs: BTreeMap<&str, ProgramUsage> = BTreeMap::new();
+    for p in procs {
+        let usage = programs
+            .entry(p.program.as_str())
+            .or_insert_with(|| ProgramUsage {
+                program: p.program.clone(),
+                processes: 0,
+                cpu_percent: 0.0,
+                memory_percent: 0.0,
+            });
+        usage.processes += 1;
+        usage.cpu_percent += p.cpu_percent;
+        usage.memory_percent += p.memory_percent;
+    }
+    let mut programs: Vec<ProgramUsage> = programs.into_values().collect();
+    programs.sort_by(|a, b| {
+        b.cpu_percent
+            .total_cmp(&a.cpu_percent)
+            .then(b.memory_percent.total_cmp(&a.memory_percent))
+            .then_with(|| a.program.cmp(&b.program))
+    });
+    programs
+}
+
+/// Every process from a fresh `ps`, as one JSON object per line. Lines are
+/// forwarded as `ps` writes them rather than after it exits.
+pub fn process_ndjson() -> Result<impl Stream<Item = std::io::Result<String>>> {
+    let mut child = Command::new("ps")
+        .args(PS_ARGS)
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("spawning ps")?;
+    let stdout = child.stdout.take().context("ps stdout not captured")?;
+    // The child rides along in the stream so dropping the response kills it.
+    Ok(ndjson_lines(BufReader::new(stdout)).map(move |line| {
+        let _alive = &child;
+        line
+    }))
+}
+
+fn ndjson_lines<R: AsyncBufRead + Unpin>(reader: R) -> impl Stream<Item = std::io::Result<String>> {
+    stream::unfold(reader.lines(), |mut lines| async move {
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    let Some(entry) = parse_ps_line(&line) else {
+                        continue;
+                    };
+                    let json = serde_json::to_string(&entry).map_err(std::io::Error::other);
+                    return Some((json.map(|j| j + "\n"), lines));
+                }
+                Ok(None) => return None,
+                Err(e) => return Some((Err(e), lines)),
+            }
+        }
+    })
+}
+
+/// Whitespace-split `df` rows after the header. A device name too long for
+/// its column makes df print it alone and continue on the next line; such
+/// lines are joined back onto the row they belong to.
+fn df_rows(output: &str) -> Vec<Vec<&str>> {
+    let mut rows = Vec::new();
+    let mut pending: Vec<&str> = Vec::new();
+    for line in output.lines().skip(1) {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.is_empty() {
+            continue;
+        }
+        if pending.is_empty() && parts.len() == 1 {
+            pending = parts;
+            continue;
+        }
+        pending.extend(parts);
+        rows.push(std::mem::take(&mut pending));
+    }
+    rows
+}
+
+/// `(total, used)` inodes per mount point from `df -iP`.
+async fn collect_inode_counts() -> HashMap<String, (u64, u64)> {
+    run_cmd("df", &["-iP"])
+        .await
+        .map(|out| parse_df_inodes(&out))
+        .unwrap_or_default()
+}
+
+/// Parse `df -iP`. GNU df prints `Inodes IUsed IFree IUse%`; BSD/macOS df
+/// keeps the block columns and appends `iused ifree %iused`. Rows without
+/// numeric counts (`-` on filesystems without an inode table) and rows
+/// reporting zero inodes are skipped.
+fn parse_df_inodes(output: &str) -> HashMap<String, (u64, u64)> {
+    let header: Vec<String> = output
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_ascii_lowercase)
+        .collect();
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let Some(used_col) = column("iused") else {
+        return HashMap::new();
+    };
+    let (total_col, free_col) = (column("inodes"), column("ifree"));
+
+    let mut counts = HashMap::new();
+    for parts in df_rows(output) {
+        let Some(mount) = parts.last() else { continue };
+        let number = |i: usize| parts.get(i).and_then(|v| v.parse::<u64>().ok());
+        let Some(used) = number(used_col) else {
+            continue;
+        };
+        let total = match total_col {
+            Some(col) => number(col),
+            None => free_col.and_then(number).map(|free| used + free),
+        };
+        if let Some(total) = total.filter(|t| *t > 0) {
+            counts.insert(mount.to_string(), (total, used));
+        }
+    }
+    counts
+}
+
+/// Unit names from `systemctl --failed --no-legend --plain`.
+#[cfg(not(target_os = "macos"))]
+fn parse_systemctl_failed(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            line.split_whitespace()
+                .find(|token| *token != "●" && *token != "*")
+                .map(|unit| unit.to_string())
+        })
+        .collect()
+}
+
+/// Parse `launchctl list` (PID, Status, Label; tab-separated) into the labels
+/// with a nonzero last exit status and the total number of loaded jobs.
+#[cfg(any(target_os = "macos", test))]
+fn parse_launchctl_list(output: &str) -> (Vec<String>, u32) {
+    let mut failed = Vec::new();
+    let mut loaded = 0;
+    for line in output.lines() {
+        let mut cols = line.split('\t');
+        let (Some(_pid), Some(status), Some(label)) = (cols.next(), cols.next(), cols.next())
+        else {
+            continue;
+        };
+        let Ok(status) = status.trim().parse::<i32>() else {
+            // Header line ("PID Status Label")
+            continue;
+        };
+        loaded += 1;
+        if status != 0 {
+            failed.push(label.trim().to_string());
+        }
+    }
+    (failed, loaded)
+}
+
+/// How many nix-daemon error lines a report keeps.
+const NIX_ERROR_LINES: usize = 20;
+
+/// Last `limit` log lines, skipping journal markers ("-- No entries --",
+/// "-- Boot ... --") and blanks. With `require_error`, only lines mentioning
+/// "error" are kept (for logs without priorities).
+fn recent_error_lines(log: &str, require_error: bool, limit: usize) -> Vec<String> {
+    let lines: Vec<&str> = log
+        .lines()
+        .map(str::trim_end)
+        .filter(|l| !l.trim().is_empty() && !l.starts_with("-- "))
+        .filter(|l| !require_error || l.to_lowercase().contains("error"))
+        .collect();
+    lines[lines.len().saturating_sub(limit)..]
+        .iter()
+        .map(|l| l.to_string())
+        .collect()
+}
+
+/// Parse `lspci -mm`: `Slot "Class" "Vendor" "Device" [-rXX] "SubVendor" "SubDevice"`.
+#[cfg(not(target_os = "macos"))]
+fn parse_lspci_mm(output: &str) -> Vec<PciDevice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let parts: Vec<&str> = line.split('"').collect();
+            if parts.len() < 6 {
+                return None;
+            }
+            Some(PciDevice {
+                slot: parts[0].trim().to_string(),
+                class: parts[1].to_string(),
+                vendor: parts[3].to_string(),
+                device: parts[5].to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse `lsusb`: `Bus 001 Device 002: ID 8087:0024 Intel Corp. Integrated Rate Matching Hub`.
+/// lsusb prints vendor and product as one string, so it all goes in `device`.
+#[cfg(not(target_os = "macos"))]
+fn parse_lsusb(output: &str) -> Vec<UsbDevice> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" ID ")?;
+            let (ids, name) = rest.split_once(' ').unwrap_or((rest, ""));
+            let (vendor_id, product_id) = ids.split_once(':')?;
+            Some(UsbDevice {
+                vendor_id: vendor_id.to_string(),
+                product_id: product_id.to_string(),
+                vendor: None,
+                device: name.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parse `/proc/mdstat`. Each array starts with `mdX : <state> [<level>]
+/// <members>`; the indented lines after it carry the `[n/m] [UU]` member
+/// status and any running resync or recovery.
+#[cfg(any(not(target_os = "macos"), test))]
+fn parse_mdstat(output: &str) -> Vec<RaidArray> {
+    let mut arrays: Vec<RaidArray> = Vec::new();
+    for line in output.lines() {
+        if let Some((name, rest)) = line.split_once(" : ") {
+            let name = name.trim();
+            if !name.starts_with("md") {
+                continue;
+            }
+            let mut words = rest.split_whitespace().peekable();
+            let mut state = words.next().unwrap_or("unknown").to_string();
+            while let Some(qualifier) = words.next_if(|w| w.starts_with('(')) {
+                state = format!("{} {}", state, qualifier);
+            }
+            let level = words.next_if(|w| !w.contains('[')).map(str::to_string);
+            let devices: Vec<String> = words
+                .map(|w| match w.split_once('[') {
+                    Some((dev, mark)) => {
+                        let flag = mark.split_once(']').map(|(_, f)| f).unwrap_or("");
+                        format!("{}{}", dev, flag)
+                    }
+                    None => w.to_string(),
+                })
+                .collect();
+            let degraded = devices.iter().any(|d| d.ends_with("(F)"));
+            arrays.push(RaidArray {
+                name: name.to_string(),
+                level,
+                state,
+                devices,
+                member_status: None,
+                degraded,
+                sync_progress: None,
+            });
+            continue;
+        }
+
+        let Some(array) = arrays.last_mut() else {
+            continue;
+        };
+        if !line.starts_with(char::is_whitespace) {
+            continue;
+        }
+        if line.contains(" blocks") {
+            let status = line
+                .split_whitespace()
+                .rev()
+                .find_map(|w| w.strip_prefix('[')?.strip_suffix(']'))
+                .filter(|s| !s.is_empty() && s.chars().all(|c| c == 'U' || c == '_'));
+            if let Some(status) = status {
+                array.degraded |= status.contains('_');
+                array.member_status = Some(status.to_string());
+            }
+        } else if let Some((action, rest)) = ["recovery", "resync", "reshape", "check"]
+            .iter()
+            .find_map(|a| Some((*a, line.split_once(&format!("{} =", a))?.1)))
+        {
+            if let Some(pct) = rest.split_whitespace().next() {
+                array.sync_progress = Some(format!("{} {}", action, pct));
+            }
+        }
+    }
+    arrays
+}
+
+/// Walk the nested `_items` tree of `system_profiler SPUSBDataType -json`.
+/// Buses and hubs without a vendor ID are descended into but not reported.
+#[cfg(any(target_os = "macos", test))]
+fn parse_system_profiler_usb(json: &str) -> Vec<UsbDevice> {
+    fn hex_id(v: Option<&serde_json::Value>) -> Option<String> {
+        // e.g. "0x05ac  (Apple Inc.)" or "apple_vendor_id"
+        let raw = v?.as_str()?.split_whitespace().next()?;
+        Some(raw.trim_start_matches("0x").to_string())
+    }
+
+    fn walk(items: &[serde_json::Value], out: &mut Vec<UsbDevice>) {
+        for item in items {
+            if let Some(vendor_id) = hex_id(item.get("vendor_id")) {
+                out.push(UsbDevice {
+                    vendor_id,
+                    product_id: hex_id(item.get("product_id")).unwrap_or_default(),
+                    vendor: item
+                        .get("manufacturer")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string()),
+                    device: item
+                        .get("_name")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("unknown")
+                        .to_string(),
+                });
+            }
+            if let Some(children) = item.get("_items").and_then(|v| v.as_array()) {
+                walk(children, out);
+            }
+        }
+    }
+
+    let parsed: serde_json::Value = serde_json::from_str(json).unwrap_or(serde_json::Value::Null);
+    let mut devices = Vec::new();
+    if let Some(buses) = parsed.get("SPUSBDataType").and_then(|v| v.as_array()) {
+        walk(buses, &mut devices);
+    }
+    devices
+}
+
+fn parse_k8s_cpu(s: &str) -> u64 {
+    // "250m" → 250, "1" → 1000
+    if let Some(millis) = s.strip_suffix('m') {
+        millis.parse().unwrap_or(0)
+    } else {
+        s.parse::<u64>().unwrap_or(0) * 1000
+    }
+}
+
+/// Where containerd keeps unpacked image layers (k3s-embedded, standalone).
+const CONTAINERD_SNAPSHOTTER_DIRS: &[&str] = &[
+    "/var/lib/rancher/k3s/agent/containerd/io.containerd.snapshotter.v1.overlayfs",
+    "/var/lib/containerd/io.containerd.snapshotter.v1.overlayfs",
+];
+
+/// Total `usedBytes` from `crictl imagefsinfo -o json`. Newer crictl nests
+/// filesystems under `status.imageFilesystems`; older releases put a single
+/// one directly under `status`. CRI encodes the uint64 as a string.
+fn parse_crictl_imagefsinfo(json: &str) -> Option<u64> {
+    let value: serde_json::Value = serde_json::from_str(json).ok()?;
+    let status = value.get("status")?;
+    let used = |fs: &serde_json::Value| -> Option<u64> {
+        let v = fs.get("usedBytes")?.get("value")?;
+        v.as_u64().or_else(|| v.as_str()?.parse().ok())
+    };
+    match status.get("imageFilesystems").and_then(|f| f.as_array()) {
+        Some(filesystems) => filesystems.iter().map(used).sum(),
+        None => used(status),
+    }
+}
+
+fn parse_k8s_memory(s: &str) -> u64 {
+    if let Some(gi) = s.strip_suffix("Gi") {
+        gi.parse::<u64>().unwrap_or(0) * 1024 * 1024 * 1024
+    } else if let Some(mi) = s.strip_suffix("Mi") {
+        mi.parse::<u64>().unwrap_or(0) * 1024 * 1024
+    } else if let Some(ki) = s.strip_suffix("Ki") {
+        ki.parse::<u64>().unwrap_or(0) * 1024
+    } else {
+        s.parse().unwrap_or(0)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════════
+// Defaults for error fallback
+// ═══════════════════════════════════════════════════════════════
+
+pub(super) fn default_hardware() -> HardwareSnapshot {
+    HardwareSnapshot {
+        cpu_model: "unknown".into(),
+        cpu_vendor: "unknown".into(),
+        cpu_architecture: "unknown".into(),
+        cpu_cores: 0,
+        cpu_threads: 0,
+        cpu_frequency_mhz: None,
+        cpu_cache_bytes: None,
+        ram_total_bytes: 0,
+        ram_available_bytes: 0,
+        swap_total_bytes: 0,
+        swap_used_bytes: 0,
+        disks: Vec::new(),
+        gpus: Vec::new(),
+        temperatures: Vec::new(),
+        power: None,
+        pci_devices: Vec::new(),
+        usb_devices: Vec::new(),
+        raid_arrays: Vec::new(),
+        cgroup_memory_limit_bytes: None,
+        cgroup_cpu_quota: None,
+    }
+}
+
+pub(super) fn default_os() -> OsSnapshot {
+    OsSnapshot {
+        distribution: "unknown".into(),
+        version: "unknown".into(),
+        kernel_version: "unknown".into(),
+        architecture: "unknown".into(),
+        platform_triple: "unknown".into(),
+        hostname: "unknown".into(),
+        product_name: None,
+        build_id: None,
+        systemd_version: None,
+        boot_time: None,
+        uptime_secs: 0,
+        timezone: None,
+        is_wsl: false,
+        virtualization: None,
+        reboot_required: false,
+        time_synced: None,
+        clock_offset_ms: None,
+        entropy_available: None,
+    }
+}
+
+pub(super) fn default_network() -> NetworkSnapshot {
+    NetworkSnapshot {
+        hostname: "unknown".into(),
+        interfaces: Vec::new(),
+        routes: Vec::new(),
+        dns_resolvers: Vec::new(),
+        default_gateway: None,
+        listening_ports: Vec::new(),
+        dns_resolver_health: Vec::new(),
+        connection_summary: ConnectionSummary::default(),
+    }
+}
+
+pub(super) fn default_nix() -> NixSnapshot {
+    NixSnapshot {
+        nix_version: "unknown".into(),
+        store_size_bytes: 0,
+        store_path_count: 0,
+        gc_roots_count: 0,
+        last_rebuild_timestamp: None,
+        current_system_path: None,
+        substituters: Vec::new(),
+        system_generations: 0,
+        channels: Vec::new(),
+        trusted_users: Vec::new(),
+        max_jobs: None,
+        sandbox_enabled: false,
+        system_flake: None,
+        eval_cache_size_bytes: None,
+        eval_cache_enabled: None,
+        recent_nix_errors: Vec::new(),
+        config_source_dirty: None,
+        config_source_rev: None,
+        active_builds: Vec::new(),
+    }
+}
+
+pub(super) fn default_health() -> HealthMetrics {
+    HealthMetrics {
+        load_average_1m: 0.0,
+        load_average_5m: 0.0,
+        load_average_15m: 0.0,
+        load_per_core: 0.0,
+        memory_usage_percent: 0.0,
+        swap_usage_percent: 0.0,
+        cpu_usage_percent: 0.0,
+        disk_usage: Vec::new(),
+        open_file_descriptors: None,
+        max_file_descriptors: None,
+        recent_oom_kills: 0,
+        oom_victims: Vec::new(),
+        failed_units: Vec::new(),
+        loaded_services: None,
+        degraded: false,
+        memory_pressure: None,
+        cpu_pressure: None,
+        io_pressure: None,
+    }
+}
+
+pub(super) fn default_security() -> SecuritySnapshot {
+    SecuritySnapshot {
+        ssh_keys_deployed: Vec::new(),
         tls_certificates: Vec::new(),
         firewall_active: false,
         firewall_rules_count: 0,
@@ -2302,156 +4248,1128 @@ fn default_security() -> SecuritySnapshot {
         sshd_running: false,
         root_login_allowed: true,
         password_auth_enabled: true,
+        security_score: 0,
+        findings: Vec::new(),
+    }
+}
+
+pub(super) fn default_processes() -> ProcessSnapshot {
+    ProcessSnapshot {
+        total_processes: 0,
+        running_processes: 0,
+        zombie_processes: 0,
+        top_cpu: Vec::new(),
+        top_memory: Vec::new(),
+        top_programs: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ── parse_k8s_cpu tests ──────────────────────────────
+
+    #[test]
+    fn parse_k8s_cpu_millis() {
+        assert_eq!(parse_k8s_cpu("250m"), 250);
+        assert_eq!(parse_k8s_cpu("100m"), 100);
+        assert_eq!(parse_k8s_cpu("1500m"), 1500);
+    }
+
+    #[test]
+    fn parse_k8s_cpu_whole_cores() {
+        assert_eq!(parse_k8s_cpu("1"), 1000);
+        assert_eq!(parse_k8s_cpu("4"), 4000);
+        assert_eq!(parse_k8s_cpu("0"), 0);
+    }
+
+    #[test]
+    fn parse_k8s_cpu_invalid() {
+        assert_eq!(parse_k8s_cpu(""), 0);
+        assert_eq!(parse_k8s_cpu("abc"), 0);
+        assert_eq!(parse_k8s_cpu("m"), 0);
+    }
+
+    // ── parse_k8s_memory tests ──────────────────────────────
+
+    #[test]
+    fn parse_k8s_memory_gi() {
+        assert_eq!(parse_k8s_memory("1Gi"), 1024 * 1024 * 1024);
+        assert_eq!(parse_k8s_memory("2Gi"), 2 * 1024 * 1024 * 1024);
+    }
+
+    #[test]
+    fn parse_k8s_memory_mi() {
+        assert_eq!(parse_k8s_memory("256Mi"), 256 * 1024 * 1024);
+        assert_eq!(parse_k8s_memory("512Mi"), 512 * 1024 * 1024);
+    }
+
+    #[test]
+    fn parse_k8s_memory_ki() {
+        assert_eq!(parse_k8s_memory("1024Ki"), 1024 * 1024);
+    }
+
+    #[test]
+    fn parse_k8s_memory_bytes() {
+        assert_eq!(parse_k8s_memory("1048576"), 1048576);
+    }
+
+    #[test]
+    fn parse_k8s_memory_invalid() {
+        assert_eq!(parse_k8s_memory(""), 0);
+        assert_eq!(parse_k8s_memory("abc"), 0);
+    }
+
+    // ── parse_crictl_imagefsinfo tests ──────────────────────────────
+
+    #[test]
+    fn parse_crictl_imagefsinfo_image_filesystems() {
+        let json = r#"{
+  "status": {
+    "imageFilesystems": [
+      {
+        "timestamp": "1760604000000000000",
+        "fsId": {
+          "mountpoint": "/var/lib/rancher/k3s/agent/containerd/io.containerd.snapshotter.v1.overlayfs"
+        },
+        "usedBytes": {
+          "value": "7516192768"
+        },
+        "inodesUsed": {
+          "value": "182311"
+        }
+      }
+    ],
+    "containerFilesystems": []
+  }
+}"#;
+        assert_eq!(parse_crictl_imagefsinfo(json), Some(7_516_192_768));
+    }
+
+    #[test]
+    fn parse_crictl_imagefsinfo_legacy_layout() {
+        let json = r#"{"status": {"timestamp": "1", "fsId": {"mountpoint": "/var/lib/containerd"}, "usedBytes": {"value": "1048576"}}}"#;
+        assert_eq!(parse_crictl_imagefsinfo(json), Some(1_048_576));
+        assert_eq!(parse_crictl_imagefsinfo("not json"), None);
+        assert_eq!(parse_crictl_imagefsinfo(r#"{"status": {}}"#), None);
+    }
+
+    // ── parse_resolv_conf tests ──────────────────────────────
+
+    #[test]
+    fn parse_resolv_conf_standard() {
+        let content = "nameserver 8.8.8.8\nnameserver 8.8.4.4\n";
+        let resolvers = parse_resolv_conf(content);
+        assert_eq!(resolvers, vec!["8.8.8.8", "8.8.4.4"]);
+    }
+
+    #[test]
+    fn parse_resolv_conf_with_comments() {
+        let content = "# Generated by NetworkManager\nnameserver 1.1.1.1\nsearch example.com\n";
+        let resolvers = parse_resolv_conf(content);
+        assert_eq!(resolvers, vec!["1.1.1.1"]);
+    }
+
+    #[test]
+    fn parse_resolv_conf_empty() {
+        let resolvers = parse_resolv_conf("");
+        assert!(resolvers.is_empty());
+    }
+
+    #[test]
+    fn parse_resolv_conf_no_nameservers() {
+        let content = "search example.com\noptions ndots:5\n";
+        let resolvers = parse_resolv_conf(content);
+        assert!(resolvers.is_empty());
+    }
+
+    // ── parse_flake_metadata tests ──────────────────────────────
+
+    #[test]
+    fn parse_flake_metadata_git_flake() {
+        let json = r#"{
+            "description": "kindling node configuration",
+            "lastModified": 1735689600,
+            "locked": {
+                "lastModified": 1735689600,
+                "narHash": "sha256-AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=",
+                "rev": "3f2a9c1d4e5b6a7980f1e2d3c4b5a69788796a5b",
+                "type": "git",
+                "url": "file:///etc/nixos"
+            },
+            "original": { "type": "git", "url": "file:///etc/nixos" },
+            "originalUrl": "git+file:///etc/nixos",
+            "path": "/nix/store/abc-source",
+            "resolvedUrl": "git+file:///etc/nixos",
+            "revision": "3f2a9c1d4e5b6a7980f1e2d3c4b5a69788796a5b",
+            "url": "git+file:///etc/nixos?rev=3f2a9c1d4e5b6a7980f1e2d3c4b5a69788796a5b"
+        }"#;
+        let info = parse_flake_metadata(json).unwrap();
+        assert_eq!(info.url, "git+file:///etc/nixos");
+        assert_eq!(
+            info.locked_rev.as_deref(),
+            Some("3f2a9c1d4e5b6a7980f1e2d3c4b5a69788796a5b")
+        );
+        assert_eq!(info.last_modified.unwrap().timestamp(), 1735689600);
+        assert!(info.configuration_revision.is_none());
+    }
+
+    #[test]
+    fn parse_flake_metadata_path_flake_has_no_rev() {
+        let json = r#"{"lastModified": 1735689600, "locked": {"type": "path", "path": "/root/.config/kindling/generated"}, "url": "path:/root/.config/kindling/generated"}"#;
+        let info = parse_flake_metadata(json).unwrap();
+        assert_eq!(info.url, "path:/root/.config/kindling/generated");
+        assert!(info.locked_rev.is_none());
+    }
+
+    #[test]
+    fn flake_stamp_tracks_flake_and_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(flake_stamp(dir.path()).is_none());
+
+        let epoch = std::time::UNIX_EPOCH;
+        let touch = |name: &str, secs: u64| {
+            let file = std::fs::File::create(dir.path().join(name)).unwrap();
+            file.set_modified(epoch + std::time::Duration::from_secs(secs))
+                .unwrap();
+        };
+        touch("flake.nix", 100);
+        let unlocked = flake_stamp(dir.path()).unwrap();
+        assert_eq!(unlocked.1, None);
+
+        touch("flake.lock", 200);
+        let locked = flake_stamp(dir.path()).unwrap();
+        assert_ne!(locked, unlocked);
+        assert_eq!(flake_stamp(dir.path()), Some(locked));
+
+        touch("flake.lock", 300);
+        assert_ne!(flake_stamp(dir.path()), Some(locked));
+    }
+
+    #[test]
+    fn parse_flake_metadata_invalid() {
+        assert!(parse_flake_metadata("not json").is_none());
+        assert!(parse_flake_metadata("{}").is_none());
+    }
+
+    #[test]
+    fn flake_source_dir_from_local_urls() {
+        assert_eq!(
+            flake_source_dir("path:/root/.config/kindling/generated").as_deref(),
+            Some("/root/.config/kindling/generated")
+        );
+        assert_eq!(
+            flake_source_dir("git+file:///etc/nixos?ref=main").as_deref(),
+            Some("/etc/nixos")
+        );
+        assert!(flake_source_dir("github:pleme-io/nix").is_none());
+    }
+
+    #[test]
+    fn git_status_dirty_detection() {
+        assert!(!git_status_is_dirty(""));
+        assert!(!git_status_is_dirty("\n"));
+        assert!(git_status_is_dirty(" M configuration.nix\n"));
+        assert!(git_status_is_dirty("A  hosts/edge-1.nix\n?? notes.txt\n"));
+        assert!(git_status_is_dirty("R  old.nix -> new.nix\n"));
+        // Untracked files never reach the flake, so they don't count.
+        assert!(!git_status_is_dirty("?? result\n?? scratch.nix\n"));
+    }
+
+    #[test]
+    fn parse_configuration_revision_from_nixos_version() {
+        let json = r#"{"configurationRevision":"3f2a9c1","nixosVersion":"25.11.20250101.abcdef0","nixpkgsRevision":"abcdef0123"}"#;
+        assert_eq!(parse_configuration_revision(json).as_deref(), Some("3f2a9c1"));
+        assert!(parse_configuration_revision(r#"{"nixosVersion":"25.11"}"#).is_none());
+    }
+
+    // ── last_rebuild_timestamp tests ───────────────────────
+
+    /// A stand-in for a generation link with a fixed mtime. Plain files
+    /// are enough: only the entry's own metadata is read.
+    fn touch_link(path: &std::path::Path, secs: u64) {
+        let file = std::fs::File::create(path).unwrap();
+        file.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn newest_generation_link_picks_latest_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        touch_link(&dir.path().join("system-41-link"), 1_700_000_000);
+        touch_link(&dir.path().join("system-43-link"), 1_700_000_500);
+        // A higher generation number with an older mtime must not win.
+        touch_link(&dir.path().join("system-99-link"), 1_600_000_000);
+        // Non-generation entries are ignored.
+        touch_link(&dir.path().join("per-user-link"), 1_800_000_000);
+        touch_link(&dir.path().join("system"), 1_800_000_000);
+
+        let newest = newest_generation_link(dir.path()).unwrap();
+        assert_eq!(newest.timestamp(), 1_700_000_500);
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn last_rebuild_prefers_current_system() {
+        let root = tempfile::tempdir().unwrap();
+        let profiles = root.path().join("nix/var/nix/profiles");
+        std::fs::create_dir_all(&profiles).unwrap();
+        touch_link(&profiles.join("system-3-link"), 1_700_000_000);
+        assert_eq!(
+            last_rebuild_timestamp(root.path()).unwrap().timestamp(),
+            1_700_000_000
+        );
+
+        std::fs::create_dir_all(root.path().join("run")).unwrap();
+        touch_link(&root.path().join("run/current-system"), 1_700_009_000);
+        assert_eq!(
+            last_rebuild_timestamp(root.path()).unwrap().timestamp(),
+            1_700_009_000
+        );
+    }
+
+    #[test]
+    fn newest_generation_link_none_without_generations() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(newest_generation_link(dir.path()).is_none());
+        assert!(newest_generation_link(&dir.path().join("missing")).is_none());
+    }
+
+    // ── eval_cache_size tests ──────────────────────────────
+
+    #[test]
+    fn eval_cache_size_sums_all_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let v5 = dir.path().join("eval-cache-v5");
+        std::fs::create_dir_all(&v5).unwrap();
+        std::fs::write(v5.join("abc.sqlite"), vec![0u8; 4096]).unwrap();
+        std::fs::write(v5.join("def.sqlite"), vec![0u8; 1024]).unwrap();
+        let v6 = dir.path().join("eval-cache-v6");
+        std::fs::create_dir_all(&v6).unwrap();
+        std::fs::write(v6.join("ghi.sqlite"), vec![0u8; 512]).unwrap();
+        // Unrelated cache entries are ignored
+        std::fs::write(dir.path().join("binary-cache-v6.sqlite"), vec![0u8; 9999]).unwrap();
+
+        assert_eq!(eval_cache_size(dir.path()), Some(4096 + 1024 + 512));
+    }
+
+    #[test]
+    fn active_builds_from_temp_roots() {
+        let print_roots = "\
+/nix/var/nix/profiles/system-42-link -> /nix/store/aaaa-nixos-system-edge-1
+/proc/812/maps -> /nix/store/bbbb-glibc-2.39
+{temp:4021} -> /nix/store/cccc-hello-2.12.1.drv
+{temp:4021} -> /nix/store/dddd-hello-2.12.1
+{temp:4188} -> /nix/store/eeee-linux-6.6.30.drv
+{temp:4190} -> /nix/store/cccc-hello-2.12.1.drv
+";
+        assert_eq!(
+            parse_temp_root_drvs(print_roots),
+            [
+                "/nix/store/cccc-hello-2.12.1.drv",
+                "/nix/store/eeee-linux-6.6.30.drv",
+                "/nix/store/cccc-hello-2.12.1.drv"
+            ]
+        );
+
+        let temproots = b"/nix/store/cccc-hello-2.12.1.drv\0/nix/store/dddd-hello-2.12.1\0\
+/nix/store/ffff-source\0/nix/store/gggg-openssl-3.0.13.drv\0";
+        assert_eq!(
+            parse_temproots(temproots),
+            [
+                "/nix/store/cccc-hello-2.12.1.drv",
+                "/nix/store/gggg-openssl-3.0.13.drv"
+            ]
+        );
+        assert!(temproots_drvs(std::path::Path::new("/nonexistent")).is_empty());
+    }
+
+    #[test]
+    fn only_locked_derivations_count_as_building() {
+        let aterm = r#"Derive([("dev","/nix/store/aaaa-hello-2.12.1-dev","",""),("out","/nix/store/bbbb-hello-2.12.1","","")],[("/nix/store/cccc-bash-5.2.drv",["out"])],[],"x86_64-linux","/bin/sh",[],[])"#;
+        assert_eq!(
+            drv_outputs(aterm),
+            [
+                "/nix/store/aaaa-hello-2.12.1-dev",
+                "/nix/store/bbbb-hello-2.12.1"
+            ]
+        );
+        let ca = r#"Derive([("out","","r:sha256","")],[],[],"x86_64-linux","/bin/sh",[],[])"#;
+        assert!(drv_outputs(ca).is_empty());
+        assert!(drv_outputs("not a derivation").is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("bbbb-hello-2.12.1");
+        let drv = dir.path().join("cccc-hello-2.12.1.drv");
+        let aterm = format!(
+            r#"Derive([("out","{}","","")],[],[],"x86_64-linux","/bin/sh",[],[])"#,
+            out.display()
+        );
+        std::fs::write(&drv, aterm).unwrap();
+        assert!(!is_building(&drv));
+        std::fs::write(format!("{}.lock", out.display()), "").unwrap();
+        assert!(is_building(&drv));
+        assert!(!is_building(&dir.path().join("missing.drv")));
+        assert!(building(vec!["/nix/store/eeee-linux-6.6.30.drv".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn eval_cache_size_none_without_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(eval_cache_size(dir.path()), None);
+        assert_eq!(eval_cache_size(&dir.path().join("missing")), None);
+    }
+
+    // ── DNS probe tests ──────────────────────────────
+
+    #[test]
+    fn build_dns_query_encodes_labels() {
+        let q = build_dns_query(0xBEEF, "cache.nixos.org").unwrap();
+        assert_eq!(&q[..4], &[0xBE, 0xEF, 0x01, 0x00]);
+        assert_eq!(&q[4..6], &[0, 1]);
+        assert_eq!(
+            &q[12..],
+            b"\x05cache\x05nixos\x03org\x00\x00\x01\x00\x01"
+        );
+    }
+
+    #[test]
+    fn build_dns_query_rejects_empty_label() {
+        assert!(build_dns_query(1, "bad..domain").is_err());
+    }
+
+    fn response(id: u16, flags: [u8; 2], answers: u16) -> Vec<u8> {
+        let mut p = id.to_be_bytes().to_vec();
+        p.extend_from_slice(&flags);
+        p.extend_from_slice(&[0, 1]);
+        p.extend_from_slice(&answers.to_be_bytes());
+        p.extend_from_slice(&[0, 0, 0, 0]);
+        p
+    }
+
+    #[test]
+    fn parse_dns_response_success() {
+        assert_eq!(parse_dns_response(7, &response(7, [0x81, 0x80], 2)).unwrap(), 2);
+    }
+
+    #[test]
+    fn parse_dns_response_errors() {
+        let nx = parse_dns_response(7, &response(7, [0x81, 0x83], 0)).unwrap_err();
+        assert!(nx.to_string().contains("NXDOMAIN"));
+        let servfail = parse_dns_response(7, &response(7, [0x81, 0x82], 0)).unwrap_err();
+        assert!(servfail.to_string().contains("SERVFAIL"));
+        assert!(parse_dns_response(8, &response(7, [0x81, 0x80], 1)).is_err());
+        assert!(parse_dns_response(7, &response(7, [0x01, 0x00], 1)).is_err());
+        assert!(parse_dns_response(7, &[0, 7, 0x81]).is_err());
+    }
+
+    #[test]
+    fn resolver_addr_keeps_the_ipv6_zone() {
+        let v4 = resolver_addr("192.168.1.1", 53).unwrap();
+        assert_eq!(v4.to_string(), "192.168.1.1:53");
+
+        let std::net::SocketAddr::V6(scoped) = resolver_addr("fe80::1%2", 53).unwrap() else {
+            panic!("expected an IPv6 address");
+        };
+        assert_eq!(scoped.ip().to_string(), "fe80::1");
+        assert_eq!(scoped.scope_id(), 2);
+
+        let err = resolver_addr("fe80::1%no-such-if0", 53).unwrap_err();
+        assert_eq!(err.to_string(), "unknown interface 'no-such-if0'");
+        assert!(resolver_addr("not-an-ip", 53).is_err());
+    }
+
+    #[tokio::test]
+    async fn probe_resolvers_skips_when_disabled_or_empty() {
+        assert!(probe_resolvers(&[], "cache.nixos.org").await.is_empty());
+        assert!(probe_resolvers(&["1.1.1.1".to_string()], "").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn dns_round_trip_against_local_mock() {
+        let mock = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = mock.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            let (len, peer) = mock.recv_from(&mut buf).await.unwrap();
+            let mut reply = buf[..len].to_vec();
+            reply[2] = 0x81;
+            reply[3] = 0x80;
+            reply[7] = 1; // ANCOUNT=1
+            mock.send_to(&reply, peer).await.unwrap();
+        });
+
+        let health = probe_resolver("127.0.0.1".to_string(), port, "example.org").await;
+        assert_eq!(health.resolver, "127.0.0.1");
+        assert!(health.ok, "{:?}", health.error);
+        assert!(health.latency_ms.is_some());
+        assert!(health.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn probe_resolver_reports_invalid_address() {
+        let health = probe_resolver("not-an-ip".to_string(), DNS_PORT, "example.org").await;
+        assert!(!health.ok);
+        assert!(health.latency_ms.is_none());
+        assert_eq!(health.error.as_deref(), Some("invalid resolver address"));
+    }
+
+    // ── parse_os_release_field tests ──────────────────────────────
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn parse_os_release_field_quoted() {
+        let content = "NAME=\"NixOS\"\nVERSION_ID=\"25.11\"\n";
+        assert_eq!(parse_os_release_field(content, "NAME"), Some("NixOS".to_string()));
+        assert_eq!(parse_os_release_field(content, "VERSION_ID"), Some("25.11".to_string()));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn parse_os_release_field_missing() {
+        let content = "NAME=\"NixOS\"\n";
+        assert_eq!(parse_os_release_field(content, "VERSION_ID"), None);
+    }
+
+    // ── extract_proc_field tests ──────────────────────────────
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn extract_proc_field_model_name() {
+        let cpuinfo = "processor\t: 0\nmodel name\t: Intel(R) Core(TM) i7\ncpu cores\t: 4\n";
+        assert_eq!(
+            extract_proc_field(cpuinfo, "model name"),
+            Some("Intel(R) Core(TM) i7".to_string())
+        );
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn extract_proc_field_missing() {
+        let cpuinfo = "processor\t: 0\n";
+        assert_eq!(extract_proc_field(cpuinfo, "model name"), None);
+    }
+
+    // ── parse_meminfo_kb tests ──────────────────────────────
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn parse_meminfo_kb_standard() {
+        let meminfo = "MemTotal:       16384000 kB\nMemFree:         2048000 kB\nMemAvailable:    8192000 kB\n";
+        assert_eq!(parse_meminfo_kb(meminfo, "MemTotal"), 16384000);
+        assert_eq!(parse_meminfo_kb(meminfo, "MemAvailable"), 8192000);
     }
-}
 
-fn default_processes() -> ProcessSnapshot {
-    ProcessSnapshot {
-        total_processes: 0,
-        running_processes: 0,
-        zombie_processes: 0,
-        top_cpu: Vec::new(),
-        top_memory: Vec::new(),
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn parse_meminfo_kb_missing_field() {
+        let meminfo = "MemTotal:       16384000 kB\n";
+        assert_eq!(parse_meminfo_kb(meminfo, "SwapTotal"), 0);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // ── detect_reboot_required tests ──────────────────────────────
 
-    // ── parse_k8s_cpu tests ──────────────────────────────
+    #[cfg(not(target_os = "macos"))]
+    fn nixos_fixture(booted_kernel: &str, current_kernel: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for name in ["kernel-a", "kernel-b"] {
+            std::fs::create_dir_all(root.join("nix/store").join(name)).unwrap();
+            std::fs::write(root.join("nix/store").join(name).join("bzImage"), "").unwrap();
+        }
+        for (system, kernel) in [("booted-system", booted_kernel), ("current-system", current_kernel)] {
+            let system_dir = root.join("run").join(system);
+            std::fs::create_dir_all(&system_dir).unwrap();
+            std::os::unix::fs::symlink(
+                root.join("nix/store").join(kernel).join("bzImage"),
+                system_dir.join("kernel"),
+            )
+            .unwrap();
+        }
+        dir
+    }
 
+    #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_k8s_cpu_millis() {
-        assert_eq!(parse_k8s_cpu("250m"), 250);
-        assert_eq!(parse_k8s_cpu("100m"), 100);
-        assert_eq!(parse_k8s_cpu("1500m"), 1500);
+    fn reboot_required_when_current_kernel_differs_from_booted() {
+        let dir = nixos_fixture("kernel-a", "kernel-b");
+        assert!(detect_reboot_required(dir.path(), "6.12.0"));
     }
 
+    #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_k8s_cpu_whole_cores() {
-        assert_eq!(parse_k8s_cpu("1"), 1000);
-        assert_eq!(parse_k8s_cpu("4"), 4000);
-        assert_eq!(parse_k8s_cpu("0"), 0);
+    fn reboot_not_required_when_booted_matches_current() {
+        let dir = nixos_fixture("kernel-a", "kernel-a");
+        assert!(!detect_reboot_required(dir.path(), "6.12.0"));
     }
 
+    #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_k8s_cpu_invalid() {
-        assert_eq!(parse_k8s_cpu(""), 0);
-        assert_eq!(parse_k8s_cpu("abc"), 0);
-        assert_eq!(parse_k8s_cpu("m"), 0);
+    fn reboot_required_marker_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("run")).unwrap();
+        std::fs::write(dir.path().join("run/reboot-required"), "").unwrap();
+        assert!(detect_reboot_required(dir.path(), "6.12.0"));
     }
 
-    // ── parse_k8s_memory tests ──────────────────────────────
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn reboot_required_newer_module_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        for release in ["6.11.9-arch1-1", "6.12.3-arch1-1"] {
+            std::fs::create_dir_all(dir.path().join("lib/modules").join(release)).unwrap();
+        }
+        assert!(detect_reboot_required(dir.path(), "6.11.9-arch1-1"));
+        assert!(!detect_reboot_required(dir.path(), "6.12.3-arch1-1"));
+    }
 
+    #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_k8s_memory_gi() {
-        assert_eq!(parse_k8s_memory("1Gi"), 1024 * 1024 * 1024);
-        assert_eq!(parse_k8s_memory("2Gi"), 2 * 1024 * 1024 * 1024);
+    fn reboot_not_required_without_signals() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!detect_reboot_required(dir.path(), "6.12.0"));
     }
 
+    // ── time sync / entropy tests ──────────────────────────────
+
     #[test]
-    fn parse_k8s_memory_mi() {
-        assert_eq!(parse_k8s_memory("256Mi"), 256 * 1024 * 1024);
-        assert_eq!(parse_k8s_memory("512Mi"), 512 * 1024 * 1024);
+    fn parse_timedatectl_ntp_synchronized() {
+        assert_eq!(parse_ntp_synchronized("NTPSynchronized=yes\n"), Some(true));
+        assert_eq!(parse_ntp_synchronized("NTPSynchronized=no\n"), Some(false));
+        assert_eq!(parse_ntp_synchronized("Failed to connect to bus"), None);
     }
 
     #[test]
-    fn parse_k8s_memory_ki() {
-        assert_eq!(parse_k8s_memory("1024Ki"), 1024 * 1024);
+    fn parse_macos_time_sync_outputs() {
+        let sntp = "sntp 4.2.8p10@1.3728-o Tue Mar 21 14:36:43 UTC 2017 (136.200.1~2533)\n\
+                    +0.012345 +/- 0.028290 time.apple.com 17.253.4.125\n";
+        let offset = parse_sntp_offset(sntp).unwrap();
+        assert!(offset.abs() < MAX_CLOCK_OFFSET_SECS);
+        assert_eq!(
+            parse_sntp_offset("-3.5 +/- 0.02 time.apple.com 17.253.4.125"),
+            Some(-3.5)
+        );
+        assert_eq!(parse_sntp_offset("sntp: no response"), None);
+
+        assert_eq!(
+            parse_network_time_setting("Network Time: Off\n"),
+            Some(false)
+        );
+        assert_eq!(parse_network_time_setting("Network Time: On"), Some(true));
+        assert_eq!(
+            parse_network_time_setting("You need administrator access"),
+            None
+        );
     }
 
     #[test]
-    fn parse_k8s_memory_bytes() {
-        assert_eq!(parse_k8s_memory("1048576"), 1048576);
+    fn parse_chrony_tracking_offset() {
+        let tracking = "\
+Reference ID    : A9FEA97B (169.254.169.123)
+Stratum         : 4
+Ref time (UTC)  : Fri Oct 16 09:12:01 2026
+System time     : 0.000123456 seconds slow of NTP time
+Last offset     : +0.000001234 seconds
+RMS offset      : 0.000012345 seconds
+Frequency       : 12.345 ppm fast
+Leap status     : Normal
+";
+        let offset = parse_chrony_offset_ms(tracking).unwrap();
+        assert!((offset + 0.123456).abs() < 1e-9, "{}", offset);
+        assert_eq!(
+            parse_chrony_offset_ms("System time     : 1.500000000 seconds fast of NTP time"),
+            Some(1500.0)
+        );
+        assert_eq!(parse_chrony_offset_ms("506 Cannot talk to daemon"), None);
+
+        assert_eq!(
+            parse_timesync_offset_ms("       Server: 10.0.0.1\n       Offset: -1.234ms\n"),
+            Some(-1.234)
+        );
+        assert_eq!(parse_timesync_offset_ms("Offset: +250us"), Some(0.25));
+        assert_eq!(parse_timesync_offset_ms("Offset: 2.5s"), Some(2500.0));
     }
 
     #[test]
-    fn parse_k8s_memory_invalid() {
-        assert_eq!(parse_k8s_memory(""), 0);
-        assert_eq!(parse_k8s_memory("abc"), 0);
+    fn parse_entropy_avail_reads_pool_size() {
+        assert_eq!(parse_entropy_avail("256\n"), Some(256));
+        assert_eq!(parse_entropy_avail(""), None);
     }
 
-    // ── parse_resolv_conf tests ──────────────────────────────
+    // ── parse_oom_kills tests ──────────────────────────────
 
+    #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_resolv_conf_standard() {
-        let content = "nameserver 8.8.8.8\nnameserver 8.8.4.4\n";
-        let resolvers = parse_resolv_conf(content);
-        assert_eq!(resolvers, vec!["8.8.8.8", "8.8.4.4"]);
+    fn parse_oom_kills_counts_and_names_victims() {
+        let log = "\
+Oct 16 09:12:01 node-1 kernel: java invoked oom-killer: gfp_mask=0x140cca(GFP_HIGHUSER_MOVABLE|__GFP_COMP), order=0, oom_score_adj=0
+Oct 16 09:12:01 node-1 kernel: Out of memory: Killed process 4242 (java) total-vm:8123456kB, anon-rss:6012345kB, file-rss:0kB, shmem-rss:0kB, UID:1000 pgtables:12345kB oom_score_adj:0
+Oct 16 09:30:44 node-1 kernel: Memory cgroup out of memory: Killed process 5150 (postgres) total-vm:2048000kB, anon-rss:1024000kB, file-rss:0kB, shmem-rss:0kB, UID:70 pgtables:4096kB oom_score_adj:0
+Oct 16 09:41:10 node-1 kernel: Out of memory: Killed process 4399 (java) total-vm:8123456kB, anon-rss:6012345kB, file-rss:0kB, shmem-rss:0kB, UID:1000 pgtables:12345kB oom_score_adj:0
+Oct 16 09:45:00 node-1 kernel: e1000e: eth0 NIC Link is Up 1000 Mbps Full Duplex
+";
+        let (kills, victims) = parse_oom_kills(log);
+        assert_eq!(kills, 3);
+        assert_eq!(victims, vec!["java".to_string(), "postgres".to_string()]);
     }
 
+    #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_resolv_conf_with_comments() {
-        let content = "# Generated by NetworkManager\nnameserver 1.1.1.1\nsearch example.com\n";
-        let resolvers = parse_resolv_conf(content);
-        assert_eq!(resolvers, vec!["1.1.1.1"]);
+    fn parse_oom_kills_legacy_format() {
+        let log = "kernel: Out of memory: Kill process 812 (mysqld) score 912 or sacrifice child\n";
+        let (kills, victims) = parse_oom_kills(log);
+        assert_eq!(kills, 1);
+        assert_eq!(victims, vec!["mysqld".to_string()]);
     }
 
+    #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_resolv_conf_empty() {
-        let resolvers = parse_resolv_conf("");
-        assert!(resolvers.is_empty());
+    fn parse_oom_kills_empty_log() {
+        assert_eq!(parse_oom_kills(""), (0, Vec::new()));
     }
 
+    #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_resolv_conf_no_nameservers() {
-        let content = "search example.com\noptions ndots:5\n";
-        let resolvers = parse_resolv_conf(content);
-        assert!(resolvers.is_empty());
+    fn parses_pressure_stall_files() {
+        let memory = "\
+some avg10=12.50 avg60=4.31 avg300=1.02 total=98765432
+full avg10=8.75 avg60=2.10 avg300=0.40 total=45678901
+";
+        let stall = parse_pressure(memory).unwrap();
+        assert_eq!(
+            stall.some,
+            StallAverages {
+                avg10: 12.5,
+                avg60: 4.31,
+                avg300: 1.02
+            }
+        );
+        assert_eq!(stall.full.unwrap().avg60, 2.10);
+
+        // CPU on kernels before 5.13 has no `full` line.
+        let cpu = parse_pressure("some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n").unwrap();
+        assert!(cpu.full.is_none());
+
+        assert!(parse_pressure("").is_none());
+        assert!(parse_pressure("some avg10=oops\n").is_none());
     }
 
-    // ── parse_os_release_field tests ──────────────────────────────
+    // ── df / ps parsing tests ──────────────────────────────
 
-    #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_os_release_field_quoted() {
-        let content = "NAME=\"NixOS\"\nVERSION_ID=\"25.11\"\n";
-        assert_eq!(parse_os_release_field(content, "NAME"), Some("NixOS".to_string()));
-        assert_eq!(parse_os_release_field(content, "VERSION_ID"), Some("25.11".to_string()));
+    fn parses_df_inodes_on_both_platforms() {
+        let gnu = "\
+Filesystem     Inodes   IUsed   IFree IUse% Mounted on
+/dev/nvme0n1p2 30531584 29000000 1531584  95% /
+/dev/sda1      0        0       0          - /boot/efi
+/dev/mapper/luks-3f2a9c1e
+               6553600  120000  6433600    2% /data
+";
+        let counts = parse_df_inodes(gnu);
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts["/"], (30531584, 29000000));
+        assert_eq!(counts["/data"], (6553600, 120000));
+
+        let bsd = "\
+Filesystem     512-blocks      Used Available Capacity  iused      ifree %iused  Mounted on
+/dev/disk3s1s1  965595304  20140480 393043392     5%   403755 1965216960    0%   /
+devfs                 410       410         0   100%      710          0  100%   /dev
+";
+        let counts = parse_df_inodes(bsd);
+        assert_eq!(counts["/"], (403755 + 1965216960, 403755));
+        assert_eq!(counts["/dev"], (710, 710));
+
+        assert!(parse_df_inodes("garbage").is_empty());
     }
 
-    #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_os_release_field_missing() {
-        let content = "NAME=\"NixOS\"\n";
-        assert_eq!(parse_os_release_field(content, "VERSION_ID"), None);
+    fn df_rows_joins_wrapped_device_lines() {
+        let output = "\
+Filesystem     Type 1K-blocks     Used Available Use% Mounted on
+/dev/nvme0n1p2 ext4 491134192 81234560 384893496  18% /
+/dev/mapper/luks-3f2a9c1e-7b4d-4e8a-9c2f-1a2b3c4d5e6f
+               ext4 976284668 51200000 925084668   6% /data
+tmpfs          tmpfs  8119632        0   8119632   0% /run/user/1000
+";
+        let rows = df_rows(output);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[1],
+            vec![
+                "/dev/mapper/luks-3f2a9c1e-7b4d-4e8a-9c2f-1a2b3c4d5e6f",
+                "ext4",
+                "976284668",
+                "51200000",
+                "925084668",
+                "6%",
+                "/data",
+            ]
+        );
+        assert_eq!(rows[2][6], "/run/user/1000");
     }
 
-    // ── extract_proc_field tests ──────────────────────────────
+    #[test]
+    fn program_name_strips_store_paths_and_wrappers() {
+        assert_eq!(
+            program_name("/nix/store/abc123-python3-3.11.9/bin/python3.11 myapp.py --port 8080"),
+            "python3.11"
+        );
+        assert_eq!(
+            program_name("/nix/store/9x8d7c6b5a4-kindling-0.4.0 daemon"),
+            "kindling-0.4.0"
+        );
+        assert_eq!(
+            program_name("/nix/store/abc123-prometheus-2.53/bin/.prometheus-wrapped --config.file=/etc/p.yml"),
+            "prometheus"
+        );
+        assert_eq!(program_name("nginx: worker process"), "nginx");
+        assert_eq!(program_name("sshd"), "sshd");
+        assert_eq!(program_name("[kworker/0:1-events]"), "[kworker/0:1-events]");
+    }
+
+    #[test]
+    fn group_by_program_sums_usage() {
+        let proc = |pid, command: &str, cpu, mem| ProcessInfo {
+            pid,
+            name: command.to_string(),
+            program: program_name(command),
+            cpu_percent: cpu,
+            memory_percent: mem,
+        };
+        let procs = vec![
+            proc(1, "/nix/store/a-pg-16/bin/postgres -D /pg", 2.0, 4.0),
+            proc(2, "postgres: checkpointer", 1.5, 1.0),
+            proc(3, "/run/current-system/sw/bin/sshd -D", 0.1, 0.2),
+        ];
+        let programs = group_by_program_name(&procs);
+        assert_eq!(programs[0].program, "postgres");
+        assert_eq!(programs[0].processes, 2);
+        assert_eq!(programs[0].cpu_percent, 3.5);
+        assert_eq!(programs[0].memory_percent, 5.0);
+        assert_eq!(programs[1].program, "sshd");
+    }
+
+    #[test]
+    fn parse_ps_line_keeps_full_command() {
+        let line = "  48213     1 app       12.5  3.1 812344 Ssl  /nix/store/abc123-java/bin/java -Xmx4g -cp /opt/app/lib/a.jar:/opt/app/lib/b.jar:/opt/app/lib/c.jar com.example.very.long.MainClass --config /etc/app/config.yaml";
+        let row = parse_ps_line(line).unwrap();
+        assert_eq!(row.pid, 48213);
+        assert_eq!(row.ppid, 1);
+        assert_eq!(row.user, "app");
+        assert_eq!(row.cpu_percent, 12.5);
+        assert_eq!(row.memory_percent, 3.1);
+        assert_eq!(row.rss_kb, 812344);
+        assert_eq!(row.stat, "Ssl");
+        assert!(row.command.starts_with("/nix/store/abc123-java/bin/java -Xmx4g"));
+        assert!(row.command.ends_with("--config /etc/app/config.yaml"));
+
+        assert!(parse_ps_line("").is_none());
+        assert!(parse_ps_line("   PID  PPID USER %CPU %MEM RSS STAT COMMAND").is_none());
+    }
+
+    #[tokio::test]
+    async fn ndjson_lines_yield_one_object_per_process() {
+        let output = "\
+    1     0 root       0.0  0.1  11840 Ss   /sbin/init
+  812     1 postgres   2.5  4.2 345600 Ssl  postgres: checkpointer
+ 4410   812 postgres   0.0  0.3  20480 S    postgres: walwriter
+
+";
+        let lines: Vec<String> = ndjson_lines(output.as_bytes())
+            .map(|l| l.unwrap())
+            .collect()
+            .await;
+        assert_eq!(lines.len(), 3);
+        for line in &lines {
+            assert!(line.ends_with('\n'));
+            assert_eq!(line.matches('\n').count(), 1);
+        }
+        let parsed: serde_json::Value = serde_json::from_str(&lines[2]).unwrap();
+        assert_eq!(parsed["pid"], 4410);
+        assert_eq!(parsed["ppid"], 812);
+        assert_eq!(parsed["user"], "postgres");
+        assert_eq!(parsed["rss_kb"], 20480);
+        assert_eq!(parsed["command"], "postgres: walwriter");
+        assert!(parsed.get("stat").is_none());
+    }
+
+    // ── parse_systemctl_failed tests ──────────────────────────────
 
     #[cfg(not(target_os = "macos"))]
     #[test]
-    fn extract_proc_field_model_name() {
-        let cpuinfo = "processor\t: 0\nmodel name\t: Intel(R) Core(TM) i7\ncpu cores\t: 4\n";
+    fn parse_systemctl_failed_units() {
+        let output = "\
+nginx.service          loaded failed failed A high performance web server
+wg-quick-wg0.service   loaded failed failed WireGuard via wg-quick(8) for wg0
+logrotate.timer        loaded failed failed Daily rotation of log files
+";
         assert_eq!(
-            extract_proc_field(cpuinfo, "model name"),
-            Some("Intel(R) Core(TM) i7".to_string())
+            parse_systemctl_failed(output),
+            vec!["nginx.service", "wg-quick-wg0.service", "logrotate.timer"]
         );
     }
 
     #[cfg(not(target_os = "macos"))]
     #[test]
-    fn extract_proc_field_missing() {
-        let cpuinfo = "processor\t: 0\n";
-        assert_eq!(extract_proc_field(cpuinfo, "model name"), None);
+    fn parse_systemctl_failed_with_bullets() {
+        let output = "● k3s.service loaded failed failed Lightweight Kubernetes\n";
+        assert_eq!(parse_systemctl_failed(output), vec!["k3s.service"]);
     }
 
-    // ── parse_meminfo_kb tests ──────────────────────────────
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn parse_systemctl_failed_none() {
+        assert!(parse_systemctl_failed("").is_empty());
+        assert!(parse_systemctl_failed("\n").is_empty());
+    }
+
+    // ── parse_launchctl_list tests ──────────────────────────────
+
+    #[test]
+    fn parse_ss_summary_counts_states() {
+        let output = "\
+Total: 1421
+TCP:   1023 (estab 412, closed 580, orphaned 3, timewait 578)
+
+Transport Total     IP        IPv6
+RAW	  1         0         1
+UDP	  12        8         4
+TCP	  443       420       23
+INET	  456       428       28
+FRAG	  0         0         0
+";
+        let summary = parse_ss_summary(output);
+        assert_eq!(summary.tcp_states["ESTABLISHED"], 412);
+        assert_eq!(summary.tcp_states["TIME_WAIT"], 578);
+        assert!(!summary.tcp_states.contains_key("CLOSED"));
+        assert!(!summary.tcp_states.contains_key("ORPHANED"));
+        assert_eq!(summary.udp_sockets, 12);
+    }
+
+    #[test]
+    fn parse_ss_summary_legacy_timewait_pair() {
+        let output = "TCP:   8 (estab 4, closed 0, orphaned 0, synrecv 1, timewait 2/0), ports 0\n";
+        let summary = parse_ss_summary(output);
+        assert_eq!(summary.tcp_states["TIME_WAIT"], 2);
+        assert_eq!(summary.tcp_states["SYN_RECV"], 1);
+        assert_eq!(summary.udp_sockets, 0);
+        assert_eq!(parse_ss_summary(""), ConnectionSummary::default());
+    }
+
+    #[test]
+    fn parse_netstat_connections_counts_states() {
+        let output = "\
+Active Internet connections (including servers)
+Proto Recv-Q Send-Q  Local Address          Foreign Address        (state)
+tcp4       0      0  192.168.1.5.52344      17.57.146.20.5223      ESTABLISHED
+tcp4       0      0  192.168.1.5.52301      140.82.112.4.443       ESTABLISHED
+tcp4       0      0  192.168.1.5.52200      151.101.1.69.443       TIME_WAIT
+tcp6       0      0  *.22                   *.*                    LISTEN
+udp4       0      0  *.5353                 *.*
+udp6       0      0  *.5353                 *.*
+Active LOCAL (UNIX) domain sockets
+Address          Type   Recv-Q Send-Q            Inode             Conn             Refs          Nextref Addr
+9a3f1c2b4e5d6f70 stream      0      0                0 9a3f1c2b4e5d6f71                0                0 /var/run/mDNSResponder
+";
+        let summary = parse_netstat_connections(output);
+        assert_eq!(summary.tcp_states["ESTABLISHED"], 2);
+        assert_eq!(summary.tcp_states["TIME_WAIT"], 1);
+        assert_eq!(summary.tcp_states["LISTEN"], 1);
+        assert_eq!(summary.tcp_states.len(), 3);
+        assert_eq!(summary.udp_sockets, 2);
+    }
+
+    #[test]
+    fn parse_launchctl_list_nonzero_status() {
+        let output = "PID\tStatus\tLabel\n\
+                      -\t0\tcom.apple.SafariHistoryServiceAgent\n\
+                      612\t0\tcom.apple.Finder\n\
+                      -\t78\torg.nixos.nix-daemon\n\
+                      -\t-9\tio.pleme.kindling\n\
+                      401\t0\tcom.apple.homed\n";
+        let (failed, loaded) = parse_launchctl_list(output);
+        assert_eq!(loaded, 5);
+        assert_eq!(failed, vec!["org.nixos.nix-daemon", "io.pleme.kindling"]);
+    }
+
+    #[test]
+    fn parse_launchctl_list_empty() {
+        assert_eq!(parse_launchctl_list("PID\tStatus\tLabel\n"), (Vec::new(), 0));
+        assert_eq!(parse_launchctl_list(""), (Vec::new(), 0));
+    }
+
+    // ── recent_error_lines tests ──────────────────────────────
+
+    #[test]
+    fn recent_error_lines_from_journalctl() {
+        let log = "\
+-- Boot 3f2a1c --
+Jan 10 12:00:01 node-1 nix-daemon[812]: error: builder for '/nix/store/abc-foo.drv' failed with exit code 2
+Jan 10 12:00:02 node-1 nix-daemon[812]: unexpected end-of-file
+
+Jan 10 12:05:00 node-1 nix-daemon[812]: error: cannot connect to socket at '/nix/var/nix/daemon-socket/socket'
+";
+        let lines = recent_error_lines(log, false, 20);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("builder for '/nix/store/abc-foo.drv' failed"));
+        assert!(lines[2].ends_with("daemon-socket/socket'"));
+    }
+
+    #[test]
+    fn recent_error_lines_keeps_last_n() {
+        let log = (1..=30)
+            .map(|i| format!("error: failure {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let lines = recent_error_lines(&log, false, 5);
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "error: failure 26");
+        assert_eq!(lines[4], "error: failure 30");
+    }
+
+    #[test]
+    fn recent_error_lines_filters_unprioritized_log() {
+        let log = "\
+accepted connection from pid 4411, user admin
+error: getting status of '/nix/store/xyz': No such file or directory
+copying path '/nix/store/def-bar' from 'https://cache.nixos.org'...
+Error: hash mismatch in fixed-output derivation
+";
+        let lines = recent_error_lines(log, true, 20);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("error: getting status"));
+        assert!(lines[1].starts_with("Error: hash mismatch"));
+    }
+
+    #[test]
+    fn recent_error_lines_no_entries() {
+        assert!(recent_error_lines("-- No entries --\n", false, 20).is_empty());
+        assert!(recent_error_lines("", true, 20).is_empty());
+    }
+
+    // ── PCI / USB parsing tests ──────────────────────────────
 
     #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_meminfo_kb_standard() {
-        let meminfo = "MemTotal:       16384000 kB\nMemFree:         2048000 kB\nMemAvailable:    8192000 kB\n";
-        assert_eq!(parse_meminfo_kb(meminfo, "MemTotal"), 16384000);
-        assert_eq!(parse_meminfo_kb(meminfo, "MemAvailable"), 8192000);
+    fn parse_lspci_mm_sample() {
+        let output = r#"00:00.0 "Host bridge" "Intel Corporation" "Xeon E3-1200 v6/7th Gen Core Processor Host Bridge/DRAM Registers" -r02 "Lenovo" "Device 2247"
+00:02.0 "VGA compatible controller" "Intel Corporation" "HD Graphics 620" -r02 "Lenovo" "Device 2247"
+01:00.0 "Non-Volatile memory controller" "Samsung Electronics Co Ltd" "NVMe SSD Controller SM961/PM961/SM963" "Samsung Electronics Co Ltd" "NVMe SSD Controller SM961/PM961/SM963"
+garbage line
+"#;
+        let devices = parse_lspci_mm(output);
+        assert_eq!(devices.len(), 3);
+        assert_eq!(
+            devices[1],
+            PciDevice {
+                slot: "00:02.0".into(),
+                class: "VGA compatible controller".into(),
+                vendor: "Intel Corporation".into(),
+                device: "HD Graphics 620".into(),
+            }
+        );
+        assert_eq!(devices[2].class, "Non-Volatile memory controller");
     }
 
     #[cfg(not(target_os = "macos"))]
     #[test]
-    fn parse_meminfo_kb_missing_field() {
-        let meminfo = "MemTotal:       16384000 kB\n";
-        assert_eq!(parse_meminfo_kb(meminfo, "SwapTotal"), 0);
+    fn parse_lsusb_sample() {
+        let output = "Bus 002 Device 001: ID 1d6b:0003 Linux Foundation 3.0 root hub\n\
+                      Bus 001 Device 003: ID 046d:c52b Logitech, Inc. Unifying Receiver\n\
+                      Bus 001 Device 004: ID 1234:5678\n";
+        let devices = parse_lsusb(output);
+        assert_eq!(devices.len(), 3);
+        assert_eq!(devices[1].vendor_id, "046d");
+        assert_eq!(devices[1].product_id, "c52b");
+        assert_eq!(devices[1].device, "Logitech, Inc. Unifying Receiver");
+        assert!(devices[1].vendor.is_none());
+        assert_eq!(devices[2].device, "");
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn parse_lspci_lsusb_empty() {
+        assert!(parse_lspci_mm("").is_empty());
+        assert!(parse_lsusb("").is_empty());
+    }
+
+    // ── parse_mdstat tests ──────────────────────────────
+
+    #[test]
+    fn parse_mdstat_healthy_and_degraded() {
+        let output = "\
+Personalities : [raid1] [raid6] [raid5] [raid4]
+md0 : active raid1 sdb1[1] sda1[0]
+      976630464 blocks super 1.2 [2/2] [UU]
+      bitmap: 1/8 pages [4KB], 65536KB chunk
+
+md1 : active raid5 sdc1[0] sdd1[1](F) sde1[3]
+      1953260544 blocks super 1.2 level 5, 512k chunk, algorithm 2 [3/2] [U_U]
+      [=>...................]  recovery =  8.5% (83058688/976630272) finish=74.2min speed=200684K/sec
+
+md2 : active (auto-read-only) raid1 sdg1[1] sdf1[0]
+      488254464 blocks super 1.2 [2/2] [UU]
+
+md127 : inactive sdh1[0](S)
+      976630488 blocks super 1.2
+
+unused devices: <none>
+";
+        let arrays = parse_mdstat(output);
+        assert_eq!(arrays.len(), 4);
+
+        assert_eq!(arrays[0].name, "md0");
+        assert_eq!(arrays[0].level.as_deref(), Some("raid1"));
+        assert_eq!(arrays[0].state, "active");
+        assert_eq!(arrays[0].devices, vec!["sdb1", "sda1"]);
+        assert_eq!(arrays[0].member_status.as_deref(), Some("UU"));
+        assert!(!arrays[0].degraded);
+        assert!(arrays[0].sync_progress.is_none());
+
+        assert_eq!(arrays[1].level.as_deref(), Some("raid5"));
+        assert_eq!(arrays[1].devices, vec!["sdc1", "sdd1(F)", "sde1"]);
+        assert_eq!(arrays[1].member_status.as_deref(), Some("U_U"));
+        assert!(arrays[1].degraded);
+        assert_eq!(arrays[1].sync_progress.as_deref(), Some("recovery 8.5%"));
+
+        assert_eq!(arrays[2].state, "active (auto-read-only)");
+        assert_eq!(arrays[2].level.as_deref(), Some("raid1"));
+
+        assert_eq!(arrays[3].state, "inactive");
+        assert!(arrays[3].level.is_none());
+        assert_eq!(arrays[3].devices, vec!["sdh1(S)"]);
+        assert!(arrays[3].member_status.is_none());
+        assert!(!arrays[3].degraded);
+    }
+
+    #[test]
+    fn parse_mdstat_without_arrays() {
+        assert!(parse_mdstat("Personalities : \nunused devices: <none>\n").is_empty());
+        assert!(parse_mdstat("").is_empty());
+    }
+
+    #[test]
+    fn parse_system_profiler_usb_nested() {
+        let json = r#"{"SPUSBDataType":[{"_name":"USB31Bus","host_controller":"AppleT8103USBXHCI","_items":[
+            {"_name":"USB3.1 Hub","vendor_id":"0x05e3  (Genesys Logic, Inc.)","product_id":"0x0626","manufacturer":"GenesysLogic","_items":[
+                {"_name":"YubiKey OTP+FIDO+CCID","vendor_id":"0x1050","product_id":"0x0407","manufacturer":"Yubico"}
+            ]}
+        ]},{"_name":"USB31Bus"}]}"#;
+        let devices = parse_system_profiler_usb(json);
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].vendor_id, "05e3");
+        assert_eq!(devices[0].device, "USB3.1 Hub");
+        assert_eq!(
+            devices[1],
+            UsbDevice {
+                vendor_id: "1050".into(),
+                product_id: "0407".into(),
+                vendor: Some("Yubico".into()),
+                device: "YubiKey OTP+FIDO+CCID".into(),
+            }
+        );
+        assert!(parse_system_profiler_usb("not json").is_empty());
     }
 
     // ── default fallback tests ──────────────────────────────
@@ -2492,4 +5410,242 @@ mod tests {
         assert!(s.root_login_allowed);
         assert!(s.password_auth_enabled);
     }
+
+    // ── collector registry tests ────────────────────────────
+
+    struct FakeCollector(Section);
+
+    impl SectionCollector for FakeCollector {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+        fn collect(&self) -> SectionFuture<'_> {
+            let section = self.0.clone();
+            Box::pin(async move { Ok(section) })
+        }
+    }
+
+    struct FailingCollector;
+
+    impl SectionCollector for FailingCollector {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+        fn collect(&self) -> SectionFuture<'_> {
+            Box::pin(async { anyhow::bail!("probe exploded") })
+        }
+    }
+
+    #[tokio::test]
+    async fn registry_assembles_report_from_fake_collectors() {
+        let fixture = crate::domain::node_report::tests::make_test_report();
+        let report = CollectorRegistry::new()
+            .with(FakeCollector(Section::Hardware(fixture.hardware.clone())))
+            .with(FakeCollector(Section::Nix(fixture.nix.clone())))
+            .with(FakeCollector(Section::Kubernetes(
+                fixture.kubernetes.clone(),
+            )))
+            .with(FailingCollector)
+            .collect()
+            .await;
+
+        assert_eq!(report.hardware.cpu_model, fixture.hardware.cpu_model);
+        assert_eq!(report.nix.nix_version, fixture.nix.nix_version);
+        assert_eq!(report.kubernetes.is_some(), fixture.kubernetes.is_some());
+        // Sections without a collector (or whose collector failed) stay zeroed.
+        assert_eq!(report.os.distribution, "unknown");
+        assert!(report.network.interfaces.is_empty());
+    }
+
+    struct SlowCollector;
+
+    impl SectionCollector for SlowCollector {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+        fn collect(&self) -> SectionFuture<'_> {
+            Box::pin(async {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                Ok(Section::Os(default_os()))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn registry_records_duration_per_section() {
+        let report = CollectorRegistry::new()
+            .with(FakeCollector(Section::Hardware(default_hardware())))
+            .with(FailingCollector)
+            .with(SlowCollector)
+            .collect()
+            .await;
+
+        let names: Vec<_> = report.collection_durations_ms.keys().collect();
+        assert_eq!(names, vec!["failing", "fake", "slow"]);
+        assert!(report.collection_durations_ms["slow"] >= 50);
+        assert_eq!(report.slowest_sections(1)[0].0, "slow");
+    }
+
+    #[tokio::test]
+    async fn registry_records_failed_sections() {
+        let registry = CollectorRegistry::new()
+            .with(FakeCollector(Section::Hardware(default_hardware())))
+            .with(FailingCollector);
+        let mut report = registry.collect().await;
+
+        assert_eq!(report.collection_errors.len(), 1);
+        assert_eq!(report.collection_errors["failing"], "probe exploded");
+        assert!(!report.collection_errors.contains_key("fake"));
+
+        // A later successful run of the same section clears its error.
+        struct RecoveredCollector;
+        impl SectionCollector for RecoveredCollector {
+            fn name(&self) -> &'static str {
+                "failing"
+            }
+            fn collect(&self) -> SectionFuture<'_> {
+                Box::pin(async { Ok(Section::Os(default_os())) })
+            }
+        }
+        CollectorRegistry::new()
+            .with(RecoveredCollector)
+            .collect_into(&mut report)
+            .await;
+        assert!(report.collection_errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn sub_probe_warnings_are_recorded_per_section() {
+        /// Hardware collector whose GPU probe fails while `.0` is set.
+        struct GpuProbeCollector(bool);
+        impl SectionCollector for GpuProbeCollector {
+            fn name(&self) -> &'static str {
+                "hardware"
+            }
+            fn collect(&self) -> SectionFuture<'_> {
+                Box::pin(async {
+                    let gpus: Result<Vec<GpuSnapshot>> = if self.0 {
+                        Err(anyhow::anyhow!("no display"))
+                    } else {
+                        Ok(Vec::new())
+                    };
+                    let mut hardware = default_hardware();
+                    hardware.gpus = gpus.unwrap_or_else(|e| {
+                        probe_warning(format!("GPU detection failed: {:#}", e));
+                        Vec::new()
+                    });
+                    Ok(Section::Hardware(hardware))
+                })
+            }
+        }
+
+        let mut report = CollectorRegistry::new()
+            .with(GpuProbeCollector(true))
+            .with(FakeCollector(Section::Os(default_os())))
+            .collect()
+            .await;
+        assert_eq!(
+            report.collection_warnings,
+            vec![CollectionWarning {
+                section: "hardware".to_string(),
+                message: "GPU detection failed: no display".to_string(),
+            }]
+        );
+        assert!(report.collection_errors.is_empty());
+
+        // A clean re-run of the section drops its old warnings.
+        CollectorRegistry::new()
+            .with(GpuProbeCollector(false))
+            .collect_into(&mut report)
+            .await;
+        assert!(report.collection_warnings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn later_collector_overrides_earlier() {
+        let mut first = default_os();
+        first.distribution = "first".into();
+        let mut second = default_os();
+        second.distribution = "second".into();
+
+        let report = CollectorRegistry::new()
+            .with(FakeCollector(Section::Os(first)))
+            .with(FakeCollector(Section::Os(second)))
+            .collect()
+            .await;
+        assert_eq!(report.os.distribution, "second");
+    }
+
+    #[test]
+    fn registry_only_keeps_named_sections() {
+        let registry = CollectorRegistry::platform(&ReportConfig::default())
+            .only(&["health".to_string(), "network".to_string()])
+            .unwrap();
+        assert_eq!(registry.names(), vec!["network", "health"]);
+
+        let err = CollectorRegistry::platform(&ReportConfig::default())
+            .only(&["health".to_string(), "gpu".to_string()])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("unknown report section 'gpu'"));
+    }
+
+    #[tokio::test]
+    async fn network_refresh_recomputes_firewall_drift() {
+        struct NetworkCollector(Vec<ListeningPort>);
+        impl SectionCollector for NetworkCollector {
+            fn name(&self) -> &'static str {
+                "network"
+            }
+            fn collect(&self) -> SectionFuture<'_> {
+                let mut network = default_network();
+                network.listening_ports = self.0.clone();
+                Box::pin(async move { Ok(Section::Network(network)) })
+            }
+        }
+        let listen = |port| ListeningPort {
+            port,
+            protocol: "tcp".to_string(),
+            address: Some("0.0.0.0".to_string()),
+            process: Some("postgres".to_string()),
+        };
+        let mut identity = NodeIdentity::from_bootstrap("cloud-server", "n1", "ops", None);
+        identity.network.firewall.allowed_tcp_ports = vec![22];
+        let drift = |report: &NodeReport| -> Vec<FindingKind> {
+            let findings = report.security.findings.iter();
+            findings
+                .filter(|f| is_firewall_drift(f))
+                .map(|f| f.kind)
+                .collect()
+        };
+
+        let mut report = empty_report("n1".to_string());
+        let registry =
+            CollectorRegistry::new().with(NetworkCollector(vec![listen(22), listen(5432)]));
+        ReportCollector::refresh_into(&registry, &mut report, Some(&identity)).await;
+        assert_eq!(drift(&report), vec![FindingKind::FirewallExposure]);
+
+        // Postgres stopped: the exposure goes instead of piling up.
+        let registry = CollectorRegistry::new().with(NetworkCollector(vec![listen(22)]));
+        ReportCollector::refresh_into(&registry, &mut report, Some(&identity)).await;
+        assert!(drift(&report).is_empty());
+    }
+
+    #[test]
+    fn node_role_decides_kubernetes_collection() {
+        let sections = |profile: &str| {
+            let identity = NodeIdentity::from_bootstrap(profile, "n1", "ops", None);
+            CollectorRegistry::platform(&ReportConfig::default())
+                .for_role(identity.node_role())
+                .names()
+        };
+        assert!(!sections("macos-developer").contains(&"kubernetes"));
+        assert!(sections("macos-developer").contains(&"nix"));
+        assert!(sections("k3s-server").contains(&"kubernetes"));
+        assert!(sections("cloud-server").contains(&"kubernetes"));
+
+        let mut laptop = NodeIdentity::from_bootstrap("macos-developer", "n1", "ops", None);
+        laptop.kubernetes.role = Some("agent".to_string());
+        assert_eq!(laptop.node_role(), NodeRole::Kubernetes);
+    }
 }
@@ -0,0 +1,174 @@
+//! ReportDelta — what changed between two reports from the same node.
+//!
+//! The fleet controller computes one on every ingest against the node's
+//! previously stored report. A node's first report has an empty delta.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::node_report::NodeReport;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReportDelta {
+    /// Listening ports present now but not before, as `port/protocol`.
+    #[serde(default)]
+    pub opened_ports: Vec<String>,
+    /// Listening ports present before but not now, as `port/protocol`.
+    #[serde(default)]
+    pub closed_ports: Vec<String>,
+    /// Nix store growth in bytes (negative after a GC).
+    #[serde(default)]
+    pub store_size_change_bytes: i64,
+    #[serde(default)]
+    pub kernel_change: Option<ValueChange>,
+    /// `/run/current-system` switched to a different store path.
+    #[serde(default)]
+    pub system_change: Option<ValueChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValueChange {
+    pub from: String,
+    pub to: String,
+}
+
+impl ReportDelta {
+    /// Compare `current` against the node's `previous` report.
+    pub fn compute(previous: &NodeReport, current: &NodeReport) -> Self {
+        let before = port_set(previous);
+        let after = port_set(current);
+
+        Self {
+            opened_ports: after.difference(&before).cloned().collect(),
+            closed_ports: before.difference(&after).cloned().collect(),
+            store_size_change_bytes: current.nix.store_size_bytes as i64
+                - previous.nix.store_size_bytes as i64,
+            kernel_change: change(&previous.os.kernel_version, &current.os.kernel_version),
+            system_change: match (
+                &previous.nix.current_system_path,
+                &current.nix.current_system_path,
+            ) {
+                (Some(from), Some(to)) => change(from, to),
+                _ => None,
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn port_set(report: &NodeReport) -> BTreeSet<String> {
+    report
+        .network
+        .listening_ports
+        .iter()
+        .map(|p| format!("{}/{}", p.port, p.protocol))
+        .collect()
+}
+
+fn change(from: &str, to: &str) -> Option<ValueChange> {
+    (from != to).then(|| ValueChange {
+        from: from.to_string(),
+        to: to.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::node_report::tests::make_test_report;
+    use crate::domain::node_report::ListeningPort;
+
+    fn port(port: u16, protocol: &str) -> ListeningPort {
+        ListeningPort {
+            port,
+            protocol: protocol.to_string(),
+            address: None,
+            process: None,
+        }
+    }
+
+    #[test]
+    fn identical_reports_have_empty_delta() {
+        let report = make_test_report();
+        let delta = ReportDelta::compute(&report, &report);
+        assert!(delta.is_empty());
+    }
+
+    #[test]
+    fn detects_opened_and_closed_ports() {
+        let mut previous = make_test_report();
+        previous.network.listening_ports = vec![port(22, "tcp"), port(80, "tcp")];
+        let mut current = make_test_report();
+        current.network.listening_ports =
+            vec![port(22, "tcp"), port(443, "tcp"), port(53, "udp")];
+
+        let delta = ReportDelta::compute(&previous, &current);
+        assert_eq!(delta.opened_ports, vec!["443/tcp", "53/udp"]);
+        assert_eq!(delta.closed_ports, vec!["80/tcp"]);
+        assert!(!delta.is_empty());
+    }
+
+    #[test]
+    fn same_port_different_protocol_is_distinct() {
+        let mut previous = make_test_report();
+        previous.network.listening_ports = vec![port(53, "tcp")];
+        let mut current = make_test_report();
+        current.network.listening_ports = vec![port(53, "udp")];
+
+        let delta = ReportDelta::compute(&previous, &current);
+        assert_eq!(delta.opened_ports, vec!["53/udp"]);
+        assert_eq!(delta.closed_ports, vec!["53/tcp"]);
+    }
+
+    #[test]
+    fn store_size_change_is_signed() {
+        let mut previous = make_test_report();
+        previous.nix.store_size_bytes = 10_000;
+        let mut current = make_test_report();
+        current.nix.store_size_bytes = 12_500;
+        assert_eq!(
+            ReportDelta::compute(&previous, &current).store_size_change_bytes,
+            2_500
+        );
+        assert_eq!(
+            ReportDelta::compute(&current, &previous).store_size_change_bytes,
+            -2_500
+        );
+    }
+
+    #[test]
+    fn detects_kernel_and_system_change() {
+        let mut previous = make_test_report();
+        previous.os.kernel_version = "6.6.30".to_string();
+        previous.nix.current_system_path = Some("/nix/store/aaa-nixos-system".to_string());
+        let mut current = make_test_report();
+        current.os.kernel_version = "6.12.4".to_string();
+        current.nix.current_system_path = Some("/nix/store/bbb-nixos-system".to_string());
+
+        let delta = ReportDelta::compute(&previous, &current);
+        assert_eq!(
+            delta.kernel_change,
+            Some(ValueChange {
+                from: "6.6.30".to_string(),
+                to: "6.12.4".to_string(),
+            })
+        );
+        assert_eq!(
+            delta.system_change.map(|c| c.to),
+            Some("/nix/store/bbb-nixos-system".to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_system_path_is_not_a_change() {
+        let mut previous = make_test_report();
+        previous.nix.current_system_path = None;
+        let mut current = make_test_report();
+        current.nix.current_system_path = Some("/nix/store/bbb-nixos-system".to_string());
+        assert!(ReportDelta::compute(&previous, &current).system_change.is_none());
+    }
+}
@@ -0,0 +1,439 @@
+//! Overall health verdict — one status summarizing a NodeReport.
+//!
+//! Also home to the thresholds the report table and HTML color values by,
+//! so a red value is one that makes the node Critical and a yellow one, or
+//! anything else needing attention (zombies, failed units, OOM kills, a
+//! pending reboot), makes it Degraded.
+
+use async_graphql::Enum;
+use serde::{Deserialize, Serialize};
+
+use super::node_report::NodeReport;
+
+/// Disk usage above this percentage is critical (red).
+pub const DISK_CRITICAL_PERCENT: f64 = 90.0;
+/// Disk usage above this percentage is degraded (yellow).
+pub const DISK_WARN_PERCENT: f64 = 75.0;
+/// Memory usage above this percentage is critical (red).
+pub const MEMORY_CRITICAL_PERCENT: f64 = 90.0;
+/// Memory usage above this percentage is degraded (yellow).
+pub const MEMORY_WARN_PERCENT: f64 = 75.0;
+/// CPU usage above this percentage is shown red. One sample is too noisy
+/// to affect the verdict; load per core does that.
+pub const CPU_CRITICAL_PERCENT: f64 = 90.0;
+/// CPU usage above this percentage is shown yellow.
+pub const CPU_WARN_PERCENT: f64 = 70.0;
+/// Inode usage above this percentage is critical: writes start failing
+/// with "no space left on device" while bytes are still free.
+pub const INODE_CRITICAL_PERCENT: f64 = 90.0;
+/// Sensor temperatures above this many °C are shown red.
+pub const TEMP_CRITICAL_CELSIUS: f64 = 85.0;
+/// Sensor temperatures above this many °C are shown yellow.
+pub const TEMP_WARN_CELSIUS: f64 = 70.0;
+/// Security scores at or above this are shown green.
+pub const SCORE_GOOD: u8 = 80;
+/// Security scores at or above this (and below [`SCORE_GOOD`]) are shown
+/// yellow; lower ones red.
+pub const SCORE_FAIR: u8 = 50;
+/// Load per core above this is critical (red).
+pub const LOAD_CRITICAL_PER_CORE: f64 = 2.0;
+/// Load per core above this is degraded (yellow).
+pub const LOAD_WARN_PER_CORE: f64 = 1.0;
+/// Clock offset beyond this many milliseconds is critical.
+pub const CLOCK_OFFSET_CRITICAL_MS: f64 = 1000.0;
+/// Clock offset beyond this many milliseconds is degraded.
+pub const CLOCK_OFFSET_WARN_MS: f64 = 100.0;
+/// Certificates expiring within this many days are critical.
+pub const CERT_CRITICAL_DAYS: i64 = 14;
+/// Certificates expiring within this many days are degraded.
+pub const CERT_WARN_DAYS: i64 = 30;
+/// Pressure stall (`some`, 60 s average) above this percentage is critical.
+const PRESSURE_CRITICAL_PERCENT: f64 = 25.0;
+/// Pressure stall (`some`, 60 s average) above this percentage is degraded.
+const PRESSURE_WARN_PERCENT: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "snake_case")]
+pub enum OverallStatus {
+    Healthy,
+    Degraded,
+    Critical,
+}
+
+impl std::fmt::Display for OverallStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Healthy => "Healthy",
+            Self::Degraded => "Degraded",
+            Self::Critical => "Critical",
+        })
+    }
+}
+
+/// Classify a report, returning the status and the reasons for it
+/// (empty when Healthy).
+pub fn classify(report: &NodeReport) -> (OverallStatus, Vec<String>) {
+    let mut status = OverallStatus::Healthy;
+    let mut reasons = Vec::new();
+    let mut flag = |level: OverallStatus, reason: String| {
+        status = status.max(level);
+        reasons.push(reason);
+    };
+
+    for du in &report.health.disk_usage {
+        if let Some(level) = level(du.usage_percent, DISK_WARN_PERCENT, DISK_CRITICAL_PERCENT) {
+            flag(
+                level,
+                format!("disk {} at {:.0}%", du.mount_point, du.usage_percent),
+            );
+        }
+        if let Some(inodes) = du.inode_percent.filter(|p| *p > INODE_CRITICAL_PERCENT) {
+            flag(
+                OverallStatus::Critical,
+                format!("disk {} inodes at {:.0}%", du.mount_point, inodes),
+            );
+        }
+    }
+    if let Some(level) = load_level(report.health.load_per_core) {
+        flag(
+            level,
+            format!("load {:.2} per core", report.health.load_per_core),
+        );
+    }
+    let memory = report.health.memory_usage_percent;
+    if let Some(level) = level(memory, MEMORY_WARN_PERCENT, MEMORY_CRITICAL_PERCENT) {
+        flag(
+            level,
+            format!("memory at {:.0}%", report.health.memory_usage_percent),
+        );
+    }
+    let pressure = [
+        ("memory", &report.health.memory_pressure),
+        ("cpu", &report.health.cpu_pressure),
+        ("io", &report.health.io_pressure),
+    ];
+    for (resource, stall) in pressure {
+        let Some(avg60) = stall.as_ref().map(|p| p.some.avg60) else {
+            continue;
+        };
+        if let Some(level) = level(avg60, PRESSURE_WARN_PERCENT, PRESSURE_CRITICAL_PERCENT) {
+            flag(level, format!("{} pressure {:.0}%", resource, avg60));
+        }
+    }
+    if report.health.recent_oom_kills > 0 {
+        flag(
+            OverallStatus::Degraded,
+            format!(
+                "{} OOM kills in the last hour",
+                report.health.recent_oom_kills
+            ),
+        );
+    }
+    if let Some(offset) = report.os.clock_offset_ms {
+        if let Some(level) = clock_offset_level(offset) {
+            flag(level, format!("clock off by {:.0} ms", offset));
+        }
+    }
+    for cert in &report.security.tls_certificates {
+        let Some(days) = cert.days_until_expiry else {
+            continue;
+        };
+        if days < 0 {
+            flag(
+                OverallStatus::Critical,
+                format!("certificate for {} expired", cert.domain),
+            );
+        } else if let Some(level) = cert_level(days) {
+            flag(
+                level,
+                format!("certificate for {} expires in {} days", cert.domain, days),
+            );
+        }
+    }
+    if !report.health.failed_units.is_empty() {
+        flag(
+            OverallStatus::Degraded,
+            format!("{} failed units", report.health.failed_units.len()),
+        );
+    } else if report.health.degraded {
+        flag(OverallStatus::Degraded, "services degraded".to_string());
+    }
+    for array in report.hardware.raid_arrays.iter().filter(|a| a.degraded) {
+        flag(
+            OverallStatus::Degraded,
+            format!("RAID array {} degraded", array.name),
+        );
+    }
+    if report.processes.zombie_processes > 0 {
+        flag(
+            OverallStatus::Degraded,
+            format!("{} zombie processes", report.processes.zombie_processes),
+        );
+    }
+
+    if report.os.reboot_required {
+        flag(OverallStatus::Degraded, "reboot required".to_string());
+    }
+
+    (status, reasons)
+}
+
+/// 1-minute load average per core; 0 when the core count is unknown.
+pub fn load_per_core(load_average_1m: f64, cpu_cores: u32) -> f64 {
+    if cpu_cores == 0 {
+        return 0.0;
+    }
+    load_average_1m / f64::from(cpu_cores)
+}
+
+/// Critical above `critical`, Degraded above `warn`, else `None`.
+pub fn level(value: f64, warn: f64, critical: f64) -> Option<OverallStatus> {
+    if value > critical {
+        Some(OverallStatus::Critical)
+    } else if value > warn {
+        Some(OverallStatus::Degraded)
+    } else {
+        None
+    }
+}
+
+pub fn load_level(per_core: f64) -> Option<OverallStatus> {
+    level(per_core, LOAD_WARN_PER_CORE, LOAD_CRITICAL_PER_CORE)
+}
+
+/// Either way off: a clock ahead is as wrong as one behind.
+pub fn clock_offset_level(offset_ms: f64) -> Option<OverallStatus> {
+    level(
+        offset_ms.abs(),
+        CLOCK_OFFSET_WARN_MS,
+        CLOCK_OFFSET_CRITICAL_MS,
+    )
+}
+
+/// For a certificate `days` from expiry.
+pub fn cert_level(days: i64) -> Option<OverallStatus> {
+    if days < CERT_CRITICAL_DAYS {
+        Some(OverallStatus::Critical)
+    } else if days < CERT_WARN_DAYS {
+        Some(OverallStatus::Degraded)
+    } else {
+        None
+    }
+}
+
+/// For a 0–100 security posture score; `None` is a good one.
+pub fn score_level(score: u8) -> Option<OverallStatus> {
+    if score >= SCORE_GOOD {
+        None
+    } else if score >= SCORE_FAIR {
+        Some(OverallStatus::Degraded)
+    } else {
+        Some(OverallStatus::Critical)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::node_report::tests::make_test_report;
+    use crate::domain::node_report::{
+        CertStatus, DiskUsage, PressureStall, RaidArray, StallAverages,
+    };
+
+    fn disk(mount_point: &str, usage_percent: f64) -> DiskUsage {
+        DiskUsage {
+            mount_point: mount_point.to_string(),
+            usage_percent,
+            inode_percent: None,
+        }
+    }
+
+    fn cert(domain: &str, days: i64) -> CertStatus {
+        CertStatus {
+            domain: domain.to_string(),
+            expiry: None,
+            days_until_expiry: Some(days),
+            issuer: None,
+        }
+    }
+
+    #[test]
+    fn baseline_report_is_healthy() {
+        let (status, reasons) = classify(&make_test_report());
+        assert_eq!(status, OverallStatus::Healthy);
+        assert!(reasons.is_empty());
+    }
+
+    #[test]
+    fn display_levels_follow_the_shared_thresholds() {
+        assert_eq!(level(75.0, DISK_WARN_PERCENT, DISK_CRITICAL_PERCENT), None);
+        assert_eq!(
+            level(80.0, CPU_WARN_PERCENT, CPU_CRITICAL_PERCENT),
+            Some(OverallStatus::Degraded)
+        );
+        assert_eq!(
+            level(86.0, TEMP_WARN_CELSIUS, TEMP_CRITICAL_CELSIUS),
+            Some(OverallStatus::Critical)
+        );
+        assert_eq!(clock_offset_level(-1500.0), Some(OverallStatus::Critical));
+        assert_eq!(cert_level(13), Some(OverallStatus::Critical));
+        assert_eq!(cert_level(14), Some(OverallStatus::Degraded));
+        assert_eq!(cert_level(30), None);
+        assert_eq!(score_level(80), None);
+        assert_eq!(score_level(50), Some(OverallStatus::Degraded));
+        assert_eq!(score_level(49), Some(OverallStatus::Critical));
+    }
+
+    #[test]
+    fn zombies_and_failed_units_are_degraded() {
+        let mut report = make_test_report();
+        report.processes.zombie_processes = 2;
+        report.health.failed_units = vec!["nginx.service".to_string()];
+        let (status, reasons) = classify(&report);
+        assert_eq!(status, OverallStatus::Degraded);
+        assert_eq!(reasons, vec!["1 failed units", "2 zombie processes"]);
+    }
+
+    #[test]
+    fn degraded_raid_array_is_degraded() {
+        let mut report = make_test_report();
+        report.hardware.raid_arrays = vec![RaidArray {
+            name: "md1".to_string(),
+            level: Some("raid5".to_string()),
+            state: "active".to_string(),
+            devices: vec!["sdc1".into(), "sdd1(F)".into(), "sde1".into()],
+            member_status: Some("U_U".to_string()),
+            degraded: true,
+            sync_progress: None,
+        }];
+        let (status, reasons) = classify(&report);
+        assert_eq!(status, OverallStatus::Degraded);
+        assert_eq!(reasons, vec!["RAID array md1 degraded"]);
+    }
+
+    #[test]
+    fn inode_exhaustion_is_critical_with_bytes_free() {
+        let mut report = make_test_report();
+        report.health.disk_usage = vec![DiskUsage {
+            inode_percent: Some(97.0),
+            ..disk("/var", 30.0)
+        }];
+        let (status, reasons) = classify(&report);
+        assert_eq!(status, OverallStatus::Critical);
+        assert_eq!(reasons, vec!["disk /var inodes at 97%"]);
+
+        report.health.disk_usage[0].inode_percent = Some(85.0);
+        assert_eq!(classify(&report).0, OverallStatus::Healthy);
+    }
+
+    #[test]
+    fn clock_drift_is_flagged() {
+        let mut report = make_test_report();
+        report.os.clock_offset_ms = Some(-250.0);
+        assert_eq!(
+            classify(&report),
+            (
+                OverallStatus::Degraded,
+                vec!["clock off by -250 ms".to_string()]
+            )
+        );
+        report.os.clock_offset_ms = Some(4000.0);
+        assert_eq!(classify(&report).0, OverallStatus::Critical);
+    }
+
+    #[test]
+    fn yellow_thresholds_are_degraded() {
+        let mut report = make_test_report();
+        report.health.disk_usage = vec![disk("/", 80.0)];
+        report.security.tls_certificates = vec![cert("example.com", 20)];
+        assert_eq!(classify(&report).0, OverallStatus::Degraded);
+    }
+
+    #[test]
+    fn full_disk_is_critical() {
+        let mut report = make_test_report();
+        report.health.disk_usage = vec![disk("/boot", 40.0), disk("/nix", 95.0)];
+        report.processes.zombie_processes = 1;
+        let (status, reasons) = classify(&report);
+        assert_eq!(status, OverallStatus::Critical);
+        assert_eq!(reasons[0], "disk /nix at 95%");
+    }
+
+    #[test]
+    fn load_is_normalized_per_core() {
+        assert_eq!(load_per_core(6.0, 4), 1.5);
+        assert_eq!(load_per_core(6.0, 0), 0.0);
+
+        let mut report = make_test_report();
+        report.health.load_per_core = load_per_core(6.0, 4);
+        let (status, reasons) = classify(&report);
+        assert_eq!(status, OverallStatus::Degraded);
+        assert_eq!(reasons, vec!["load 1.50 per core"]);
+
+        report.health.load_per_core = load_per_core(9.0, 4);
+        assert_eq!(classify(&report).0, OverallStatus::Critical);
+    }
+
+    #[test]
+    fn oom_kills_and_pending_reboot_are_degraded() {
+        let mut report = make_test_report();
+        report.health.recent_oom_kills = 3;
+        report.os.reboot_required = true;
+        let (status, reasons) = classify(&report);
+        assert_eq!(status, OverallStatus::Degraded);
+        assert_eq!(
+            reasons,
+            vec!["3 OOM kills in the last hour", "reboot required"]
+        );
+    }
+
+    #[test]
+    fn pressure_stalls_are_flagged() {
+        let stall = |avg60| PressureStall {
+            some: StallAverages {
+                avg10: avg60,
+                avg60,
+                avg300: avg60,
+            },
+            full: None,
+        };
+        let mut report = make_test_report();
+        report.health.cpu_pressure = Some(stall(2.0));
+        report.health.io_pressure = Some(stall(15.0));
+        let (status, reasons) = classify(&report);
+        assert_eq!(status, OverallStatus::Degraded);
+        assert_eq!(reasons, vec!["io pressure 15%"]);
+
+        report.health.memory_pressure = Some(stall(40.0));
+        let (status, reasons) = classify(&report);
+        assert_eq!(status, OverallStatus::Critical);
+        assert_eq!(reasons, vec!["memory pressure 40%", "io pressure 15%"]);
+    }
+
+    #[test]
+    fn memory_pressure_is_critical() {
+        let mut report = make_test_report();
+        report.health.memory_usage_percent = 93.5;
+        assert_eq!(classify(&report).0, OverallStatus::Critical);
+    }
+
+    #[test]
+    fn expiring_or_expired_cert_is_critical() {
+        let mut report = make_test_report();
+        report.security.tls_certificates = vec![cert("soon.example.com", 7)];
+        assert_eq!(classify(&report).0, OverallStatus::Critical);
+
+        report.security.tls_certificates = vec![cert("old.example.com", -3)];
+        let (status, reasons) = classify(&report);
+        assert_eq!(status, OverallStatus::Critical);
+        assert_eq!(reasons, vec!["certificate for old.example.com expired"]);
+    }
+
+    #[test]
+    fn serializes_snake_case() {
+        assert_eq!(
+            serde_json::to_string(&OverallStatus::Degraded).unwrap(),
+            "\"degraded\""
+        );
+    }
+}
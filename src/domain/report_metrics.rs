@@ -0,0 +1,28 @@
+//! Prometheus text exposition of a node report.
+//!
+//! Served by the daemon's `/metrics` endpoint and printed by
+//! `kindling report --format prometheus`, so both produce the same output
+//! for the same report.
+
+use std::fmt::Write;
+
+use super::node_report::NodeReport;
+
+/// Content type of the text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Render the metrics carried by `report`.
+pub fn render(report: &NodeReport) -> String {
+    let mut body = String::from(
+        "# HELP kindling_report_section_duration_ms Time the last report collection spent per section.\n\
+         # TYPE kindling_report_section_duration_ms gauge\n",
+    );
+    for (section, ms) in &report.collection_durations_ms {
+        let _ = writeln!(
+            body,
+            "kindling_report_section_duration_ms{{section=\"{}\"}} {}",
+            section, ms
+        );
+    }
+    body
+}
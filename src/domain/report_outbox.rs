@@ -0,0 +1,367 @@
+//! Outbox for reports that could not be pushed to the fleet controller.
+//!
+//! `kindling report --push` retries with backoff; a push that still fails is
+//! written to `~/.config/kindling/outbox/` through [`ReportStore`], so each
+//! queued file is written atomically and checksum-verified when read back.
+//! File names start with a zero-padded timestamp, so sorting them gives the
+//! order they were queued in, and a flush replays them in that order.
+//!
+//! Only failures that may clear up are retried and queued: transport errors
+//! (including timeouts), 5xx, 408 and 429. Any other refusal means the
+//! controller will never take that report, so it is dropped instead, or set
+//! aside as `.rejected` when it was already queued.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use tracing::warn;
+
+use super::node_report::StoredReport;
+use super::report_store::ReportStore;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    /// Delay before the second attempt; doubled before each one after that.
+    pub initial_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            initial_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Request timeout for report pushes, so a stalled controller counts as a
+/// (retryable) failure instead of hanging the push.
+pub const PUSH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The controller refused a report with a status that retrying won't fix.
+#[derive(Debug, thiserror::Error)]
+#[error("controller rejected the report: {status} {body}")]
+pub struct Rejected {
+    pub status: reqwest::StatusCode,
+    pub body: String,
+}
+
+/// Whether a push failed for good (see [`Rejected`]).
+pub fn is_rejected(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<Rejected>().is_some()
+}
+
+fn is_retryable(status: reqwest::StatusCode) -> bool {
+    status.is_server_error()
+        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Controller ingest URL for one node's report.
+pub fn push_endpoint(controller_url: &str, hostname: &str) -> String {
+    format!(
+        "{}/api/v1/fleet/nodes/{}/report",
+        controller_url.trim_end_matches('/'),
+        hostname
+    )
+}
+
+/// POST a report to the controller, retrying transport errors and
+/// retryable statuses with exponential backoff. Returns the last error, or
+/// a [`Rejected`] one straight away.
+pub async fn push(
+    client: &reqwest::Client,
+    controller_url: &str,
+    stored: &StoredReport,
+    policy: &RetryPolicy,
+) -> Result<()> {
+    let endpoint = push_endpoint(controller_url, &stored.report.hostname);
+    let mut delay = policy.initial_delay;
+    let mut attempt = 1;
+    loop {
+        let err = match client.post(&endpoint).json(stored).send().await {
+            Ok(resp) if resp.status().is_success() => return Ok(()),
+            Ok(resp) if is_retryable(resp.status()) => anyhow::anyhow!(
+                "{} {}",
+                resp.status(),
+                resp.text().await.unwrap_or_default()
+            ),
+            Ok(resp) => {
+                return Err(Rejected {
+                    status: resp.status(),
+                    body: resp.text().await.unwrap_or_default(),
+                }
+                .into())
+            }
+            Err(e) => anyhow::Error::new(e).context(format!("POST {}", endpoint)),
+        };
+        if attempt >= policy.attempts {
+            return Err(err);
+        }
+        warn!(attempt, error = %err, "report push failed, retrying");
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+        attempt += 1;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushOutcome {
+    pub sent: usize,
+    pub remaining: usize,
+}
+
+pub struct Outbox {
+    dir: PathBuf,
+}
+
+impl Outbox {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Default outbox directory: `~/.config/kindling/outbox/`
+    pub fn default_dir() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_else(|| PathBuf::from("~/.config"))
+            .join("kindling")
+            .join("outbox")
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Queue a report behind everything already waiting.
+    pub async fn enqueue(&self, stored: &StoredReport) -> Result<PathBuf> {
+        let nanos = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        let path = self
+            .dir
+            .join(format!("{:020}-{}.json", nanos, stored.report.hostname));
+        ReportStore::new(path.clone()).write(stored).await?;
+        Ok(path)
+    }
+
+    /// Queued report files, oldest first. A missing directory is empty.
+    pub fn pending(&self) -> Result<Vec<PathBuf>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("reading {}", self.dir.display())),
+        };
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    /// Push queued reports in order, removing each once the controller has
+    /// it. Stops at the first retryable failure so later reports never
+    /// overtake it. Files that fail verification are renamed to `.bad`, and
+    /// reports the controller rejects to `.rejected`; both are skipped.
+    pub async fn flush(
+        &self,
+        client: &reqwest::Client,
+        controller_url: &str,
+        policy: &RetryPolicy,
+    ) -> Result<FlushOutcome> {
+        let pending = self.pending()?;
+        let mut sent = 0;
+        for (i, path) in pending.iter().enumerate() {
+            let stored = match ReportStore::new(path.clone()).read().await {
+                Ok(stored) => stored,
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "setting aside unreadable outbox entry");
+                    let _ = std::fs::rename(path, path.with_extension("json.bad"));
+                    continue;
+                }
+            };
+            if let Err(e) = push(client, controller_url, &stored, policy).await {
+                if is_rejected(&e) {
+                    warn!(path = %path.display(), error = %e, "setting aside rejected outbox entry");
+                    std::fs::rename(path, path.with_extension("json.rejected"))
+                        .with_context(|| format!("setting aside {}", path.display()))?;
+                    continue;
+                }
+                warn!(path = %path.display(), error = %e, "outbox flush stopped");
+                return Ok(FlushOutcome {
+                    sent,
+                    remaining: pending.len() - i,
+                });
+            }
+            std::fs::remove_file(path).with_context(|| format!("removing {}", path.display()))?;
+            sent += 1;
+        }
+        Ok(FlushOutcome { sent, remaining: 0 })
+    }
+}
+
+/// Fail with the outbox location when reports are still waiting.
+pub fn ensure_drained(outbox: &Outbox, outcome: FlushOutcome) -> Result<()> {
+    if outcome.remaining > 0 {
+        bail!(
+            "{} report(s) still queued in {}",
+            outcome.remaining,
+            outbox.dir().display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::node_report::tests::make_test_report;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::Router;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Controller stand-in that answers 503 until `up` is set, then counts
+    /// accepted reports.
+    async fn serve_controller(up: Arc<AtomicBool>, received: Arc<AtomicUsize>) -> String {
+        let app = Router::new().route(
+            "/api/v1/fleet/nodes/{hostname}/report",
+            post(move || {
+                let up = up.clone();
+                let received = received.clone();
+                async move {
+                    if !up.load(Ordering::SeqCst) {
+                        return StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    received.fetch_add(1, Ordering::SeqCst);
+                    StatusCode::OK
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    fn quick_retry() -> RetryPolicy {
+        RetryPolicy {
+            attempts: 2,
+            initial_delay: Duration::from_millis(1),
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_push_is_queued_and_flushed_later() {
+        let dir = tempfile::tempdir().unwrap();
+        let outbox = Outbox::new(dir.path().join("outbox"));
+        let up = Arc::new(AtomicBool::new(false));
+        let received = Arc::new(AtomicUsize::new(0));
+        let url = serve_controller(up.clone(), received.clone()).await;
+        let client = reqwest::Client::new();
+
+        let stored = StoredReport::new(make_test_report());
+        let err = push(&client, &url, &stored, &quick_retry())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("503"));
+        outbox.enqueue(&stored).await.unwrap();
+        outbox.enqueue(&stored).await.unwrap();
+        assert_eq!(outbox.pending().unwrap().len(), 2);
+
+        let outcome = outbox.flush(&client, &url, &quick_retry()).await.unwrap();
+        assert_eq!(
+            outcome,
+            FlushOutcome {
+                sent: 0,
+                remaining: 2
+            }
+        );
+        assert!(ensure_drained(&outbox, outcome).is_err());
+
+        up.store(true, Ordering::SeqCst);
+        let outcome = outbox.flush(&client, &url, &quick_retry()).await.unwrap();
+        assert_eq!(
+            outcome,
+            FlushOutcome {
+                sent: 2,
+                remaining: 0
+            }
+        );
+        assert_eq!(received.load(Ordering::SeqCst), 2);
+        assert!(outbox.pending().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn rejected_reports_are_not_retried_or_kept_queued() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let app = Router::new().route(
+            "/api/v1/fleet/nodes/{hostname}/report",
+            post(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async { (StatusCode::BAD_REQUEST, "hostname mismatch") }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = reqwest::Client::new();
+        let stored = StoredReport::new(make_test_report());
+
+        let err = push(&client, &url, &stored, &quick_retry())
+            .await
+            .unwrap_err();
+        assert!(is_rejected(&err));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        let dir = tempfile::tempdir().unwrap();
+        let outbox = Outbox::new(dir.path().to_path_buf());
+        let queued = outbox.enqueue(&stored).await.unwrap();
+        let outcome = outbox.flush(&client, &url, &quick_retry()).await.unwrap();
+        assert_eq!(
+            outcome,
+            FlushOutcome {
+                sent: 0,
+                remaining: 0
+            }
+        );
+        assert!(queued.with_extension("json.rejected").exists());
+        assert!(!is_retryable(StatusCode::BAD_REQUEST));
+        assert!(is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(StatusCode::BAD_GATEWAY));
+    }
+
+    #[tokio::test]
+    async fn pending_is_ordered_and_skips_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let outbox = Outbox::new(dir.path().to_path_buf());
+        assert!(Outbox::new(dir.path().join("missing"))
+            .pending()
+            .unwrap()
+            .is_empty());
+
+        let first = outbox
+            .enqueue(&StoredReport::new(make_test_report()))
+            .await
+            .unwrap();
+        let second = outbox
+            .enqueue(&StoredReport::new(make_test_report()))
+            .await
+            .unwrap();
+        std::fs::write(dir.path().join("00-partial.json.tmp"), b"{").unwrap();
+
+        assert_eq!(outbox.pending().unwrap(), vec![first, second]);
+    }
+
+    #[test]
+    fn push_endpoint_trims_trailing_slash() {
+        assert_eq!(
+            push_endpoint("http://controller:9100/", "edge-1"),
+            "http://controller:9100/api/v1/fleet/nodes/edge-1/report"
+        );
+    }
+}
@@ -3,13 +3,14 @@
 //! Provides the file persistence layer for the one-way report pipeline:
 //! Discovery → ReportStore → MemoryCache → API
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context, Result};
 use tokio::sync::Mutex;
 use tracing::warn;
 
 use super::node_report::StoredReport;
+use crate::paths::probe_writable;
 
 pub struct ReportStore {
     path: PathBuf,
@@ -84,10 +85,27 @@ impl ReportStore {
         Ok(stored)
     }
 
+    /// Create the cache directory and write-test it, so an unwritable cache
+    /// is caught at startup rather than on the first refresh.
+    pub async fn probe_writable(&self) -> Result<()> {
+        let dir = match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("creating directory {}", dir.display()))?;
+        probe_writable(dir).with_context(|| format!("{} is not writable", dir.display()))
+    }
+
     /// Check whether the cache file exists on disk.
     pub fn exists(&self) -> bool {
         self.path.exists()
     }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
 }
 
 #[cfg(test)]
@@ -95,6 +113,7 @@ mod tests {
     use super::*;
     use crate::domain::node_report::*;
     use chrono::Utc;
+    use std::collections::BTreeMap;
 
     fn make_test_report() -> NodeReport {
         NodeReport {
@@ -117,6 +136,11 @@ mod tests {
                 gpus: vec![],
                 temperatures: vec![],
                 power: None,
+                pci_devices: vec![],
+                usb_devices: vec![],
+                raid_arrays: vec![],
+                cgroup_memory_limit_bytes: None,
+                cgroup_cpu_quota: None,
             },
             os: OsSnapshot {
                 distribution: "NixOS".to_string(),
@@ -133,6 +157,10 @@ mod tests {
                 timezone: None,
                 is_wsl: false,
                 virtualization: None,
+                reboot_required: false,
+                time_synced: None,
+                clock_offset_ms: None,
+                entropy_available: None,
             },
             network: NetworkSnapshot {
                 hostname: "test-node".to_string(),
@@ -141,6 +169,8 @@ mod tests {
                 dns_resolvers: vec![],
                 default_gateway: None,
                 listening_ports: vec![],
+                dns_resolver_health: vec![],
+                connection_summary: ConnectionSummary::default(),
             },
             nix: NixSnapshot {
                 nix_version: "2.24.12".to_string(),
@@ -155,18 +185,34 @@ mod tests {
                 trusted_users: vec!["root".to_string()],
                 max_jobs: None,
                 sandbox_enabled: true,
+                system_flake: None,
+                eval_cache_size_bytes: None,
+                eval_cache_enabled: None,
+                recent_nix_errors: vec![],
+                config_source_dirty: None,
+                config_source_rev: None,
+                active_builds: Vec::new(),
             },
             kubernetes: None,
             health: HealthMetrics {
                 load_average_1m: 0.5,
                 load_average_5m: 0.3,
                 load_average_15m: 0.2,
+                load_per_core: 0.125,
                 memory_usage_percent: 50.0,
                 swap_usage_percent: 0.0,
                 cpu_usage_percent: 10.0,
                 disk_usage: vec![],
                 open_file_descriptors: None,
                 max_file_descriptors: None,
+                recent_oom_kills: 0,
+                oom_victims: vec![],
+                failed_units: vec![],
+                loaded_services: None,
+                degraded: false,
+                memory_pressure: None,
+                cpu_pressure: None,
+                io_pressure: None,
             },
             security: SecuritySnapshot {
                 ssh_keys_deployed: vec![],
@@ -177,6 +223,8 @@ mod tests {
                 sshd_running: true,
                 root_login_allowed: false,
                 password_auth_enabled: false,
+                security_score: 0,
+                findings: vec![],
             },
             processes: ProcessSnapshot {
                 total_processes: 100,
@@ -184,7 +232,12 @@ mod tests {
                 zombie_processes: 0,
                 top_cpu: vec![],
                 top_memory: vec![],
+                top_programs: vec![],
             },
+            collection_durations_ms: BTreeMap::new(),
+            collection_errors: BTreeMap::new(),
+            collection_warnings: Vec::new(),
+            declared: DeclaredLabels::default(),
         }
     }
 
@@ -255,6 +308,46 @@ mod tests {
         assert!(path.exists());
     }
 
+    #[tokio::test]
+    async fn probe_writable_creates_cache_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ReportStore::new(dir.path().join("nested/report.json"));
+
+        store.probe_writable().await.unwrap();
+        assert!(dir.path().join("nested").is_dir());
+        let leftover = std::fs::read_dir(dir.path().join("nested")).unwrap().count();
+        assert_eq!(leftover, 0, "probe file should be removed");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn probe_writable_fails_on_read_only_directory() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let ro = dir.path().join("ro");
+        std::fs::create_dir(&ro).unwrap();
+        std::fs::set_permissions(&ro, std::fs::Permissions::from_mode(0o555)).unwrap();
+        // root ignores directory permissions; nothing to assert then
+        if std::fs::write(ro.join("probe"), b"").is_ok() {
+            return;
+        }
+
+        let store = ReportStore::new(ro.join("report.json"));
+        let err = store.probe_writable().await.unwrap_err();
+        assert!(format!("{:#}", err).contains("is not writable"));
+    }
+
+    #[tokio::test]
+    async fn probe_writable_fails_when_parent_is_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("not-a-dir");
+        std::fs::write(&file, b"").unwrap();
+
+        let store = ReportStore::new(file.join("report.json"));
+        assert!(store.probe_writable().await.is_err());
+    }
+
     #[test]
     fn exists_false_for_missing_file() {
         let store = ReportStore::new(PathBuf::from("/nonexistent/path/report.json"));
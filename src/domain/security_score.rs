@@ -0,0 +1,186 @@
+//! Security posture scoring — a 0–100 summary of a collected SecuritySnapshot.
+//!
+//! Scoring starts at 90. Deployed SSH keys add 10; every finding subtracts
+//! its penalty. sshd settings only count while sshd is running.
+
+use super::node_report::{FindingKind, FindingSeverity, SecurityFinding, SecuritySnapshot};
+use super::report_health::{self, OverallStatus};
+
+const BASE_SCORE: i32 = 90;
+const SSH_KEYS_BONUS: i32 = 10;
+
+/// Compute the posture score and the findings that lowered it.
+pub fn assess(security: &SecuritySnapshot) -> (u8, Vec<SecurityFinding>) {
+    let mut findings = Vec::new();
+    let mut score = BASE_SCORE;
+
+    let mut penalize = |penalty: i32, severity: FindingSeverity, message: String| {
+        score -= penalty;
+        findings.push(SecurityFinding {
+            severity,
+            kind: FindingKind::Posture,
+            message,
+        });
+    };
+
+    if !security.firewall_active {
+        penalize(25, FindingSeverity::High, "firewall is inactive".to_string());
+    }
+    if security.sshd_running && security.root_login_allowed {
+        penalize(25, FindingSeverity::High, "sshd permits root login".to_string());
+    }
+    if security.sshd_running && security.password_auth_enabled {
+        penalize(
+            20,
+            FindingSeverity::Medium,
+            "sshd permits password authentication".to_string(),
+        );
+    }
+    for cert in &security.tls_certificates {
+        let Some(days) = cert.days_until_expiry else {
+            continue;
+        };
+        if days < 0 {
+            penalize(
+                20,
+                FindingSeverity::High,
+                format!("certificate for {} expired {} days ago", cert.domain, -days),
+            );
+        } else if let Some(level) = report_health::cert_level(days) {
+            let (penalty, severity) = match level {
+                OverallStatus::Critical => (15, FindingSeverity::High),
+                _ => (5, FindingSeverity::Medium),
+            };
+            penalize(
+                penalty,
+                severity,
+                format!("certificate for {} expires in {} days", cert.domain, days),
+            );
+        }
+    }
+
+    if security.ssh_keys_deployed.is_empty() {
+        findings.push(SecurityFinding {
+            severity: FindingSeverity::Low,
+            kind: FindingKind::Posture,
+            message: "no SSH authorized keys deployed".to_string(),
+        });
+    } else {
+        score += SSH_KEYS_BONUS;
+    }
+
+    (score.clamp(0, 100) as u8, findings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::node_report::CertStatus;
+
+    fn hardened() -> SecuritySnapshot {
+        SecuritySnapshot {
+            ssh_keys_deployed: vec!["ops@laptop".to_string()],
+            tls_certificates: vec![],
+            firewall_active: true,
+            firewall_rules_count: 12,
+            firewall_backend: Some("nftables".to_string()),
+            sshd_running: true,
+            root_login_allowed: false,
+            password_auth_enabled: false,
+            security_score: 0,
+            findings: vec![],
+        }
+    }
+
+    fn cert(domain: &str, days: i64) -> CertStatus {
+        CertStatus {
+            domain: domain.to_string(),
+            expiry: None,
+            days_until_expiry: Some(days),
+            issuer: None,
+        }
+    }
+
+    fn messages(findings: &[SecurityFinding]) -> Vec<&str> {
+        findings.iter().map(|f| f.message.as_str()).collect()
+    }
+
+    #[test]
+    fn hardened_host_scores_full_marks() {
+        let (score, findings) = assess(&hardened());
+        assert_eq!(score, 100);
+        assert!(findings.is_empty());
+    }
+
+    #[test]
+    fn missing_ssh_keys_loses_bonus() {
+        let mut s = hardened();
+        s.ssh_keys_deployed.clear();
+        let (score, findings) = assess(&s);
+        assert_eq!(score, 90);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, FindingSeverity::Low);
+    }
+
+    #[test]
+    fn permissive_sshd_and_no_firewall() {
+        let mut s = hardened();
+        s.firewall_active = false;
+        s.root_login_allowed = true;
+        s.password_auth_enabled = true;
+        let (score, findings) = assess(&s);
+        assert_eq!(score, 100 - 25 - 25 - 20);
+        assert_eq!(
+            messages(&findings),
+            vec![
+                "firewall is inactive",
+                "sshd permits root login",
+                "sshd permits password authentication",
+            ]
+        );
+        assert_eq!(findings[2].severity, FindingSeverity::Medium);
+    }
+
+    #[test]
+    fn sshd_settings_ignored_when_not_running() {
+        let mut s = hardened();
+        s.sshd_running = false;
+        s.root_login_allowed = true;
+        s.password_auth_enabled = true;
+        assert_eq!(assess(&s), (100, vec![]));
+    }
+
+    #[test]
+    fn expiring_certificates_graded_by_urgency() {
+        let mut s = hardened();
+        s.tls_certificates = vec![
+            cert("ok.example.com", 90),
+            cert("soon.example.com", 20),
+            cert("urgent.example.com", 3),
+            cert("old.example.com", -2),
+        ];
+        let (score, findings) = assess(&s);
+        assert_eq!(score, 100 - 5 - 15 - 20);
+        assert_eq!(
+            messages(&findings),
+            vec![
+                "certificate for soon.example.com expires in 20 days",
+                "certificate for urgent.example.com expires in 3 days",
+                "certificate for old.example.com expired 2 days ago",
+            ]
+        );
+    }
+
+    #[test]
+    fn worst_case_clamps_to_zero() {
+        let mut s = hardened();
+        s.ssh_keys_deployed.clear();
+        s.firewall_active = false;
+        s.root_login_allowed = true;
+        s.password_auth_enabled = true;
+        s.tls_certificates = (0..5).map(|i| cert(&format!("c{i}.example.com"), -1)).collect();
+        let (score, findings) = assess(&s);
+        assert_eq!(score, 0);
+        assert_eq!(findings.len(), 9);
+    }
+}
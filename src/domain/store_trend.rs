@@ -0,0 +1,315 @@
+//! Nix store growth tracking.
+//!
+//! Every full report collection appends a sample (store size, path count,
+//! and the disk holding `/nix/store`) to `store-trend.jsonl` next to the
+//! report cache, at most once per [`MIN_SAMPLE_INTERVAL_SECS`], keeping the
+//! newest [`MAX_SAMPLES`]. `kindling
+//! store trend` fits a line through the samples to get growth per day and
+//! projects when that disk reaches a usage threshold.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::node_report::NodeReport;
+
+/// Samples closer together than this are dropped; the daemon refreshes
+/// far more often than store growth is worth recording.
+pub const MIN_SAMPLE_INTERVAL_SECS: i64 = 3600;
+
+/// Samples kept in the file: 90 days at one per hour. Older ones are
+/// dropped when a new sample would exceed it.
+pub const MAX_SAMPLES: usize = 90 * 24;
+
+const SECS_PER_DAY: f64 = 86_400.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoreSample {
+    pub timestamp: DateTime<Utc>,
+    pub store_size_bytes: u64,
+    pub store_path_count: u64,
+    /// Mount point of the disk holding the store (`/nix` or `/`).
+    #[serde(default)]
+    pub disk_mount: Option<String>,
+    #[serde(default)]
+    pub disk_total_bytes: u64,
+    #[serde(default)]
+    pub disk_used_bytes: u64,
+}
+
+impl StoreSample {
+    pub fn from_report(report: &NodeReport) -> Self {
+        let disk = ["/nix", "/"].iter().find_map(|mount| {
+            report
+                .hardware
+                .disks
+                .iter()
+                .find(|d| d.mount_point == *mount)
+        });
+        Self {
+            timestamp: report.timestamp,
+            store_size_bytes: report.nix.store_size_bytes,
+            store_path_count: report.nix.store_path_count,
+            disk_mount: disk.map(|d| d.mount_point.clone()),
+            disk_total_bytes: disk.map(|d| d.total_bytes).unwrap_or(0),
+            disk_used_bytes: disk.map(|d| d.used_bytes).unwrap_or(0),
+        }
+    }
+}
+
+/// Sample file that lives next to the report cache file.
+pub fn samples_path(report_cache_file: &Path) -> PathBuf {
+    report_cache_file.with_file_name("store-trend.jsonl")
+}
+
+/// Append a sample for `report` unless the last one is too recent or the
+/// report has no store data. Returns whether a sample was written.
+pub fn record(path: &Path, report: &NodeReport) -> Result<bool> {
+    record_capped(path, report, MAX_SAMPLES)
+}
+
+fn record_capped(path: &Path, report: &NodeReport, max_samples: usize) -> Result<bool> {
+    if report.nix.store_size_bytes == 0 {
+        return Ok(false);
+    }
+    let sample = StoreSample::from_report(report);
+    let mut samples = read(path)?;
+    if let Some(last) = samples.last() {
+        if (sample.timestamp - last.timestamp).num_seconds() < MIN_SAMPLE_INTERVAL_SECS {
+            return Ok(false);
+        }
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {}", parent.display()))?;
+    }
+    if samples.len() < max_samples {
+        let line = sample_line(&sample)?;
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("failed to append to {}", path.display()))?;
+        return Ok(true);
+    }
+
+    // Full: rewrite without the oldest samples.
+    samples.push(sample);
+    let keep = samples.split_off(samples.len() - max_samples);
+    let content = keep.iter().map(sample_line).collect::<Result<String>>()?;
+    let tmp_path = path.with_extension("jsonl.tmp");
+    std::fs::write(&tmp_path, content)
+        .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, path)
+        .with_context(|| format!("failed to replace {}", path.display()))?;
+    Ok(true)
+}
+
+fn sample_line(sample: &StoreSample) -> Result<String> {
+    let mut line = serde_json::to_string(sample).context("failed to serialize store sample")?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Read all samples, oldest first. Unparseable lines are skipped.
+pub fn read(path: &Path) -> Result<Vec<StoreSample>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StoreTrend {
+    pub samples: usize,
+    pub span_days: f64,
+    pub size_bytes_per_day: f64,
+    pub paths_per_day: f64,
+    pub latest: StoreSample,
+    pub threshold_percent: f64,
+    /// Days until the store disk crosses `threshold_percent` at the current
+    /// growth rate; `Some(0.0)` if already past it, `None` if the store
+    /// isn't growing or the disk is unknown.
+    pub days_until_threshold: Option<f64>,
+}
+
+/// Fit growth rates through `samples` (oldest first) and project when the
+/// store disk reaches `threshold_percent`. Needs two samples at different
+/// times.
+pub fn compute(samples: &[StoreSample], threshold_percent: f64) -> Option<StoreTrend> {
+    let first = samples.first()?;
+    let latest = samples.last()?.clone();
+    let days =
+        |s: &StoreSample| (s.timestamp - first.timestamp).num_seconds() as f64 / SECS_PER_DAY;
+
+    let (size_bytes_per_day, _) = linear_fit(
+        &samples
+            .iter()
+            .map(|s| (days(s), s.store_size_bytes as f64))
+            .collect::<Vec<_>>(),
+    )?;
+    let (paths_per_day, _) = linear_fit(
+        &samples
+            .iter()
+            .map(|s| (days(s), s.store_path_count as f64))
+            .collect::<Vec<_>>(),
+    )?;
+
+    let days_until_threshold = days_until(
+        latest.disk_used_bytes,
+        latest.disk_total_bytes,
+        threshold_percent,
+        size_bytes_per_day,
+    );
+    Some(StoreTrend {
+        samples: samples.len(),
+        span_days: days(&latest),
+        size_bytes_per_day,
+        paths_per_day,
+        threshold_percent,
+        days_until_threshold,
+        latest,
+    })
+}
+
+/// Days for `used` to reach `threshold_percent` of `total` growing at
+/// `bytes_per_day`.
+fn days_until(used: u64, total: u64, threshold_percent: f64, bytes_per_day: f64) -> Option<f64> {
+    if total == 0 {
+        return None;
+    }
+    let remaining = total as f64 * threshold_percent / 100.0 - used as f64;
+    if remaining <= 0.0 {
+        Some(0.0)
+    } else if bytes_per_day > 0.0 {
+        Some(remaining / bytes_per_day)
+    } else {
+        None
+    }
+}
+
+/// Least-squares line through `points`: `(slope, intercept)`. `None` with
+/// fewer than two points or when every x is the same.
+fn linear_fit(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let var_x: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if var_x == 0.0 {
+        return None;
+    }
+    let cov: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let slope = cov / var_x;
+    Some((slope, mean_y - slope * mean_x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::node_report::tests::make_test_report;
+
+    const GIB: u64 = 1 << 30;
+
+    fn sample(day: i64, size_gib: u64, paths: u64) -> StoreSample {
+        StoreSample {
+            timestamp: DateTime::from_timestamp(1_750_000_000 + day * 86_400, 0).unwrap(),
+            store_size_bytes: size_gib * GIB,
+            store_path_count: paths,
+            disk_mount: Some("/".to_string()),
+            disk_total_bytes: 100 * GIB,
+            disk_used_bytes: (size_gib + 20) * GIB,
+        }
+    }
+
+    #[test]
+    fn linear_fit_recovers_exact_line() {
+        let (slope, intercept) =
+            linear_fit(&[(0.0, 3.0), (1.0, 5.0), (2.0, 7.0), (4.0, 11.0)]).unwrap();
+        assert!((slope - 2.0).abs() < 1e-9);
+        assert!((intercept - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn linear_fit_needs_spread() {
+        assert!(linear_fit(&[(1.0, 2.0)]).is_none());
+        assert!(linear_fit(&[(1.0, 2.0), (1.0, 4.0)]).is_none());
+    }
+
+    #[test]
+    fn projects_days_until_threshold() {
+        // 2 GiB/day from 40 GiB; disk used = store + 20 GiB on a 100 GiB disk.
+        let samples = vec![
+            sample(0, 40, 10_000),
+            sample(1, 42, 10_500),
+            sample(3, 46, 11_500),
+        ];
+        let trend = compute(&samples, 90.0).unwrap();
+        assert_eq!(trend.samples, 3);
+        assert!((trend.span_days - 3.0).abs() < 1e-9);
+        assert!((trend.size_bytes_per_day - 2.0 * GIB as f64).abs() < 1.0);
+        assert!((trend.paths_per_day - 500.0).abs() < 1e-6);
+        // Latest used 66 GiB, threshold 90 GiB → 24 GiB at 2 GiB/day.
+        assert!((trend.days_until_threshold.unwrap() - 12.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn shrinking_store_has_no_projection() {
+        let samples = vec![sample(0, 50, 12_000), sample(2, 44, 11_000)];
+        let trend = compute(&samples, 90.0).unwrap();
+        assert!(trend.size_bytes_per_day < 0.0);
+        assert!(trend.days_until_threshold.is_none());
+    }
+
+    #[test]
+    fn already_past_threshold_is_zero_days() {
+        assert_eq!(days_until(95 * GIB, 100 * GIB, 90.0, 1.0), Some(0.0));
+        assert_eq!(days_until(10, 0, 90.0, 1.0), None);
+    }
+
+    #[test]
+    fn record_rate_limits_samples() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = samples_path(&dir.path().join("report.json"));
+        assert!(path.ends_with("store-trend.jsonl"));
+
+        let mut report = make_test_report();
+        assert!(record(&path, &report).unwrap());
+        report.timestamp += chrono::Duration::minutes(10);
+        assert!(!record(&path, &report).unwrap());
+        report.timestamp += chrono::Duration::hours(2);
+        assert!(record(&path, &report).unwrap());
+        assert_eq!(read(&path).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn record_drops_oldest_samples_past_the_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = samples_path(&dir.path().join("report.json"));
+
+        let mut report = make_test_report();
+        let mut written = Vec::new();
+        for _ in 0..5 {
+            assert!(record_capped(&path, &report, 3).unwrap());
+            written.push(report.timestamp);
+            report.timestamp += chrono::Duration::hours(2);
+        }
+        let kept: Vec<_> = read(&path).unwrap().iter().map(|s| s.timestamp).collect();
+        assert_eq!(kept, written[2..]);
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use async_graphql::SimpleObject;
 use serde::{Deserialize, Serialize};
 
@@ -7,6 +9,9 @@ pub struct NixStatus {
     pub version: Option<String>,
     pub nix_path: Option<String>,
     pub install_method: Option<String>,
+    /// Distinct nix installs found on the machine, when more than one.
+    #[serde(default)]
+    pub conflicting_installs: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -34,6 +39,24 @@ pub struct NixConfig {
     pub cores: Option<String>,
     pub experimental_features: Vec<String>,
     pub sandbox: Option<String>,
+    /// Required experimental features absent from `experimental_features`.
+    #[serde(default)]
+    pub missing_features: Vec<String>,
+    /// Every setting whose value differs from Nix's built-in default,
+    /// written as it would be in nix.conf.
+    #[serde(default)]
+    pub overrides: HashMap<String, String>,
+}
+
+impl NixConfig {
+    /// Features from `required` that are not enabled, in `required` order.
+    pub fn features_missing(&self, required: &[String]) -> Vec<String> {
+        required
+            .iter()
+            .filter(|f| !self.experimental_features.contains(f))
+            .cloned()
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
@@ -44,6 +67,19 @@ pub struct GcStatus {
     pub last_gc_freed_bytes: Option<u64>,
 }
 
+/// Limits for a garbage collection run. The default collects everything
+/// unreachable.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcOptions {
+    /// Delete profile generations older than this many days first,
+    /// written `<N>d` (e.g. `30d`).
+    #[serde(default)]
+    pub older_than: Option<String>,
+    /// Stop once this many bytes have been freed.
+    #[serde(default)]
+    pub max_freed: Option<u64>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct GcResult {
     pub freed_bytes: u64,
@@ -57,6 +93,54 @@ pub struct OptimiseResult {
     pub duration_secs: f64,
 }
 
+/// Dry-run estimate of what `nix store optimise` would free.
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct OptimiseEstimate {
+    pub deduplicable_bytes: u64,
+    /// Files that would be replaced by a hard link to an identical copy.
+    pub duplicate_files: u64,
+    pub scanned_files: u64,
+    pub duration_secs: f64,
+}
+
+/// Outcome of `nix store verify`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, SimpleObject)]
+pub struct StoreVerifyResult {
+    /// Paths whose contents no longer match their recorded hash.
+    pub corrupted_paths: Vec<String>,
+    /// Valid paths whose files are gone from disk.
+    pub missing_paths: Vec<String>,
+    /// Whether `--repair` was passed.
+    pub repair: bool,
+    pub duration_secs: f64,
+}
+
+/// Package name and version read from a store path, the way
+/// `builtins.parseDrvName` splits them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct StorePathName {
+    pub store_path: String,
+    pub name: String,
+    /// `None` for paths without a version, e.g. `…-source`.
+    pub version: Option<String>,
+}
+
+/// One line of `nix-store --gc --print-roots`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, SimpleObject)]
+pub struct GcRoot {
+    /// The link keeping the path alive, e.g. `/nix/var/nix/profiles/system-42-link`,
+    /// `/proc/1234/maps`, or `{censored}` when hidden from non-root users.
+    pub root_link: String,
+    pub store_path: String,
+}
+
+impl GcRoot {
+    /// Runtime roots held by running processes rather than a symlink on disk.
+    pub fn is_transient(&self) -> bool {
+        self.root_link.starts_with("/proc/") || self.root_link.starts_with('{')
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct CacheInfo {
     pub substituter: String,
@@ -72,6 +156,47 @@ pub struct DaemonHealth {
     pub nix: NixStatus,
 }
 
+/// Exactly which build of kindling is running.
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct BuildInfo {
+    pub version: String,
+    /// `None` when built from a tree without git metadata.
+    pub git_commit: Option<String>,
+    /// RFC 3339; `SOURCE_DATE_EPOCH` when the build set it.
+    pub build_timestamp: Option<String>,
+    pub rustc_version: Option<String>,
+    /// Cargo features compiled in.
+    pub features: Vec<String>,
+    /// Whether the gRPC API is compiled in (the `grpc` feature).
+    pub grpc: bool,
+}
+
+impl BuildInfo {
+    /// Details of this binary, embedded by build.rs.
+    pub fn current() -> Self {
+        let features: Vec<String> = [
+            ("aws", cfg!(feature = "aws")),
+            ("grpc", cfg!(feature = "grpc")),
+            ("pki-only", cfg!(feature = "pki-only")),
+        ]
+        .into_iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(name, _)| name.to_string())
+        .collect();
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: option_env!("KINDLING_GIT_COMMIT").map(str::to_string),
+            build_timestamp: option_env!("KINDLING_BUILD_TIMESTAMP")
+                .and_then(|secs| secs.parse().ok())
+                .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                .map(|t| t.to_rfc3339()),
+            rustc_version: option_env!("KINDLING_RUSTC_VERSION").map(str::to_string),
+            features,
+            grpc: cfg!(feature = "grpc"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelemetryPayload {
     pub timestamp: String,
@@ -95,6 +220,7 @@ mod tests {
             version: Some("2.24.12".to_string()),
             nix_path: Some("/nix/store/bin/nix".to_string()),
             install_method: Some("determinate".to_string()),
+            conflicting_installs: vec![],
         };
         let json = serde_json::to_string(&status).unwrap();
         let deserialized: NixStatus = serde_json::from_str(&json).unwrap();
@@ -191,11 +317,55 @@ mod tests {
             cores: Some("0".to_string()),
             experimental_features: vec!["nix-command".to_string(), "flakes".to_string()],
             sandbox: Some("true".to_string()),
+            missing_features: vec![],
+            overrides: HashMap::from([("max-jobs".to_string(), "auto".to_string())]),
         };
         let json = serde_json::to_string(&config).unwrap();
         let deserialized: NixConfig = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized.substituters.len(), 1);
         assert_eq!(deserialized.experimental_features.len(), 2);
+        assert_eq!(deserialized.overrides["max-jobs"], "auto");
+    }
+
+    fn config_with_features(features: &[&str]) -> NixConfig {
+        NixConfig {
+            substituters: vec![],
+            trusted_public_keys: vec![],
+            max_jobs: None,
+            cores: None,
+            experimental_features: features.iter().map(|f| f.to_string()).collect(),
+            sandbox: None,
+            missing_features: vec![],
+            overrides: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn features_missing_with_flakes() {
+        let required = crate::config::default_required_features();
+        let config = config_with_features(&["nix-command", "flakes", "ca-derivations"]);
+        assert!(config.features_missing(&required).is_empty());
+    }
+
+    #[test]
+    fn features_missing_without_flakes() {
+        let required = crate::config::default_required_features();
+        assert_eq!(
+            config_with_features(&["nix-command"]).features_missing(&required),
+            vec!["flakes".to_string()]
+        );
+        assert_eq!(
+            config_with_features(&[]).features_missing(&required),
+            vec!["nix-command".to_string(), "flakes".to_string()]
+        );
+    }
+
+    #[test]
+    fn nix_config_missing_features_defaults_empty() {
+        let json = r#"{"substituters":[],"trusted_public_keys":[],"max_jobs":null,"cores":null,"experimental_features":[],"sandbox":null}"#;
+        let config: NixConfig = serde_json::from_str(json).unwrap();
+        assert!(config.missing_features.is_empty());
+        assert!(config.overrides.is_empty());
     }
 
     #[test]
@@ -215,6 +385,7 @@ mod tests {
                 version: Some("2.24.0".to_string()),
                 nix_path: None,
                 install_method: None,
+                conflicting_installs: vec![],
             },
         };
         let json = serde_json::to_string(&health).unwrap();
@@ -235,6 +406,7 @@ mod tests {
                 version: None,
                 nix_path: None,
                 install_method: None,
+                conflicting_installs: vec![],
             },
             platform: PlatformInfo {
                 os: "Linux".to_string(),
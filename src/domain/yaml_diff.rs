@@ -0,0 +1,142 @@
+//! Field-level diff of two YAML documents.
+//!
+//! Mappings are walked key by key and every difference is reported at its
+//! dot path, e.g. `network.firewall.allowed_tcp_ports`. Sequences and
+//! scalars are compared whole, so a changed list shows up as one changed
+//! field rather than per-index noise.
+
+use serde::Serialize;
+use serde_yaml::Value;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "lowercase")]
+pub enum FieldChange {
+    /// Present only on the right-hand side.
+    Added { path: String, value: String },
+    /// Present only on the left-hand side.
+    Removed { path: String, value: String },
+    Changed {
+        path: String,
+        from: String,
+        to: String,
+    },
+}
+
+impl FieldChange {
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Added { path, .. } | Self::Removed { path, .. } | Self::Changed { path, .. } => {
+                path
+            }
+        }
+    }
+}
+
+/// Differences going from `left` to `right`, sorted by path.
+pub fn diff(left: &Value, right: &Value) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    walk("", left, right, &mut changes);
+    changes.sort_by(|a, b| a.path().cmp(b.path()));
+    changes
+}
+
+fn walk(path: &str, left: &Value, right: &Value, changes: &mut Vec<FieldChange>) {
+    match (left, right) {
+        (Value::Mapping(l), Value::Mapping(r)) => {
+            for (key, lv) in l {
+                let child = join(path, key);
+                match r.get(key) {
+                    Some(rv) => walk(&child, lv, rv, changes),
+                    None if !lv.is_null() => changes.push(FieldChange::Removed {
+                        path: child,
+                        value: render(lv),
+                    }),
+                    None => {}
+                }
+            }
+            for (key, rv) in r {
+                if !l.contains_key(key) && !rv.is_null() {
+                    changes.push(FieldChange::Added {
+                        path: join(path, key),
+                        value: render(rv),
+                    });
+                }
+            }
+        }
+        _ if left == right => {}
+        _ => changes.push(FieldChange::Changed {
+            path: path.to_string(),
+            from: render(left),
+            to: render(right),
+        }),
+    }
+}
+
+fn join(path: &str, key: &Value) -> String {
+    let key = match key {
+        Value::String(s) => s.clone(),
+        other => render(other),
+    };
+    if path.is_empty() {
+        key
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+/// One-line rendering of a value: scalars as-is, collections as flow YAML.
+fn render(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        other => serde_json::to_string(other).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node_identity::NodeIdentity;
+
+    #[test]
+    fn identities_differing_in_one_nested_field() {
+        let daemon = NodeIdentity::from_bootstrap("k8s-cloud-server", "edge-1", "ops", None);
+        let mut local = daemon.clone();
+        local.network.firewall.allowed_tcp_ports = vec![22, 443];
+
+        let changes = diff(
+            &serde_yaml::to_value(&daemon).unwrap(),
+            &serde_yaml::to_value(&local).unwrap(),
+        );
+        assert_eq!(changes.len(), 1, "{:?}", changes);
+        assert_eq!(changes[0].path(), "network.firewall.allowed_tcp_ports");
+        assert!(matches!(&changes[0], FieldChange::Changed { to, .. } if to == "[22,443]"));
+
+        assert!(diff(
+            &serde_yaml::to_value(&daemon).unwrap(),
+            &serde_yaml::to_value(&daemon).unwrap()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn added_and_removed_keys() {
+        let left: Value = serde_yaml::from_str("a: 1\nb:\n  c: x\n  gone: y\n").unwrap();
+        let right: Value = serde_yaml::from_str("a: 1\nb:\n  c: x\n  new: z\nd: ~\n").unwrap();
+        assert_eq!(
+            diff(&left, &right),
+            vec![
+                FieldChange::Removed {
+                    path: "b.gone".to_string(),
+                    value: "y".to_string()
+                },
+                FieldChange::Added {
+                    path: "b.new".to_string(),
+                    value: "z".to_string()
+                },
+            ]
+        );
+    }
+}
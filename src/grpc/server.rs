@@ -81,6 +81,8 @@ impl KindlingService for KindlingGrpc {
             cores: c.cores.unwrap_or_default(),
             experimental_features: c.experimental_features,
             sandbox: c.sandbox.unwrap_or_default(),
+            missing_features: c.missing_features,
+            overrides: c.overrides,
         }))
     }
 
@@ -9,18 +9,33 @@ mod harden;
 mod grpc;
 mod nix;
 mod node_identity;
+mod output;
+mod paths;
 mod platform;
+mod secret;
 mod server;
 mod telemetry;
 mod tend_setup;
 mod tools;
 mod vpn;
 
+use std::io::IsTerminal;
+use std::process::ExitCode;
+
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
 #[command(name = "kindling", version, about = "Cross-platform unattended Nix installer and daemon")]
 struct Cli {
+    /// Disable colored output (also honors NO_COLOR; off when stdout is not a TTY)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Hide progress output from bootstrap, install and apply; warnings
+    /// and errors still go to stderr
+    #[arg(long, short = 'q', global = true)]
+    quiet: bool,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -39,7 +54,19 @@ enum Commands {
     },
 
     /// Uninstall Nix using the install receipt
-    Uninstall,
+    Uninstall {
+        /// Show what would be removed without running the uninstaller
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Skip backing up /nix/receipt.json before uninstalling
+        #[arg(long)]
+        no_backup: bool,
+
+        /// Uninstall without asking for confirmation
+        #[arg(long)]
+        no_confirm: bool,
+    },
 
     /// Check Nix installation status
     Check,
@@ -65,6 +92,10 @@ enum Commands {
         #[arg(long)]
         org: Option<String>,
 
+        /// Upper bound in seconds on the whole tend sync, retry included
+        #[arg(long, default_value_t = 600)]
+        tend_timeout: u64,
+
         /// Skip confirmation prompts
         #[arg(long)]
         no_confirm: bool,
@@ -86,8 +117,16 @@ enum Commands {
         age_key_file: Option<String>,
 
         /// Path to existing node.yaml (skip interactive setup)
-        #[arg(long)]
+        #[arg(long, conflicts_with = "from_url")]
         node_config: Option<String>,
+
+        /// Download node.yaml from this URL (skip interactive setup)
+        #[arg(long)]
+        from_url: Option<String>,
+
+        /// File holding a bearer token sent with --from-url
+        #[arg(long, requires = "from_url")]
+        token_file: Option<String>,
     },
 
     /// Run the kindling daemon (REST + GraphQL + telemetry)
@@ -96,6 +135,18 @@ enum Commands {
         #[arg(long)]
         http_addr: Option<String>,
 
+        /// Also serve the API on this Unix socket (overrides config)
+        #[arg(long)]
+        unix_socket: Option<String>,
+
+        /// Serve on a non-loopback --http-addr even though the API is unauthenticated
+        #[arg(long)]
+        allow_insecure: bool,
+
+        /// Write the daemon PID to this file (overrides config); removed on shutdown
+        #[arg(long)]
+        pid_file: Option<String>,
+
         /// gRPC listen address (overrides config, requires grpc feature)
         #[arg(long)]
         grpc_addr: Option<String>,
@@ -107,6 +158,10 @@ enum Commands {
         /// Path to config file (default: ~/.config/kindling/config.toml)
         #[arg(long)]
         config: Option<String>,
+
+        /// Validate the daemon config and exit without starting any server
+        #[arg(long)]
+        config_check: bool,
     },
 
     /// Manage machine profiles
@@ -115,11 +170,78 @@ enum Commands {
         command: ProfileCommands,
     },
 
+    /// Manage the node identity (node.yaml)
+    Identity {
+        #[command(subcommand)]
+        command: IdentityCommands,
+    },
+
     /// Read node.yaml, regenerate Nix config, and rebuild the system
     Apply {
         /// Show what would change without applying
         #[arg(long)]
         diff: bool,
+
+        /// Build the system configuration without switching to it
+        #[arg(long, conflicts_with = "diff")]
+        no_activate: bool,
+
+        /// Build first, and switch to the result only if the build succeeds
+        #[arg(long, conflicts_with_all = ["diff", "no_activate"])]
+        switch_on_success_only: bool,
+
+        /// Where to link the built system (with --no-activate; default
+        /// `result` in the generated flake directory)
+        #[arg(long, value_name = "PATH", requires = "no_activate")]
+        out_link: Option<std::path::PathBuf>,
+
+        /// Profile to apply (overrides `profile` in node.yaml)
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Rebuild command to run (default derived from the profile platform:
+        /// darwin-rebuild or nixos-rebuild)
+        #[arg(long)]
+        rebuild_cmd: Option<String>,
+
+        /// Offload builds to this SSH host (default: network.ssh.builder in
+        /// node.yaml; `local` builds here)
+        #[arg(long, value_name = "HOST")]
+        build_host: Option<String>,
+    },
+
+    /// List past `kindling apply` runs (newest first)
+    History {
+        /// Output format (table or json)
+        #[arg(long, default_value = "table")]
+        format: String,
+
+        /// Number of entries to show
+        #[arg(long, short = 'n', default_value_t = 20)]
+        limit: usize,
+    },
+
+    /// Nix store analysis
+    Store {
+        #[command(subcommand)]
+        command: StoreCommands,
+    },
+
+    /// Collect Nix store garbage on this machine (no daemon needed)
+    Gc {
+        /// List what would be deleted without deleting anything. With
+        /// --older-than, lists the generations to remove and the paths dead
+        /// now, but not paths that only those generations keep alive
+        #[arg(long)]
+        dry_run: bool,
+
+        /// First remove profile generations older than this (e.g. 30d)
+        #[arg(long, value_name = "AGE")]
+        older_than: Option<String>,
+
+        /// Output format (table or json)
+        #[arg(long, default_value = "table")]
+        format: String,
     },
 
     /// Fleet management — deploy to remote nodes
@@ -130,10 +252,15 @@ enum Commands {
 
     /// Generate a runtime report for this node
     Report {
-        /// Output format (table or json)
+        /// Output format (table, json, oneline for status bars, prometheus, or
+        /// html for a self-contained page)
         #[arg(long, default_value = "table")]
         format: String,
 
+        /// Write the html report to this file instead of stdout
+        #[arg(long, value_name = "FILE")]
+        output: Option<std::path::PathBuf>,
+
         /// Push report to fleet controller
         #[arg(long)]
         push: bool,
@@ -142,13 +269,40 @@ enum Commands {
         #[arg(long)]
         controller_url: Option<String>,
 
-        /// Force live collection (bypass cache), write result to disk store
+        /// Force live collection (bypass cache), write result to disk store.
+        /// Exits 0 if every section was collected, 1 if any fell back to
+        /// defaults, 2 if collection failed outright
         #[arg(long)]
         fresh: bool,
 
         /// Read from persisted file on disk (no daemon needed, no collection)
         #[arg(long)]
         cached: bool,
+
+        /// Collect fresh and assert against a baseline YAML; exits 1 on any failure
+        #[arg(long, value_name = "FILE")]
+        compare_baseline: Option<std::path::PathBuf>,
+
+        /// Strip SSH keys, process arguments and public IPs from the output
+        /// (a --push still sends the full report)
+        #[arg(long)]
+        redact: bool,
+
+        /// Collect from a remote host over SSH (user@host or a fleet peer name)
+        #[arg(long, value_name = "TARGET", conflicts_with_all = ["fresh", "cached", "compare_baseline"])]
+        remote: Option<String>,
+
+        /// List sections that fell back to zeroed defaults, and why
+        #[arg(long, conflicts_with = "compare_baseline")]
+        explain_fallbacks: bool,
+
+        /// Send reports queued by failed pushes, oldest first, then exit
+        #[arg(long, conflicts_with_all = ["push", "compare_baseline", "remote", "explain_fallbacks"])]
+        flush_outbox: bool,
+
+        /// Give up on live collection after this many seconds (exit 2 with --fresh)
+        #[arg(long, value_name = "SECS", conflicts_with_all = ["cached", "remote", "flush_outbox"])]
+        collect_timeout: Option<u64>,
     },
 
     /// Server mode — K3s cluster bootstrap and monitoring
@@ -197,12 +351,60 @@ enum Commands {
         #[arg(long, global = true, default_value = "table")]
         format: String,
 
+        /// Re-run a read-only query every SECS seconds until Ctrl+C
+        #[arg(long, global = true, value_name = "SECS")]
+        watch: Option<u64>,
+
+        /// Request timeout in seconds (default depends on the subcommand:
+        /// 3 for health, 30 min for gc-run/optimise, 2 min for refresh-report)
+        #[arg(long, global = true, value_name = "SECS")]
+        timeout: Option<u64>,
+
+        /// PEM CA certificate to trust for an https:// daemon (default: tls.ca_cert)
+        #[arg(long, global = true, value_name = "FILE")]
+        ca_cert: Option<std::path::PathBuf>,
+
+        /// Accept any certificate from an https:// daemon (self-signed, wrong host)
+        #[arg(long, global = true)]
+        insecure: bool,
+
         #[command(subcommand)]
         command: commands::query::QueryCommands,
     },
 
     /// Show the materialized config at a tier (bare/default/discovered/custom/env).
     ConfigShow(shikumi::cli::ConfigShowCommand),
+
+    /// Find kindling daemons on the LAN over mDNS
+    Discover {
+        /// Seconds to wait for answers
+        #[arg(long, default_value_t = 3)]
+        timeout: u64,
+
+        /// Add the daemons found to `nodes` in the user config
+        #[arg(long)]
+        save: bool,
+
+        /// Output format (table or json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Inspect the merged kindling config
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the effective config with the layer that set each value
+    Show {
+        /// Output format (table or json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -216,6 +418,57 @@ enum ProfileCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum IdentityCommands {
+    /// Interactively create node.yaml (all values required as flags without a TTY)
+    Init {
+        /// Machine profile from kindling-profiles
+        #[arg(long)]
+        profile: Option<String>,
+
+        /// Hostname for this machine (default: detected)
+        #[arg(long)]
+        hostname: Option<String>,
+
+        /// Username
+        #[arg(long)]
+        user: Option<String>,
+
+        /// Email for the user and git config
+        #[arg(long)]
+        email: Option<String>,
+
+        /// Path to age key file for SOPS secrets
+        #[arg(long)]
+        age_key_file: Option<String>,
+
+        /// Overwrite an existing node.yaml
+        #[arg(long)]
+        force: bool,
+    },
+    /// Validate node.yaml, optionally against the live node (exit 2 on drift)
+    Validate {
+        /// Check declared hardware, firewall, trusted users, and k8s role
+        /// against the node's current report
+        #[arg(long)]
+        against_report: bool,
+
+        /// Collect a fresh report instead of asking the daemon
+        #[arg(long, requires = "against_report")]
+        fresh: bool,
+
+        /// Output format (table or json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+    /// Print node.yaml merged over its `extends` bases and overlays
+    Show {
+        /// Output format (yaml or json)
+        #[arg(long, default_value = "yaml")]
+        format: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum ServerCommands {
     /// Run the server bootstrap sequence (config → identity → rebuild → K3s → FluxCD)
@@ -385,21 +638,131 @@ enum PkiCommands {
     },
 }
 
+#[derive(Subcommand)]
+enum StoreCommands {
+    /// Store growth per day and when the store disk will fill up
+    Trend {
+        /// Output format (table or json)
+        #[arg(long, default_value = "table")]
+        format: String,
+
+        /// Disk usage percentage to project against
+        #[arg(long, default_value_t = 90.0)]
+        threshold: f64,
+    },
+    /// Hard-link identical files in the Nix store
+    Optimise {
+        /// Only estimate the space deduplication would free
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output format (table or json)
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum FleetCommands {
     /// Check connectivity to all fleet peers
-    Status,
+    Status {
+        /// Fleet controller URL, for the configs last applied to each peer
+        #[arg(long, default_value = "http://localhost:9100")]
+        controller_url: String,
+    },
     /// Deploy configuration to a remote node
     Apply {
         /// Node name (must be in fleet.peers)
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        node: Option<String>,
+
+        /// Deploy to every fleet peer; a failure on one doesn't stop the rest
+        #[arg(long)]
+        all: bool,
+
+        /// With --all, skip peers whose resolved identity and flake lock match
+        /// the ones last applied, as recorded on the fleet controller
+        #[arg(long, requires = "all")]
+        only_changed: bool,
+
+        /// Build the closure here, `nix copy` it to the node, and activate remotely
+        #[arg(long)]
+        build_locally: bool,
+
+        /// With --all, continue the last run: skip nodes it already deployed
+        /// and retry the ones that failed or were interrupted
+        #[arg(long, requires = "all")]
+        resume: bool,
+
+        /// Fleet controller URL, where each successful deploy is recorded
+        #[arg(long, default_value = "http://localhost:9100")]
+        controller_url: String,
+    },
+    /// List nodes stored by this fleet controller, filtered by tags/labels
+    List {
+        /// Output format (table or json)
+        #[arg(long, default_value = "table")]
+        format: String,
+
+        /// Only nodes declaring this tag (repeatable; all must match)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+
+        /// Only nodes with this key=value node label (repeatable; all must match)
+        #[arg(long = "label", value_name = "KEY=VALUE")]
+        labels: Vec<String>,
+    },
+    /// Annotate a node on the fleet controller (`key=` removes a key)
+    Annotate {
+        /// Hostname the node reports under
         node: String,
+
+        /// Annotations to set, e.g. status="scheduled for decommission"
+        #[arg(required = true, value_name = "KEY=VALUE")]
+        annotations: Vec<String>,
+
+        /// Fleet controller URL
+        #[arg(long, default_value = "http://localhost:9100")]
+        controller_url: String,
+    },
+    /// Summarize drift across the nodes stored by this fleet controller
+    Drift {
+        /// Output format (table or json)
+        #[arg(long, default_value = "table")]
+        format: String,
+
+        /// Only show drift at or above this severity (warning or critical)
+        #[arg(long)]
+        severity: Option<String>,
+    },
+    /// Export every stored node's identity and latest report for backup
+    Export {
+        /// File to write
+        #[arg(long, short = 'o')]
+        output: String,
+
+        /// Archive format: json (one array) or tar (`<hostname>/identity.yaml`
+        /// and `<hostname>/report.json`)
+        #[arg(long, default_value = "json")]
+        format: String,
     },
 }
 
-fn main() -> anyhow::Result<()> {
+/// Whether ANSI color should be emitted.
+fn color_enabled(no_color_flag: bool, no_color_env: bool, stdout_is_tty: bool) -> bool {
+    !no_color_flag && !no_color_env && stdout_is_tty
+}
+
+fn main() -> anyhow::Result<ExitCode> {
     let cli = Cli::parse();
 
-    match cli.command {
+    let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    if !color_enabled(cli.no_color, no_color_env, std::io::stdout().is_terminal()) {
+        colored::control::set_override(false);
+    }
+    output::set_quiet(cli.quiet);
+
+    let result = match cli.command {
         Commands::Install {
             backend,
             no_confirm,
@@ -407,7 +770,11 @@ fn main() -> anyhow::Result<()> {
             let backend = backend.parse()?;
             commands::install::run(backend, no_confirm)
         }
-        Commands::Uninstall => commands::uninstall::run(),
+        Commands::Uninstall {
+            dry_run,
+            no_backup,
+            no_confirm,
+        } => commands::uninstall::run(dry_run, no_backup, no_confirm),
         Commands::Check => commands::check::run(),
         Commands::Ensure { version } => {
             let version_req = version
@@ -419,37 +786,137 @@ fn main() -> anyhow::Result<()> {
             skip_direnv,
             skip_tend,
             org,
+            tend_timeout,
             no_confirm,
             profile,
             hostname,
             user,
             age_key_file,
             node_config,
+            from_url,
+            token_file,
         } => commands::bootstrap::run(
             skip_direnv,
             skip_tend,
             org,
+            tend_timeout,
             no_confirm,
             profile,
             hostname,
             user,
             age_key_file,
             node_config,
+            from_url,
+            token_file,
         ),
         Commands::Daemon {
             http_addr,
+            unix_socket,
+            allow_insecure,
+            pid_file,
+            grpc_addr,
+            log_level,
+            config,
+            config_check,
+        } => commands::daemon::run(
+            http_addr,
+            unix_socket,
+            allow_insecure,
+            pid_file,
             grpc_addr,
             log_level,
             config,
-        } => commands::daemon::run(http_addr, grpc_addr, log_level, config),
+            config_check,
+        ),
         Commands::Profile { command } => match command {
             ProfileCommands::List => commands::profile::list(),
             ProfileCommands::Show { name } => commands::profile::show(&name),
         },
-        Commands::Apply { diff } => commands::apply::run(diff),
+        Commands::Identity { command } => match command {
+            IdentityCommands::Init {
+                profile,
+                hostname,
+                user,
+                email,
+                age_key_file,
+                force,
+            } => commands::identity::init(commands::identity::InitArgs {
+                profile,
+                hostname,
+                user,
+                email,
+                age_key_file,
+                force,
+            }),
+            IdentityCommands::Validate {
+                against_report,
+                fresh,
+                format,
+            } => return commands::identity::validate(against_report, fresh, &format),
+            IdentityCommands::Show { format } => commands::identity::show(&format),
+        },
+        Commands::Apply {
+            diff,
+            no_activate,
+            switch_on_success_only,
+            out_link,
+            profile,
+            rebuild_cmd,
+            build_host,
+        } => commands::apply::run(
+            diff,
+            no_activate,
+            switch_on_success_only,
+            out_link.as_deref(),
+            profile.as_deref(),
+            rebuild_cmd.as_deref(),
+            build_host.as_deref(),
+        ),
+        Commands::History { format, limit } => commands::history::run(&format, limit),
+        Commands::Store { command } => match command {
+            StoreCommands::Trend { format, threshold } => {
+                commands::store::trend(&format, threshold)
+            }
+            StoreCommands::Optimise { dry_run, format } => {
+                commands::store::optimise(dry_run, &format)
+            }
+        },
+        Commands::Gc {
+            dry_run,
+            older_than,
+            format,
+        } => commands::gc::run(dry_run, older_than.as_deref(), &format),
         Commands::Fleet { command } => match command {
-            FleetCommands::Status => commands::fleet::status(),
-            FleetCommands::Apply { node } => commands::fleet::apply(&node),
+            FleetCommands::Status { controller_url } => commands::fleet::status(&controller_url),
+            FleetCommands::Apply {
+                node,
+                all,
+                only_changed,
+                build_locally,
+                resume,
+                controller_url,
+            } => commands::fleet::apply(
+                node.as_deref(),
+                all,
+                only_changed,
+                build_locally,
+                resume,
+                &controller_url,
+            ),
+            FleetCommands::List {
+                format,
+                tags,
+                labels,
+            } => commands::fleet::list(&format, &tags, &labels),
+            FleetCommands::Annotate {
+                node,
+                annotations,
+                controller_url,
+            } => commands::fleet::annotate(&node, &annotations, &controller_url),
+            FleetCommands::Drift { format, severity } => {
+                commands::fleet::drift(&format, severity.as_deref())
+            }
+            FleetCommands::Export { output, format } => commands::fleet::export(&output, &format),
         },
         Commands::Vpn { command } => match command {
             VpnCommands::Profiles => commands::vpn::run_profiles(),
@@ -523,14 +990,65 @@ fn main() -> anyhow::Result<()> {
             controller_url,
             fresh,
             cached,
-        } => commands::report::run(&format, push, controller_url.as_deref(), fresh, cached),
+            compare_baseline,
+            redact,
+            remote,
+            explain_fallbacks,
+            flush_outbox,
+            collect_timeout,
+            output,
+        } => {
+            return commands::report::run(
+                &format,
+                push,
+                controller_url.as_deref(),
+                fresh,
+                cached,
+                compare_baseline.as_deref(),
+                redact,
+                remote.as_deref(),
+                explain_fallbacks,
+                flush_outbox,
+                collect_timeout,
+                output.as_deref(),
+            );
+        }
         Commands::Query {
             node,
             format,
+            watch,
+            timeout,
+            ca_cert,
+            insecure,
             command,
-        } => commands::query::run(node.as_deref(), &format, &command),
+        } => {
+            let tls = client::ClientTls { ca_cert, insecure };
+            commands::query::run(node.as_deref(), &format, watch, timeout, &tls, &command)
+        }
         Commands::ConfigShow(cmd) => cmd
             .run::<crate::config::Config>("KINDLING_TIER")
             .map_err(|e| anyhow::anyhow!(e)),
+        Commands::Discover {
+            timeout,
+            save,
+            format,
+        } => commands::discover::run(timeout, save, &format),
+        Commands::Config { command } => match command {
+            ConfigCommands::Show { format } => commands::config::show(&format),
+        },
+    };
+    result.map(|()| ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_enabled_only_on_tty_without_opt_out() {
+        assert!(color_enabled(false, false, true));
+        assert!(!color_enabled(true, false, true));
+        assert!(!color_enabled(false, true, true));
+        assert!(!color_enabled(false, false, false));
     }
 }
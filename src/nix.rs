@@ -7,48 +7,126 @@ pub struct NixStatus {
     pub installed: bool,
     pub version: Option<semver::Version>,
     pub nix_path: Option<PathBuf>,
+    /// Every distinct nix install found ("<path> (<version>)"), populated
+    /// only when there is more than one.
+    pub conflicting_installs: Vec<String>,
 }
 
+/// Detect the nix in use (PATH first, then well-known locations, then the
+/// home profile), and flag any other distinct installs alongside it.
 pub fn detect() -> NixStatus {
-    // Check PATH first
-    if let Some(path) = find_in_path("nix") {
-        return status_from_path(&path);
+    let installs = distinct_installs(
+        candidate_paths()
+            .into_iter()
+            .map(|p| {
+                let resolved = std::fs::canonicalize(&p).unwrap_or_else(|_| p.clone());
+                (p, InstallKey::of(resolved))
+            })
+            .collect(),
+    );
+
+    let Some(primary) = installs.first() else {
+        return NixStatus {
+            installed: false,
+            version: None,
+            nix_path: None,
+            conflicting_installs: Vec::new(),
+        };
+    };
+
+    let mut status = status_from_path(primary);
+    if installs.len() > 1 {
+        status.conflicting_installs = installs
+            .iter()
+            .map(|path| describe_install(path, parse_version(path).as_ref()))
+            .collect();
     }
+    status
+}
+
+/// All nix binaries that exist, in detection priority order (may repeat).
+fn candidate_paths() -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = std::env::var_os("PATH")
+        .map(|paths| {
+            std::env::split_paths(&paths)
+                .map(|dir| dir.join("nix"))
+                .filter(|path| path.is_file())
+                .collect()
+        })
+        .unwrap_or_default();
 
-    // Check well-known locations
+    // Well-known locations
     let well_known = [
         "/nix/var/nix/profiles/default/bin/nix",
         "/run/current-system/sw/bin/nix",
     ];
+    candidates.extend(
+        well_known
+            .iter()
+            .map(PathBuf::from)
+            .filter(|path| path.exists()),
+    );
 
-    for location in &well_known {
-        let path = PathBuf::from(location);
+    // Home profile
+    if let Some(home) = dirs::home_dir() {
+        let path = home.join(".nix-profile/bin/nix");
         if path.exists() {
-            return status_from_path(&path);
+            candidates.push(path);
         }
     }
 
-    // Check home profile
-    if let Some(home) = dirs::home_dir() {
-        let path = home.join(".nix-profile/bin/nix");
-        if path.exists() {
-            return status_from_path(&path);
+    candidates
+}
+
+/// What makes two nix binaries the same install. The system profile, the
+/// default profile and `~/.nix-profile` often point at different store
+/// paths of one Nix version (e.g. mid-upgrade), so the version from the
+/// store path name decides; a binary outside the store is only itself.
+#[derive(Debug, PartialEq)]
+enum InstallKey {
+    Version(String),
+    Binary(PathBuf),
+}
+
+impl InstallKey {
+    fn of(resolved: PathBuf) -> Self {
+        match store_path_version(&resolved) {
+            Some(version) => Self::Version(version),
+            None => Self::Binary(resolved),
         }
     }
+}
 
-    NixStatus {
-        installed: false,
-        version: None,
-        nix_path: None,
+/// Nix version named by the store path a binary lives in, e.g. `2.24.12`
+/// for `/nix/store/<hash>-nix-2.24.12/bin/nix`.
+fn store_path_version(resolved: &Path) -> Option<String> {
+    let dir = resolved.strip_prefix("/nix/store").ok()?.iter().next()?;
+    let (_hash, name) = dir.to_str()?.split_once('-')?;
+    let version = name.strip_prefix("nix-")?;
+    version
+        .starts_with(|c: char| c.is_ascii_digit())
+        .then(|| version.to_string())
+}
+
+/// Collapse `(path, key)` candidates to one path per install, keeping the
+/// first-seen path (priority order) for each.
+fn distinct_installs(candidates: Vec<(PathBuf, InstallKey)>) -> Vec<PathBuf> {
+    let mut seen: Vec<InstallKey> = Vec::new();
+    let mut installs = Vec::new();
+    for (path, key) in candidates {
+        if !seen.contains(&key) {
+            seen.push(key);
+            installs.push(path);
+        }
     }
+    installs
 }
 
-fn find_in_path(name: &str) -> Option<PathBuf> {
-    std::env::var_os("PATH").and_then(|paths| {
-        std::env::split_paths(&paths)
-            .map(|dir| dir.join(name))
-            .find(|path| path.is_file())
-    })
+fn describe_install(path: &Path, version: Option<&semver::Version>) -> String {
+    match version {
+        Some(v) => format!("{} ({})", path.display(), v),
+        None => format!("{} (unknown version)", path.display()),
+    }
 }
 
 fn status_from_path(path: &Path) -> NixStatus {
@@ -57,6 +135,7 @@ fn status_from_path(path: &Path) -> NixStatus {
         installed: true,
         version,
         nix_path: Some(path.to_path_buf()),
+        conflicting_installs: Vec::new(),
     }
 }
 
@@ -119,15 +198,112 @@ mod tests {
             assert!(status.nix_path.is_some());
         } else {
             assert!(status.version.is_none());
+            assert!(status.conflicting_installs.is_empty());
         }
     }
 
+    #[test]
+    fn distinct_installs_collapses_symlinks_to_same_binary() {
+        let determinate = PathBuf::from("/nix/store/aaa-nix-2.24.12/bin/nix");
+        let candidates = vec![
+            (
+                PathBuf::from("/nix/var/nix/profiles/default/bin/nix"),
+                InstallKey::of(determinate.clone()),
+            ),
+            (
+                PathBuf::from("/run/current-system/sw/bin/nix"),
+                InstallKey::of(determinate),
+            ),
+        ];
+        assert_eq!(
+            distinct_installs(candidates),
+            vec![PathBuf::from("/nix/var/nix/profiles/default/bin/nix")]
+        );
+    }
+
+    #[test]
+    fn distinct_installs_collapses_store_paths_of_one_version() {
+        let candidates = vec![
+            (
+                PathBuf::from("/run/current-system/sw/bin/nix"),
+                InstallKey::of(PathBuf::from("/nix/store/aaa-nix-2.24.12/bin/nix")),
+            ),
+            (
+                PathBuf::from("/root/.nix-profile/bin/nix"),
+                InstallKey::of(PathBuf::from("/nix/store/ccc-nix-2.24.12/bin/nix")),
+            ),
+        ];
+        assert_eq!(
+            distinct_installs(candidates),
+            vec![PathBuf::from("/run/current-system/sw/bin/nix")]
+        );
+    }
+
+    #[test]
+    fn install_key_falls_back_to_the_binary() {
+        assert_eq!(
+            InstallKey::of(PathBuf::from("/nix/store/aaa-nix-2.24.12/bin/nix")),
+            InstallKey::Version("2.24.12".to_string())
+        );
+        for binary in ["/usr/bin/nix", "/nix/store/ddd-nix-wrapped/bin/nix"] {
+            assert_eq!(
+                InstallKey::of(PathBuf::from(binary)),
+                InstallKey::Binary(PathBuf::from(binary))
+            );
+        }
+    }
+
+    #[test]
+    fn distinct_installs_keeps_separate_binaries_in_priority_order() {
+        let key = |resolved: &str| InstallKey::of(PathBuf::from(resolved));
+        let candidates = vec![
+            (
+                PathBuf::from("/usr/local/bin/nix"),
+                key("/nix/store/bbb-nix-2.18.1/bin/nix"),
+            ),
+            (
+                PathBuf::from("/nix/var/nix/profiles/default/bin/nix"),
+                key("/nix/store/aaa-nix-2.24.12/bin/nix"),
+            ),
+            (
+                PathBuf::from("/usr/local/bin/nix"),
+                key("/nix/store/bbb-nix-2.18.1/bin/nix"),
+            ),
+        ];
+        assert_eq!(
+            distinct_installs(candidates),
+            vec![
+                PathBuf::from("/usr/local/bin/nix"),
+                PathBuf::from("/nix/var/nix/profiles/default/bin/nix"),
+            ]
+        );
+    }
+
+    #[test]
+    fn distinct_installs_empty() {
+        assert!(distinct_installs(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn describe_install_formats_version() {
+        let path = Path::new("/nix/var/nix/profiles/default/bin/nix");
+        assert_eq!(
+            describe_install(path, Some(&semver::Version::new(2, 24, 12))),
+            "/nix/var/nix/profiles/default/bin/nix (2.24.12)"
+        );
+        assert_eq!(
+            describe_install(path, None),
+            "/nix/var/nix/profiles/default/bin/nix (unknown version)"
+        );
+    }
+
     #[test]
     fn nix_status_serializes() {
         let status = NixStatus {
             installed: true,
             version: Some(semver::Version::new(2, 24, 0)),
             nix_path: Some(PathBuf::from("/nix/store/bin/nix")),
+            conflicting_installs: Vec::new(),
         };
         let json = serde_json::to_string(&status).unwrap();
         assert!(json.contains("\"installed\":true"));
@@ -140,6 +316,7 @@ mod tests {
             installed: false,
             version: None,
             nix_path: None,
+            conflicting_installs: Vec::new(),
         };
         let json = serde_json::to_string(&status).unwrap();
         assert!(json.contains("\"installed\":false"));
@@ -4,13 +4,23 @@
 //! Profiles in kindling-profiles consume these values via `kindling.nodeIdentity.*`.
 
 pub mod nix_gen;
+pub mod secrets;
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use async_graphql::SimpleObject;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use crate::paths::expand_path;
+
+/// Env var holding YAML applied over node.yaml after every overlay file, so
+/// cloud-init can tweak an image's baked-in identity without writing files.
+pub const IDENTITY_OVERLAY_ENV: &str = "KINDLING_IDENTITY_OVERLAY";
+
+/// Directory beside node.yaml where `extends: <name>` finds `<name>.yaml`.
+pub const BASE_PROFILES_DIR: &str = "profiles";
+
 /// Top-level node identity configuration.
 #[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
 pub struct NodeIdentity {
@@ -18,6 +28,12 @@ pub struct NodeIdentity {
     pub profile: String,
     pub hostname: String,
 
+    /// Base identity merged under this one (this file's values win): a
+    /// YAML path relative to this file, or the name of a base in
+    /// `profiles/` beside node.yaml.
+    #[serde(default)]
+    pub extends: Option<String>,
+
     #[serde(default)]
     pub user: UserConfig,
 
@@ -74,14 +90,23 @@ fn default_shell() -> String {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, SimpleObject)]
 pub struct SecretsConfig {
+    /// Where named secrets are resolved from: `sops`, `env` or `file`.
     #[serde(default = "default_secrets_provider")]
     pub provider: String,
+    /// sops-encrypted YAML the `sops` provider extracts secrets from.
+    #[serde(default)]
+    pub sops_file: Option<String>,
+    /// Directory the `file` provider reads (default `/run/secrets`).
+    #[serde(default)]
+    pub secrets_dir: Option<String>,
     #[serde(default)]
     pub age_key_file: Option<String>,
     #[serde(default)]
     pub ssh_authorized_keys: Vec<String>,
     #[serde(default)]
     pub tls_certificates: Vec<TlsCertificate>,
+    /// Age keys for this node's secrets. `age1…` recipients among them are
+    /// checked against the identity in `age_key_file` after rotations.
     #[serde(default)]
     pub age_keys: Vec<String>,
 }
@@ -505,10 +530,66 @@ pub fn deep_merge(base: &mut serde_yaml::Value, overlay: serde_yaml::Value) {
     }
 }
 
+/// Merge the `extends` chain of the identity document `doc`, read from
+/// `path`, beneath it: each base goes under the file that names it, so the
+/// node's own values win. A base that extends one already in the chain is
+/// an error.
+pub fn resolve_extends(doc: serde_yaml::Value, path: &Path) -> Result<serde_yaml::Value> {
+    let root = path.parent().unwrap_or(Path::new("."));
+    let mut chain = vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())];
+    let mut next = extends_of(&doc);
+    let mut from = root.to_path_buf();
+    let mut merged = doc;
+    while let Some(reference) = next {
+        let base_path = extends_path(&reference, root, &from);
+        let canonical = base_path
+            .canonicalize()
+            .unwrap_or_else(|_| base_path.clone());
+        if chain.contains(&canonical) {
+            chain.push(canonical);
+            let names: Vec<String> = chain.iter().map(|p| p.display().to_string()).collect();
+            bail!("circular extends: {}", names.join(" -> "));
+        }
+        let content = std::fs::read_to_string(&base_path)
+            .with_context(|| format!("failed to read base identity {}", base_path.display()))?;
+        let mut base: serde_yaml::Value = serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse base identity {}", base_path.display()))?;
+        next = extends_of(&base);
+        from = base_path.parent().unwrap_or(root).to_path_buf();
+        chain.push(canonical);
+        deep_merge(&mut base, merged);
+        merged = base;
+    }
+    Ok(merged)
+}
+
+fn extends_of(doc: &serde_yaml::Value) -> Option<String> {
+    doc.get("extends")?.as_str().map(str::to_string)
+}
+
+/// A bare name is `profiles/<name>.yaml` beside node.yaml (`root`); a path
+/// is relative to the file that names it (`from`).
+fn extends_path(reference: &str, root: &Path, from: &Path) -> PathBuf {
+    let is_path =
+        reference.contains('/') || reference.ends_with(".yaml") || reference.ends_with(".yml");
+    if !is_path {
+        return root
+            .join(BASE_PROFILES_DIR)
+            .join(format!("{}.yaml", reference));
+    }
+    let path = expand_path(reference);
+    if path.is_relative() {
+        from.join(path)
+    } else {
+        path
+    }
+}
+
 /// Remove a dot-separated field path from a serde_yaml::Value tree.
 ///
 /// e.g. `remove_field_path(&mut val, "secrets.age_keys")` removes the `age_keys`
-/// key from the `secrets` mapping.
+/// key from the `secrets` mapping. A `*` segment applies the rest of the path
+/// to every element of a sequence.
 pub fn remove_field_path(val: &mut serde_yaml::Value, path: &str) {
     let parts: Vec<&str> = path.split('.').collect();
     if parts.is_empty() {
@@ -521,6 +602,14 @@ fn remove_field_recursive(val: &mut serde_yaml::Value, parts: &[&str]) {
     if parts.is_empty() {
         return;
     }
+    if parts[0] == "*" {
+        if let serde_yaml::Value::Sequence(items) = val {
+            for item in items {
+                remove_field_recursive(item, &parts[1..]);
+            }
+        }
+        return;
+    }
     if let serde_yaml::Value::Mapping(map) = val {
         let key = serde_yaml::Value::String(parts[0].to_string());
         if parts.len() == 1 {
@@ -531,7 +620,76 @@ fn remove_field_recursive(val: &mut serde_yaml::Value, parts: &[&str]) {
     }
 }
 
+/// Set a dot-separated field path in a serde_yaml::Value tree, creating
+/// missing mappings along the way.
+///
+/// e.g. `set_field_path(&mut val, "hardware.cpu.cores", 8.into())`. A numeric
+/// segment indexes into an existing sequence.
+pub fn set_field_path(
+    val: &mut serde_yaml::Value,
+    path: &str,
+    value: serde_yaml::Value,
+) -> Result<()> {
+    let parts: Vec<&str> = path.split('.').collect();
+    if parts.iter().any(|p| p.is_empty()) {
+        anyhow::bail!("invalid field path '{}'", path);
+    }
+    let mut current = val;
+    for (i, part) in parts.iter().enumerate() {
+        if current.is_null() {
+            *current = serde_yaml::Value::Mapping(Default::default());
+        }
+        let last = i == parts.len() - 1;
+        current = match current {
+            serde_yaml::Value::Mapping(map) => {
+                let key = serde_yaml::Value::String(part.to_string());
+                if last {
+                    map.insert(key, value);
+                    return Ok(());
+                }
+                map.entry(key).or_insert(serde_yaml::Value::Null)
+            }
+            serde_yaml::Value::Sequence(items) => {
+                let Some(index) = part.parse::<usize>().ok().filter(|&n| n < items.len()) else {
+                    anyhow::bail!("'{}' is not an index into {}", part, parts[..i].join("."));
+                };
+                if last {
+                    items[index] = value;
+                    return Ok(());
+                }
+                &mut items[index]
+            }
+            _ => anyhow::bail!("{} is not a mapping", parts[..i].join(".")),
+        };
+    }
+    Ok(())
+}
+
+/// What a node is for, as far as runtime probing is concerned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeRole {
+    /// A developer machine: a macOS profile with no cluster role.
+    Workstation,
+    /// A Kubernetes server or agent.
+    Kubernetes,
+    Server,
+}
+
 impl NodeIdentity {
+    /// The node's role, from `kubernetes.role` or else the profile name.
+    pub fn node_role(&self) -> NodeRole {
+        if self.kubernetes.role.is_some()
+            || self.profile.starts_with("k3s")
+            || self.profile.starts_with("k8s")
+        {
+            NodeRole::Kubernetes
+        } else if nix_gen::is_darwin_profile(&self.profile) {
+            NodeRole::Workstation
+        } else {
+            NodeRole::Server
+        }
+    }
+
     /// Default path for node.yaml (workstation mode: `~/.config/kindling/node.yaml`)
     pub fn default_path() -> PathBuf {
         dirs::config_dir()
@@ -553,24 +711,38 @@ impl NodeIdentity {
             .join("identity.d")
     }
 
-    /// Load from a YAML file
+    /// Load from a YAML file, merged over its `extends` bases
     pub fn load(path: &Path) -> Result<Self> {
         let content = std::fs::read_to_string(path)
             .with_context(|| format!("failed to read node identity from {}", path.display()))?;
-        let identity: NodeIdentity = serde_yaml::from_str(&content)
+        let doc: serde_yaml::Value = serde_yaml::from_str(&content)
+            .with_context(|| format!("failed to parse node identity from {}", path.display()))?;
+        let identity: NodeIdentity = serde_yaml::from_value(resolve_extends(doc, path)?)
             .with_context(|| format!("failed to parse node identity from {}", path.display()))?;
         Ok(identity)
     }
 
-    /// Load base identity from a YAML file, then apply overlay files from
-    /// the default overlay dir plus any extra dirs, sorted alphabetically.
+    /// Load base identity from a YAML file and its `extends` chain, then
+    /// apply overlay files from the default overlay dir plus any extra
+    /// dirs, sorted alphabetically.
+    /// YAML in `KINDLING_IDENTITY_OVERLAY` is applied last, over all files.
     ///
     /// Bad overlay files log a warning and are skipped. Bad base file is a hard error.
     pub fn load_with_overlays(base_path: &Path, extra_overlay_dirs: &[String]) -> Result<Self> {
+        let env_overlay = std::env::var(IDENTITY_OVERLAY_ENV).ok();
+        Self::load_with_overlays_and_env(base_path, extra_overlay_dirs, env_overlay.as_deref())
+    }
+
+    fn load_with_overlays_and_env(
+        base_path: &Path,
+        extra_overlay_dirs: &[String],
+        env_overlay: Option<&str>,
+    ) -> Result<Self> {
         let content = std::fs::read_to_string(base_path)
             .with_context(|| format!("failed to read base identity from {}", base_path.display()))?;
-        let mut base: serde_yaml::Value = serde_yaml::from_str(&content)
+        let base: serde_yaml::Value = serde_yaml::from_str(&content)
             .with_context(|| format!("failed to parse base identity from {}", base_path.display()))?;
+        let mut base = resolve_extends(base, base_path)?;
 
         // Collect all overlay dirs: default + extras
         let mut overlay_dirs = vec![Self::default_overlay_dir()];
@@ -624,6 +796,22 @@ impl NodeIdentity {
             }
         }
 
+        if let Some(overlay_content) = env_overlay.filter(|s| !s.trim().is_empty()) {
+            match serde_yaml::from_str::<serde_yaml::Value>(overlay_content) {
+                Ok(overlay_val) => {
+                    tracing::info!(env = IDENTITY_OVERLAY_ENV, "applying identity overlay");
+                    deep_merge(&mut base, overlay_val);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        env = IDENTITY_OVERLAY_ENV,
+                        error = %e,
+                        "skipping invalid overlay from environment"
+                    );
+                }
+            }
+        }
+
         let identity: NodeIdentity = serde_yaml::from_value(base)
             .context("failed to deserialize merged identity")?;
         Ok(identity)
@@ -656,6 +844,23 @@ impl NodeIdentity {
         Ok(())
     }
 
+    /// Copy with `~` and `$VAR` expanded in the fields that hold
+    /// filesystem paths (age key file, org base dirs, token files).
+    pub fn with_expanded_paths(&self) -> Self {
+        let expand = |p: &str| expand_path(p).to_string_lossy().into_owned();
+        let mut identity = self.clone();
+        if let Some(file) = identity.secrets.age_key_file.as_mut() {
+            *file = expand(file);
+        }
+        for org in &mut identity.workspace.orgs {
+            org.base_dir = expand(&org.base_dir);
+            if let Some(file) = org.github_token_file.as_mut() {
+                *file = expand(file);
+            }
+        }
+        identity
+    }
+
     /// Serialize to JSON (for Nix consumption via builtins.fromJSON)
     pub fn to_json(&self) -> Result<String> {
         serde_json::to_string_pretty(self).context("failed to serialize node identity to JSON")
@@ -672,6 +877,7 @@ impl NodeIdentity {
             version: "1".to_string(),
             profile: profile.to_string(),
             hostname: hostname.to_string(),
+            extends: None,
             user: UserConfig {
                 name: user.to_string(),
                 uid: 1000,
@@ -763,6 +969,24 @@ mod tests {
         assert_eq!(tags[0].as_str(), Some("x"));
     }
 
+    // ── set_field_path tests ─────────────────────────────────
+
+    #[test]
+    fn set_field_path_creates_and_replaces() {
+        let mut val =
+            serde_yaml::from_str::<serde_yaml::Value>("hostname: a\npeers:\n  - name: p1").unwrap();
+        set_field_path(&mut val, "hardware.cpu.cores", 8.into()).unwrap();
+        set_field_path(&mut val, "hostname", "b".into()).unwrap();
+        set_field_path(&mut val, "peers.0.name", "p2".into()).unwrap();
+        assert_eq!(val["hardware"]["cpu"]["cores"].as_u64(), Some(8));
+        assert_eq!(val["hostname"].as_str(), Some("b"));
+        assert_eq!(val["peers"][0]["name"].as_str(), Some("p2"));
+
+        assert!(set_field_path(&mut val, "hostname.inner", 1.into()).is_err());
+        assert!(set_field_path(&mut val, "peers.3.name", 1.into()).is_err());
+        assert!(set_field_path(&mut val, "a..b", 1.into()).is_err());
+    }
+
     // ── remove_field_path tests ──────────────────────────────
 
     #[test]
@@ -785,6 +1009,17 @@ mod tests {
         assert_eq!(val["secrets"]["provider"].as_str(), Some("sops"));
     }
 
+    #[test]
+    fn remove_field_path_wildcard_over_list() {
+        let mut val = serde_yaml::from_str::<serde_yaml::Value>(
+            "peers:\n  - name: a\n    key: k1\n  - name: b\n    key: k2"
+        ).unwrap();
+        remove_field_path(&mut val, "peers.*.key");
+        assert!(val["peers"][0]["key"].is_null());
+        assert!(val["peers"][1]["key"].is_null());
+        assert_eq!(val["peers"][1]["name"].as_str(), Some("b"));
+    }
+
     #[test]
     fn remove_field_path_nonexistent_is_noop() {
         let mut val = serde_yaml::from_str::<serde_yaml::Value>("name: alice").unwrap();
@@ -801,6 +1036,33 @@ mod tests {
         assert_eq!(val, original);
     }
 
+    #[test]
+    fn with_expanded_paths_resolves_home() {
+        let home = dirs::home_dir().unwrap();
+        let mut id = NodeIdentity::from_bootstrap("cloud-server", "node1", "deploy", Some("~/age.key"));
+        id.workspace.orgs = vec![OrgConfig {
+            name: "pleme-io".to_string(),
+            base_dir: "$HOME/code/github/pleme-io".to_string(),
+            github_token_file: Some("/run/secrets/gh".to_string()),
+        }];
+
+        let expanded = id.with_expanded_paths();
+        assert_eq!(
+            expanded.secrets.age_key_file.as_deref(),
+            Some(home.join("age.key").to_str().unwrap())
+        );
+        assert_eq!(
+            expanded.workspace.orgs[0].base_dir,
+            home.join("code/github/pleme-io").to_string_lossy()
+        );
+        assert_eq!(
+            expanded.workspace.orgs[0].github_token_file.as_deref(),
+            Some("/run/secrets/gh")
+        );
+        // The original identity (written back to node.yaml) is untouched.
+        assert_eq!(id.secrets.age_key_file.as_deref(), Some("~/age.key"));
+    }
+
     // ── from_bootstrap tests ──────────────────────────────
 
     #[test]
@@ -959,6 +1221,79 @@ mod tests {
         assert_eq!(identity.hostname, "good");
     }
 
+    #[test]
+    fn load_with_overlays_applies_env_overlay_last() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let base_path = dir.path().join("node.yaml");
+        std::fs::write(&base_path, "version: '1'\nprofile: base\nhostname: original\nuser:\n  name: root\n  uid: 0\n  shell: bash\n  email: ''").unwrap();
+
+        let overlay_dir = dir.path().join("overlays");
+        std::fs::create_dir_all(&overlay_dir).unwrap();
+        std::fs::write(overlay_dir.join("99-file.yaml"), "hostname: from-file\nprofile: file").unwrap();
+
+        let identity = NodeIdentity::load_with_overlays_and_env(
+            &base_path,
+            &[overlay_dir.to_string_lossy().to_string()],
+            Some("hostname: i-0abc123\n"),
+        ).unwrap();
+        assert_eq!(identity.hostname, "i-0abc123");
+        assert_eq!(identity.profile, "file");
+
+        let identity = NodeIdentity::load_with_overlays_and_env(
+            &base_path,
+            &[overlay_dir.to_string_lossy().to_string()],
+            Some("{{invalid yaml}}}"),
+        ).unwrap();
+        assert_eq!(identity.hostname, "from-file");
+    }
+
+    #[test]
+    fn extends_merges_base_under_node() {
+        let dir = tempfile::tempdir().unwrap();
+        let profiles = dir.path().join(BASE_PROFILES_DIR);
+        std::fs::create_dir_all(&profiles).unwrap();
+        std::fs::write(
+            profiles.join("k3s-base.yaml"),
+            "version: '1'\nprofile: k3s-server\nhostname: base\nuser:\n  name: ops\n  uid: 1000\n  shell: zsh\n  email: ''\nnix:\n  trusted_users: [root, ops]",
+        )
+        .unwrap();
+        let node_path = dir.path().join("node.yaml");
+        std::fs::write(&node_path, "extends: k3s-base\nhostname: node-7\n").unwrap();
+
+        let identity = NodeIdentity::load(&node_path).unwrap();
+        assert_eq!(identity.hostname, "node-7");
+        assert_eq!(identity.profile, "k3s-server");
+        assert_eq!(identity.user.shell, "zsh");
+        assert_eq!(identity.nix.trusted_users, vec!["root", "ops"]);
+
+        // Overlays still apply on top of the merged chain.
+        let overlays = dir.path().join("identity.d");
+        std::fs::create_dir_all(&overlays).unwrap();
+        std::fs::write(overlays.join("10-user.yaml"), "user:\n  shell: bash").unwrap();
+        let identity = NodeIdentity::load_with_overlays_and_env(
+            &node_path,
+            &[overlays.to_string_lossy().to_string()],
+            None,
+        )
+        .unwrap();
+        assert_eq!(identity.hostname, "node-7");
+        assert_eq!(identity.user.shell, "bash");
+    }
+
+    #[test]
+    fn extends_rejects_cycles() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a.yaml"), "extends: ./b.yaml\nhostname: a").unwrap();
+        std::fs::write(dir.path().join("b.yaml"), "extends: ./a.yaml\nhostname: b").unwrap();
+        let node_path = dir.path().join("node.yaml");
+        std::fs::write(&node_path, "extends: a.yaml\nhostname: n").unwrap();
+
+        let err = NodeIdentity::load(&node_path).unwrap_err();
+        assert!(err.to_string().starts_with("circular extends: "), "{}", err);
+        assert!(err.to_string().ends_with("a.yaml"));
+    }
+
     #[test]
     fn load_with_overlays_ignores_non_yaml_files() {
         let dir = tempfile::tempdir().unwrap();
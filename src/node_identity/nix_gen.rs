@@ -5,6 +5,7 @@
 //! from the serialized node.json.
 
 use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
 use std::path::{Path, PathBuf};
 
 use super::NodeIdentity;
@@ -17,13 +18,33 @@ pub fn generated_dir() -> PathBuf {
         .join("generated")
 }
 
+fn node_json(identity: &NodeIdentity) -> Result<String> {
+    // Nix reads these as literal paths, so `~`/`$HOME` must be resolved here.
+    identity.with_expanded_paths().to_json()
+}
+
+/// "sha256:<hex>" over the node.json that would be generated for `identity`
+/// and the `flake.lock` it is built against, so a changed overlay, `extends`
+/// base or locked input all change the checksum. Paths expand against this
+/// host's environment, so the same identity hashes alike wherever `$HOME`
+/// matches.
+pub fn deploy_checksum(identity: &NodeIdentity, flake_lock: Option<&str>) -> Result<String> {
+    let mut hasher = Sha256::new();
+    hasher.update(node_json(identity)?);
+    if let Some(lock) = flake_lock {
+        hasher.update(b"\0");
+        hasher.update(lock);
+    }
+    Ok(format!("sha256:{:x}", hasher.finalize()))
+}
+
 /// Write node.json (JSON serialization of NodeIdentity for Nix consumption).
 pub fn write_node_json(identity: &NodeIdentity, dir: &Path) -> Result<PathBuf> {
     std::fs::create_dir_all(dir)
         .with_context(|| format!("failed to create generated directory {}", dir.display()))?;
 
     let json_path = dir.join("node.json");
-    let json = identity.to_json()?;
+    let json = node_json(identity)?;
     std::fs::write(&json_path, &json)
         .with_context(|| format!("failed to write {}", json_path.display()))?;
 
@@ -48,13 +69,65 @@ pub fn write_flake_nix(identity: &NodeIdentity, dir: &Path) -> Result<PathBuf> {
 
 /// Generate the full Nix files (node.json + flake.nix).
 pub fn generate(identity: &NodeIdentity) -> Result<PathBuf> {
-    let dir = generated_dir();
-    write_node_json(identity, &dir)?;
-    write_flake_nix(identity, &dir)?;
-    Ok(dir)
+    generate_in(identity, &generated_dir())
+}
+
+/// Generate node.json + flake.nix into an explicit directory.
+pub fn generate_in(identity: &NodeIdentity, dir: &Path) -> Result<PathBuf> {
+    write_node_json(identity, dir)?;
+    write_flake_nix(identity, dir)?;
+    Ok(dir.to_path_buf())
+}
+
+/// Marker `kindling apply` leaves in the generated dir after switching.
+const ACTIVATION_FILE: &str = "activation.json";
+
+/// Files whose contents make up the generated flake.
+const FLAKE_FILES: [&str; 3] = ["flake.nix", "flake.lock", "node.json"];
+
+/// The system the generated flake was last switched to, and a digest of
+/// the flake files it was built from.
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Activation {
+    system_path: PathBuf,
+    flake_digest: String,
+}
+
+/// "sha256:<hex>" over the generated flake's files; a missing file hashes
+/// as empty.
+fn flake_digest(dir: &Path) -> String {
+    let mut hasher = Sha256::new();
+    for name in FLAKE_FILES {
+        hasher.update(name);
+        hasher.update(b"\0");
+        hasher.update(std::fs::read(dir.join(name)).unwrap_or_default());
+        hasher.update(b"\0");
+    }
+    format!("sha256:{:x}", hasher.finalize())
+}
+
+/// Record that the flake in `dir`, as it is now, was switched to as
+/// `system_path`.
+pub fn record_activation(dir: &Path, system_path: &Path) -> Result<()> {
+    let activation = Activation {
+        system_path: system_path.to_path_buf(),
+        flake_digest: flake_digest(dir),
+    };
+    let path = dir.join(ACTIVATION_FILE);
+    std::fs::write(&path, serde_json::to_string_pretty(&activation)?)
+        .with_context(|| format!("failed to write {}", path.display()))
 }
 
-fn is_darwin_profile(profile: &str) -> bool {
+/// Whether `system_path` was built from the flake in `dir` as it is now:
+/// false once it is regenerated, relocked or built without a switch.
+pub fn is_activated_as(dir: &Path, system_path: &Path) -> bool {
+    std::fs::read_to_string(dir.join(ACTIVATION_FILE))
+        .ok()
+        .and_then(|s| serde_json::from_str::<Activation>(&s).ok())
+        .is_some_and(|a| a.system_path == system_path && a.flake_digest == flake_digest(dir))
+}
+
+pub(crate) fn is_darwin_profile(profile: &str) -> bool {
     matches!(profile, "macos-developer")
 }
 
@@ -186,6 +259,23 @@ mod tests {
         NodeIdentity::from_bootstrap(profile, hostname, "root", None)
     }
 
+    #[test]
+    fn activation_matches_only_the_unchanged_flake() {
+        let dir = tempfile::tempdir().unwrap();
+        let system = Path::new("/nix/store/abc-nixos-system-edge-1");
+        generate_in(&test_identity("cloud-server", "edge-1"), dir.path()).unwrap();
+        assert!(!is_activated_as(dir.path(), system));
+
+        record_activation(dir.path(), system).unwrap();
+        assert!(is_activated_as(dir.path(), system));
+        let other = Path::new("/nix/store/def-nixos-system-edge-1");
+        assert!(!is_activated_as(dir.path(), other));
+
+        // Relocking (or regenerating) without a switch breaks the match.
+        std::fs::write(dir.path().join("flake.lock"), "{}").unwrap();
+        assert!(!is_activated_as(dir.path(), system));
+    }
+
     #[test]
     fn is_darwin_profile_matches_macos_developer() {
         assert!(is_darwin_profile("macos-developer"));
@@ -198,6 +288,23 @@ mod tests {
         assert!(!is_darwin_profile(""));
     }
 
+    #[test]
+    fn deploy_checksum_covers_identity_and_lock() {
+        let id = test_identity("cloud-server", "n1");
+        let base = deploy_checksum(&id, None).unwrap();
+        assert!(base.starts_with("sha256:"));
+        assert_eq!(deploy_checksum(&id, None).unwrap(), base);
+
+        let locked = deploy_checksum(&id, Some(r#"{"version":7}"#)).unwrap();
+        assert_ne!(locked, base);
+        assert_ne!(
+            deploy_checksum(&id, Some(r#"{"version":8}"#)).unwrap(),
+            locked
+        );
+        let other = test_identity("cloud-server", "n2");
+        assert_ne!(deploy_checksum(&other, None).unwrap(), base);
+    }
+
     #[test]
     fn nixos_flake_contains_hostname() {
         let id = test_identity("cloud-server", "my-node");
@@ -0,0 +1,267 @@
+//! Secret resolution, dispatched on `secrets.provider` in node.yaml.
+//!
+//! - `sops` — key extracted from the sops-encrypted `secrets.sops_file`
+//! - `env`  — `KINDLING_SECRET_<NAME>` environment variables
+//! - `file` — one file per secret under `secrets.secrets_dir`
+//!   (default `/run/secrets`, where sops-nix decrypts to)
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use super::SecretsConfig;
+use crate::paths::expand_path;
+use crate::secret::Secret;
+
+/// Prefix for secrets read by the `env` provider.
+const ENV_PREFIX: &str = "KINDLING_SECRET_";
+/// Where the `file` provider looks when `secrets_dir` is unset.
+const DEFAULT_SECRETS_DIR: &str = "/run/secrets";
+/// Secret holding the node's age identity when `age_key_file` is unset.
+pub const AGE_KEY_SECRET: &str = "age_key";
+
+/// A source of named secrets (e.g. "github_token").
+pub trait SecretProvider {
+    fn name(&self) -> &'static str;
+    /// The secret's value with surrounding whitespace trimmed.
+    fn get(&self, key: &str) -> Result<Secret>;
+}
+
+/// The provider selected by `secrets.provider`. An unset provider means
+/// sops, matching the node.yaml default.
+pub fn provider_for(config: &SecretsConfig) -> Result<Box<dyn SecretProvider>> {
+    match config.provider.as_str() {
+        "" | "sops" => Ok(Box::new(SopsProvider {
+            file: config.sops_file.as_deref().map(expand_path),
+        })),
+        "env" => Ok(Box::new(EnvProvider::from_process_env())),
+        "file" => Ok(Box::new(FileProvider {
+            dir: expand_path(
+                config
+                    .secrets_dir
+                    .as_deref()
+                    .unwrap_or(DEFAULT_SECRETS_DIR),
+            ),
+        })),
+        other => bail!(
+            "unsupported secrets provider '{}' (expected sops, env or file)",
+            other
+        ),
+    }
+}
+
+/// The node's age identity: the contents of `age_key_file` when set,
+/// otherwise the `age_key` secret. The sops provider can't supply it,
+/// since sops needs the age key to decrypt, so `None` then.
+pub fn age_key(config: &SecretsConfig) -> Option<Result<Secret>> {
+    if let Some(file) = config.age_key_file.as_deref() {
+        let path = expand_path(file);
+        return Some(
+            std::fs::read_to_string(&path)
+                .map(Secret::new)
+                .with_context(|| format!("reading age key {}", path.display())),
+        );
+    }
+    match config.provider.as_str() {
+        "" | "sops" => None,
+        _ => Some(resolve(config, AGE_KEY_SECRET)),
+    }
+}
+
+/// Resolve `key` through the configured provider.
+pub fn resolve(config: &SecretsConfig, key: &str) -> Result<Secret> {
+    let provider = provider_for(config)?;
+    provider
+        .get(key)
+        .with_context(|| format!("resolving secret '{}' via {} provider", key, provider.name()))
+}
+
+/// `sops -d --extract '["<key>"]' <sops_file>`.
+pub struct SopsProvider {
+    file: Option<PathBuf>,
+}
+
+impl SecretProvider for SopsProvider {
+    fn name(&self) -> &'static str {
+        "sops"
+    }
+
+    fn get(&self, key: &str) -> Result<Secret> {
+        let file = self
+            .file
+            .as_ref()
+            .ok_or_else(|| anyhow!("secrets.sops_file is not set"))?;
+        let out = std::process::Command::new("sops")
+            .arg("-d")
+            .arg("--extract")
+            .arg(format!("[\"{}\"]", key))
+            .arg(file)
+            .output()
+            .context("invoke sops -d (sops binary not in PATH?)")?;
+        if !out.status.success() {
+            bail!(
+                "sops could not extract '{}' from {}: {}",
+                key,
+                file.display(),
+                String::from_utf8_lossy(&out.stderr).trim()
+            );
+        }
+        let value = String::from_utf8(out.stdout).context("sops output not UTF-8")?;
+        Ok(Secret::new(value.trim().to_string()))
+    }
+}
+
+/// Reads `KINDLING_SECRET_<KEY>`, with the key upper-cased and `-`/`.`
+/// mapped to `_`.
+pub struct EnvProvider {
+    lookup: EnvLookup,
+}
+
+type EnvLookup = Box<dyn Fn(&str) -> Option<String>>;
+
+impl EnvProvider {
+    pub fn from_process_env() -> Self {
+        Self::with_lookup(|name| std::env::var(name).ok())
+    }
+
+    fn with_lookup(lookup: impl Fn(&str) -> Option<String> + 'static) -> Self {
+        Self {
+            lookup: Box::new(lookup),
+        }
+    }
+
+    fn var_name(key: &str) -> String {
+        let key: String = key
+            .chars()
+            .map(|c| match c {
+                '-' | '.' => '_',
+                c => c.to_ascii_uppercase(),
+            })
+            .collect();
+        format!("{}{}", ENV_PREFIX, key)
+    }
+}
+
+impl SecretProvider for EnvProvider {
+    fn name(&self) -> &'static str {
+        "env"
+    }
+
+    fn get(&self, key: &str) -> Result<Secret> {
+        let var = Self::var_name(key);
+        match (self.lookup)(&var) {
+            Some(value) if !value.trim().is_empty() => Ok(Secret::new(value.trim().to_string())),
+            _ => bail!("secret '{}' not set (expected ${})", key, var),
+        }
+    }
+}
+
+/// Reads `<dir>/<key>`.
+pub struct FileProvider {
+    dir: PathBuf,
+}
+
+impl SecretProvider for FileProvider {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+
+    fn get(&self, key: &str) -> Result<Secret> {
+        if key.is_empty() || key.contains('/') || key.starts_with('.') {
+            bail!("invalid secret name '{}'", key);
+        }
+        let path = self.dir.join(key);
+        let value = std::fs::read_to_string(&path)
+            .with_context(|| format!("reading secret {}", path.display()))?;
+        Ok(Secret::new(value.trim().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env_provider(vars: &'static [(&'static str, &'static str)]) -> EnvProvider {
+        EnvProvider::with_lookup(move |name| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        })
+    }
+
+    #[test]
+    fn env_provider_resolves_named_secret() {
+        let provider = env_provider(&[
+            ("KINDLING_SECRET_GITHUB_TOKEN", "ghp_abc123\n"),
+            ("KINDLING_SECRET_ATTIC_PUSH_TOKEN", "attic-xyz"),
+        ]);
+        assert_eq!(provider.get("github_token").unwrap().expose(), "ghp_abc123");
+        assert_eq!(
+            provider.get("attic-push.token").unwrap().expose(),
+            "attic-xyz"
+        );
+    }
+
+    #[test]
+    fn env_provider_reports_missing_variable() {
+        let provider = env_provider(&[("KINDLING_SECRET_EMPTY", "  ")]);
+        let err = provider.get("github_token").unwrap_err();
+        assert!(err.to_string().contains("$KINDLING_SECRET_GITHUB_TOKEN"));
+        assert!(provider.get("empty").is_err());
+    }
+
+    #[test]
+    fn file_provider_reads_secret_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("github_token"), "ghp_file\n").unwrap();
+        let provider = FileProvider {
+            dir: dir.path().to_path_buf(),
+        };
+        assert_eq!(provider.get("github_token").unwrap().expose(), "ghp_file");
+        assert!(provider.get("missing").is_err());
+        assert!(provider.get("../etc/shadow").is_err());
+    }
+
+    #[test]
+    fn age_key_prefers_the_key_file_then_the_provider() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("age_key"), "AGE-SECRET-KEY-1PROVIDER\n").unwrap();
+        let key_file = dir.path().join("keys.txt");
+        std::fs::write(&key_file, "AGE-SECRET-KEY-1FILE\n").unwrap();
+
+        let mut config = SecretsConfig {
+            provider: "file".to_string(),
+            secrets_dir: Some(dir.path().display().to_string()),
+            ..Default::default()
+        };
+        let key = age_key(&config).unwrap().unwrap();
+        assert_eq!(key.expose(), "AGE-SECRET-KEY-1PROVIDER");
+
+        config.age_key_file = Some(key_file.display().to_string());
+        let key = age_key(&config).unwrap().unwrap();
+        assert_eq!(key.expose().trim(), "AGE-SECRET-KEY-1FILE");
+
+        let sops = SecretsConfig::default();
+        assert!(age_key(&sops).is_none());
+    }
+
+    #[test]
+    fn provider_for_dispatches_on_provider_field() {
+        let mut config = SecretsConfig::default();
+        assert_eq!(provider_for(&config).unwrap().name(), "sops");
+        config.provider = "env".to_string();
+        assert_eq!(provider_for(&config).unwrap().name(), "env");
+        config.provider = "file".to_string();
+        assert_eq!(provider_for(&config).unwrap().name(), "file");
+        config.provider = "vault".to_string();
+        let err = provider_for(&config).err().unwrap();
+        assert!(err.to_string().contains("unsupported secrets provider 'vault'"));
+    }
+
+    #[test]
+    fn sops_provider_requires_a_file() {
+        let err = resolve(&SecretsConfig::default(), "github_token").unwrap_err();
+        assert_eq!(err.to_string(), "resolving secret 'github_token' via sops provider");
+        assert!(format!("{:#}", err).contains("secrets.sops_file is not set"));
+    }
+}
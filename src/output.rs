@@ -0,0 +1,77 @@
+//! Progress output for the setup commands (bootstrap, install, apply).
+//!
+//! `--quiet` drops their `>>`/`ok`/`::` progress lines so automation sees
+//! only warnings and errors (on stderr) and final results. Like
+//! `--no-color` it is a process-wide switch, set once in `main`.
+
+use std::fmt;
+use std::io::Write;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Print one progress line to stdout unless `--quiet` is set.
+pub fn print_progress(args: fmt::Arguments<'_>) {
+    write_progress(&mut std::io::stdout(), is_quiet(), args);
+}
+
+fn write_progress(out: &mut impl Write, quiet: bool, args: fmt::Arguments<'_>) {
+    if !quiet {
+        let _ = writeln!(out, "{}", args);
+    }
+}
+
+/// Stdout for child processes whose own progress output (installers,
+/// rebuilds) `--quiet` should hide. Their stderr is left alone so
+/// failures stay visible.
+pub fn child_stdout() -> Stdio {
+    if is_quiet() {
+        Stdio::null()
+    } else {
+        Stdio::inherit()
+    }
+}
+
+/// `println!` for progress lines; a no-op under `--quiet`.
+macro_rules! progress {
+    () => {
+        $crate::output::print_progress(format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::print_progress(format_args!($($arg)*))
+    };
+}
+pub(crate) use progress;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quiet_mode_emits_nothing() {
+        let steps = [">> Step 1: Nix", "ok Nix 2.24.0 already installed", ""];
+
+        let mut out = Vec::new();
+        for step in &steps {
+            write_progress(&mut out, true, format_args!("{}", step));
+        }
+        assert!(out.is_empty());
+
+        for step in &steps {
+            write_progress(&mut out, false, format_args!("{}", step));
+        }
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            ">> Step 1: Nix\nok Nix 2.24.0 already installed\n\n"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Expansion of user-supplied filesystem paths (`~`, `$VAR`, `${VAR}`).
+//!
+//! Apply only where a path is about to hit the filesystem (or be handed to
+//! Nix as one), never to arbitrary config strings.
+
+use std::path::{Path, PathBuf};
+
+/// Expand a leading `~` and any `$VAR`/`${VAR}` references. Undefined
+/// variables are left as written rather than collapsing to an empty string,
+/// so `$UNSET/key` can't silently become `/key`.
+pub fn expand_path(path: &str) -> PathBuf {
+    PathBuf::from(expand_with(path, dirs::home_dir(), |name| {
+        std::env::var(name).ok()
+    }))
+}
+
+/// Check that files can be created in the existing directory `dir` by
+/// writing and removing a probe file.
+pub fn probe_writable(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(format!(".kindling-write-probe-{}", std::process::id()));
+    std::fs::write(&probe, b"")?;
+    std::fs::remove_file(&probe)
+}
+
+fn expand_with(
+    path: &str,
+    home: Option<PathBuf>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> String {
+    let path = match (path.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{}", home.display(), rest)
+        }
+        _ => path.to_string(),
+    };
+
+    let mut out = String::with_capacity(path.len());
+    let mut rest = path.as_str();
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let after = &rest[pos + 1..];
+        let (name, consumed) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+        match (name.is_empty(), lookup(name)) {
+            (false, Some(value)) => out.push_str(&value),
+            _ => out.push_str(&rest[pos..pos + 1 + consumed]),
+        }
+        rest = &after[consumed..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(path: &str) -> String {
+        expand_with(
+            path,
+            Some(PathBuf::from("/home/alice")),
+            |name| match name {
+                "HOME" => Some("/home/alice".to_string()),
+                "XDG_CONFIG_HOME" => Some("/home/alice/.config".to_string()),
+                _ => None,
+            },
+        )
+    }
+
+    #[test]
+    fn expands_tilde() {
+        assert_eq!(expand("~"), "/home/alice");
+        assert_eq!(
+            expand("~/.config/sops/age/keys.txt"),
+            "/home/alice/.config/sops/age/keys.txt"
+        );
+        // `~user` and a tilde mid-path are not home references.
+        assert_eq!(expand("~bob/keys.txt"), "~bob/keys.txt");
+        assert_eq!(expand("/srv/~/keys.txt"), "/srv/~/keys.txt");
+    }
+
+    #[test]
+    fn tilde_without_home_is_left_alone() {
+        assert_eq!(expand_with("~/keys.txt", None, |_| None), "~/keys.txt");
+    }
+
+    #[test]
+    fn expands_home_variable() {
+        assert_eq!(expand("$HOME/code/github"), "/home/alice/code/github");
+        assert_eq!(expand("${HOME}/code"), "/home/alice/code");
+        assert_eq!(
+            expand("${XDG_CONFIG_HOME}/kindling/report.json"),
+            "/home/alice/.config/kindling/report.json"
+        );
+    }
+
+    #[test]
+    fn undefined_variables_are_kept_verbatim() {
+        assert_eq!(expand("$NOPE/keys.txt"), "$NOPE/keys.txt");
+        assert_eq!(expand("${NOPE}/keys.txt"), "${NOPE}/keys.txt");
+        assert_eq!(expand("$HOME/$NOPE"), "/home/alice/$NOPE");
+    }
+
+    #[test]
+    fn plain_and_malformed_paths_pass_through() {
+        assert_eq!(expand("/etc/age.key"), "/etc/age.key");
+        assert_eq!(expand("/tmp/cost$"), "/tmp/cost$");
+        assert_eq!(expand("/tmp/${unterminated"), "/tmp/${unterminated");
+    }
+}
@@ -0,0 +1,68 @@
+//! Secret values that stay out of logs.
+//!
+//! `Secret` wraps a resolved secret (an API token, an age key, a bootstrap
+//! token) so that an accidental `{:?}` or `{}` in a log line, error or
+//! panic prints `[redacted]`. Reading the value takes an explicit
+//! `expose()`. Serde sees the plain value, so config files that hold tokens
+//! still round-trip; report and telemetry types must not carry a `Secret`.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// What `Debug` and `Display` print instead of the value.
+pub const REDACTED: &str = "[redacted]";
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret<T = String>(T);
+
+impl<T> Secret<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// The secret value itself; keep it out of anything that gets logged.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<T> fmt::Display for Secret<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting_redacts_the_value() {
+        let secret = Secret::new("AGE-SECRET-KEY-1FAKE".to_string());
+        assert_eq!(format!("{:?}", secret), "[redacted]");
+        assert_eq!(format!("{}", secret), "[redacted]");
+        assert_eq!(format!("{:?}", Some(secret.clone())), "Some([redacted])");
+        assert_eq!(secret.expose(), "AGE-SECRET-KEY-1FAKE");
+    }
+
+    #[test]
+    fn serde_sees_the_plain_value() {
+        let secret: Secret = serde_json::from_str(r#""ghp_abc123""#).unwrap();
+        assert_eq!(secret.expose(), "ghp_abc123");
+        assert_eq!(serde_json::to_string(&secret).unwrap(), r#""ghp_abc123""#);
+    }
+}
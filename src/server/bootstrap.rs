@@ -847,9 +847,9 @@ fn generate_k3s_config_yaml(config: &ClusterConfig) -> Result<String> {
         .bootstrap_secrets
         .as_ref()
         .and_then(|s| s.get("k3s_server_token"))
-        .filter(|t| !t.is_empty())
+        .filter(|t| !t.expose().is_empty())
     {
-        lines.push(format!("token: \"{}\"", token));
+        lines.push(format!("token: \"{}\"", token.expose()));
     }
 
     // Server-only config keys: these are only valid for `k3s server`, not
@@ -1541,7 +1541,7 @@ fn provision_bootstrap_secrets(config: &ClusterConfig) -> Result<usize> {
 
     for target in BOOTSTRAP_SECRET_TARGETS {
         let raw_value = match secrets.get(target.key) {
-            Some(v) if !v.is_empty() => v,
+            Some(v) if !v.expose().is_empty() => v.expose(),
             _ => continue,
         };
 
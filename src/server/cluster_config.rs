@@ -12,6 +12,7 @@ use crate::node_identity::{
     FluxcdConfig, KubernetesConfig, NodeIdentity, SecretsConfig, UserConfig,
     VpnFirewallConfig, VpnLinkConfig, VpnPeerConfig,
 };
+use crate::secret::Secret;
 
 /// Top-level cluster configuration from cloud-init JSON.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,9 +70,9 @@ pub struct ClusterConfig {
 
     /// Bootstrap secrets delivered via cloud-init.
     /// Keys: "sops_age_key", "flux_github_token", etc.
-    /// Values: the raw secret content (not paths).
+    /// Values: the raw secret content (not paths), redacted in `Debug`.
     #[serde(default)]
-    pub bootstrap_secrets: Option<BTreeMap<String, String>>,
+    pub bootstrap_secrets: Option<BTreeMap<String, Secret>>,
 
     /// W3b: SSM SecureString references for the secret-free boot path.
     /// Keys are the same bootstrap-secret names as `bootstrap_secrets`
@@ -199,8 +199,9 @@ fn parse_bootstrap_secrets() {
     }"#;
     let config = ClusterConfig::from_json(json).unwrap();
     let secrets = config.bootstrap_secrets.as_ref().unwrap();
-    assert_eq!(secrets.get("sops_age_key").unwrap(), "AGE-SECRET-KEY-1FAKE...");
-    assert_eq!(secrets.get("flux_github_token").unwrap(), "ghp_faketoken123");
+    assert_eq!(secrets["sops_age_key"].expose(), "AGE-SECRET-KEY-1FAKE...");
+    assert_eq!(secrets["flux_github_token"].expose(), "ghp_faketoken123");
+    assert!(!format!("{:?}", config).contains("AGE-SECRET-KEY"));
 }
 
 #[test]
@@ -3,18 +3,25 @@ use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
 use axum::extract::State;
 use axum::response::Html;
 use axum::routing::get;
-use axum::Router;
+use axum::{Extension, Router};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::net::TcpListener;
-use tower_http::trace::TraceLayer;
 use tracing::{info, warn};
 
+use crate::api::auth;
 use crate::api::graphql::{self, KindlingSchema};
 use crate::api::rest::{self, AppState};
-use crate::config::DaemonConfig;
+use crate::config::{DaemonConfig, InsecureBindPolicy, TokenScope};
+use crate::domain::fleet_store::FleetStore;
 use crate::domain::nix_service::NixService;
 use crate::domain::node_service::NodeService;
+use crate::node_identity::secrets;
+use crate::paths::expand_path;
+use crate::server::jitter;
+use crate::server::mdns;
+use crate::server::pid_file::PidFile;
+use crate::server::tls::{self, TlsListener};
 
 pub async fn run(config: DaemonConfig) -> Result<()> {
     // JSON tracing for systemd/pod log drivers. shidou honors RUST_LOG and
@@ -23,6 +30,13 @@ pub async fn run(config: DaemonConfig) -> Result<()> {
 
     info!(version = env!("CARGO_PKG_VERSION"), "Kindling daemon starting");
 
+    // Held until shutdown; dropping it removes the file.
+    let pid_file = config
+        .pid_file
+        .as_deref()
+        .map(|path| PidFile::acquire(&expand_path(path)))
+        .transpose()?;
+
     // Create shared services
     let nix_service = NixService::new(config.clone());
     let node_service = Arc::new(NodeService::new(
@@ -30,34 +44,98 @@ pub async fn run(config: DaemonConfig) -> Result<()> {
         config.report.clone(),
     ));
 
+    // Fail fast rather than on the first refresh if the cache can't be written
+    node_service.check_cache_writable().await?;
+
     // Load persisted report from disk into memory cache (startup)
     node_service.load_from_disk().await;
 
+    let fleet = if config.fleet_controller.enabled {
+        let store = FleetStore::open(expand_path(&config.fleet_controller.state_file)).await?;
+        info!(state_file = %config.fleet_controller.state_file, "fleet controller enabled");
+        Some(Arc::new(store))
+    } else {
+        None
+    };
+
     let app_state = AppState {
         nix: nix_service.clone(),
         node: node_service.clone(),
+        fleet,
+        fleet_max_report_bytes: config.fleet_controller.max_report_bytes,
     };
 
     // Build GraphQL schema
-    let schema = graphql::build_schema(nix_service.clone(), node_service.clone());
+    let schema = graphql::build_schema(
+        nix_service.clone(),
+        node_service.clone(),
+        app_state.fleet.clone(),
+    );
 
     // Build GraphQL sub-router with its own state
     let graphql_router = Router::new()
         .route("/graphql", get(graphql_playground).post(graphql_handler))
         .with_state(schema);
 
-    // Build Axum router: REST (with AppState) + GraphQL (with schema state)
-    let app = rest::router(app_state)
-        .merge(graphql_router)
-        .layer(TraceLayer::new_for_http());
-
-    // Bind HTTP listener
-    let http_addr = &config.http_addr;
-    let listener = TcpListener::bind(http_addr)
+    // Build Axum router: REST (with AppState) + GraphQL (with schema state).
+    // Auth sits inside CORS so preflight requests don't need a token.
+    let secrets = node_service
+        .identity()
         .await
-        .with_context(|| format!("binding to {}", http_addr))?;
+        .map(|identity| identity.secrets)
+        .unwrap_or_default();
+    let auth_config = auth::resolve_tokens(&config.auth, |name| secrets::resolve(&secrets, name))?;
+    let app = rest::with_cors(
+        auth::with_auth(rest::router(app_state).merge(graphql_router), &auth_config),
+        &config.cors_allowed_origins,
+    )?;
+    let app = rest::with_request_tracing(app);
 
-    info!(addr = %http_addr, "HTTP server listening");
+    // Bind HTTP listeners: TCP unless http_addr is empty, plus an optional UDS
+    let http_addr = &config.http_addr;
+    let listener = if http_addr.is_empty() {
+        None
+    } else {
+        if config.auth.tokens.is_empty() {
+            check_bind_exposure(http_addr, config.insecure_bind)?;
+        }
+        let listener = TcpListener::bind(http_addr)
+            .await
+            .with_context(|| format!("binding to {}", http_addr))?;
+        info!(addr = %http_addr, tls = config.tls.enabled, "HTTP server listening");
+        Some(listener)
+    };
+    let tls_config = if config.tls.enabled && listener.is_some() {
+        let certificates = node_service
+            .identity()
+            .await
+            .map(|identity| identity.secrets.tls_certificates)
+            .unwrap_or_default();
+        let (cert, key) = tls::resolve_cert_paths(&config.tls, &certificates)?;
+        Some(tls::load_server_config(&cert, &key)?)
+    } else {
+        None
+    };
+    #[cfg(unix)]
+    let unix_listener = match config.unix_socket.as_deref() {
+        Some(path) => {
+            let listener = bind_unix_socket(std::path::Path::new(path))?;
+            info!(path = %path, "HTTP server listening on Unix socket");
+            Some(listener)
+        }
+        None => None,
+    };
+    #[cfg(not(unix))]
+    if config.unix_socket.is_some() {
+        anyhow::bail!("unix_socket is only supported on Unix platforms");
+    }
+    #[cfg(unix)]
+    let has_listener = listener.is_some() || unix_listener.is_some();
+    #[cfg(not(unix))]
+    let has_listener = listener.is_some();
+    if !has_listener {
+        anyhow::bail!("no listener configured: set daemon.http_addr or daemon.unix_socket");
+    }
 
     // Spawn initial discovery (background — daemon starts serving immediately)
     {
@@ -78,12 +156,33 @@ pub async fn run(config: DaemonConfig) -> Result<()> {
         });
     }
 
+    // Announce the daemon on the LAN
+    if config.advertise {
+        let hostname = hostname::get()
+            .map(|h| h.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        match mdns::ServiceRecord::for_daemon(&hostname, &config.http_addr, config.tls.enabled) {
+            Some(record) => {
+                tokio::spawn(async move {
+                    if let Err(e) = mdns::advertise(record).await {
+                        warn!(error = %e, "mDNS advertisement stopped");
+                    }
+                });
+            }
+            None => warn!(
+                addr = %config.http_addr,
+                "daemon.advertise is set but http_addr is not reachable from the LAN; not advertising"
+            ),
+        }
+    }
+
     // Spawn telemetry push loop
     if config.telemetry.enabled {
         let telemetry_service = nix_service.clone();
         let telemetry_config = config.telemetry.clone();
+        let jitter = config.interval_jitter_percent;
         tokio::spawn(async move {
-            crate::telemetry::run_push_loop(telemetry_service, &telemetry_config).await;
+            crate::telemetry::run_push_loop(telemetry_service, &telemetry_config, jitter).await;
         });
     }
 
@@ -91,11 +190,12 @@ pub async fn run(config: DaemonConfig) -> Result<()> {
     if config.gc.schedule_secs > 0 {
         let gc_service = nix_service.clone();
         let gc_interval = config.gc.schedule_secs;
+        let jitter = config.interval_jitter_percent;
         tokio::spawn(async move {
             let mut interval =
                 tokio::time::interval(Duration::from_secs(gc_interval));
             loop {
-                interval.tick().await;
+                jitter::tick(&mut interval, jitter).await;
                 info!("Running scheduled garbage collection");
                 match gc_service.trigger_gc().await {
                     Ok(result) => {
@@ -118,12 +218,13 @@ pub async fn run(config: DaemonConfig) -> Result<()> {
     if config.report.refresh_interval_secs > 0 {
         let report_node = node_service.clone();
         let interval_secs = config.report.refresh_interval_secs;
+        let jitter = config.interval_jitter_percent;
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
             // Skip the first tick — initial discovery already handles it
             interval.tick().await;
             loop {
-                interval.tick().await;
+                jitter::tick(&mut interval, jitter).await;
                 match report_node.refresh().await {
                     Ok(stored) => {
                         info!(
@@ -157,15 +258,120 @@ pub async fn run(config: DaemonConfig) -> Result<()> {
     // server or background task that needs drain notification asks for a
     // token via .token() and awaits .wait().
     let shutdown = tsunagu::ShutdownController::install();
-    axum::serve(listener, app.into_make_service())
-        .with_graceful_shutdown(shutdown.token().wait())
-        .await
-        .context("HTTP server error")?;
+    let tcp = async {
+        match (listener, tls_config) {
+            (Some(listener), Some(tls_config)) => axum::serve(
+                TlsListener::new(listener, tls_config)?,
+                app.clone().into_make_service(),
+            )
+            .with_graceful_shutdown(shutdown.token().wait())
+            .await
+            .context("HTTPS server error"),
+            (Some(listener), None) => axum::serve(listener, app.clone().into_make_service())
+                .with_graceful_shutdown(shutdown.token().wait())
+                .await
+                .context("HTTP server error"),
+            (None, _) => Ok(()),
+        }
+    };
+    #[cfg(unix)]
+    let uds = async {
+        match unix_listener {
+            Some(listener) => axum::serve(listener, app.clone().into_make_service())
+                .with_graceful_shutdown(shutdown.token().wait())
+                .await
+                .context("Unix socket server error"),
+            None => Ok(()),
+        }
+    };
+    #[cfg(not(unix))]
+    let uds = async { Ok::<(), anyhow::Error>(()) };
+    tokio::try_join!(tcp, uds)?;
+
+    #[cfg(unix)]
+    if let Some(ref path) = config.unix_socket {
+        let _ = std::fs::remove_file(path);
+    }
+    drop(pid_file);
 
     info!("Kindling daemon stopped");
     Ok(())
 }
 
+/// Warn about or refuse an HTTP address reachable from other hosts.
+fn check_bind_exposure(http_addr: &str, policy: InsecureBindPolicy) -> Result<()> {
+    if is_loopback_bind(http_addr) {
+        return Ok(());
+    }
+    match policy {
+        InsecureBindPolicy::Allow => Ok(()),
+        InsecureBindPolicy::Warn => {
+            warn!(
+                addr = %http_addr,
+                "!! HTTP API is bound to a non-loopback address WITHOUT authentication; \
+                 anyone who can reach it can trigger GC, store optimisation and report refreshes"
+            );
+            Ok(())
+        }
+        InsecureBindPolicy::Refuse => anyhow::bail!(
+            "refusing to serve the unauthenticated HTTP API on non-loopback address {}\n   \
+             Bind to 127.0.0.1, use daemon.unix_socket, or pass --allow-insecure.",
+            http_addr
+        ),
+    }
+}
+
+/// Whether a listen address only accepts local connections. Unspecified
+/// addresses (0.0.0.0, ::) and hostnames other than `localhost` count as exposed.
+fn is_loopback_bind(http_addr: &str) -> bool {
+    if let Ok(addr) = http_addr.parse::<std::net::SocketAddr>() {
+        return addr.ip().is_loopback();
+    }
+    match http_addr.rsplit_once(':') {
+        Some((host, _port)) => host.eq_ignore_ascii_case("localhost"),
+        None => false,
+    }
+}
+
+/// Bind the API socket, replacing a stale socket left by a previous run,
+/// and restrict it to owner and group. A socket something still accepts
+/// connections on is left alone.
+#[cfg(unix)]
+fn bind_unix_socket(path: &std::path::Path) -> Result<tokio::net::UnixListener> {
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            anyhow::bail!("{} exists and is not a socket", path.display());
+        }
+        match std::os::unix::net::UnixStream::connect(path) {
+            Ok(_) => anyhow::bail!(
+                "{} is in use (is another kindling daemon running?)",
+                path.display()
+            ),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::ConnectionRefused | std::io::ErrorKind::NotFound
+                ) =>
+            {
+                std::fs::remove_file(path)
+                    .with_context(|| format!("removing stale socket {}", path.display()))?;
+            }
+            Err(e) => return Err(e).with_context(|| format!("checking socket {}", path.display())),
+        }
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating directory {}", parent.display()))?;
+    }
+    let listener = tokio::net::UnixListener::bind(path)
+        .with_context(|| format!("binding to {}", path.display()))?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o660))
+        .with_context(|| format!("setting permissions on {}", path.display()))?;
+    Ok(listener)
+}
+
 async fn graphql_playground() -> Html<String> {
     Html(
         async_graphql::http::playground_source(
@@ -176,8 +382,126 @@ async fn graphql_playground() -> Html<String> {
 
 async fn graphql_handler(
     State(schema): State<KindlingSchema>,
+    scope: Option<Extension<TokenScope>>,
     req: GraphQLRequest,
 ) -> GraphQLResponse {
-    schema.execute(req.into_inner()).await.into()
+    let mut req = req.into_inner();
+    if let Some(Extension(scope)) = scope {
+        req = req.data(scope);
+    }
+    schema.execute(req).await.into()
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::client::KindlingClient;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn loopback_addresses_are_local() {
+        assert!(is_loopback_bind("127.0.0.1:9100"));
+        assert!(is_loopback_bind("127.1.2.3:9100"));
+        assert!(is_loopback_bind("[::1]:9100"));
+        assert!(is_loopback_bind("localhost:9100"));
+    }
+
+    #[test]
+    fn wildcard_and_public_addresses_are_exposed() {
+        assert!(!is_loopback_bind("0.0.0.0:9100"));
+        assert!(!is_loopback_bind("[::]:9100"));
+        assert!(!is_loopback_bind("192.168.1.10:9100"));
+        assert!(!is_loopback_bind("203.0.113.5:9100"));
+        assert!(!is_loopback_bind("node-1.internal:9100"));
+        assert!(!is_loopback_bind("garbage"));
+    }
+
+    #[test]
+    fn bind_exposure_policy() {
+        assert!(check_bind_exposure("0.0.0.0:9100", InsecureBindPolicy::Warn).is_ok());
+        assert!(check_bind_exposure("0.0.0.0:9100", InsecureBindPolicy::Allow).is_ok());
+        let err = check_bind_exposure("0.0.0.0:9100", InsecureBindPolicy::Refuse).unwrap_err();
+        assert!(err.to_string().contains("--allow-insecure"));
+        assert!(check_bind_exposure("127.0.0.1:9100", InsecureBindPolicy::Refuse).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bind_unix_socket_sets_mode_and_replaces_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("kindling.sock");
+
+        let first = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(async { bind_unix_socket(&path).map(drop) });
+        first.unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+
+        // The socket file outlives the dropped listener; rebinding must succeed.
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(async { bind_unix_socket(&path).map(drop) })
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bind_unix_socket_refuses_regular_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kindling.sock");
+        std::fs::write(&path, "").unwrap();
+        let err = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(async { bind_unix_socket(&path).map(drop) })
+            .unwrap_err();
+        assert!(err.to_string().contains("not a socket"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn bind_unix_socket_refuses_live_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kindling.sock");
+        let _live = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        let err = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(async { bind_unix_socket(&path).map(drop) })
+            .unwrap_err();
+        assert!(err.to_string().contains("in use"));
+        assert!(path.exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn health_over_unix_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kindling.sock");
+
+        let config = DaemonConfig::default();
+        let state = AppState {
+            nix: NixService::new(config.clone()),
+            node: Arc::new(NodeService::new(config.identity.clone(), config.report.clone())),
+            fleet: None,
+            fleet_max_report_bytes: 0,
+        };
+        let listener = bind_unix_socket(&path).unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, rest::router(state).into_make_service())
+                .await
+                .unwrap();
+        });
+
+        let client = KindlingClient::new(
+            &format!("unix://{}", path.display()),
+            crate::client::DEFAULT_TIMEOUT,
+            &crate::client::ClientTls::default(),
+        )
+        .unwrap();
+        let health = client.health().await.unwrap();
+        assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+    }
+}
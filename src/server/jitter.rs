@@ -0,0 +1,43 @@
+//! Jitter for the daemon's periodic loops.
+//!
+//! Nodes started together would otherwise refresh, push telemetry and GC in
+//! lockstep. Each tick of a loop's `interval` is followed by a random delay
+//! of up to `percent`% of the period, so the gap between two runs varies by
+//! ±`percent`% while the average cadence stays the period.
+
+use std::time::Duration;
+
+use tokio::time::Interval;
+
+/// Random per-tick delay in `[0, period * percent / 100]`.
+pub fn tick_offset(period: Duration, percent: u32) -> Duration {
+    let max = period.mul_f64(f64::from(percent.min(100)) / 100.0);
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    max.mul_f64(rand::random_range(0.0..=1.0))
+}
+
+/// Wait for the next tick of `interval`, then its random offset.
+pub async fn tick(interval: &mut Interval, percent: u32) {
+    interval.tick().await;
+    let offset = tick_offset(interval.period(), percent);
+    if !offset.is_zero() {
+        tokio::time::sleep(offset).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offset_stays_within_bounds() {
+        let period = Duration::from_secs(300);
+        for _ in 0..1000 {
+            assert!(tick_offset(period, 10) <= Duration::from_secs(30));
+        }
+        assert!(tick_offset(period, 250) <= period);
+        assert_eq!(tick_offset(period, 0), Duration::ZERO);
+    }
+}
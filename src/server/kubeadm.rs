@@ -215,6 +215,7 @@ fn get_kubeadm_token(config: &ClusterConfig) -> Option<String> {
         .bootstrap_secrets
         .as_ref()
         .and_then(|s| s.get("kubeadm_token"))
+        .map(|t| t.expose())
         .filter(|t| !t.is_empty())
         .cloned()
 }
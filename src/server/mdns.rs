@@ -0,0 +1,242 @@
+//! mDNS (DNS-SD) advertisement and discovery of kindling daemons.
+//!
+//! With `daemon.advertise` set, the daemon registers `_kindling._tcp.local.`
+//! on the LAN, and `kindling discover` browses for it. Both go through the
+//! `mdns-sd` responder, which does the probing, announcing and conflict
+//! resolution and shares UDP 5353 with avahi/mDNSResponder.
+
+use std::collections::BTreeMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use mdns_sd::{DaemonEvent, ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+/// DNS-SD service type kindling daemons register under.
+pub const SERVICE_TYPE: &str = "_kindling._tcp.local.";
+
+/// TXT key carrying the daemon's version.
+const TXT_VERSION: &str = "version";
+/// TXT key set to `1` by daemons serving their API over TLS.
+const TXT_TLS: &str = "tls";
+
+/// The records one daemon announces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceRecord {
+    /// Instance name: the node's short hostname.
+    pub instance: String,
+    pub port: u16,
+    /// Addresses to announce; empty means every address of the host.
+    pub addresses: Vec<IpAddr>,
+    /// TXT `key=value` entries.
+    pub txt: Vec<(String, String)>,
+}
+
+impl ServiceRecord {
+    /// Record for a daemon listening on `http_addr`. `None` when the
+    /// address is loopback-only, since nothing else on the LAN could reach
+    /// it. Unspecified addresses advertise all of the host's addresses.
+    /// `tls` adds a `tls=1` TXT entry so browsers know to use `https://`.
+    pub fn for_daemon(hostname: &str, http_addr: &str, tls: bool) -> Option<Self> {
+        let (host, port) = http_addr.rsplit_once(':')?;
+        let port = port.parse().ok()?;
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let addresses = match host.parse::<IpAddr>() {
+            Ok(ip) if ip.is_loopback() => return None,
+            Ok(ip) if !ip.is_unspecified() => vec![ip],
+            Err(_) if host.eq_ignore_ascii_case("localhost") => return None,
+            _ => Vec::new(),
+        };
+        let instance = hostname.split('.').next().unwrap_or(hostname);
+        let mut txt = vec![(
+            TXT_VERSION.to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+        )];
+        if tls {
+            txt.push((TXT_TLS.to_string(), "1".to_string()));
+        }
+        Some(Self {
+            instance: instance.to_string(),
+            port,
+            addresses,
+            txt,
+        })
+    }
+
+    fn service_info(&self) -> Result<ServiceInfo> {
+        let host_name = format!("{}.local.", self.instance);
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            &self.instance,
+            &host_name,
+            &self.addresses[..],
+            self.port,
+            &self.txt[..],
+        )
+        .with_context(|| format!("building mDNS record for {}", self.instance))?;
+        Ok(if self.addresses.is_empty() {
+            info.enable_addr_auto()
+        } else {
+            info
+        })
+    }
+}
+
+/// A daemon found by [`browse`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiscoveredDaemon {
+    pub instance: String,
+    /// SRV target, e.g. `edge-1.local`.
+    pub host: String,
+    pub port: u16,
+    pub addresses: Vec<Ipv4Addr>,
+    pub version: Option<String>,
+    /// The daemon advertised `tls=1`.
+    pub tls: bool,
+}
+
+impl DiscoveredDaemon {
+    fn from_info(info: &ServiceInfo) -> Self {
+        let suffix = format!(".{}", SERVICE_TYPE);
+        let fullname = info.get_fullname();
+        let mut addresses: Vec<Ipv4Addr> = info.get_addresses_v4().into_iter().copied().collect();
+        addresses.sort();
+        Self {
+            instance: fullname
+                .strip_suffix(&suffix)
+                .unwrap_or(fullname)
+                .to_string(),
+            host: info.get_hostname().trim_end_matches('.').to_string(),
+            port: info.get_port(),
+            addresses,
+            version: info.get_property_val_str(TXT_VERSION).map(str::to_string),
+            tls: info.get_property_val_str(TXT_TLS) == Some("1"),
+        }
+    }
+
+    /// Base URL of the daemon's API, preferring an advertised address.
+    pub fn url(&self) -> String {
+        let scheme = if self.tls { "https" } else { "http" };
+        match self.addresses.first() {
+            Some(addr) => format!("{}://{}:{}", scheme, addr, self.port),
+            None => format!("{}://{}:{}", scheme, self.host, self.port),
+        }
+    }
+}
+
+/// Register `record` and keep answering for it until the task is dropped.
+/// Responder errors (a failed send on one interface, say) are logged and
+/// the advertisement carries on.
+pub async fn advertise(record: ServiceRecord) -> Result<()> {
+    let responder = ServiceDaemon::new().context("starting mDNS responder")?;
+    let events = responder.monitor().context("monitoring mDNS responder")?;
+    responder
+        .register(record.service_info()?)
+        .context("registering mDNS service")?;
+    info!(
+        instance = %record.instance,
+        port = record.port,
+        addresses = ?record.addresses,
+        "advertising daemon over mDNS"
+    );
+
+    while let Ok(event) = events.recv_async().await {
+        match event {
+            DaemonEvent::Error(e) => warn!(error = %e, "mDNS responder error"),
+            other => debug!(event = ?other, "mDNS responder event"),
+        }
+    }
+    Ok(())
+}
+
+/// Browse the LAN for kindling daemons and collect answers for `wait`.
+pub async fn browse(wait: Duration) -> Result<Vec<DiscoveredDaemon>> {
+    let querier = ServiceDaemon::new().context("starting mDNS querier")?;
+    let events = querier
+        .browse(SERVICE_TYPE)
+        .context("browsing for kindling daemons")?;
+
+    let mut found = BTreeMap::new();
+    let deadline = tokio::time::Instant::now() + wait;
+    while let Ok(Ok(event)) = tokio::time::timeout_at(deadline, events.recv_async()).await {
+        if let ServiceEvent::ServiceResolved(info) = event {
+            let daemon = DiscoveredDaemon::from_info(&info);
+            found.insert(daemon.instance.clone(), daemon);
+        }
+    }
+    let _ = querier.shutdown();
+    Ok(found.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record() -> ServiceRecord {
+        ServiceRecord {
+            instance: "edge-1".to_string(),
+            port: 9100,
+            addresses: vec![IpAddr::V4(Ipv4Addr::new(192, 168, 1, 20))],
+            txt: vec![(TXT_VERSION.to_string(), "0.3.0".to_string())],
+        }
+    }
+
+    #[test]
+    fn service_record_maps_to_a_discovered_daemon() {
+        let info = record().service_info().unwrap();
+        assert_eq!(info.get_fullname(), "edge-1._kindling._tcp.local.");
+        assert_eq!(
+            DiscoveredDaemon::from_info(&info),
+            DiscoveredDaemon {
+                instance: "edge-1".to_string(),
+                host: "edge-1.local".to_string(),
+                port: 9100,
+                addresses: vec![Ipv4Addr::new(192, 168, 1, 20)],
+                version: Some("0.3.0".to_string()),
+                tls: false,
+            }
+        );
+        assert_eq!(
+            DiscoveredDaemon::from_info(&info).url(),
+            "http://192.168.1.20:9100"
+        );
+
+        let mut tls = record();
+        tls.txt.push((TXT_TLS.to_string(), "1".to_string()));
+        let found = DiscoveredDaemon::from_info(&tls.service_info().unwrap());
+        assert!(found.tls);
+        assert_eq!(found.url(), "https://192.168.1.20:9100");
+    }
+
+    #[test]
+    fn record_for_daemon_uses_the_bind_address() {
+        let rec =
+            ServiceRecord::for_daemon("edge-1.lab.example.com", "10.0.0.7:9100", false).unwrap();
+        assert_eq!(rec.instance, "edge-1");
+        assert_eq!(rec.port, 9100);
+        assert_eq!(rec.addresses, vec![IpAddr::V4(Ipv4Addr::new(10, 0, 0, 7))]);
+        assert_eq!(rec.txt[0].0, TXT_VERSION);
+        assert!(rec.service_info().is_ok());
+
+        let any = ServiceRecord::for_daemon("edge-1", "0.0.0.0:9100", true).unwrap();
+        assert!(any.addresses.is_empty());
+        assert!(any.service_info().unwrap().is_addr_auto());
+        assert!(any.txt.contains(&(TXT_TLS.to_string(), "1".to_string())));
+
+        assert_eq!(
+            ServiceRecord::for_daemon("edge-1", "127.0.0.1:9100", false),
+            None
+        );
+        assert_eq!(
+            ServiceRecord::for_daemon("edge-1", "localhost:9100", false),
+            None
+        );
+        assert_eq!(
+            ServiceRecord::for_daemon("edge-1", "[::1]:9100", false),
+            None
+        );
+        assert_eq!(ServiceRecord::for_daemon("edge-1", "0.0.0.0", false), None);
+    }
+}
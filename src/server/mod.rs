@@ -7,16 +7,24 @@
 //! - `kubeadm` — kubeadm config generation for upstream Kubernetes
 //! - `health` — K3s API + FluxCD health polling
 //! - `daemon` — HTTP/GraphQL daemon server (pre-existing)
+//! - `pid_file` — daemon PID file with stale-lock detection
+//! - `jitter` — random per-tick offsets for the daemon's periodic loops
+//! - `mdns` — LAN advertisement and discovery of daemons (`_kindling._tcp`)
+//! - `tls` — TLS termination for the daemon's TCP listener
 
 pub mod bootstrap;
 pub mod cluster_config;
 pub mod daemon;
 pub mod health;
+pub mod jitter;
 pub mod kubeadm;
+pub mod mdns;
 // persistent_state pulls in aws-sdk-ec2 (~600k LoC after macro expansion)
 // and is the build-time bottleneck for kindling. Gated behind the `aws`
 // cargo feature (default-enabled; AMI consumers keep the module, kasou-VM
 // consumers build with --no-default-features and skip the dep entirely).
 #[cfg(feature = "aws")]
 pub mod persistent_state;
+pub mod pid_file;
+pub mod tls;
 pub mod wireguard_fast;
@@ -0,0 +1,138 @@
+//! Daemon PID file — written on startup, removed on shutdown.
+//!
+//! A PID file left by a crashed daemon is stale and gets replaced; one
+//! naming a live process means another daemon owns it, so startup fails.
+
+use anyhow::{bail, Context, Result};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Holds the PID file for the daemon's lifetime; dropping it removes the file.
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Write this process's PID to `path`, refusing if a live process
+    /// already holds it.
+    pub fn acquire(path: &Path) -> Result<Self> {
+        if let Some(pid) = read_pid(path) {
+            if pid != std::process::id() && process_alive(pid) {
+                bail!(
+                    "daemon already running with PID {} (pid file {})",
+                    pid,
+                    path.display()
+                );
+            }
+            warn!(pid, path = %path.display(), "replacing stale pid file");
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("creating directory {}", parent.display()))?;
+        }
+        std::fs::write(path, format!("{}\n", std::process::id()))
+            .with_context(|| format!("writing pid file {}", path.display()))?;
+        info!(path = %path.display(), "wrote pid file");
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Only remove the file if it still names us.
+        if read_pid(&self.path) == Some(std::process::id()) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// PID recorded in `path`; `None` when missing or unparseable.
+fn read_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Whether a process with this PID exists (signal 0 probes without
+/// delivering anything; EPERM means it exists but belongs to another user).
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    if pid <= 0 {
+        return false;
+    }
+    let rc = unsafe { libc::kill(pid, 0) };
+    rc == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without a cheap liveness probe, assume the recorded process is alive.
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// PID of a child that has already exited and been reaped.
+    #[cfg(unix)]
+    fn dead_pid() -> u32 {
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+        pid
+    }
+
+    #[test]
+    fn acquire_writes_and_drop_removes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run").join("kindling.pid");
+        {
+            let _pid_file = PidFile::acquire(&path).unwrap();
+            assert_eq!(read_pid(&path), Some(std::process::id()));
+        }
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stale_pid_file_is_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kindling.pid");
+        let stale = dead_pid();
+        assert!(!process_alive(stale));
+        std::fs::write(&path, format!("{}\n", stale)).unwrap();
+
+        let _pid_file = PidFile::acquire(&path).unwrap();
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn live_pid_file_refuses_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kindling.pid");
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().unwrap();
+        std::fs::write(&path, child.id().to_string()).unwrap();
+
+        let err = PidFile::acquire(&path).unwrap_err();
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(err.to_string().contains("already running"));
+        // The other daemon's file is left in place.
+        assert_eq!(read_pid(&path), Some(child.id()));
+    }
+
+    #[test]
+    fn garbage_pid_file_is_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("kindling.pid");
+        std::fs::write(&path, "not a pid").unwrap();
+        let _pid_file = PidFile::acquire(&path).unwrap();
+        assert_eq!(read_pid(&path), Some(std::process::id()));
+    }
+}
@@ -0,0 +1,249 @@
+//! TLS termination for the daemon's TCP listener.
+//!
+//! The certificate and key are PEM files, named either directly in
+//! `daemon.tls` or through an entry of node.yaml `secrets.tls_certificates`.
+//! Handshakes run off the accept loop, so a client that connects and never
+//! finishes one cannot hold up other connections.
+
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use tokio_rustls::rustls::{self, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+use tracing::{debug, warn};
+
+use crate::config::DaemonTlsConfig;
+use crate::node_identity::TlsCertificate;
+use crate::paths::expand_path;
+
+/// A client gets this long to complete its handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Certificate and key files to serve: the explicit pair from `tls`, else
+/// the `secrets.tls_certificates` entry for `tls.domain` (or the first one
+/// with both files).
+pub fn resolve_cert_paths(
+    tls: &DaemonTlsConfig,
+    certificates: &[TlsCertificate],
+) -> Result<(PathBuf, PathBuf)> {
+    match (&tls.cert_file, &tls.key_file) {
+        (Some(cert), Some(key)) => return Ok((expand_path(cert), expand_path(key))),
+        (None, None) => {}
+        _ => bail!("daemon.tls needs both cert_file and key_file, or neither"),
+    }
+    let entry = certificates
+        .iter()
+        .filter(|c| match &tls.domain {
+            Some(domain) => c.domain == *domain,
+            None => true,
+        })
+        .find(|c| c.cert_file.is_some() && c.key_file.is_some());
+    match (entry, &tls.domain) {
+        (Some(c), _) => Ok((
+            expand_path(c.cert_file.as_deref().unwrap_or_default()),
+            expand_path(c.key_file.as_deref().unwrap_or_default()),
+        )),
+        (None, Some(domain)) => bail!(
+            "daemon.tls.domain {} has no secrets.tls_certificates entry with cert_file and key_file",
+            domain
+        ),
+        (None, None) => bail!(
+            "daemon.tls is enabled but no certificate is configured: set daemon.tls.cert_file \
+             and key_file, or add one to secrets.tls_certificates in node.yaml"
+        ),
+    }
+}
+
+/// rustls server config from a PEM certificate chain and private key.
+pub fn load_server_config(cert: &Path, key: &Path) -> Result<Arc<ServerConfig>> {
+    let chain = CertificateDer::pem_file_iter(cert)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| anyhow!("reading certificate {}: {}", cert.display(), e))?;
+    if chain.is_empty() {
+        bail!("no certificate found in {}", cert.display());
+    }
+    let key = PrivateKeyDer::from_pem_file(key)
+        .map_err(|e| anyhow!("reading private key {}: {}", key.display(), e))?;
+
+    let mut config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .context("configuring TLS protocol versions")?
+            .with_no_client_auth()
+            .with_single_cert(chain, key)
+            .context("certificate and private key do not match")?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+/// TCP listener that hands `axum::serve` only connections whose TLS
+/// handshake has completed.
+pub struct TlsListener {
+    incoming: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl TlsListener {
+    pub fn new(listener: TcpListener, config: Arc<ServerConfig>) -> io::Result<Self> {
+        let local_addr = listener.local_addr()?;
+        let acceptor = TlsAcceptor::from(config);
+        let (tx, incoming) = mpsc::channel(64);
+        tokio::spawn(async move {
+            loop {
+                let (stream, peer) = tokio::select! {
+                    _ = tx.closed() => return,
+                    accepted = listener.accept() => match accepted {
+                        Ok(conn) => conn,
+                        Err(e) => {
+                            warn!(error = %e, "accepting TLS connection failed");
+                            tokio::time::sleep(Duration::from_millis(100)).await;
+                            continue;
+                        }
+                    },
+                };
+                let acceptor = acceptor.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(tls)) => {
+                            let _ = tx.send((tls, peer)).await;
+                        }
+                        Ok(Err(e)) => debug!(peer = %peer, error = %e, "TLS handshake failed"),
+                        Err(_) => debug!(peer = %peer, "TLS handshake timed out"),
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            incoming,
+            local_addr,
+        })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.incoming.recv().await {
+            Some(conn) => conn,
+            // The accept task only stops once this listener is gone.
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::rest::{self, AppState};
+    use crate::client::{ClientTls, KindlingClient, DEFAULT_TIMEOUT};
+    use crate::config::DaemonConfig;
+    use crate::domain::nix_service::NixService;
+    use crate::domain::node_service::NodeService;
+
+    fn cert_entry(domain: &str, files: bool) -> TlsCertificate {
+        TlsCertificate {
+            domain: domain.to_string(),
+            cert_file: files.then(|| format!("/etc/ssl/{}.crt", domain)),
+            key_file: files.then(|| format!("/etc/ssl/{}.key", domain)),
+            issuer: None,
+        }
+    }
+
+    #[test]
+    fn cert_paths_prefer_explicit_files_then_secrets() {
+        let certs = vec![
+            cert_entry("acme.example.com", false),
+            cert_entry("node.example.com", true),
+            cert_entry("api.example.com", true),
+        ];
+        let mut tls = DaemonTlsConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_cert_paths(&tls, &certs).unwrap().0,
+            PathBuf::from("/etc/ssl/node.example.com.crt")
+        );
+
+        tls.domain = Some("api.example.com".to_string());
+        assert_eq!(
+            resolve_cert_paths(&tls, &certs).unwrap().1,
+            PathBuf::from("/etc/ssl/api.example.com.key")
+        );
+        tls.domain = Some("acme.example.com".to_string());
+        assert!(resolve_cert_paths(&tls, &certs).is_err());
+
+        tls.cert_file = Some("/srv/tls.crt".to_string());
+        assert!(resolve_cert_paths(&tls, &certs).is_err());
+        tls.key_file = Some("/srv/tls.key".to_string());
+        assert_eq!(
+            resolve_cert_paths(&tls, &certs).unwrap(),
+            (PathBuf::from("/srv/tls.crt"), PathBuf::from("/srv/tls.key"))
+        );
+    }
+
+    #[tokio::test]
+    async fn health_over_tls_with_custom_ca() {
+        let dir = tempfile::tempdir().unwrap();
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_path = dir.path().join("tls.crt");
+        let key_path = dir.path().join("tls.key");
+        std::fs::write(&cert_path, cert.cert.pem()).unwrap();
+        std::fs::write(&key_path, cert.key_pair.serialize_pem()).unwrap();
+
+        let config = DaemonConfig::default();
+        let state = AppState {
+            nix: NixService::new(config.clone()),
+            node: Arc::new(NodeService::new(
+                config.identity.clone(),
+                config.report.clone(),
+            )),
+            fleet: None,
+            fleet_max_report_bytes: 0,
+        };
+        let app = rest::router(state);
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = tcp.local_addr().unwrap().port();
+        let config = load_server_config(&cert_path, &key_path).unwrap();
+        let listener = TlsListener::new(tcp, config).unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let url = format!("https://localhost:{}", port);
+
+        // Untrusted self-signed certificate: refused by default.
+        let client = KindlingClient::new(&url, DEFAULT_TIMEOUT, &ClientTls::default()).unwrap();
+        assert!(client.health().await.is_err());
+
+        let trusted = ClientTls {
+            ca_cert: Some(cert_path),
+            insecure: false,
+        };
+        let client = KindlingClient::new(&url, DEFAULT_TIMEOUT, &trusted).unwrap();
+        assert_eq!(
+            client.health().await.unwrap().version,
+            env!("CARGO_PKG_VERSION")
+        );
+
+        let insecure = ClientTls {
+            ca_cert: None,
+            insecure: true,
+        };
+        let client = KindlingClient::new(&url, DEFAULT_TIMEOUT, &insecure).unwrap();
+        assert!(client.health().await.is_ok());
+    }
+}
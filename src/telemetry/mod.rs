@@ -7,9 +7,14 @@ use tracing::{info, warn};
 
 use crate::config::TelemetryConfig;
 use crate::domain::nix_service::NixService;
+use crate::server::jitter;
 use crate::telemetry::vector::VectorClient;
 
-pub async fn run_push_loop(service: Arc<NixService>, config: &TelemetryConfig) {
+pub async fn run_push_loop(
+    service: Arc<NixService>,
+    config: &TelemetryConfig,
+    jitter_percent: u32,
+) {
     let client = VectorClient::new(&config.vector_url);
     let interval_secs = config.push_interval_secs;
 
@@ -22,7 +27,7 @@ pub async fn run_push_loop(service: Arc<NixService>, config: &TelemetryConfig) {
     let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
 
     loop {
-        interval.tick().await;
+        jitter::tick(&mut interval, jitter_percent).await;
         let payload = service.telemetry_payload().await;
         if let Err(e) = client.push(&payload).await {
             warn!(error = %e, "Failed to push telemetry to Vector");
@@ -1,10 +1,26 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
-use std::path::PathBuf;
-use std::process::Command;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
+use crate::output::progress;
 use crate::tools;
 
+/// How often a running `tend sync` is checked against its deadline.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Per-repo outcome of a `tend sync`, after the retry.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SyncSummary {
+    pub synced: Vec<String>,
+    pub failed: Vec<String>,
+    /// Whether the last `tend sync` run exited successfully.
+    pub success: bool,
+}
+
 /// Ensure tend is installed (via nix profile if missing).
 pub fn ensure_installed() -> Result<()> {
     if tools::find("tend").is_some() {
@@ -60,15 +76,17 @@ pub fn ensure_config(org: &str) -> Result<()> {
     Ok(())
 }
 
-/// Run `tend sync --quiet` to clone workspace repos.
-pub fn sync() -> Result<()> {
+/// Run `tend sync` to clone workspace repos, retrying the repos it reports
+/// as failed once. Both runs together are bounded by `timeout`; running past
+/// it kills tend and is an error. `None` when the sync was skipped.
+pub fn sync(timeout: Duration) -> Result<Option<SyncSummary>> {
     let config_path = tend_config_path()?;
     if !config_path.exists() {
         println!(
             "{} No tend config found, skipping sync",
             "::".blue().bold()
         );
-        return Ok(());
+        return Ok(None);
     }
 
     let tend = match tools::find("tend") {
@@ -78,31 +96,171 @@ pub fn sync() -> Result<()> {
                 "{} tend not found on PATH, skipping sync",
                 "!!".yellow().bold()
             );
-            return Ok(());
+            return Ok(None);
         }
     };
 
+    let deadline = Instant::now() + timeout;
     println!("{} Running tend sync...", "::".blue().bold());
+    let (success, output) = run_sync(&tend, &[], deadline, timeout)?;
+    let mut summary = parse_sync_output(&output);
+    summary.success = success;
 
-    let status = Command::new(&tend)
-        .args(["sync"])
-        .status()
-        .context("failed to run tend sync")?;
+    if !success {
+        // Retry only what failed; without per-repo detail, the whole sync.
+        let retry = summary.failed.clone();
+        println!(
+            "{} tend sync exited unsuccessfully, retrying {}",
+            "::".blue().bold(),
+            if retry.is_empty() {
+                "the full sync".to_string()
+            } else {
+                format!("{} failed repo(s)", retry.len())
+            }
+        );
+        let (success, output) = run_sync(&tend, &retry, deadline, timeout)?;
+        summary.merge_retry(parse_sync_output(&output), success);
+    }
 
-    if !status.success() {
+    if summary.success && summary.failed.is_empty() {
         println!(
-            "{} tend sync exited with status {} (non-fatal)",
-            "!!".yellow().bold(),
-            status
+            "{} tend sync complete ({} repo(s) synced)",
+            "ok".green().bold(),
+            summary.synced.len()
         );
-    } else {
-        println!("{} tend sync complete", "ok".green().bold());
     }
+    Ok(Some(summary))
+}
 
-    Ok(())
+impl SyncSummary {
+    /// Fold in a retry run: repos it synced no longer count as failed.
+    fn merge_retry(&mut self, retry: SyncSummary, success: bool) {
+        for repo in retry.synced {
+            self.failed.retain(|r| *r != repo);
+            if !self.synced.contains(&repo) {
+                self.synced.push(repo);
+            }
+        }
+        for repo in retry.failed {
+            if !self.failed.contains(&repo) {
+                self.failed.push(repo);
+            }
+        }
+        self.success = success;
+    }
+}
+
+/// Per-repo results from tend's output. tend prints one line per repo,
+/// either as `✓ repo` / `✗ repo: error` or as a verb (`cloned`, `updated`,
+/// `failed`, …) followed by the repo; other lines are ignored.
+pub fn parse_sync_output(output: &str) -> SyncSummary {
+    let mut summary = SyncSummary::default();
+    for line in output.lines() {
+        let mut words = line.split_whitespace();
+        let (Some(marker), Some(repo)) = (words.next(), words.next()) else {
+            continue;
+        };
+        let repo = repo.trim_end_matches(':').to_string();
+        let list = match marker.trim_end_matches(':').to_lowercase().as_str() {
+            "✓" | "ok" | "cloned" | "updated" | "synced" | "up-to-date" => &mut summary.synced,
+            "✗" | "x" | "failed" | "error" => &mut summary.failed,
+            _ => continue,
+        };
+        if !list.contains(&repo) {
+            list.push(repo);
+        }
+    }
+    summary
+}
+
+/// One `tend sync` run limited to `repos` (all when empty), echoing its
+/// output as it arrives. Returns whether it succeeded and what it printed.
+fn run_sync(
+    tend: &Path,
+    repos: &[String],
+    deadline: Instant,
+    timeout: Duration,
+) -> Result<(bool, String)> {
+    let mut child = Command::new(tend)
+        .arg("sync")
+        .args(repos)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("failed to run tend sync")?;
+    let stdout = echo_lines(child.stdout.take(), false);
+    let stderr = echo_lines(child.stderr.take(), true);
+
+    let status = loop {
+        if let Some(status) = child.try_wait().context("waiting for tend sync")? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("tend sync timed out after {}s", timeout.as_secs());
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    };
+
+    let mut output = stdout.join().unwrap_or_default();
+    output.push_str(&stderr.join().unwrap_or_default());
+    Ok((status.success(), output))
+}
+
+/// Forward a child's pipe line by line while keeping a copy of it.
+fn echo_lines<R: Read + Send + 'static>(pipe: Option<R>, to_stderr: bool) -> JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut captured = String::new();
+        let Some(pipe) = pipe else {
+            return captured;
+        };
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            if to_stderr {
+                eprintln!("  {}", line);
+            } else {
+                progress!("  {}", line);
+            }
+            captured.push_str(&line);
+            captured.push('\n');
+        }
+        captured
+    })
 }
 
 fn tend_config_path() -> Result<PathBuf> {
     let config_dir = dirs::config_dir().context("could not determine config directory")?;
     Ok(config_dir.join("tend").join("config.yaml"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+Syncing workspace pleme-io (4 repos)
+  ✓ kindling
+  ✓ tend: up to date
+  ✗ substrate: git clone failed: Connection reset by peer
+  failed blackmatter: timeout
+2 synced, 2 failed
+";
+
+    #[test]
+    fn summary_lists_synced_and_failed_repos() {
+        let summary = parse_sync_output(OUTPUT);
+        assert_eq!(summary.synced, ["kindling", "tend"]);
+        assert_eq!(summary.failed, ["substrate", "blackmatter"]);
+        assert!(parse_sync_output("Nothing to do\n").synced.is_empty());
+    }
+
+    #[test]
+    fn retry_clears_repos_that_recovered() {
+        let mut summary = parse_sync_output(OUTPUT);
+        let retry = parse_sync_output("✓ substrate\n✗ blackmatter: timeout\n");
+        summary.merge_retry(retry, false);
+        assert_eq!(summary.synced, ["kindling", "tend", "substrate"]);
+        assert_eq!(summary.failed, ["blackmatter"]);
+        assert!(!summary.success);
+    }
+}
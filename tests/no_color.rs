@@ -0,0 +1,30 @@
+//! Color opt-outs, checked against the real binary so the process-wide
+//! `colored` override is never flipped under other tests.
+
+use std::process::Command;
+
+/// Run `kindling check` with colors forced on unless `opt_out` disables them.
+fn check_output(opt_out: impl FnOnce(&mut Command) -> &mut Command) -> String {
+    let home = tempfile::tempdir().unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_kindling"));
+    cmd.arg("check")
+        .env("HOME", home.path())
+        .env("CLICOLOR_FORCE", "1")
+        .env_remove("NO_COLOR");
+    let output = opt_out(&mut cmd).output().unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn no_color_flag_strips_ansi() {
+    let stdout = check_output(|cmd| cmd.arg("--no-color"));
+    assert!(stdout.contains("kindling check"));
+    assert!(!stdout.contains('\x1b'));
+}
+
+#[test]
+fn no_color_env_strips_ansi() {
+    let stdout = check_output(|cmd| cmd.env("NO_COLOR", "1"));
+    assert!(stdout.contains("kindling check"));
+    assert!(!stdout.contains('\x1b'));
+}